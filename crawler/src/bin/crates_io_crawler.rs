@@ -288,7 +288,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
     
     // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    cryptocrawl_crawler::logging::init(log::LevelFilter::Info, cryptocrawl_crawler::logging::LogFormat::Text);
     
     // Perform the crawl
     let report = crawl_crates_io(args.max_depth, args.follow_subdomains, args.max_links).await?;
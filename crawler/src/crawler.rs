@@ -1,19 +1,727 @@
-use crate::models::{CrawledPage, CrawlResult, Task};
-use crate::robots::{RobotsManager, is_javascript_dependent};
+use crate::models::{CrawledPage, CrawlResult, CrawlScope, CrawlStats, Task, CrawlPlan, PlannedUrl, SeedSource, PageMetadata, content_type_matches, is_html_content_type, media_type};
+use crate::robots::{JsAssessment, RobotsManager, ROBOTS_PRODUCT_TOKEN, assess_javascript_dependence};
 use crate::headless::HeadlessBrowser;
+use crate::rate_limit::RequestBudget;
+use crate::output::{JsonlWriter, gzip_path};
+use crate::storage::{PageRecord, StorageRoute, StorageRouter, StorageSink};
+use crate::soft404::Soft404Detector;
+use crate::visited::VisitedSet;
 use anyhow::{Result, anyhow};
-use log::{info, warn, debug, trace};
+use tracing::{info, info_span, warn, debug, trace, Instrument};
 use url::Url;
 use std::collections::{HashSet, VecDeque, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH, Instant};
-use std::sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}};
-use scraper::{Html, Selector};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}};
+use scraper::{ElementRef, Html, Selector};
 use reqwest::Client;
-use std::io::Write;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
 use serde_json;
 use crate::db::Database;
+use once_cell::sync::Lazy;
+use publicsuffix::Psl;
+use crate::metrics::METRICS;
 use chrono;
+use encoding_rs::{Encoding, UTF_8};
+use tokio::sync::Semaphore;
+
+/// Sitemap URLs queued before the crawl starts, unless `with_sitemap_seed_limit` says otherwise
+const DEFAULT_SITEMAP_SEED_LIMIT: usize = 100;
+
+/// Redirects followed for a single request unless `with_max_redirects` says otherwise
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Largest resource fetched when the HEAD pre-check is on, unless `with_max_page_size` says otherwise
+const DEFAULT_MAX_PAGE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Content types saved to the asset directory, unless `with_asset_content_types` says otherwise
+const DEFAULT_ASSET_CONTENT_TYPES: [&str; 2] = ["application/pdf", "image/*"];
+
+/// Most out-of-scope pages fetched by the external hop, unless `with_max_external_links` says otherwise
+const DEFAULT_MAX_EXTERNAL_LINKS: usize = 100;
+
+/// Redirect chain entries kept before old ones are dropped
+const MAX_TRACKED_REDIRECTS: usize = 10_000;
+
+/// Build the HTTP client used for pages, robots.txt and sitemaps.
+/// Redirects are followed up to `max_redirects` and logged in `redirect_hops`.
+fn http_client(user_agent: &str, max_redirects: usize, accept_invalid_certs: bool, redirect_hops: &RedirectHops) -> Client {
+    let redirect_hops = redirect_hops.clone();
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        // The previous URLs include the one first requested, so their count is the redirects so far
+        let redirects = attempt.previous().len();
+        if redirects > max_redirects {
+            attempt.error(format!("too many redirects (more than {})", max_redirects))
+        } else {
+            redirect_hops.record(attempt.url(), redirects);
+            attempt.follow()
+        }
+    });
+    
+    Client::builder()
+        .user_agent(user_agent)
+        .gzip(true)
+        .redirect(redirect_policy)
+        .timeout(std::time::Duration::from_secs(30))
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// Whether the crawler obeys robots.txt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RobotsEnforcement {
+    /// Skip every URL robots.txt disallows
+    #[default]
+    Strict,
+    /// Ignore robots.txt entirely
+    Off,
+}
+
+/// Politeness presets for a crawl.
+///
+/// | Profile    | Workers | Delay before each request | Requests per host at once | Retries | First retry after |
+/// |------------|---------|---------------------------|---------------------------|---------|-------------------|
+/// | Aggressive | 20      | none                      | 20                        | 1       | 250 ms            |
+/// | Normal     | 10      | 50 ms                     | 10                        | 3       | 1 s               |
+/// | Polite     | 2       | 1 s                       | 1                         | 5       | 5 s               |
+///
+/// `Normal` is the default and matches how the crawler has always behaved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CrawlProfile {
+    /// Crawl as fast as possible; only for sites you own or have permission to hammer
+    Aggressive,
+    /// Balanced defaults
+    #[default]
+    Normal,
+    /// One request per host at a time with a long delay
+    Polite,
+}
+
+impl CrawlProfile {
+    /// Number of parallel crawl workers
+    pub fn workers(self) -> usize {
+        match self {
+            CrawlProfile::Aggressive => 20,
+            CrawlProfile::Normal => 10,
+            CrawlProfile::Polite => 2,
+        }
+    }
+    
+    /// Delay each worker waits before every request
+    pub fn request_delay(self) -> std::time::Duration {
+        match self {
+            CrawlProfile::Aggressive => std::time::Duration::ZERO,
+            CrawlProfile::Normal => std::time::Duration::from_millis(50),
+            CrawlProfile::Polite => std::time::Duration::from_secs(1),
+        }
+    }
+    
+    /// Maximum number of requests in flight to the same host
+    pub fn per_host_concurrency(self) -> usize {
+        match self {
+            CrawlProfile::Aggressive => 20,
+            CrawlProfile::Normal => 10,
+            CrawlProfile::Polite => 1,
+        }
+    }
+    
+    /// Number of times a URL that failed to load is retried
+    pub fn max_retries(self) -> usize {
+        match self {
+            CrawlProfile::Aggressive => 1,
+            CrawlProfile::Normal => 3,
+            CrawlProfile::Polite => 5,
+        }
+    }
+    
+    /// Wait before the first retry of a failed URL; each further retry waits twice as long
+    pub fn retry_backoff(self) -> std::time::Duration {
+        match self {
+            CrawlProfile::Aggressive => std::time::Duration::from_millis(250),
+            CrawlProfile::Normal => std::time::Duration::from_secs(1),
+            CrawlProfile::Polite => std::time::Duration::from_secs(5),
+        }
+    }
+}
+
+/// Thresholds used to stop the crawler from wandering into URL traps
+/// such as endless calendars, repeating paths and faceted search pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrapGuard {
+    /// Number of leading path segments that make up a prefix
+    pub prefix_segments: usize,
+    /// URLs enqueued under the same path prefix before the rest are dropped
+    pub max_urls_per_prefix: usize,
+    /// Times the same segment may appear in one path, e.g. `/a/a/a/` has 3
+    pub max_segment_repeats: usize,
+    /// Distinct query strings enqueued for the same path
+    pub max_query_variants: usize,
+}
+
+impl Default for TrapGuard {
+    fn default() -> Self {
+        Self {
+            prefix_segments: 2,
+            max_urls_per_prefix: 1000,
+            max_segment_repeats: 3,
+            max_query_variants: 50,
+        }
+    }
+}
+
+/// Which JavaScript-dependent pages are rendered in headless Chrome. Rendering is
+/// expensive, so the rest are stored as fetched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderPolicy {
+    /// Render the pages the crawl starts from
+    pub render_seed: bool,
+    /// Render pages whose URL contains any of these substrings, e.g. `/docs/`
+    pub render_patterns: Vec<String>,
+    /// Percentage (0-100) of the remaining JavaScript-dependent pages to render
+    pub sample_percent: u8,
+}
+
+impl Default for RenderPolicy {
+    fn default() -> Self {
+        Self {
+            render_seed: true,
+            render_patterns: Vec::new(),
+            sample_percent: 0,
+        }
+    }
+}
+
+impl RenderPolicy {
+    /// Whether a JavaScript-dependent page at `depth` should be rendered.
+    /// Sampling hashes the URL, so the same pages are picked on every run.
+    pub fn should_render(&self, url: &Url, depth: usize) -> bool {
+        if self.render_seed && depth == 0 {
+            return true;
+        }
+        
+        let url_str = url.as_str();
+        if self.render_patterns.iter().any(|pattern| url_str.contains(pattern.as_str())) {
+            return true;
+        }
+        
+        if self.sample_percent == 0 {
+            return false;
+        }
+        fnv1a(url_str.as_bytes()) % 100 < u64::from(self.sample_percent.min(100))
+    }
+}
+
+/// 64-bit FNV-1a hash. Unlike `DefaultHasher` it is the same in every build and Rust
+/// release, so anything derived from it (samples, file names) stays stable across runs.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// When a page counts as JavaScript-dependent, and how sure the crawler must be
+/// before rendering it. See [`assess_javascript_dependence`] for the indicators and their weights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JsDetection {
+    /// Indicators a page needs before it counts as JavaScript-dependent
+    pub min_reasons: usize,
+    /// Confidence score (0-1) a JavaScript-dependent page needs before headless Chrome renders it
+    pub min_score: f64,
+}
+
+impl Default for JsDetection {
+    fn default() -> Self {
+        Self {
+            min_reasons: 1,
+            // A lone weak indicator, such as a `window.` reference, isn't worth a render
+            min_score: 0.25,
+        }
+    }
+}
+
+impl JsDetection {
+    /// Whether a page with this assessment should be rendered
+    fn warrants_render(&self, assessment: &JsAssessment) -> bool {
+        assessment.is_dependent && assessment.score >= self.min_score
+    }
+}
+
+/// A kind of element the crawler reads links to follow from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum LinkSelector {
+    /// `<a href>`
+    Anchor,
+    /// `<area href>` in image maps
+    Area,
+    /// `<link rel="next">`, the following page of a paginated listing
+    NextPage,
+    /// `<link rel="prev">`, the preceding page of a paginated listing
+    PrevPage,
+    /// The `data-href` attribute of any element, used by script-driven navigation
+    DataHref,
+}
+
+impl LinkSelector {
+    /// Anchors, plus `rel="next"` so paginated listings are followed to the end
+    pub const DEFAULT: [LinkSelector; 2] = [LinkSelector::Anchor, LinkSelector::NextPage];
+    
+    /// CSS selector for the candidate elements
+    fn css(self) -> &'static str {
+        match self {
+            Self::Anchor => "a[href]",
+            Self::Area => "area[href]",
+            Self::NextPage | Self::PrevPage => "link[rel][href]",
+            Self::DataHref => "[data-href]",
+        }
+    }
+    
+    /// The link target of a matched element, if it is one this selector follows
+    fn href<'a>(self, element: &ElementRef<'a>) -> Option<&'a str> {
+        let value = element.value();
+        match self {
+            Self::Anchor | Self::Area => value.attr("href"),
+            Self::NextPage | Self::PrevPage => {
+                // `rel` is a case-insensitive token list; "previous" is the legacy spelling of "prev"
+                let wanted: &[&str] = if self == Self::NextPage { &["next"] } else { &["prev", "previous"] };
+                let rel = value.attr("rel")?;
+                rel.split_ascii_whitespace()
+                    .any(|token| wanted.iter().any(|wanted| token.eq_ignore_ascii_case(wanted)))
+                    .then(|| value.attr("href"))
+                    .flatten()
+            }
+            Self::DataHref => value.attr("data-href"),
+        }
+    }
+}
+
+/// Counters behind a [`TrapGuard`] for a single crawl
+#[derive(Debug, Default)]
+struct TrapTracker {
+    guard: TrapGuard,
+    prefix_counts: HashMap<String, usize>,
+    query_variants: HashMap<String, HashSet<String>>,
+    /// Traps already logged, so each one is only reported once
+    reported: HashSet<String>,
+}
+
+impl TrapTracker {
+    fn new(guard: TrapGuard) -> Self {
+        Self { guard, ..Default::default() }
+    }
+    
+    /// Record `url` as about to be enqueued, or return why it looks like a trap
+    fn check(&mut self, url: &Url) -> Option<String> {
+        let host = url.host_str().unwrap_or("");
+        let segments: Vec<&str> = url.path().split('/').filter(|s| !s.is_empty()).collect();
+        
+        // Step 1: Repeating path segments
+        let mut repeats: HashMap<&str, usize> = HashMap::new();
+        for segment in &segments {
+            let count = repeats.entry(segment).or_insert(0);
+            *count += 1;
+            if *count > self.guard.max_segment_repeats {
+                return Some(format!("segment '{}' repeats more than {} times in paths on {}",
+                                    segment, self.guard.max_segment_repeats, host));
+            }
+        }
+        
+        // Step 2: Query-parameter permutations for the same path
+        let path_key = format!("{}{}", host, url.path());
+        if url.query().is_some() {
+            // Reordered parameters count as the same variant
+            let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+            pairs.sort();
+            let variant = serde_json::to_string(&pairs).unwrap_or_default();
+            let variants = self.query_variants.entry(path_key.clone()).or_default();
+            if !variants.contains(&variant) {
+                if variants.len() >= self.guard.max_query_variants {
+                    return Some(format!("more than {} query variants for {}",
+                                        self.guard.max_query_variants, path_key));
+                }
+                variants.insert(variant);
+            }
+        }
+        
+        // Step 3: URLs per path prefix
+        let prefix = format!("{}/{}", host, segments.iter()
+            .take(self.guard.prefix_segments)
+            .copied()
+            .collect::<Vec<_>>()
+            .join("/"));
+        let count = self.prefix_counts.entry(prefix.clone()).or_insert(0);
+        if *count >= self.guard.max_urls_per_prefix {
+            return Some(format!("more than {} URLs under {}", self.guard.max_urls_per_prefix, prefix));
+        }
+        *count += 1;
+        
+        None
+    }
+    
+    /// Check `url` and log the first time each trap is hit; returns whether to enqueue it
+    fn allow(&mut self, url: &Url) -> bool {
+        match self.check(url) {
+            None => true,
+            Some(reason) => {
+                if self.reported.insert(reason.clone()) {
+                    info!("Suppressing crawler trap: {}", reason);
+                } else {
+                    debug!("Suppressed {} ({})", url, reason);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Lengths of the redirect chains the HTTP client followed, keyed by where each chain led
+#[derive(Debug, Clone, Default)]
+pub struct RedirectHops(Arc<Mutex<HashMap<String, usize>>>);
+
+impl RedirectHops {
+    /// Note that `target` was reached after `redirects` redirects
+    fn record(&self, target: &Url, redirects: usize) {
+        let mut chains = self.0.lock().unwrap();
+        // Chains nobody asks about (robots.txt, sitemaps) shouldn't pile up
+        if chains.len() >= MAX_TRACKED_REDIRECTS {
+            chains.clear();
+        }
+        chains.insert(target.to_string(), redirects);
+    }
+    
+    /// Redirects followed to reach `url`, forgetting the chain. A page that was redirected
+    /// counts at least one, even if its chain was dropped.
+    pub fn take(&self, url: &Url) -> usize {
+        self.0.lock().unwrap().remove(url.as_str()).unwrap_or(1)
+    }
+}
+
+/// How far a page is from the crawl's seeds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Distance {
+    /// Links followed
+    links: usize,
+    /// Requests made, counting each link and each redirect
+    hops: usize,
+}
+
+impl Distance {
+    /// Distance of a page linked from this one
+    fn next(self) -> Self {
+        Self { links: self.links + 1, hops: self.hops + 1 }
+    }
+    
+    /// Distance after following `redirects` redirects to the page
+    fn redirected(self, redirects: usize) -> Self {
+        Self { hops: self.hops + redirects, ..self }
+    }
+    
+    /// Depth checked against the task's limit
+    fn depth(self, redirects_count_as_depth: bool) -> usize {
+        if redirects_count_as_depth { self.hops } else { self.links }
+    }
+}
+
+/// Out-of-scope link targets collected for the external hop
+struct ExternalLinks {
+    /// Most links kept
+    max: usize,
+    /// Links in the order they were found
+    urls: Vec<Url>,
+    /// Links already kept or dropped
+    seen: HashSet<String>,
+}
+
+impl ExternalLinks {
+    fn new(max: usize) -> Self {
+        Self { max, urls: Vec::new(), seen: HashSet::new() }
+    }
+    
+    /// Keep an out-of-scope link unless the cap is reached
+    fn offer(&mut self, url: &Url) {
+        if self.urls.len() >= self.max || !matches!(url.scheme(), "http" | "https") {
+            return;
+        }
+        if self.seen.insert(url.to_string()) {
+            self.urls.push(url.clone());
+        }
+    }
+}
+
+/// Failed URLs waiting to be fetched again. Each retry waits twice as long as the one
+/// before it, and a URL still failing after `max_retries` retries is given up on.
+struct RetryQueue {
+    /// Retries allowed after the first attempt
+    max_retries: usize,
+    /// Wait before the first retry
+    backoff: std::time::Duration,
+    /// URLs waiting for their next attempt
+    pending: VecDeque<PendingRetry>,
+}
+
+/// A URL waiting for its next attempt
+struct PendingRetry {
+    url: Url,
+    /// Attempts made so far
+    attempts: usize,
+    /// When the next attempt may start
+    ready_at: Instant,
+}
+
+/// What happens to a URL after a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RetryOutcome {
+    /// It is fetched again once `delay` has passed
+    Scheduled { delay: std::time::Duration },
+    /// It has no retries left
+    GaveUp,
+}
+
+impl RetryQueue {
+    fn new(max_retries: usize, backoff: std::time::Duration) -> Self {
+        Self { max_retries, backoff, pending: VecDeque::new() }
+    }
+    
+    /// Record that attempt number `attempts` (1 for the first fetch) at `url` failed
+    fn record_failure(&mut self, url: Url, attempts: usize) -> RetryOutcome {
+        if attempts > self.max_retries {
+            return RetryOutcome::GaveUp;
+        }
+        
+        let delay = self.backoff.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+        self.pending.push_back(PendingRetry { url, attempts, ready_at: Instant::now() + delay });
+        RetryOutcome::Scheduled { delay }
+    }
+    
+    /// Take a URL whose backoff has passed, with the number of attempts made so far
+    fn next_ready(&mut self) -> Option<(Url, usize)> {
+        let now = Instant::now();
+        let index = self.pending.iter().position(|retry| retry.ready_at <= now)?;
+        self.pending.remove(index).map(|retry| (retry.url, retry.attempts))
+    }
+    
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Schedule another attempt at a URL that failed, or store that the crawl gave up on it
+/// with its last error. Returns whether the URL will be retried.
+fn retry_or_give_up(retries: &mut RetryQueue, db: Option<&Database>, task_id: &str, url: &Url, attempts: usize, error: &str) -> bool {
+    match retries.record_failure(url.clone(), attempts) {
+        RetryOutcome::Scheduled { delay } => {
+            warn!("Attempt {} at {} failed: {}. Retrying in {:?}", attempts, url, error, delay);
+            true
+        }
+        RetryOutcome::GaveUp => {
+            warn!("Giving up on {} after {} attempts: {}", url, attempts, error);
+            if let Some(db) = db {
+                if let Err(e) = db.save_failed_url(task_id, url.as_str(), attempts as u32, error) {
+                    warn!("Failed to store failed URL {} in database: {}", url, e);
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Frontier of a crawl: the URLs waiting to be fetched and every URL already queued or fetched
+#[derive(Debug, Clone, Default)]
+pub struct CrawlState {
+    /// URLs fetched first, such as the seeds
+    important_queue: Arc<Mutex<VecDeque<Url>>>,
+    /// URLs fetched once the important queue is empty
+    regular_queue: Arc<Mutex<VecDeque<Url>>>,
+    /// URLs queued or fetched so far, so none is fetched twice
+    visited: Arc<Mutex<VisitedSet>>,
+}
+
+impl CrawlState {
+    /// Lengths of the important and regular queues
+    pub fn queue_lengths(&self) -> (usize, usize) {
+        let important = self.important_queue.lock().map(|queue| queue.len()).unwrap_or_default();
+        let regular = self.regular_queue.lock().map(|queue| queue.len()).unwrap_or_default();
+        (important, regular)
+    }
+    
+    /// URLs waiting to be fetched
+    pub fn queue_len(&self) -> usize {
+        let (important, regular) = self.queue_lengths();
+        important + regular
+    }
+    
+    /// URLs queued or fetched so far
+    pub fn visited_count(&self) -> usize {
+        self.visited.lock().map(|visited| visited.len()).unwrap_or_default()
+    }
+    
+    /// Forget the previous crawl's URLs
+    fn clear(&self) {
+        if let Ok(mut queue) = self.important_queue.lock() {
+            queue.clear();
+        }
+        if let Ok(mut queue) = self.regular_queue.lock() {
+            queue.clear();
+        }
+        if let Ok(mut visited) = self.visited.lock() {
+            visited.clear();
+        }
+    }
+}
+
+/// Live counters for a crawl in progress, shared with whoever started the crawl
+#[derive(Debug, Clone)]
+pub struct CrawlProgress {
+    /// Pages crawled so far
+    pub pages: Arc<AtomicUsize>,
+    /// Bytes downloaded so far
+    pub bytes: Arc<AtomicUsize>,
+    /// When the crawl started
+    pub started: Instant,
+    /// Pages crawled since the last `take_pages`, without their bodies
+    pages_buffer: Arc<Mutex<Vec<CrawledPage>>>,
+    /// Workers currently running
+    active_workers: Arc<AtomicUsize>,
+    /// Queues and visited URLs of the crawl
+    state: CrawlState,
+    /// Pages per status code and bytes per content type so far
+    stats: Arc<Mutex<CrawlStats>>,
+}
+
+/// Snapshot of a crawl's workers and queues for live status views
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LiveStatus {
+    /// Workers currently running
+    pub active_workers: usize,
+    /// URLs waiting in the important queue
+    pub important_queue: usize,
+    /// URLs waiting in the regular queue
+    pub regular_queue: usize,
+    /// Pages crawled so far
+    pub pages: usize,
+    /// Bytes downloaded so far
+    pub bytes: usize,
+    /// Average pages per second since the crawl started
+    pub pages_per_second: f64,
+    /// Seconds since the crawl started
+    pub elapsed_secs: u64,
+}
+
+/// Counts a worker as active until dropped
+struct WorkerGuard(Arc<AtomicUsize>);
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl CrawlProgress {
+    /// Create counters for a crawl starting now
+    pub fn new() -> Self {
+        Self {
+            pages: Arc::new(AtomicUsize::new(0)),
+            bytes: Arc::new(AtomicUsize::new(0)),
+            started: Instant::now(),
+            pages_buffer: Arc::new(Mutex::new(Vec::new())),
+            active_workers: Arc::new(AtomicUsize::new(0)),
+            state: CrawlState::default(),
+            stats: Arc::new(Mutex::new(CrawlStats::default())),
+        }
+    }
+    
+    /// Count a worker as active for as long as the returned guard lives
+    fn track_worker(&self) -> WorkerGuard {
+        self.active_workers.fetch_add(1, Ordering::SeqCst);
+        WorkerGuard(Arc::clone(&self.active_workers))
+    }
+    
+    /// Queues and visited URLs of the crawl
+    pub fn state(&self) -> &CrawlState {
+        &self.state
+    }
+    
+    /// URLs waiting to be fetched
+    pub fn queue_len(&self) -> usize {
+        self.state.queue_len()
+    }
+    
+    /// URLs queued or fetched so far
+    pub fn visited_count(&self) -> usize {
+        self.state.visited_count()
+    }
+    
+    /// Pages crawled so far
+    pub fn pages_crawled(&self) -> usize {
+        self.pages.load(Ordering::Relaxed)
+    }
+    
+    /// Snapshot of the workers, queues and counters
+    pub fn live_status(&self) -> LiveStatus {
+        let (important_queue, regular_queue) = self.state.queue_lengths();
+        let pages = self.pages.load(Ordering::Relaxed);
+        let elapsed = self.started.elapsed();
+        
+        LiveStatus {
+            active_workers: self.active_workers.load(Ordering::Relaxed),
+            important_queue,
+            regular_queue,
+            pages,
+            bytes: self.bytes.load(Ordering::Relaxed),
+            pages_per_second: if elapsed.as_secs_f64() > 0.0 { pages as f64 / elapsed.as_secs_f64() } else { 0.0 },
+            elapsed_secs: elapsed.as_secs(),
+        }
+    }
+    
+    /// Remember a crawled page until it is reported, and count it in the metrics
+    fn record_page(&self, page: &CrawledPage) {
+        METRICS.pages_crawled.inc();
+        METRICS.bytes_downloaded.inc_by(page.size as u64);
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.record(page);
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            METRICS.pages_per_second.set(self.pages.load(Ordering::Relaxed) as f64 / elapsed);
+        }
+        
+        if let Ok(mut buffer) = self.pages_buffer.lock() {
+            buffer.push(CrawledPage { body: None, ..page.clone() });
+        }
+    }
+    
+    /// Breakdown of the pages crawled so far by status code and content type
+    pub fn stats(&self) -> CrawlStats {
+        self.stats.lock().map(|stats| stats.clone()).unwrap_or_default()
+    }
+    
+    /// Take the pages crawled since the last call
+    pub fn take_pages(&self) -> Vec<CrawledPage> {
+        match self.pages_buffer.lock() {
+            Ok(mut buffer) => std::mem::take(&mut *buffer),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Put back pages that could not be reported, ahead of any crawled since
+    pub fn restore_pages(&self, pages: Vec<CrawledPage>) {
+        if let Ok(mut buffer) = self.pages_buffer.lock() {
+            buffer.splice(0..0, pages);
+        }
+    }
+    
+    /// Milliseconds since the crawl started
+    pub fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+impl Default for CrawlProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Implementation of the web crawler
 pub struct Crawler {
@@ -21,6 +729,18 @@ pub struct Crawler {
     current_task: Option<Task>,
     /// HTTP client
     client: Client,
+    /// User agent sent with every request
+    user_agent: String,
+    /// Redirects followed for a single request
+    max_redirects: usize,
+    /// Whether invalid TLS certificates are accepted, for internal sites with self-signed ones
+    accept_invalid_certs: bool,
+    /// Redirect chains followed by the HTTP client
+    redirect_hops: RedirectHops,
+    /// Whether the HTTP client was supplied through `with_client` and must be kept as is
+    custom_client: bool,
+    /// Whether each redirect counts as a level of depth
+    redirects_count_as_depth: bool,
     /// Robots.txt and sitemap manager
     robots_manager: RobotsManager,
     /// Track JavaScript-dependent sites
@@ -29,32 +749,116 @@ pub struct Crawler {
     headless_browser: Option<Arc<HeadlessBrowser>>,
     /// Whether to use headless Chrome for JavaScript sites
     use_headless_chrome: bool,
+    /// Whether headless Chrome carries cookies from one page to the next
+    browser_cookies: bool,
     /// Database connection
     db: Option<Database>,
+    /// Counters updated as the crawl progresses
+    progress: Option<CrawlProgress>,
+    /// Whether robots.txt rules are obeyed
+    robots_enforcement: RobotsEnforcement,
+    /// Whether the readable text of each page is stored
+    text_extraction: bool,
+    /// Whether the raw HTML of each page is stored
+    store_html: bool,
+    /// Worker count, delays, per-host concurrency and retries
+    profile: CrawlProfile,
+    /// Retries per failed URL, overriding the profile's
+    max_retries: Option<usize>,
+    /// Limits that keep the crawl out of URL traps
+    trap_guard: TrapGuard,
+    /// Fetch only the URLs listed in sitemaps and never follow links
+    sitemap_only: bool,
+    /// Fetch only these URLs, such as the ones an earlier crawl gave up on, and never follow links
+    retry_urls: Option<Vec<Url>>,
+    /// Skip pages the task already crawled, following the links stored for them instead
+    skip_crawled: bool,
+    /// Gzip the JSONL dump, written as `.jsonl.gz`
+    compress_output: bool,
+    /// Most sitemap URLs queued as seeds when links are followed
+    sitemap_seed_limit: usize,
+    /// Check each resource's type and size with a HEAD request before downloading it
+    head_precheck: bool,
+    /// Flag pages that look like the host's "not found" page despite a 2xx status
+    soft_404_detection: bool,
+    /// Expected URLs of the fingerprint set replacing the exact visited set
+    fingerprint_visited: Option<usize>,
+    /// Largest resource the HEAD pre-check lets through or an asset saved, in bytes
+    max_page_size: u64,
+    /// Directory assets are saved to instead of the database; assets are not saved when unset
+    asset_dir: Option<PathBuf>,
+    /// Content types saved to the asset directory, wildcards such as `image/*` included
+    asset_content_types: Vec<String>,
+    /// Sinks for pages of given content types and sizes; other pages go to the database
+    storage_routes: Vec<StorageRoute>,
+    /// Fetch the targets of links leaving the task scope, without following their links
+    external_hop: bool,
+    /// Most out-of-scope pages the external hop fetches
+    max_external_links: usize,
+    /// Which JavaScript-dependent pages headless Chrome renders
+    render_policy: RenderPolicy,
+    /// Thresholds for treating a page as JavaScript-dependent
+    js_detection: JsDetection,
+    /// Elements links are read from
+    link_selectors: Vec<LinkSelector>,
+    /// Requests per second shared with other crawls; unlimited when unset
+    request_budget: Option<RequestBudget>,
+    /// File of extra URLs, one per line, queued as seeds next to the target
+    seed_urls_file: Option<PathBuf>,
 }
 
 impl Default for Crawler {
     fn default() -> Self {
         // Create a reqwest client with default settings
-        let client = Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36")
-            .gzip(true)
-            .redirect(reqwest::redirect::Policy::limited(10))
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        let user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36";
+        let redirect_hops = RedirectHops::default();
+        let client = http_client(user_agent, DEFAULT_MAX_REDIRECTS, false, &redirect_hops);
             
-        // Create robots manager with the same user agent
-        let robots_manager = RobotsManager::new("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36")
+        // robots.txt groups are matched against the product token, not the header
+        let robots_manager = RobotsManager::new(ROBOTS_PRODUCT_TOKEN)
             .with_client(client.clone());
             
         Self {
             current_task: None,
             client,
+            user_agent: user_agent.to_string(),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            accept_invalid_certs: false,
+            redirect_hops,
+            custom_client: false,
+            redirects_count_as_depth: false,
             robots_manager,
             js_dependent_sites: HashSet::new(),
             headless_browser: None,
             use_headless_chrome: false,
+            browser_cookies: false,
             db: None,
+            progress: None,
+            robots_enforcement: RobotsEnforcement::Strict,
+            text_extraction: false,
+            store_html: false,
+            profile: CrawlProfile::Normal,
+            max_retries: None,
+            trap_guard: TrapGuard::default(),
+            sitemap_only: false,
+            retry_urls: None,
+            skip_crawled: false,
+            compress_output: false,
+            sitemap_seed_limit: DEFAULT_SITEMAP_SEED_LIMIT,
+            head_precheck: false,
+            soft_404_detection: false,
+            fingerprint_visited: None,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            asset_dir: None,
+            asset_content_types: DEFAULT_ASSET_CONTENT_TYPES.iter().map(|ct| ct.to_string()).collect(),
+            storage_routes: Vec::new(),
+            external_hop: false,
+            max_external_links: DEFAULT_MAX_EXTERNAL_LINKS,
+            render_policy: RenderPolicy::default(),
+            js_detection: JsDetection::default(),
+            link_selectors: LinkSelector::DEFAULT.to_vec(),
+            request_budget: None,
+            seed_urls_file: None,
         }
     }
 }
@@ -64,26 +868,54 @@ impl Crawler {
     pub fn new(task: Task) -> Self {
         // Create a reqwest client with default settings
         let user_agent = "CryptoCrawl/0.1 (https://github.com/yourusername/cryptocrawl)";
-        let client = Client::builder()
-            .user_agent(user_agent)
-            .gzip(true)
-            .redirect(reqwest::redirect::Policy::limited(10))
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        let redirect_hops = RedirectHops::default();
+        let client = http_client(user_agent, DEFAULT_MAX_REDIRECTS, false, &redirect_hops);
             
-        // Create robots manager with the same user agent
-        let robots_manager = RobotsManager::new(user_agent)
+        // robots.txt groups are matched against the product token, not the header
+        let robots_manager = RobotsManager::new(ROBOTS_PRODUCT_TOKEN)
             .with_client(client.clone());
             
         Self {
             current_task: Some(task),
             client,
+            user_agent: user_agent.to_string(),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            accept_invalid_certs: false,
+            redirect_hops,
+            custom_client: false,
+            redirects_count_as_depth: false,
             robots_manager,
             js_dependent_sites: HashSet::new(),
             headless_browser: None,
             use_headless_chrome: false,
+            browser_cookies: false,
             db: None,
+            progress: None,
+            robots_enforcement: RobotsEnforcement::Strict,
+            text_extraction: false,
+            store_html: false,
+            profile: CrawlProfile::Normal,
+            max_retries: None,
+            trap_guard: TrapGuard::default(),
+            sitemap_only: false,
+            retry_urls: None,
+            skip_crawled: false,
+            compress_output: false,
+            sitemap_seed_limit: DEFAULT_SITEMAP_SEED_LIMIT,
+            head_precheck: false,
+            soft_404_detection: false,
+            fingerprint_visited: None,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            asset_dir: None,
+            asset_content_types: DEFAULT_ASSET_CONTENT_TYPES.iter().map(|ct| ct.to_string()).collect(),
+            storage_routes: Vec::new(),
+            external_hop: false,
+            max_external_links: DEFAULT_MAX_EXTERNAL_LINKS,
+            render_policy: RenderPolicy::default(),
+            js_detection: JsDetection::default(),
+            link_selectors: LinkSelector::DEFAULT.to_vec(),
+            request_budget: None,
+            seed_urls_file: None,
         }
     }
     
@@ -93,11 +925,280 @@ impl Crawler {
         self
     }
     
+    /// Carry cookies between the pages headless Chrome renders instead of starting each one fresh
+    pub fn with_browser_cookies(mut self, enabled: bool) -> Self {
+        self.browser_cookies = enabled;
+        self
+    }
+    
+    /// Store the readable text of each page alongside it in the database
+    pub fn with_text_extraction(mut self, enabled: bool) -> Self {
+        self.text_extraction = enabled;
+        self
+    }
+    
+    /// Store the raw HTML of each page in the database (off by default to save space)
+    pub fn with_html_storage(mut self, enabled: bool) -> Self {
+        self.store_html = enabled;
+        self
+    }
+    
+    /// Set how many redirects are followed for a single request (10 by default)
+    pub fn with_max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self.rebuild_client();
+        self
+    }
+    
+    /// Count every redirect followed to reach a page as a level of depth, so a page at
+    /// depth N that redirects twice lands at depth N + 2. Off by default: a redirect
+    /// target takes the depth of the link that led to it.
+    pub fn with_redirects_count_as_depth(mut self, enabled: bool) -> Self {
+        self.redirects_count_as_depth = enabled;
+        self
+    }
+    
+    /// Accept invalid TLS certificates, in the HTTP client and headless Chrome alike.
+    /// Only meant for internal sites with self-signed certificates; off by default.
+    pub fn with_danger_accept_invalid_certs(mut self, enabled: bool) -> Self {
+        if enabled {
+            warn!("TLS certificate validation is DISABLED: invalid and self-signed certificates will be accepted");
+        }
+        self.accept_invalid_certs = enabled;
+        self.rebuild_client();
+        self
+    }
+    
+    /// Use a fully configured HTTP client for pages, robots.txt and sitemaps, e.g. for
+    /// HTTP/2, custom DNS, pool sizing or TCP keepalive. The built-in client should be
+    /// matched where it matters:
+    ///
+    /// - `gzip(true)`, or compressed pages are stored compressed
+    /// - a redirect policy (the default follows 10), or redirects are returned as pages
+    /// - a request timeout (30 seconds by default), or a stalled server stalls a worker
+    /// - a user agent; robots.txt rules are matched against [`Crawler::with_robots_user_agent`] instead
+    ///
+    /// Redirect hops can only be counted by the built-in client's redirect policy, so with
+    /// a custom one every redirected page counts as a single hop. The client is kept as is by
+    /// `with_max_redirects` and `with_danger_accept_invalid_certs`, which only apply to the built-in one.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self.custom_client = true;
+        self.robots_manager = self.robots_manager.clone().with_client(self.client.clone());
+        self
+    }
+    
+    /// Rebuild the HTTP client after a setting it depends on changed
+    fn rebuild_client(&mut self) {
+        if self.custom_client {
+            warn!("Keeping the HTTP client passed to with_client; redirect and certificate settings are not applied to it");
+            return;
+        }
+        self.client = http_client(&self.user_agent, self.max_redirects, self.accept_invalid_certs, &self.redirect_hops);
+        self.robots_manager = self.robots_manager.clone().with_client(self.client.clone());
+    }
+    
+    /// Robots manager for a crawl of `task`, matching its robots token when it sets one
+    fn robots_manager_for(&self, task: &Task) -> RobotsManager {
+        match task.robots_user_agent.as_deref() {
+            Some(token) => self.robots_manager.clone().with_user_agent(token),
+            None => self.robots_manager.clone(),
+        }
+    }
+    
+    /// Set the politeness profile (see [`CrawlProfile`] for what each one uses)
+    pub fn with_profile(mut self, profile: CrawlProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+    
+    /// Set the thresholds used to detect crawler traps (see [`TrapGuard`] for the defaults)
+    pub fn with_trap_guard(mut self, guard: TrapGuard) -> Self {
+        self.trap_guard = guard;
+        self
+    }
+    
+    /// Fetch every URL listed in the site's sitemaps once, without following links
+    pub fn with_sitemap_only(mut self, enabled: bool) -> Self {
+        self.sitemap_only = enabled;
+        self
+    }
+    
+    /// Fetch only these URLs and store them under the task, without following links.
+    /// Used to give URLs an earlier crawl of the task gave up on another chance.
+    pub fn with_retry_urls(mut self, urls: Vec<Url>) -> Self {
+        self.retry_urls = Some(urls);
+        self
+    }
+    
+    /// Resume an interrupted crawl of the same task: pages already in the database for it
+    /// are not fetched again, and the links stored for them that weren't crawled yet are
+    /// queued instead. Needs a database (see [`Crawler::set_database`]).
+    pub fn with_skip_crawled(mut self, enabled: bool) -> Self {
+        self.skip_crawled = enabled;
+        self
+    }
+    
+    /// Gzip the JSONL dump of the crawl. `.gz` is appended to its path; `import` reads
+    /// either form.
+    pub fn with_compressed_output(mut self, enabled: bool) -> Self {
+        self.compress_output = enabled;
+        self
+    }
+    
+    /// Flag soft 404s: pages served with a 2xx status that are really the host's "not found"
+    /// page. The first page of each host triggers one extra request, for a random URL that
+    /// can't exist; see [`Soft404Detector`] for how pages are compared with its response.
+    /// Flagged pages are stored as usual, with `soft_404` set.
+    pub fn with_soft_404_detection(mut self, enabled: bool) -> Self {
+        self.soft_404_detection = enabled;
+        self
+    }
+    
+    /// Remember visited URLs by their 64-bit fingerprints, with room reserved for `expected`
+    /// URLs, instead of an exact set, for crawls of millions of pages where the set would take
+    /// gigabytes. The set grows past `expected`. See [`VisitedSet`].
+    pub fn with_fingerprint_visited(mut self, expected: usize) -> Self {
+        self.fingerprint_visited = Some(expected);
+        self
+    }
+    
+    /// Send a HEAD request before each download and skip resources whose type isn't
+    /// allowed or whose size exceeds the page size budget
+    pub fn with_head_precheck(mut self, enabled: bool) -> Self {
+        self.head_precheck = enabled;
+        self
+    }
+    
+    /// Set the size budget used by the HEAD pre-check and for saved assets (10 MiB by default)
+    pub fn with_max_page_size(mut self, bytes: u64) -> Self {
+        self.max_page_size = bytes;
+        self
+    }
+    
+    /// Stream resources of the asset content types (PDFs and images by default) to files in
+    /// this directory, named after a hash of their URL, instead of storing them in the database.
+    /// Assets are saved even if the task doesn't allow their content type; ones larger than
+    /// the page size budget are skipped.
+    pub fn with_asset_dir(mut self, dir: PathBuf) -> Self {
+        self.asset_dir = Some(dir);
+        self
+    }
+    
+    /// Set the content types saved to the asset directory, e.g. `application/pdf` or `image/*`
+    pub fn with_asset_content_types(mut self, content_types: Vec<String>) -> Self {
+        self.asset_content_types = content_types;
+        self
+    }
+    
+    /// Store the pages matching `route` in its sink instead of the database. Routes are tried
+    /// in the order they were added; pages matching none go to the database, if there is one.
+    /// Pages stored elsewhere have no `crawled_pages` row, so the links found on them aren't
+    /// recorded and `--resume` fetches them again. Route to a [`crate::storage::CompositeSink`]
+    /// that includes the database to keep both.
+    pub fn with_storage_route(mut self, route: StorageRoute) -> Self {
+        self.storage_routes.push(route);
+        self
+    }
+    
+    /// Also fetch pages that links lead to outside the task scope, one hop out.
+    /// They are stored with `is_external` set and their links are never followed.
+    pub fn with_external_hop(mut self, enabled: bool) -> Self {
+        self.external_hop = enabled;
+        self
+    }
+    
+    /// Set how many out-of-scope pages the external hop fetches (100 by default)
+    pub fn with_max_external_links(mut self, max: usize) -> Self {
+        self.max_external_links = max;
+        self
+    }
+    
+    /// Retry a URL that failed to load this many times instead of the profile's count
+    pub fn with_max_retries(mut self, retries: usize) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+    
+    /// Choose which JavaScript-dependent pages headless Chrome renders (only the seed by default)
+    pub fn with_render_policy(mut self, policy: RenderPolicy) -> Self {
+        self.render_policy = policy;
+        self
+    }
+    
+    /// Set how many indicators make a page JavaScript-dependent and how confident the
+    /// detection must be before the page is rendered (see [`JsDetection`] for the defaults)
+    pub fn with_js_detection(mut self, detection: JsDetection) -> Self {
+        self.js_detection = detection;
+        self
+    }
+    
+    /// Draw every page request from a budget shared with other crawls, on top of the
+    /// per-host limits of the profile
+    pub fn with_request_budget(mut self, budget: RequestBudget) -> Self {
+        self.request_budget = Some(budget);
+        self
+    }
+    
+    /// Choose the elements links are read from (anchors and `rel="next"` by default)
+    pub fn with_link_selectors(mut self, selectors: impl IntoIterator<Item = LinkSelector>) -> Self {
+        self.link_selectors = selectors.into_iter().collect();
+        self
+    }
+    
+    /// Also start from the URLs in this file, one per line. Blank lines and `#` comments are
+    /// ignored; invalid or out-of-scope URLs are logged and skipped.
+    pub fn with_seed_urls_file(mut self, path: PathBuf) -> Self {
+        self.seed_urls_file = Some(path);
+        self
+    }
+    
+    /// Set whether robots.txt is obeyed (strict by default)
+    pub fn with_robots_enforcement(mut self, enforcement: RobotsEnforcement) -> Self {
+        self.robots_enforcement = enforcement;
+        self
+    }
+    
+    /// Match robots.txt `User-agent` groups against this token ([`ROBOTS_PRODUCT_TOKEN`] by default).
+    /// Only rule matching uses it; requests keep sending the HTTP `User-Agent`. A task's own
+    /// `robots_user_agent` takes precedence.
+    pub fn with_robots_user_agent(mut self, token: &str) -> Self {
+        self.robots_manager = self.robots_manager.clone().with_user_agent(token);
+        self
+    }
+    
+    /// Ignore robots.txt for these domains and their subdomains only (see [`RobotsManager::with_override_domains`])
+    pub fn with_robots_override_domains(mut self, domains: HashSet<String>) -> Self {
+        self.robots_manager = self.robots_manager.clone().with_override_domains(domains);
+        self
+    }
+    
+    /// Queue at most this many sitemap URLs as seeds; sitemaps are only read as far as needed.
+    /// Sitemap-only crawls still queue every URL.
+    pub fn with_sitemap_seed_limit(mut self, limit: usize) -> Self {
+        self.sitemap_seed_limit = limit;
+        self
+    }
+    
+    /// Sitemap paths tried when robots.txt lists none (see [`RobotsManager::with_sitemap_locations`])
+    pub fn with_sitemap_locations(mut self, locations: Vec<String>) -> Self {
+        self.robots_manager = self.robots_manager.clone().with_sitemap_locations(locations);
+        self
+    }
+    
+    /// Report pages and bytes crawled so far through shared counters
+    pub fn with_progress(mut self, progress: CrawlProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+    
     /// Initialize headless browser (lazy initialization)
     async fn ensure_headless_browser(&mut self) -> Result<()> {
         if self.use_headless_chrome && self.headless_browser.is_none() {
             info!("Initializing headless Chrome browser");
-            let mut browser = HeadlessBrowser::new();
+            let mut browser = HeadlessBrowser::new()
+                .with_cookie_persistence(self.browser_cookies)
+                .with_ignore_certificate_errors(self.accept_invalid_certs);
             browser.start().await?;
             self.headless_browser = Some(Arc::new(browser));
         }
@@ -109,6 +1210,26 @@ impl Crawler {
         self.current_task.as_ref()
     }
     
+    /// Counters shared with whoever watches the crawl, set with `with_progress` or by the last crawl
+    pub fn progress(&self) -> Option<&CrawlProgress> {
+        self.progress.as_ref()
+    }
+    
+    /// URLs waiting to be fetched by the current or last crawl
+    pub fn queue_len(&self) -> usize {
+        self.progress.as_ref().map_or(0, CrawlProgress::queue_len)
+    }
+    
+    /// URLs queued or fetched by the current or last crawl
+    pub fn visited_count(&self) -> usize {
+        self.progress.as_ref().map_or(0, CrawlProgress::visited_count)
+    }
+    
+    /// Pages crawled by the current or last crawl
+    pub fn pages_crawled(&self) -> usize {
+        self.progress.as_ref().map_or(0, CrawlProgress::pages_crawled)
+    }
+    
     /// Set the current task
     pub fn set_task(&mut self, task: Task) {
         self.current_task = Some(task);
@@ -119,7 +1240,9 @@ impl Crawler {
         None // This is a placeholder - in a real implementation, this would track the current result
     }
     
-    /// Crawl a URL based on the provided task, streaming results to a JSONL file
+    /// Crawl a URL based on the provided task, streaming results to a JSONL file.
+    /// Everything logged during the crawl is inside a `crawl` span carrying the task ID.
+    #[tracing::instrument(name = "crawl", skip_all, fields(task_id = %task.id))]
     pub async fn crawl_with_streaming(&mut self, task: &Task, output_file: Option<File>) -> Result<CrawlResult> {
         // Create the result object
         let mut result = CrawlResult::new(&task.id, &task.target_url);
@@ -136,7 +1259,9 @@ impl Crawler {
         // Initialize headless browser if enabled
         if self.use_headless_chrome {
             info!("Initializing headless Chrome browser");
-            let mut browser = HeadlessBrowser::new();
+            let mut browser = HeadlessBrowser::new()
+                .with_cookie_persistence(self.browser_cookies)
+                .with_ignore_certificate_errors(self.accept_invalid_certs);
             match browser.start().await {
                 Ok(_) => {
                     info!("Headless Chrome browser initialized successfully");
@@ -167,112 +1292,166 @@ impl Crawler {
         };
         
         // Initialize robots.txt manager (from its builder pattern to actual object)
-        let mut robots_manager = self.robots_manager.clone();
-        
-        let mut visited = HashSet::new();
-        
-        // Check for sitemaps
-        info!("Checking for sitemaps at {}", base_domain);
-        let mut initial_urls = Vec::new();
-        initial_urls.push(initial_url.clone());
-        
-        // Add some well-known crates.io pages to ensure we have enough initial URLs
-        if base_domain == "crates.io" {
-            info!("Adding well-known crates.io URLs to the initial queue");
-            let known_paths = [
-                "/", 
-                "/crates", 
-                "/categories", 
-                "/keywords",
-                "/crates/tokio",
-                "/crates/serde",
-                "/crates/rand",
-                "/crates/reqwest",
-                "/crates/actix-web",
-                "/crates/chrono",
-                "/categories/asynchronous",
-                "/categories/web-programming"
-            ];
-            
-            for path in known_paths {
-                if let Ok(url) = Url::parse(&format!("https://crates.io{}", path)) {
-                    if !initial_urls.iter().any(|u| u.as_str() == url.as_str()) {
-                        info!("Added known URL: {}", url);
-                        initial_urls.push(url);
-                    }
+        let mut robots_manager = self.robots_manager_for(task);
+        
+        // Start from the target, well-known and sitemap URLs within the task's domain,
+        // or from every sitemap URL when links are not followed
+        let sitemap_only = self.sitemap_only;
+        let follow_links = !sitemap_only && self.retry_urls.is_none();
+        let initial_urls: Vec<Url> = if let Some(urls) = &self.retry_urls {
+            urls.clone()
+        } else if sitemap_only {
+            let urls = sitemap_seeds(&mut robots_manager, &base_domain).await;
+            result.sitemap_urls_found = Some(urls.len());
+            urls
+        } else {
+            seed_urls(&mut robots_manager, &initial_url, &base_domain, self.sitemap_seed_limit)
+                .await
+                .into_iter()
+                .map(|(url, _)| url)
+                .collect()
+        };
+        let mut initial_urls: Vec<Url> = initial_urls
+            .into_iter()
+            .filter(|url| in_scope(url, &base_domain, task.scope()))
+            .collect();
+        
+        // Add the curated seeds, if any, after the ones found for the target
+        if let Some(path) = &self.seed_urls_file {
+            let seeds = load_seed_urls(path)?;
+            let before = initial_urls.len();
+            for url in seeds {
+                if !in_scope(&url, &base_domain, task.scope()) {
+                    warn!("Skipping seed {}: outside the task's scope", url);
+                } else if !initial_urls.contains(&url) {
+                    initial_urls.push(url);
                 }
             }
+            info!("Queued {} seed URLs from {}", initial_urls.len() - before, path.display());
         }
         
-        match robots_manager.get_sitemap_urls(&base_domain).await {
-            Ok(sitemap_urls) if !sitemap_urls.is_empty() => {
-                info!("Found {} sitemaps for {}", sitemap_urls.len(), base_domain);
-                
-                // Add URLs from sitemaps to our initial queue to speed up the start
-                let mut added = 0;
-                for url_str in sitemap_urls {
-                    if added >= 100 { // Increased from 50 to 100
-                        break;
-                    }
+        // What to store for each page besides its metadata
+        let text_extraction = self.text_extraction;
+        let store_html = self.store_html;
+        
+        // Fetch the target's robots.txt once so every worker starts with it cached
+        let robots_enforcement = self.robots_enforcement;
+        if robots_enforcement == RobotsEnforcement::Strict {
+            if let Err(e) = robots_manager.is_allowed(&initial_url).await {
+                warn!("Failed to check robots.txt for {}: {}", initial_url, e);
+            }
+        } else {
+            warn!("robots.txt enforcement is off for {}", base_domain);
+        }
+        
+        // Track crawled pages count and size (shared with the caller if it asked for progress)
+        let progress = self.progress.get_or_insert_with(CrawlProgress::new).clone();
+        let pages_count = Arc::clone(&progress.pages);
+        let total_size = Arc::clone(&progress.bytes);
+        pages_count.store(0, Ordering::SeqCst);
+        total_size.store(0, Ordering::SeqCst);
+        
+        // Queues for BFS crawling with prioritization and the set of visited URLs,
+        // shared through the progress so they can be watched while the crawl runs
+        progress.state.clear();
+        let important_queue = Arc::clone(&progress.state.important_queue);
+        let regular_queue = Arc::clone(&progress.state.regular_queue);
+        let visited = Arc::clone(&progress.state.visited);
+        *visited.lock().unwrap() = match self.fingerprint_visited {
+            Some(expected) => VisitedSet::fingerprints(expected),
+            None => VisitedSet::default(),
+        };
+        visited.lock().unwrap().insert(initial_url.to_string());
+        
+        // Add initial URLs to the queues, marked visited so links to them aren't queued again
+        for url in initial_urls {
+            visited.lock().unwrap().insert(url.to_string());
+            important_queue.lock().unwrap().push_back(url.clone());
+        }
+        
+        if sitemap_only {
+            info!("Sitemap-only crawl of {}: {} URLs queued, links will not be followed",
+                  base_domain, important_queue.lock().unwrap().len());
+        } else if !follow_links {
+            info!("Retrying {} URLs of {}, links will not be followed",
+                  important_queue.lock().unwrap().len(), base_domain);
+        }
+        
+        // Track how far each URL is from the seeds, in links and in requests
+        let depth_map = Arc::new(Mutex::new(HashMap::new()));
+        depth_map.lock().unwrap().insert(initial_url.to_string(), Distance::default());
+        
+        // Resume from the pages stored for the task: mark them visited and queue the links
+        // found on them in their place, loaded in one query rather than per URL
+        if self.skip_crawled {
+            match self.db.as_ref().map(|db| db.get_crawled_urls(&task.id)) {
+                Some(Ok(crawled)) => {
+                    let mut visited = visited.lock().unwrap();
+                    visited.extend(crawled.iter().map(|(url, _, _)| url.clone()));
+                    important_queue.lock().unwrap().retain(|url| !visited.contains(url.as_str()));
                     
-                    if !visited.contains(&url_str) {
-                        visited.insert(url_str.clone());
-                        
-                        match Url::parse(&url_str) {
-                            Ok(url) => {
-                                if !initial_urls.iter().any(|u| u.as_str() == url.as_str()) {
-                                    initial_urls.push(url);
-                                    added += 1;
-                                }
-                            },
-                            Err(e) => {
-                                warn!("Failed to parse sitemap URL {}: {}", url_str, e);
+                    let mut regular_queue = regular_queue.lock().unwrap();
+                    let mut depth_map = depth_map.lock().unwrap();
+                    for (_, depth, links) in &crawled {
+                        let depth = depth.unwrap_or_default() as usize;
+                        let distance = Distance { links: depth, hops: depth }.next();
+                        for link in links {
+                            let Ok(url) = Url::parse(link) else {
+                                continue;
+                            };
+                            if in_scope(&url, &base_domain, task.scope()) && visited.insert(url.to_string()) {
+                                depth_map.insert(url.to_string(), distance);
+                                regular_queue.push_back(url);
                             }
                         }
                     }
+                    
+                    info!("Resuming task {}: skipping {} crawled pages, {} links queued from them",
+                          task.id, crawled.len(), regular_queue.len());
                 }
-                
-                info!("Added {} URLs from sitemaps to the initial queue", added);
-            },
-            Ok(urls) if urls.is_empty() => {
-                info!("No URLs found in sitemaps for {}", base_domain);
-            },
-            Ok(_) => {
-                info!("No sitemaps found for {}", base_domain);
-            },
-            Err(e) => {
-                warn!("Failed to get sitemaps for {}: {}", base_domain, e);
+                Some(Err(e)) => warn!("Failed to load the pages task {} already crawled: {}", task.id, e),
+                None => warn!("Cannot skip crawled pages of task {} without a database", task.id),
             }
         }
+        let redirect_hops = self.redirect_hops.clone();
+        let redirects_count_as_depth = self.redirects_count_as_depth;
         
-        // Create a queue for BFS crawling with prioritization
-        let important_queue = Arc::new(Mutex::new(VecDeque::new()));
-        let regular_queue = Arc::new(Mutex::new(VecDeque::new()));
+        // Skip oversized or unwanted resources before downloading them
+        let head_precheck = self.head_precheck;
         
-        // Add initial URLs to the queues
-        for url in initial_urls {
-            important_queue.lock().unwrap().push_back(url.clone());
-        }
+        // Compare pages with the not-found page of their host
+        let soft_404_detector = self.soft_404_detection
+            .then(|| Arc::new(Soft404Detector::new(self.client.clone(), task.accept_language())));
+        let max_page_size = self.max_page_size;
+        let bytes_saved = Arc::new(AtomicUsize::new(0));
         
-        // Create a set to track visited URLs
-        let visited = Arc::new(Mutex::new(HashSet::new()));
-        visited.lock().unwrap().insert(initial_url.to_string());
+        // Save assets to files rather than the database
+        let asset_dir = self.asset_dir.clone().map(Arc::new);
+        let asset_content_types = Arc::new(self.asset_content_types.clone());
         
-        // Track URL depths
-        let depth_map = Arc::new(Mutex::new(HashMap::new()));
-        depth_map.lock().unwrap().insert(initial_url.to_string(), 0);
+        // Track URLs per path prefix and query variants to avoid crawler traps
+        let traps = Arc::new(Mutex::new(TrapTracker::new(self.trap_guard)));
+        
+        // Collect out-of-scope links to fetch one hop out once the crawl is done
+        let external_hop = self.external_hop;
+        let external_links = Arc::new(Mutex::new(ExternalLinks::new(self.max_external_links)));
         
-        // Track crawled pages count
-        let pages_count = Arc::new(AtomicUsize::new(0));
-        let total_size = Arc::new(AtomicUsize::new(0));
+        // Stop every worker once the task's runtime limit has passed
+        let deadline = task.max_runtime_secs.map(|secs| start_time + std::time::Duration::from_secs(secs));
+        let time_limited = Arc::new(AtomicBool::new(false));
         
-        // Rate limiting delay (reduced from 200ms to 50ms)
-        let rate_limit_delay = std::time::Duration::from_millis(50);
+        // Worker count, delays and retries come from the crawl profile
+        let profile = self.profile;
+        let rate_limit_delay = profile.request_delay();
+        let num_workers = profile.workers();
+        let max_retries = self.max_retries.unwrap_or(profile.max_retries());
+        let retry_backoff = profile.retry_backoff();
         
-        // Determine how many workers to use
-        let num_workers = 10;
+        // Limit how many requests go to the same host at once
+        let per_host_concurrency = profile.per_host_concurrency();
+        let host_limits: Arc<Mutex<HashMap<String, Arc<Semaphore>>>> = Arc::new(Mutex::new(HashMap::new()));
         
-        info!("Starting {} parallel crawl workers", num_workers);
+        info!("Starting {} parallel crawl workers ({:?} profile)", num_workers, profile);
         
         // Create handles for all worker tasks
         let mut handles = Vec::new();
@@ -283,8 +1462,19 @@ impl Crawler {
         // Create shared database reference if available
         let db = self.db.as_ref().map(|db| Arc::new(db.clone()));
         
+        // Pick the sink of each page: a matching storage route, or else the database
+        let storage = Arc::new(StorageRouter::new(
+            self.storage_routes.clone(),
+            db.clone().map(|db| db as Arc<dyn StorageSink>),
+        ));
+        
         // Create shared headless browser if available
         let use_headless_chrome = self.use_headless_chrome;
+        let render_policy = Arc::new(self.render_policy.clone());
+        let js_detection = self.js_detection;
+        let link_selectors: Arc<[LinkSelector]> = self.link_selectors.clone().into();
+        let request_budget = self.request_budget.clone();
+        let rendered_pages = Arc::new(AtomicUsize::new(0));
         
         // Get the path of the output file if provided
         let output_path = if let Some(_) = output_file {
@@ -330,18 +1520,44 @@ impl Crawler {
             Some(path)
         };
         
+        // Open the dump once; every worker appends to the same buffered writer
+        let output_path = output_path.map(|path| if self.compress_output {
+            gzip_path(path).to_string_lossy().into_owned()
+        } else {
+            path
+        });
+        let output: Option<Arc<JsonlWriter>> = output_path.as_ref().and_then(|path| {
+            match JsonlWriter::create(path, self.compress_output) {
+                Ok(writer) => Some(Arc::new(writer)),
+                Err(e) => {
+                    warn!("Failed to open output file at {}: {}", path, e);
+                    None
+                }
+            }
+        });
+        
         // Initialize a shared headless browser
         let shared_browser = if self.use_headless_chrome {
             info!("Initializing headless Chrome browser for workers");
             
             // Create a new headless browser instance
-            let mut browser = HeadlessBrowser::new();
+            let mut browser = HeadlessBrowser::new()
+                .with_cookie_persistence(self.browser_cookies)
+                .with_ignore_certificate_errors(self.accept_invalid_certs);
             
             // Start the browser
             match browser.start().await {
                 Ok(()) => {
                     // Browser successfully started
                     info!("Successfully initialized headless Chrome browser");
+                    if let Some(login) = &task.login {
+                        match Url::parse(&login.url) {
+                            Ok(login_url) => if let Err(e) = browser.login(&login_url, &login.selectors, &login.credentials).await {
+                                warn!("Login at {} failed: {}. Gated pages will not be authenticated.", login.url, e);
+                            },
+                            Err(e) => warn!("Invalid login URL {}: {}", login.url, e),
+                        }
+                    }
                     // Store the browser in the crawler wrapped in Arc
                     let browser_arc = Arc::new(browser);
                     self.headless_browser = Some(browser_arc.clone());
@@ -354,9 +1570,15 @@ impl Crawler {
                 }
             }
         } else {
+            if task.login.is_some() {
+                warn!("Task {} has a login but headless Chrome is disabled, so it will be ignored", task.id);
+            }
             None
         };
         
+        // A logged-in session lives in the browser, so every page goes through it
+        let authenticated = shared_browser.is_some() && task.login.is_some();
+        
         // Clone the task for workers
         let task = task.clone();
         
@@ -367,21 +1589,39 @@ impl Crawler {
             let regular_queue = Arc::clone(&regular_queue);
             let visited = Arc::clone(&visited);
             let depth_map = Arc::clone(&depth_map);
+            let redirect_hops = redirect_hops.clone();
+            let traps = Arc::clone(&traps);
+            let external_links = Arc::clone(&external_links);
+            let bytes_saved = Arc::clone(&bytes_saved);
+            let progress = progress.clone();
             let pages_count = Arc::clone(&pages_count);
             let total_size = Arc::clone(&total_size);
             let client = Arc::clone(&client);
             // Create a fresh copy of robots manager for each worker
             let mut worker_robots_manager = robots_manager.clone();
-            let output_path = output_path.clone();
+            let output = output.clone();
             let task = task.clone();
             let domain = base_domain.clone();
             let db = db.clone();
+            let storage = Arc::clone(&storage);
             let use_headless_chrome = use_headless_chrome;
+            let render_policy = Arc::clone(&render_policy);
+            let link_selectors = Arc::clone(&link_selectors);
+            let request_budget = request_budget.clone();
+            let soft_404_detector = soft_404_detector.clone();
+            let asset_dir = asset_dir.clone();
+            let asset_content_types = Arc::clone(&asset_content_types);
+            let rendered_pages = Arc::clone(&rendered_pages);
             let shared_browser = shared_browser.clone();
+            let authenticated = authenticated;
+            let host_limits = Arc::clone(&host_limits);
+            let time_limited = Arc::clone(&time_limited);
             
             // Spawn the worker task
             let handle = tokio::spawn(async move {
                 info!("Worker {} started", worker_id);
+                let _active = METRICS.track_worker();
+                let _running = progress.track_worker();
                 
                 // Small delay to stagger worker startup and reduce contention
                 tokio::time::sleep(std::time::Duration::from_millis(worker_id as u64 * 100)).await;
@@ -389,28 +1629,29 @@ impl Crawler {
                 // Worker-local URL buffer to reduce contention
                 let mut local_urls_to_process = Vec::with_capacity(10);
                 let mut pages_processed = 0;
-                let mut retry_queue = VecDeque::<(Url, usize)>::new();
+                let mut retry_queue = RetryQueue::new(max_retries, retry_backoff);
                 
                 loop {
+                    // Check if the crawl has run out of time
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        if !time_limited.swap(true, Ordering::SeqCst) {
+                            warn!("Runtime limit of {}s reached for task {}, stopping the crawl",
+                                  task.max_runtime_secs.unwrap_or_default(), task.id);
+                        }
+                        break;
+                    }
+                    
                     // Check if we've reached the maximum number of pages
                     if pages_count.load(Ordering::SeqCst) >= task.max_links.unwrap_or(1000) {
                         info!("Worker {} stopping: reached maximum pages limit ({})", worker_id, task.max_links.unwrap_or(1000));
                         break;
                     }
                     
-                    // First, check retry queue for URLs that previously failed
-                    if !retry_queue.is_empty() {
-                        if let Some((url, retries)) = retry_queue.pop_front() {
-                            if retries < 3 { // Allow up to 3 retries
-                                info!("Worker {} retrying URL (attempt {}/3): {}", worker_id, retries + 1, url);
-                                local_urls_to_process.push(url.clone());
-                                retry_queue.push_back((url, retries + 1));
-                            }
-                        }
-                    }
+                    // Failed URLs whose backoff has passed go before new ones
+                    let retry = retry_queue.next_ready();
                     
                     // If our local buffer is empty, refill it
-                    if local_urls_to_process.is_empty() {
+                    if retry.is_none() && local_urls_to_process.is_empty() {
                         // Try to get URLs from the important queue first, then from the regular queue
                         {
                             let mut important = important_queue.lock().unwrap();
@@ -457,19 +1698,26 @@ impl Crawler {
                         }
                     }
                     
-                    // Process the next URL from our local buffer
-                    let current_url = match local_urls_to_process.pop() {
-                        Some(url) => url,
-                        None => continue, // This shouldn't happen, but just in case
+                    // Process the next URL from our local buffer, counting attempts for retries
+                    let (current_url, attempt) = match retry {
+                        Some((url, attempts)) => {
+                            info!("Worker {} retrying {} (attempt {}/{})", worker_id, url, attempts + 1, max_retries + 1);
+                            (url, attempts + 1)
+                        }
+                        None => match local_urls_to_process.pop() {
+                            Some(url) => (url, 1),
+                            None => continue, // This shouldn't happen, but just in case
+                        },
                     };
                     
                     let current_url_str = current_url.to_string();
                     
                     // Get the depth for this URL
-                    let current_depth = {
-                        let depth_map_guard = depth_map.lock().unwrap();
-                        *depth_map_guard.get(&current_url_str).unwrap_or(&0)
-                    };
+                    let distance = depth_map.lock().unwrap()
+                        .get(&current_url_str)
+                        .copied()
+                        .unwrap_or_default();
+                    let current_depth = distance.depth(redirects_count_as_depth);
                     
                     // Check if we've reached the maximum depth
                     if current_depth >= task.max_depth as usize {
@@ -478,23 +1726,17 @@ impl Crawler {
                     
                     debug!("Worker {} crawling {} (depth {})", worker_id, current_url_str, current_depth);
                     
-                    // Skip robots.txt check for same domain if we've already checked it before
-                    // This avoids redundant network requests
-                    let domain_str = current_url.host_str().unwrap_or("unknown");
-                    let _robots_cache_key = format!("{}:{}", domain_str, worker_id % 3); // Simple sharding by worker ID
-                    
-                    // Check robots.txt restrictions
-                    let allowed = if worker_id % 3 == 0 { // Only 1/3 of workers check robots.txt to reduce overhead
-                        match worker_robots_manager.is_allowed(&current_url).await {
+                    // Check robots.txt restrictions (cached per domain, so this rarely hits the network)
+                    let allowed = match robots_enforcement {
+                        RobotsEnforcement::Strict => match worker_robots_manager.is_allowed(&current_url).await {
                             Ok(allowed) => allowed,
                             Err(e) => {
                                 warn!("Failed to check robots.txt for {}: {}", current_url_str, e);
                                 // Continue anyway in case of robots.txt error
                                 true
                             }
-                        }
-                    } else {
-                        true // Other workers skip the check to improve throughput
+                        },
+                        RobotsEnforcement::Off => true,
                     };
                     
                     if !allowed {
@@ -502,18 +1744,53 @@ impl Crawler {
                         continue;
                     }
                     
-                    // Add rate limiting delay (reduced to improve throughput)
+                    // Wait for a free slot for this host, held until the page has been processed
+                    let host_limit = {
+                        let mut limits = host_limits.lock().unwrap();
+                        let host = current_url.host_str().unwrap_or_default().to_string();
+                        Arc::clone(limits.entry(host)
+                            .or_insert_with(|| Arc::new(Semaphore::new(per_host_concurrency))))
+                    };
+                    let _host_permit = host_limit.acquire_owned().await;
+                    
+                    // Add rate limiting delay
                     tokio::time::sleep(rate_limit_delay).await;
                     
+                    // Check type and size before downloading the body
+                    if head_precheck {
+                        if let Some(budget) = &request_budget {
+                            budget.acquire().await;
+                        }
+                        if let Some(info) = probe_resource(&client, &current_url).await {
+                            let skip_reason = if !task.allows_content_type(info.content_type.as_deref()) {
+                                Some(format!("{} content", info.content_type.as_deref().unwrap_or("unknown")))
+                            } else {
+                                match info.length {
+                                    Some(length) if length > max_page_size => Some(format!("{} bytes", length)),
+                                    _ => None,
+                                }
+                            };
+                            
+                            if let Some(reason) = skip_reason {
+                                debug!("Skipping {} after HEAD pre-check: {}", current_url_str, reason);
+                                bytes_saved.fetch_add(info.length.unwrap_or(0) as usize, Ordering::SeqCst);
+                                continue;
+                            }
+                        }
+                    }
+                    
                     // Fetch the page
-                    let response = match client.get(current_url.clone())
-                        .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8")
-                        .header(reqwest::header::ACCEPT_LANGUAGE, "en-US,en;q=0.5")
+                    if let Some(budget) = &request_budget {
+                        budget.acquire().await;
+                    }
+                    let response = match page_request(&client, &current_url, task.accept_language())
                         .send()
                         .await {
                         Ok(resp) => resp,
                         Err(e) => {
-                            warn!("Failed to fetch {}: {}", current_url_str, e);
+                            if retry_or_give_up(&mut retry_queue, db.as_deref(), &task.id, &current_url, attempt, &e.to_string()) {
+                                continue;
+                            }
                             // Create a crawled page with error information
                             let page = CrawledPage {
                                 url: current_url_str.clone(),
@@ -525,32 +1802,22 @@ impl Crawler {
                                 content_type: None,
                                 status_code: None,
                                 body: None,
+                                is_external: false,
                             };
                             
                             // Update counters
                             pages_count.fetch_add(1, Ordering::SeqCst);
+                            progress.record_page(&page);
                             
                             // Stream the page to the output file if provided
-                            if let Some(ref path) = output_path {
-                                if let Ok(json) = serde_json::to_string(&page) {
-                                    // Append to the output file
-                                    if let Ok(mut file) = std::fs::OpenOptions::new()
-                                        .create(true)
-                                        .append(true)
-                                        .open(path) {
-                                        if writeln!(file, "{}", json).is_err() {
-                                            warn!("Failed to write to output file");
-                                        } else {
-                                            debug!("Successfully wrote page to {}", path);
-                                        }
-                                    } else {
-                                        warn!("Failed to open output file at {}", path);
-                                    }
+                            if let Some(output) = &output {
+                                if let Err(e) = output.write_page(&page) {
+                                    warn!("Failed to write to output file: {}", e);
                                 }
                             }
                             
-                            // Store the complete page in the database (not just stats)
-                            if let Some(db) = &db {
+                            // Store the complete page (not just stats)
+                            if let Some(sink) = storage.sink_for(page.content_type.as_deref(), page.size as u64) {
                                 // Get the HTML content
                                 let html_content = match &page.body {
                                     Some(content) => content.clone(),
@@ -558,21 +1825,26 @@ impl Crawler {
                                 };
                                 
                                 // Detect if page is JavaScript dependent
-                                let (is_js_dependent, js_reasons) = is_javascript_dependent(&html_content);
+                                let JsAssessment { is_dependent: is_js_dependent, reasons: js_reasons, .. } =
+                                    assess_javascript_dependence(&html_content, js_detection.min_reasons);
                                 
-                                // Add to crawled_pages table
-                                if let Err(e) = db.save_crawled_page(
-                                    &task.id,
-                                    &page.url,
-                                    &domain.to_string(),
-                                    page.status_code.unwrap_or(0) as i32,
-                                    page.content_type.as_deref(),
-                                    page.size as i64,
-                                    page.body.as_deref(),
-                                    is_js_dependent,
-                                    if js_reasons.is_empty() { None } else { Some(js_reasons.join(", ")) }
-                                ) {
-                                    warn!("Failed to store crawled page in database: {}", e);
+                                if let Err(e) = sink.store(&PageRecord {
+                                    task_id: task.id.clone(),
+                                    url: page.url.clone(),
+                                    requested_url: Some(current_url_str.clone()),
+                                    domain: domain.to_string(),
+                                    status: page.status_code.unwrap_or(0) as i32,
+                                    content_type: page.content_type.clone(),
+                                    size: page.size as i64,
+                                    html: page.body.clone().filter(|_| store_html),
+                                    text_content: text_extraction.then(|| extract_text(&html_content)),
+                                    is_javascript_dependent: is_js_dependent,
+                                    javascript_dependency_reasons: if js_reasons.is_empty() { None } else { Some(js_reasons.join(", ")) },
+                                    depth: current_depth as u32,
+                                    hops: distance.hops as u32,
+                                    ..PageRecord::default()
+                                }) {
+                                    warn!("Failed to store crawled page: {}", e);
                                 }
                             }
                             
@@ -597,51 +1869,164 @@ impl Crawler {
                         continue;
                     }
                     
+                    // Server errors are usually temporary, so fetch the page again before storing the error
+                    if status.is_server_error()
+                        && retry_or_give_up(&mut retry_queue, db.as_deref(), &task.id, &current_url, attempt, &format!("HTTP {}", status)) {
+                        continue;
+                    }
+                    
+                    // Follow the page's identity through redirects: it is stored and its links
+                    // resolved under the final URL, which is only crawled once
+                    let requested_url_str = current_url_str;
+                    let mut final_url = response.url().clone();
+                    final_url.set_fragment(None);
+                    let redirected = final_url != current_url;
+                    if redirected {
+                        debug!("{} redirected to {}", requested_url_str, final_url);
+                        
+                        if !in_scope(&final_url, &domain, task.scope()) {
+                            debug!("Skipping {}: redirected outside the task's domain to {}", requested_url_str, final_url);
+                            continue;
+                        }
+                        // A retry finds the target marked visited by its own earlier attempt
+                        if !visited.lock().unwrap().insert(final_url.to_string()) && attempt == 1 {
+                            debug!("Skipping {}: redirect target {} was already visited", requested_url_str, final_url);
+                            continue;
+                        }
+                    }
+                    let current_url = final_url;
+                    let current_url_str = current_url.to_string();
+                    
+                    // Each redirect is a hop of its own, which may push the page past the depth limit
+                    let distance = if redirected {
+                        distance.redirected(redirect_hops.take(&current_url))
+                    } else {
+                        distance
+                    };
+                    let current_depth = distance.depth(redirects_count_as_depth);
+                    if current_depth >= task.max_depth as usize {
+                        debug!("Skipping {}: {} hops away after redirects, past the depth limit", current_url_str, distance.hops);
+                        continue;
+                    }
+                    
                     let content_type = response.headers()
                         .get(reqwest::header::CONTENT_TYPE)
                         .and_then(|h| h.to_str().ok())
                         .map(|s| s.to_string());
                     
-                    // Skip non-HTML content
-                    if let Some(ct) = &content_type {
-                        if !ct.contains("text/html") && !ct.contains("application/xhtml+xml") {
-                            debug!("Skipping non-HTML content: {}", current_url_str);
-                            continue;
+                    let is_html = match content_type.as_deref() {
+                        Some(ct) => is_html_content_type(ct),
+                        None => true,
+                    };
+                    let asset_target = asset_dir.as_deref().filter(|_| {
+                        let media_type = content_type.as_deref().map(media_type).unwrap_or_default();
+                        !is_html && asset_content_types.iter().any(|allowed| content_type_matches(allowed, &media_type))
+                    });
+                    
+                    // Skip content types the task doesn't ask for, unless they are saved as assets
+                    if asset_target.is_none() && !task.allows_content_type(content_type.as_deref()) {
+                        debug!("Skipping {} content: {}", content_type.as_deref().unwrap_or("unknown"), current_url_str);
+                        continue;
+                    }
+                    
+                    // Store other allowed content (JSON, PDF, ...) as is, or save it to a file,
+                    // without looking for links
+                    if !is_html {
+                        let (page, asset_path) = if let Some(asset_dir) = asset_target {
+                            let asset = match save_asset(response, &current_url, content_type.as_deref(), asset_dir, max_page_size).await {
+                                Ok(Some(asset)) => asset,
+                                Ok(None) => {
+                                    debug!("Skipping asset {}: larger than {} bytes", current_url_str, max_page_size);
+                                    continue;
+                                }
+                                Err(e) => {
+                                    retry_or_give_up(&mut retry_queue, db.as_deref(), &task.id, &current_url, attempt, &e.to_string());
+                                    continue;
+                                }
+                            };
+                            
+                            let mut page = non_html_page(&current_url_str, content_type, status.as_u16(), &[]);
+                            page.size = asset.size;
+                            page.body = None;
+                            (page, Some(asset.path))
+                        } else {
+                            let bytes = match response.bytes().await {
+                                Ok(bytes) => bytes,
+                                Err(e) => {
+                                    retry_or_give_up(&mut retry_queue, db.as_deref(), &task.id, &current_url, attempt, &e.to_string());
+                                    continue;
+                                }
+                            };
+                            
+                            (non_html_page(&current_url_str, content_type, status.as_u16(), &bytes), None)
+                        };
+                        pages_count.fetch_add(1, Ordering::SeqCst);
+                        total_size.fetch_add(page.size, Ordering::SeqCst);
+                        progress.record_page(&page);
+                        
+                        if let Some(output) = &output {
+                            if let Err(e) = output.write_page(&page) {
+                                warn!("Failed to write to output file: {}", e);
+                            }
+                        }
+                        
+                        if let Some(sink) = storage.sink_for(page.content_type.as_deref(), page.size as u64) {
+                            let stored = sink.store(&PageRecord {
+                                task_id: task.id.clone(),
+                                url: page.url.clone(),
+                                requested_url: Some(requested_url_str.clone()),
+                                domain: domain.clone(),
+                                status: page.status_code.unwrap_or(0) as i32,
+                                content_type: page.content_type.clone(),
+                                size: page.size as i64,
+                                text_content: page.body.clone().filter(|_| text_extraction),
+                                depth: current_depth as u32,
+                                hops: distance.hops as u32,
+                                ..PageRecord::default()
+                            });
+                            if let Err(e) = stored {
+                                warn!("Failed to store crawled page: {}", e);
+                            } else if let (Some(db), Some(asset_path)) = (&db, &asset_path) {
+                                if let Err(e) = db.update_crawled_page_asset(&task.id, &page.url, &asset_path.to_string_lossy()) {
+                                    warn!("Failed to record asset path in database: {}", e);
+                                }
+                            }
                         }
+                        
+                        continue;
                     }
                     
-                    // Get the HTML content
-                    let body = match response.text().await {
-                        Ok(html) => {
+                    // Get the HTML content, decoded with the charset the page declares
+                    let (body, charset) = match response.bytes().await {
+                        Ok(bytes) => {
+                            let (html, charset) = decode_body(&bytes, content_type.as_deref());
+                            
                             // Detect if the site is JavaScript-dependent
-                            let (is_js_dependent, js_reasons) = is_javascript_dependent(&html);
+                            let js_assessment = assess_javascript_dependence(&html, js_detection.min_reasons);
                             
                             let mut content = html.clone();
                             let domain_str = current_url.host_str().unwrap_or("unknown");
                             
-                            // Check if page is an important page that needs JavaScript processing
-                            let needs_js_processing = is_js_dependent && 
-                                (current_url_str.contains("/crates/") || 
-                                 current_url_str.contains("/keywords/") ||
-                                 current_url_str.contains("/categories/") ||
-                                 current_url_str.contains("/docs/") ||
-                                 current_depth <= 1); // Process JS for root pages and first level
+                            // Check if the detection is confident enough and the render policy picks this page
+                            let needs_js_processing = authenticated || (js_detection.warrants_render(&js_assessment) &&
+                                render_policy.should_render(&current_url, current_depth));
                             
                             if needs_js_processing && use_headless_chrome {
-                                info!("Detected JavaScript-dependent site: {} - Reasons: {:?}", domain_str, js_reasons);
+                                info!("Detected JavaScript-dependent site: {} - Score {:.2}, reasons: {:?}", domain_str, js_assessment.score, js_assessment.reasons);
                                 
                                 // Try to use the shared browser if it's available
                                 if let Some(shared) = &shared_browser {
                                     info!("Worker {} using shared headless browser for {}", worker_id, current_url_str);
                                     
                                     // Extract content using headless browser
-                                    let rendered_content = HeadlessBrowser::extract_content(shared.clone(), &current_url, 3).await;
+                                    let rendered_content = HeadlessBrowser::extract_content(shared.clone(), &current_url, 3, task.accept_language.as_deref()).await;
                                     
                                     // Process the content result
                                     match rendered_content {
                                         Ok(content_result) => {
                                             info!("Successfully extracted rendered content using headless Chrome for {}", current_url_str);
                                             content = content_result;
+                                            rendered_pages.fetch_add(1, Ordering::SeqCst);
                                         },
                                         Err(e) => {
                                             warn!("Failed to extract content with headless Chrome: {}. Falling back to regular content.", e);
@@ -650,7 +2035,7 @@ impl Crawler {
                                     }
                                     
                                     // Extract links using headless browser
-                                    let js_links_result = HeadlessBrowser::extract_links(shared.clone(), &current_url, 3).await;
+                                    let js_links_result = HeadlessBrowser::extract_links(shared.clone(), &current_url, 3, task.accept_language.as_deref()).await;
                                     
                                     // Process the extracted links
                                     match js_links_result {
@@ -658,13 +2043,14 @@ impl Crawler {
                                             info!("Successfully extracted {} links using headless Chrome for {}", js_links.len(), current_url_str);
                                             
                                             // Process the links extracted by headless Chrome
-                                            if current_depth < task.max_depth as usize - 1 {
+                                            if follow_links && current_depth < task.max_depth as usize - 1 {
                                                 info!("Processing {} links from headless Chrome", js_links.len());
                                                 
                                                 let mut visited_guard = visited.lock().unwrap();
                                                 let mut depth_map_guard = depth_map.lock().unwrap();
                                                 let mut important_guard = important_queue.lock().unwrap();
                                                 let mut regular_guard = regular_queue.lock().unwrap();
+                                                let mut traps_guard = traps.lock().unwrap();
                                                 
                                                 for link in js_links {
                                                     let _link_str = link.to_string();
@@ -681,12 +2067,12 @@ impl Crawler {
                                                     }
                                                     
                                                     // Check if we should follow this URL
-                                                    let should_follow = is_same_domain(&normalized_link, &domain, task.follow_subdomains);
+                                                    let should_follow = in_scope(&normalized_link, &domain, task.scope());
                                                     
-                                                    if should_follow {
+                                                    if should_follow && traps_guard.allow(&normalized_link) {
                                                         // Check robots.txt - done outside the mutex lock later
                                                         visited_guard.insert(normalized_link_str.clone());
-                                                        depth_map_guard.insert(normalized_link_str.clone(), current_depth + 1);
+                                                        depth_map_guard.insert(normalized_link_str.clone(), distance.next());
                                                         
                                                         // Prioritize important URLs
                                                         let has_important_patterns = normalized_link_str.contains("/docs/") || 
@@ -699,6 +2085,8 @@ impl Crawler {
                                                         } else {
                                                             regular_guard.push_back(normalized_link);
                                                         }
+                                                    } else if !should_follow && external_hop {
+                                                        external_links.lock().unwrap().offer(&normalized_link);
                                                     }
                                                 }
                                             }
@@ -710,17 +2098,14 @@ impl Crawler {
                                 } else {
                                     warn!("Worker {} has no shared browser. Continuing with regular content for {}", worker_id, current_url_str);
                                 }
-                            } else if is_js_dependent {
-                                debug!("Skipping headless Chrome for less important JS page: {}", current_url_str);
+                            } else if js_assessment.is_dependent {
+                                debug!("Render policy skips headless Chrome for JS page: {}", current_url_str);
                             }
                             
-                            content
+                            (content, charset)
                         },
                         Err(e) => {
-                            warn!("Failed to get text from response: {}. Adding URL to retry queue.", e);
-                            
-                            // Add to retry queue if not already retried too many times
-                            retry_queue.push_back((current_url.clone(), 1));
+                            retry_or_give_up(&mut retry_queue, db.as_deref(), &task.id, &current_url, attempt, &e.to_string());
                             
                             // Skip the rest of processing for this URL
                             continue;
@@ -738,11 +2123,13 @@ impl Crawler {
                         content_type,
                         status_code: Some(status.as_u16()),
                         body: Some(body.clone()),
+                        is_external: false,
                     };
                     
                     // Update counters
                     pages_count.fetch_add(1, Ordering::SeqCst);
                     total_size.fetch_add(page.size, Ordering::SeqCst);
+                    progress.record_page(&page);
                     
                     // Log progress every 20 pages per worker (reduced logging frequency)
                     pages_processed += 1;
@@ -751,75 +2138,86 @@ impl Crawler {
                         info!("Worker {} - Processed {} pages (Total: {})", worker_id, pages_processed, current_count);
                     }
                     
-                    // Stream the page to the output file if provided - do this in parallel
-                    if let Some(path) = &output_path {
-                        let json_result = serde_json::to_string(&page);
-                        match json_result {
-                            Ok(json) => {
-                                // Use a separate task for file I/O to avoid blocking
-                                let path_clone = path.to_string();
-                                tokio::spawn(async move {
-                                    if let Ok(mut file) = std::fs::OpenOptions::new()
-                                        .create(true)
-                                        .append(true)
-                                        .open(&path_clone) {
-                                        if writeln!(file, "{}", json).is_err() {
-                                            warn!("Failed to write to output file");
-                                        }
-                                    } else {
-                                        warn!("Failed to open output file at {}", path_clone);
-                                    }
-                                });
-                            },
-                            Err(_) => warn!("Failed to serialize page to JSON")
+                    // Stream the page to the output file if provided; the write only
+                    // reaches the file when the buffer fills
+                    if let Some(output) = &output {
+                        if let Err(e) = output.write_page(&page) {
+                            warn!("Failed to write to output file: {}", e);
                         }
                     }
                     
-                    // Store page in database in a non-blocking way
-                    if let Some(db) = &db {
+                    // Store the page in a non-blocking way
+                    if let Some(sink) = storage.sink_for(page.content_type.as_deref(), page.size as u64) {
                         // Get the HTML content
                         let html_content = match &page.body {
                             Some(content) => content.clone(),
                             None => String::new(),
                         };
                         
-                        // Clone what we need for the database task
-                        let db_clone = db.clone();
+                        // Successful pages may still be the host's "not found" page
+                        let soft_404 = match &soft_404_detector {
+                            Some(detector) if status.is_success() => detector.is_soft_404(&current_url, &html_content).await,
+                            _ => false,
+                        };
+                        if soft_404 {
+                            debug!("{} looks like a soft 404", current_url_str);
+                        }
+                        
+                        // Clone what we need for the storage task
+                        let sink = Arc::clone(sink);
                         let task_id = task.id.clone();
                         let url = page.url.clone();
+                        let requested_url = requested_url_str.clone();
                         let domain_clone = domain.clone();
                         let status_code = page.status_code.unwrap_or(0) as i32;
                         let content_type_clone = page.content_type.clone();
                         let size = page.size as i64;
                         
-                        // Detect JS dependency outside the database task
-                        let (is_js_dependent, js_reasons) = is_javascript_dependent(&html_content);
+                        // Detect JS dependency outside the storage task
+                        let JsAssessment { is_dependent: is_js_dependent, reasons: js_reasons, .. } =
+                            assess_javascript_dependence(&html_content, js_detection.min_reasons);
                         let js_reasons_str = if js_reasons.is_empty() { 
                             None 
                         } else { 
                             Some(js_reasons.join(", ")) 
                         };
                         
-                        // Spawn a separate task for database operations
+                        // Spawn a separate task for storage operations
                         tokio::spawn(async move {
-                            if let Err(e) = db_clone.save_crawled_page(
-                                &task_id,
-                                &url,
-                                &domain_clone,
-                                status_code,
-                                content_type_clone.as_deref(),
+                            // Extract the text here to keep the worker free for fetching
+                            let text_content = text_extraction.then(|| extract_text(&html_content));
+                            let metadata = extract_metadata(&html_content);
+                            
+                            if let Err(e) = sink.store(&PageRecord {
+                                task_id,
+                                url,
+                                requested_url: Some(requested_url),
+                                domain: domain_clone,
+                                status: status_code,
+                                content_type: content_type_clone,
                                 size,
-                                None, // Don't store the full HTML in DB to save space
-                                is_js_dependent,
-                                js_reasons_str
-                            ) {
-                                warn!("Failed to store crawled page in database: {}", e);
+                                html: store_html.then_some(html_content),
+                                text_content,
+                                charset: Some(charset.to_string()),
+                                metadata: Some(metadata),
+                                is_javascript_dependent: is_js_dependent,
+                                javascript_dependency_reasons: js_reasons_str,
+                                depth: current_depth as u32,
+                                hops: distance.hops as u32,
+                                soft_404,
+                            }) {
+                                warn!("Failed to store crawled page: {}", e);
                             }
-                        });
+                        }.in_current_span());
+                    }
+                    
+                    // Sitemap-only crawls and retries never follow links
+                    if !follow_links {
+                        continue;
                     }
                     
                     // Extract links
-                    let extracted_links = match Self::extract_links_from_html(&body, &current_url).await {
+                    let extracted_links = match Self::extract_links_from_html(&body, &current_url, &link_selectors).await {
                         Ok(links) => {
                             if links.len() > 0 {
                                 debug!("Worker {} found {} links to process in {}", worker_id, links.len(), current_url_str);
@@ -840,10 +2238,12 @@ impl Crawler {
                                 let normalized_link_str = normalized_link.to_string();
                                 
                                 // Check if we should follow this URL (without holding locks)
-                                let should_follow = is_same_domain(&normalized_link, &domain, task.follow_subdomains);
+                                let should_follow = in_scope(&normalized_link, &domain, task.scope());
                                 
                                 if should_follow {
                                     new_links.push((normalized_link, normalized_link_str));
+                                } else if external_hop {
+                                    external_links.lock().unwrap().offer(&normalized_link);
                                 }
                             }
                             
@@ -852,8 +2252,10 @@ impl Crawler {
                                 // Step 1: Check which links are already visited (with minimal lock time)
                                 let unvisited_links = {
                                     let visited_guard = visited.lock().unwrap();
+                                    let mut traps_guard = traps.lock().unwrap();
                                     new_links.into_iter()
                                         .filter(|(_, link_str)| !visited_guard.contains(link_str))
+                                        .filter(|(link, _)| traps_guard.allow(link))
                                         .collect::<Vec<_>>()
                                 };
                                 
@@ -864,7 +2266,7 @@ impl Crawler {
                                     
                                     for (_, link_str) in &unvisited_links {
                                         visited_guard.insert(link_str.clone());
-                                        depth_map_guard.insert(link_str.clone(), current_depth + 1);
+                                        depth_map_guard.insert(link_str.clone(), distance.next());
                                     }
                                 } // Release locks before categorizing
                                 
@@ -923,7 +2325,7 @@ impl Crawler {
                     // Store the extracted links in the database
                     if !extracted_links.is_empty() && extracted_links.len() > 0 {
                         if let Some(db) = &db {
-                            if let Err(e) = db.update_crawled_page_links(&page.url, &extracted_links) {
+                            if let Err(e) = db.update_crawled_page_links(&task.id, &page.url, &extracted_links) {
                                 warn!("Failed to update links for page in database: {}", e);
                             }
                         }
@@ -932,7 +2334,7 @@ impl Crawler {
                 
                 info!("Worker {} finished", worker_id);
                 Ok::<_, anyhow::Error>(())
-            });
+            }.instrument(info_span!("worker", worker_id)));
             
             handles.push(handle);
         }
@@ -951,6 +2353,53 @@ impl Crawler {
             }
         }
         
+        // Fetch each out-of-scope link target once, never following its links
+        let time_limited = time_limited.load(Ordering::SeqCst);
+        if external_hop && time_limited {
+            info!("Skipping the external hop: the crawl ran out of time");
+        } else if external_hop {
+            let urls = std::mem::take(&mut external_links.lock().unwrap().urls);
+            info!("Fetching {} external link targets one hop out of {}", urls.len(), base_domain);
+            
+            let mut fetched = 0;
+            for url in urls {
+                if robots_enforcement == RobotsEnforcement::Strict {
+                    match robots_manager.is_allowed(&url).await {
+                        Ok(false) => {
+                            debug!("Skipping external {}: disallowed by robots.txt", url);
+                            continue;
+                        },
+                        Ok(true) => {},
+                        Err(e) => warn!("Failed to check robots.txt for {}: {}", url, e),
+                    }
+                }
+                
+                tokio::time::sleep(rate_limit_delay).await;
+                let (page, title) = fetch_external_page(&client, &url).await;
+                fetched += 1;
+                
+                if let Some(output) = &output {
+                    if let Err(e) = output.write_page(&page) {
+                        warn!("Failed to write to output file: {}", e);
+                    }
+                }
+                
+                if let Some(db) = &db {
+                    if let Err(e) = db.save_external_page(&task.id, &page, title.as_deref()) {
+                        warn!("Failed to store external page {}: {}", page.url, e);
+                    }
+                }
+            }
+            info!("Fetched {} external link targets", fetched);
+        }
+        
+        if let (Some(output), Some(path)) = (&output, &output_path) {
+            match output.finish() {
+                Ok(()) => info!("Wrote crawl dump to {}", path),
+                Err(e) => warn!("Failed to finish output file at {}: {}", path, e),
+            }
+        }
+        
         // Record elapsed time
         let crawl_duration = start_time.elapsed();
         
@@ -960,111 +2409,656 @@ impl Crawler {
                 info!("Note: {} is a JavaScript-dependent site, content may be incomplete", host);
             }
         }
-        
-        // Clean up shared browser if we created one
-        info!("Shutting down headless browser if needed");
-        if let Some(browser) = &self.headless_browser {
-            match HeadlessBrowser::stop_browser(browser.clone()).await {
-                Ok(()) => info!("Headless browser stopped successfully"),
-                Err(e) => warn!("Error stopping headless browser: {}", e),
+        
+        // Clean up shared browser if we created one
+        info!("Shutting down headless browser if needed");
+        if let Some(browser) = &self.headless_browser {
+            match HeadlessBrowser::stop_browser(browser.clone()).await {
+                Ok(()) => info!("Headless browser stopped successfully"),
+                Err(e) => warn!("Error stopping headless browser: {}", e),
+            }
+            self.headless_browser = None;
+        }
+        
+        // Update the result with the final counts
+        result.pages_count = pages_count.load(Ordering::SeqCst);
+        result.total_size = total_size.load(Ordering::SeqCst) as u64;
+        result.bytes_saved = bytes_saved.load(Ordering::SeqCst) as u64;
+        result.set_stats(progress.stats());
+        result.rendered_pages = rendered_pages.load(Ordering::SeqCst);
+        result.time_limited = time_limited;
+        
+        // Mark the crawl as complete
+        result.complete();
+        
+        info!("Completed crawl of {} - {} pages, {} bytes total in {:.2?}",
+            task.target_url, result.pages_count, result.total_size, crawl_duration);
+        if result.time_limited {
+            info!("Crawl of {} was cut short by its runtime limit; the result is partial", task.target_url);
+        }
+        if head_precheck {
+            info!("HEAD pre-check saved {} bytes", result.bytes_saved);
+        }
+        if self.use_headless_chrome {
+            info!("Rendered {} pages with headless Chrome, {} fetched plain",
+                result.rendered_pages, result.plain_pages());
+        }
+        if let Some(found) = result.sitemap_urls_found {
+            info!("Fetched {} of {} sitemap URLs for {}", result.pages_count, found, base_domain);
+        }
+        if self.fingerprint_visited.is_some() {
+            info!("Visited set held {} URL fingerprints", visited.lock().unwrap().len());
+        }
+        
+        Ok(result)
+    }
+    
+    /// Crawl a URL based on the current task (for backwards compatibility)
+    pub async fn crawl_current(&mut self) -> Result<CrawlResult> {
+        match &self.current_task {
+            Some(task) => {
+                // Clone the task to avoid borrowing issues
+                let task_clone = task.clone();
+                self.crawl_with_streaming(&task_clone, None).await
+            },
+            None => Err(anyhow!("No task set for crawler")),
+        }
+    }
+
+    /// Crawl a URL based on the provided task
+    pub async fn crawl(&mut self, task: &Task) -> Result<CrawlResult> {
+        // Simply delegate to the streaming version with no output file
+        self.crawl_with_streaming(task, None).await
+    }
+
+    /// Extract links from HTML text, reading them from the elements the selectors match
+    async fn extract_links_from_html(html: &str, base_url: &Url, selectors: &[LinkSelector]) -> Result<Vec<Url>> {
+        debug!("Document parsed, extracting links from {}", base_url);
+        
+        // Regular HTML parsing
+        let document = Html::parse_document(html);
+        let mut urls = Vec::new();
+        
+        for &link_selector in selectors {
+            let selector = Selector::parse(link_selector.css())
+                .map_err(|e| anyhow!("Failed to parse selector {}: {}", link_selector.css(), e))?;
+            let mut count = 0;
+            
+            for element in document.select(&selector) {
+                let Some(href) = link_selector.href(&element) else {
+                    continue;
+                };
+                count += 1;
+                
+                // Parse the URL, handling relative URLs
+                match base_url.join(href) {
+                    Ok(mut url) => {
+                        // Normalize URL by removing fragment
+                        url.set_fragment(None);
+                        urls.push(url);
+                    }
+                    Err(e) => {
+                        trace!("Failed to parse URL from href {}: {}", href, e);
+                    }
+                }
+            }
+            
+            trace!("Found {} links for {:?}", count, link_selector);
+        }
+        
+        debug!("Extracted {} links from {}", urls.len(), base_url);
+        
+        Ok(urls)
+    }
+
+    /// Preview the URLs a crawl of `task` would start from and whether each would be crawled.
+    /// Only robots.txt and sitemaps are fetched; no page bodies are downloaded.
+    pub async fn plan(&self, task: &Task) -> Result<CrawlPlan> {
+        let mut initial_url = Url::parse(&task.target_url)
+            .map_err(|e| anyhow!("Failed to parse target URL: {}", e))?;
+        initial_url.set_fragment(None);
+        
+        let base_domain = match initial_url.host_str() {
+            Some(host) => host.to_string(),
+            None => return Err(anyhow!("URL has no host: {}", initial_url)),
+        };
+        
+        let mut robots_manager = self.robots_manager_for(task);
+        
+        // List the sitemaps the crawl would read, falling back to the common locations
+        let mut sitemaps = robots_manager.get_sitemaps_from_robots(&base_domain).await
+            .unwrap_or_default();
+        if sitemaps.is_empty() {
+            sitemaps = robots_manager.fallback_sitemaps(&base_domain);
+        }
+        
+        // Decide for each seed URL whether the crawl would fetch it
+        let max_pages = task.max_links.unwrap_or(1000);
+        let mut queued = 0;
+        let mut urls = Vec::new();
+        
+        for (url, source) in seed_urls(&mut robots_manager, &initial_url, &base_domain, self.sitemap_seed_limit).await {
+            let denied = if !in_scope(&url, &base_domain, task.scope()) {
+                Some("outside the task's domain")
+            } else if self.robots_enforcement == RobotsEnforcement::Strict
+                && !robots_manager.is_allowed(&url).await.unwrap_or(true) {
+                Some("blocked by robots.txt")
+            } else if task.max_depth == 0 {
+                Some("max depth is 0")
+            } else if queued >= max_pages {
+                Some("beyond the page limit")
+            } else {
+                None
+            };
+            
+            if denied.is_none() {
+                queued += 1;
+            }
+            
+            urls.push(PlannedUrl {
+                url: url.to_string(),
+                source,
+                allowed: denied.is_none(),
+                reason: denied.unwrap_or(match self.robots_enforcement {
+                    RobotsEnforcement::Strict => "allowed by robots.txt",
+                    RobotsEnforcement::Off => "robots.txt not enforced",
+                }).to_string(),
+            });
+        }
+        
+        Ok(CrawlPlan {
+            task_id: task.id.clone(),
+            target_url: initial_url.to_string(),
+            sitemaps,
+            urls,
+        })
+    }
+    
+    /// Set the database connection for the crawler
+    pub fn set_database(&mut self, db: Database) -> &mut Self {
+        // Keep robots.txt and sitemaps in the same database so they survive restarts
+        self.robots_manager = self.robots_manager.clone().with_persistent_cache(db.clone());
+        self.db = Some(db);
+        self
+    }
+}
+
+// Helper function to collect the URLs a crawl starts from: the target itself,
+// well-known pages for some sites and up to `sitemap_limit` sitemap entries
+async fn seed_urls(
+    robots_manager: &mut RobotsManager,
+    initial_url: &Url,
+    base_domain: &str,
+    sitemap_limit: usize,
+) -> Vec<(Url, SeedSource)> {
+    let mut seeds = vec![(initial_url.clone(), SeedSource::Target)];
+    
+    // Add some well-known crates.io pages to ensure we have enough initial URLs
+    if base_domain == "crates.io" {
+        info!("Adding well-known crates.io URLs to the initial queue");
+        let known_paths = [
+            "/", 
+            "/crates", 
+            "/categories", 
+            "/keywords",
+            "/crates/tokio",
+            "/crates/serde",
+            "/crates/rand",
+            "/crates/reqwest",
+            "/crates/actix-web",
+            "/crates/chrono",
+            "/categories/asynchronous",
+            "/categories/web-programming"
+        ];
+        
+        for path in known_paths {
+            if let Ok(url) = Url::parse(&format!("https://crates.io{}", path)) {
+                if !seeds.iter().any(|(u, _)| u.as_str() == url.as_str()) {
+                    info!("Added known URL: {}", url);
+                    seeds.push((url, SeedSource::WellKnown));
+                }
+            }
+        }
+    }
+    
+    // Check for sitemaps, reading only enough entries to fill the limit despite duplicates of the seeds above
+    if sitemap_limit == 0 {
+        return seeds;
+    }
+    info!("Checking for sitemaps at {}", base_domain);
+    match robots_manager.get_sitemap_urls_up_to(base_domain, sitemap_limit + seeds.len()).await {
+        Ok(sitemap_urls) if !sitemap_urls.is_empty() => {
+            info!("Found {} sitemap URLs for {}", sitemap_urls.len(), base_domain);
+            
+            // Sort so the same sitemap always yields the same seeds
+            let mut sitemap_urls: Vec<String> = sitemap_urls.into_iter().collect();
+            sitemap_urls.sort();
+            
+            // Add URLs from sitemaps to our initial queue to speed up the start
+            let mut added = 0;
+            for url_str in sitemap_urls {
+                if added >= sitemap_limit {
+                    break;
+                }
+                
+                match Url::parse(&url_str) {
+                    Ok(url) => {
+                        if !seeds.iter().any(|(u, _)| u.as_str() == url.as_str()) {
+                            seeds.push((url, SeedSource::Sitemap));
+                            added += 1;
+                        }
+                    },
+                    Err(e) => {
+                        warn!("Failed to parse sitemap URL {}: {}", url_str, e);
+                    }
+                }
+            }
+            
+            info!("Added {} URLs from sitemaps to the initial queue", added);
+        },
+        Ok(_) => {
+            info!("No URLs found in sitemaps for {}", base_domain);
+        },
+        Err(e) => {
+            warn!("Failed to get sitemaps for {}: {}", base_domain, e);
+        }
+    }
+    
+    seeds
+}
+
+/// Type and size of a resource, read from response headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResourceInfo {
+    content_type: Option<String>,
+    length: Option<u64>,
+}
+
+impl ResourceInfo {
+    /// Read the type and size from a response. A partial response to a ranged GET
+    /// carries the full size in `Content-Range` rather than `Content-Length`.
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|h: &reqwest::header::HeaderValue| h.to_str().ok());
+        let length = match header(reqwest::header::CONTENT_RANGE) {
+            Some(range) => range.rsplit('/').next().and_then(|total| total.parse().ok()),
+            None => header(reqwest::header::CONTENT_LENGTH).and_then(|len| len.parse().ok()),
+        };
+        
+        Self {
+            content_type: header(reqwest::header::CONTENT_TYPE).map(|s| s.to_string()),
+            length,
+        }
+    }
+}
+
+/// Find a resource's type and size without downloading it: a HEAD request, or a
+/// one-byte ranged GET for servers that reject HEAD
+async fn probe_resource(client: &Client, url: &Url) -> Option<ResourceInfo> {
+    match client.head(url.clone()).send().await {
+        Ok(response) if response.status().is_success() => {
+            return Some(ResourceInfo::from_headers(response.headers()));
+        },
+        Ok(response) => debug!("HEAD {} returned {}, trying a ranged GET", url, response.status()),
+        Err(e) => debug!("HEAD {} failed: {}, trying a ranged GET", url, e),
+    }
+    
+    // Dropping the response without reading it leaves the body undownloaded,
+    // even if the server ignores the range
+    match client.get(url.clone()).header(reqwest::header::RANGE, "bytes=0-0").send().await {
+        Ok(response) if response.status().is_success() => Some(ResourceInfo::from_headers(response.headers())),
+        Ok(response) => {
+            debug!("Ranged GET {} returned {}", url, response.status());
+            None
+        },
+        Err(e) => {
+            debug!("Ranged GET {} failed: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Every URL listed in the domain's sitemaps, sorted and without duplicates
+async fn sitemap_seeds(robots_manager: &mut RobotsManager, base_domain: &str) -> Vec<Url> {
+    info!("Checking for sitemaps at {}", base_domain);
+    let sitemap_urls = match robots_manager.get_sitemap_urls(base_domain).await {
+        Ok(urls) => urls,
+        Err(e) => {
+            warn!("Failed to get sitemaps for {}: {}", base_domain, e);
+            return Vec::new();
+        }
+    };
+    
+    let mut urls: Vec<Url> = sitemap_urls
+        .iter()
+        .filter_map(|url_str| match Url::parse(url_str) {
+            Ok(mut url) => {
+                url.set_fragment(None);
+                Some(url)
+            },
+            Err(e) => {
+                warn!("Failed to parse sitemap URL {}: {}", url_str, e);
+                None
             }
-            self.headless_browser = None;
+        })
+        .collect();
+    urls.sort();
+    urls.dedup();
+    
+    info!("Found {} sitemap URLs for {}", urls.len(), base_domain);
+    urls
+}
+
+/// Read seed URLs from a file, one per line. Blank lines and `#` comments are ignored;
+/// lines that aren't http(s) URLs are logged and skipped.
+fn load_seed_urls(path: &Path) -> Result<Vec<Url>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read seed file {}: {}", path.display(), e))?;
+    
+    let mut urls = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
         
-        // Update the result with the final counts
-        result.pages_count = pages_count.load(Ordering::SeqCst);
-        result.total_size = total_size.load(Ordering::SeqCst) as u64;
-        
-        // Mark the crawl as complete
-        result.complete();
-        
-        info!("Completed crawl of {} - {} pages, {} bytes total in {:.2?}",
-            task.target_url, result.pages_count, result.total_size, crawl_duration);
-        
-        Ok(result)
-    }
-    
-    /// Crawl a URL based on the current task (for backwards compatibility)
-    pub async fn crawl_current(&mut self) -> Result<CrawlResult> {
-        match &self.current_task {
-            Some(task) => {
-                // Clone the task to avoid borrowing issues
-                let task_clone = task.clone();
-                self.crawl_with_streaming(&task_clone, None).await
+        match Url::parse(line) {
+            Ok(mut url) if matches!(url.scheme(), "http" | "https") => {
+                url.set_fragment(None);
+                urls.push(url);
             },
-            None => Err(anyhow!("No task set for crawler")),
+            Ok(url) => warn!("Skipping seed on line {} of {}: unsupported scheme {}", index + 1, path.display(), url.scheme()),
+            Err(e) => warn!("Skipping seed on line {} of {}: {} ({})", index + 1, path.display(), line, e),
         }
     }
+    
+    Ok(urls)
+}
 
-    /// Crawl a URL based on the provided task
-    pub async fn crawl(&mut self, task: &Task) -> Result<CrawlResult> {
-        // Simply delegate to the streaming version with no output file
-        self.crawl_with_streaming(task, None).await
+/// Build the page for a non-HTML response. Textual bodies (JSON, XML, plain text) are kept;
+/// binary ones such as PDFs are only counted.
+fn non_html_page(url: &str, content_type: Option<String>, status: u16, bytes: &[u8]) -> CrawledPage {
+    let media_type = content_type.as_deref().map(media_type).unwrap_or_default();
+    let is_text = media_type.starts_with("text/")
+        || media_type.ends_with("json")
+        || media_type.ends_with("xml");
+    
+    CrawledPage {
+        url: url.to_string(),
+        size: bytes.len(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        content_type,
+        status_code: Some(status),
+        body: if is_text { Some(String::from_utf8_lossy(bytes).into_owned()) } else { None },
+        is_external: false,
     }
+}
 
-    /// Extract links from HTML text
-    async fn extract_links_from_html(html: &str, base_url: &Url) -> Result<Vec<Url>> {
-        debug!("Document parsed, extracting links from {}", base_url);
-        
-        // Regular HTML parsing
-        let document = Html::parse_document(html);
-        let mut urls = Vec::new();
-        
-        // Create a selector for anchor tags with href attributes
-        let selector = Selector::parse("a[href]").unwrap_or_else(|_| {
-            warn!("Failed to parse selector, using fallback");
-            Selector::parse("a").unwrap() // This should never fail
-        });
-        
-        // Find all anchor tags with href attributes
-        let links = document.select(&selector);
-        let mut count = 0;
-        
-        for link in links {
-            count += 1;
-            if let Some(href) = link.value().attr("href") {
-                // Parse the URL, handling relative URLs
-                match base_url.join(href) {
-                    Ok(mut url) => {
-                        // Normalize URL by removing fragment
-                        url.set_fragment(None);
-                        urls.push(url);
-                    }
-                    Err(e) => {
-                        trace!("Failed to parse URL from href {}: {}", href, e);
-                    }
-                }
+/// A resource saved to the asset directory
+struct SavedAsset {
+    /// File the body was written to
+    path: PathBuf,
+    /// Bytes written
+    size: usize,
+}
+
+/// Stream a response body to `{dir}/{hash}.{ext}` a chunk at a time, so it is never held in
+/// memory. Returns `None`, leaving no file behind, if the body is larger than `max_size`.
+async fn save_asset(mut response: reqwest::Response, url: &Url, content_type: Option<&str>, dir: &Path, max_size: u64) -> Result<Option<SavedAsset>> {
+    use tokio::io::AsyncWriteExt;
+    
+    if response.content_length().is_some_and(|length| length > max_size) {
+        return Ok(None);
+    }
+    
+    tokio::fs::create_dir_all(dir).await
+        .map_err(|e| anyhow!("Failed to create asset directory {}: {}", dir.display(), e))?;
+    let path = dir.join(asset_file_name(url, content_type));
+    
+    // Written under a temporary name so an interrupted download never looks complete
+    let partial = path.with_extension("part");
+    let mut file = tokio::fs::File::create(&partial).await?;
+    let mut size = 0;
+    let written = async {
+        while let Some(chunk) = response.chunk().await? {
+            size += chunk.len() as u64;
+            if size > max_size {
+                return Ok(false);
             }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok::<_, anyhow::Error>(true)
+    }.await;
+    drop(file);
+    
+    match written {
+        Ok(true) => {
+            tokio::fs::rename(&partial, &path).await?;
+            Ok(Some(SavedAsset { path, size: size as usize }))
+        }
+        Ok(false) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            Ok(None)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            Err(e)
         }
-        
-        trace!("Found {} anchor tags with href attributes", count);
-        debug!("Extracted {} links from {}", urls.len(), base_url);
-        
-        Ok(urls)
     }
+}
 
-    /// Set the database connection for the crawler
-    pub fn set_database(&mut self, db: Database) -> &mut Self {
-        self.db = Some(db);
-        self
+/// File name of a saved asset: an FNV-1a hash of its URL, so a re-crawl by any build overwrites the same file, and
+/// the URL's extension or else one for the content type
+fn asset_file_name(url: &Url, content_type: Option<&str>) -> String {
+    let extension = Path::new(url.path())
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| extension.len() <= 5 && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_lowercase)
+        .unwrap_or_else(|| {
+            let extension = match content_type.map(media_type).as_deref() {
+                Some("application/pdf") => "pdf",
+                Some("image/png") => "png",
+                Some("image/jpeg") => "jpg",
+                Some("image/gif") => "gif",
+                Some("image/webp") => "webp",
+                Some("image/svg+xml") => "svg",
+                Some("application/zip") => "zip",
+                _ => "bin",
+            };
+            extension.to_string()
+        });
+    
+    format!("{:016x}.{}", fnv1a(url.as_str().as_bytes()), extension)
+}
+
+/// Request for a page with the browser-like headers every page fetch sends
+pub(crate) fn page_request(client: &Client, url: &Url, accept_language: &str) -> reqwest::RequestBuilder {
+    client.get(url.clone())
+        .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8")
+        .header(reqwest::header::ACCEPT_LANGUAGE, accept_language)
+}
+
+/// Fetch a page outside the task scope to check that it is alive and read its title.
+/// Failures are recorded as a page without a status.
+async fn fetch_external_page(client: &Client, url: &Url) -> (CrawledPage, Option<String>) {
+    let mut page = CrawledPage {
+        url: url.to_string(),
+        size: 0,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        content_type: None,
+        status_code: None,
+        body: None,
+        is_external: true,
+    };
+    
+    let response = match client.get(url.clone()).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            debug!("External link {} is unreachable: {}", url, e);
+            return (page, None);
+        }
+    };
+    
+    page.status_code = Some(response.status().as_u16());
+    page.content_type = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .map(|ct| ct.to_string());
+    
+    let bytes = match response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            debug!("Failed to read external page {}: {}", url, e);
+            return (page, None);
+        }
+    };
+    page.size = bytes.len();
+    
+    // Only HTML pages have a title
+    let is_html = page.content_type.as_deref().map(is_html_content_type).unwrap_or(true);
+    if !is_html {
+        return (page, None);
     }
+    let (html, _) = decode_body(&bytes, page.content_type.as_deref());
+    let title = Selector::parse("title").ok().and_then(|selector| {
+        Html::parse_document(&html)
+            .select(&selector)
+            .next()
+            .map(|title| title.text().collect::<String>().trim().to_string())
+            .filter(|title| !title.is_empty())
+    });
+    
+    (page, title)
 }
 
-// Helper function to check if a URL is in the same domain or subdomain
-fn is_same_domain(url: &Url, target_domain: &str, include_subdomains: bool) -> bool {
-    if let Some(host) = url.host_str() {
-        let is_same = host == target_domain;
-        let is_subdomain = include_subdomains && host.ends_with(&format!(".{}", target_domain));
-        
-        debug!("Checking domain for {}: host={}, target={}, is_same={}, is_subdomain={}", 
-            url, host, target_domain, is_same, is_subdomain);
+/// Decode a page body using the charset from its byte order mark, its Content-Type header
+/// or a `<meta charset>` tag, in that order, falling back to UTF-8.
+/// Returns the decoded text and the name of the charset used.
+pub fn decode_body(bytes: &[u8], content_type: Option<&str>) -> (String, &'static str) {
+    let declared = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| charset_from_meta(bytes));
+    
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| declared.and_then(|label| Encoding::for_label(label.as_bytes())))
+        .unwrap_or(UTF_8);
+    
+    // decode() strips the BOM and replaces malformed sequences
+    let (text, used, _) = encoding.decode(bytes);
+    (text.into_owned(), used.name())
+}
+
+/// Charset parameter of a Content-Type value, e.g. `text/html; charset=Shift_JIS`
+fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+    })
+}
+
+/// Charset declared by a `<meta charset>` or `<meta http-equiv="Content-Type">` tag near the start of the page
+fn charset_from_meta(bytes: &[u8]) -> Option<String> {
+    // The declaration must appear in the first 1024 bytes, which are ASCII-compatible in every charset we decode
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_lowercase();
+    let regex = regex::Regex::new(r#"<meta[^>]+charset\s*=\s*["']?([a-z0-9_:.\-]+)"#).ok()?;
+    regex.captures(&head).map(|captures| captures[1].to_string())
+}
+
+/// Extract the meta description and Open Graph title, description and image of an HTML page
+pub fn extract_metadata(html: &str) -> PageMetadata {
+    let document = Html::parse_document(html);
+    
+    // First non-empty content attribute of the meta tags matching the selector
+    let content = |selector: &str| {
+        let selector = Selector::parse(selector).ok()?;
+        document.select(&selector)
+            .filter_map(|element| element.value().attr("content"))
+            .map(|content| content.trim())
+            .find(|content| !content.is_empty())
+            .map(|content| content.to_string())
+    };
+    
+    PageMetadata {
+        description: content(r#"meta[name="description" i]"#),
+        og_title: content(r#"meta[property="og:title"]"#),
+        og_description: content(r#"meta[property="og:description"]"#),
+        og_image: content(r#"meta[property="og:image"]"#),
+    }
+}
+
+/// Extract the readable text of an HTML page: scripts and styles are dropped and whitespace collapsed
+pub fn extract_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut text = String::new();
+    
+    for node in document.tree.root().descendants() {
+        if let Some(fragment) = node.value().as_text() {
+            // Skip text that is never rendered
+            let hidden = node.ancestors().any(|ancestor| matches!(
+                ancestor.value().as_element().map(|element| element.name()),
+                Some("script" | "style" | "noscript" | "template" | "head")
+            ));
             
-        if is_same || is_subdomain {
-            return true;
+            if !hidden {
+                text.push_str(fragment);
+                text.push(' ');
+            }
         }
     }
     
-    false
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Helper function to check if a URL is in the same domain or subdomain
+fn in_scope(url: &Url, target_domain: &str, scope: CrawlScope) -> bool {
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return false,
+    };
+    
+    let allowed = match scope {
+        CrawlScope::Host => host == target_domain,
+        CrawlScope::Domain => host == target_domain || host.ends_with(&format!(".{}", target_domain)),
+        CrawlScope::RegistrableDomain => match (registrable_domain(host), registrable_domain(target_domain)) {
+            (Some(host_domain), Some(target)) => host_domain == target,
+            // IP addresses and bare hostnames have no registrable domain
+            _ => host == target_domain,
+        },
+    };
+    
+    debug!("Checking scope for {}: host={}, target={}, scope={}, allowed={}",
+        url, host, target_domain, scope, allowed);
+    
+    allowed
+}
+
+/// Public suffix list used to find registrable domains
+static PUBLIC_SUFFIXES: Lazy<publicsuffix::List> = Lazy::new(|| {
+    include_str!("../data/public_suffix_list.dat")
+        .parse()
+        .expect("bundled public suffix list is valid")
+});
+
+/// Registrable domain (eTLD+1) of a host, e.g. `example.co.uk` for `www.example.co.uk`
+fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let domain = PUBLIC_SUFFIXES.domain(host.as_bytes())?;
+    
+    // Only suffixes from the list count; unknown TLDs would otherwise match as wildcards
+    if !domain.suffix().is_known() {
+        return None;
+    }
+    
+    std::str::from_utf8(domain.as_bytes()).ok().map(|domain| domain.to_string())
 }
 
 // Helper function to extract links from an HTML document
@@ -1131,4 +3125,462 @@ fn extract_links(document: &Html, base_url: &Url) -> Result<Vec<Url>> {
     
     info!("Extracted {} links from {}", links.len(), base_url);
     Ok(links)
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn meta_charset_is_used_to_decode_the_page() {
+        let bytes = include_bytes!("../tests/fixtures/windows-1251.html");
+        let (html, charset) = decode_body(bytes, Some("text/html"));
+        
+        assert_eq!(charset, "windows-1251");
+        
+        let document = Html::parse_document(&html);
+        let title = document.select(&Selector::parse("title").unwrap()).next().unwrap();
+        assert_eq!(title.text().collect::<String>(), "Криптовалюты и блокчейн");
+        
+        let base = Url::parse("https://example.ru/").unwrap();
+        let links = Crawler::extract_links_from_html(&html, &base, &LinkSelector::DEFAULT).await.unwrap();
+        assert!(links.contains(&Url::parse("https://example.ru/новости/биткоин").unwrap()));
+        assert!(links.contains(&Url::parse("https://example.ru/рынок?валюта=эфир").unwrap()));
+    }
+    
+    /// A listing page with a link of every kind the crawler can read
+    const PAGINATED_LISTING: &str = r#"<html><head>
+        <link rel="Next" href="/list?page=3">
+        <link rel="prev" href="/list?page=1">
+        <link rel="stylesheet" href="/style.css">
+        </head><body>
+        <a href="/item/1#reviews">Item</a>
+        <map name="nav"><area shape="rect" coords="0,0,10,10" href="/map/area"></map>
+        <div data-href="/card/7">Card</div>
+        </body></html>"#;
+    
+    #[tokio::test]
+    async fn each_link_selector_reads_its_own_elements() {
+        let base = Url::parse("https://example.com/list?page=2").unwrap();
+        let cases = [
+            (LinkSelector::Anchor, "https://example.com/item/1"),
+            (LinkSelector::Area, "https://example.com/map/area"),
+            (LinkSelector::NextPage, "https://example.com/list?page=3"),
+            (LinkSelector::PrevPage, "https://example.com/list?page=1"),
+            (LinkSelector::DataHref, "https://example.com/card/7"),
+        ];
+        
+        for (selector, expected) in cases {
+            let links = Crawler::extract_links_from_html(PAGINATED_LISTING, &base, &[selector]).await.unwrap();
+            assert_eq!(links, [Url::parse(expected).unwrap()], "{:?}", selector);
+        }
+    }
+    
+    #[tokio::test]
+    async fn default_link_selectors_follow_anchors_and_the_next_page() {
+        let base = Url::parse("https://example.com/list?page=2").unwrap();
+        let links = Crawler::extract_links_from_html(PAGINATED_LISTING, &base, &LinkSelector::DEFAULT).await.unwrap();
+        
+        assert_eq!(links, [
+            Url::parse("https://example.com/item/1").unwrap(),
+            Url::parse("https://example.com/list?page=3").unwrap(),
+        ]);
+    }
+    
+    #[test]
+    fn asset_names_hash_the_url_and_keep_an_extension() {
+        let pdf = Url::parse("https://example.com/papers/Whitepaper.PDF?v=2").unwrap();
+        let name = asset_file_name(&pdf, Some("application/pdf"));
+        assert!(name.ends_with(".pdf"));
+        assert_eq!(name, asset_file_name(&pdf, None));
+        
+        let logo = Url::parse("https://example.com/logo").unwrap();
+        assert!(asset_file_name(&logo, Some("image/svg+xml; charset=utf-8")).ends_with(".svg"));
+        assert!(asset_file_name(&logo, None).ends_with(".bin"));
+    }
+    
+    #[tokio::test]
+    async fn assets_are_streamed_to_disk_within_the_size_budget() {
+        use axum::{routing::get, Router};
+        
+        let body = vec![7u8; 4096];
+        let served = body.clone();
+        let app = Router::new().route("/paper.pdf", get(move || async move {
+            ([(axum::http::header::CONTENT_TYPE, "application/pdf")], served)
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/paper.pdf", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let dir = tempfile::TempDir::new().unwrap();
+        let client = Client::new();
+        
+        let response = client.get(url.clone()).send().await.unwrap();
+        let asset = save_asset(response, &url, Some("application/pdf"), dir.path(), 10_000).await.unwrap().unwrap();
+        assert_eq!(asset.size, body.len());
+        assert_eq!(asset.path.extension().unwrap(), "pdf");
+        assert_eq!(std::fs::read(&asset.path).unwrap(), body);
+        
+        // Over budget nothing is kept, not even a partial file
+        std::fs::remove_file(&asset.path).unwrap();
+        let response = client.get(url.clone()).send().await.unwrap();
+        assert!(save_asset(response, &url, Some("application/pdf"), dir.path(), 1_000).await.unwrap().is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+    
+    #[test]
+    fn content_type_charset_wins_over_meta() {
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("<meta charset=\"utf-8\"><title>暗号資産</title>");
+        let (html, charset) = decode_body(&bytes, Some("text/html; charset=\"Shift_JIS\""));
+        
+        assert_eq!(charset, "Shift_JIS");
+        assert!(html.contains("<title>暗号資産</title>"));
+    }
+    
+    #[test]
+    fn undeclared_charset_defaults_to_utf8() {
+        let (html, charset) = decode_body("<p>Grüße</p>".as_bytes(), None);
+        
+        assert_eq!(charset, "UTF-8");
+        assert_eq!(html, "<p>Grüße</p>");
+    }
+    
+    #[test]
+    fn metadata_is_extracted_from_meta_tags() {
+        let html = r#"<html><head>
+            <meta name="Description" content=" Fast async runtime ">
+            <meta property="og:title" content="tokio">
+            <meta property="og:description" content="">
+            <meta property="og:image" content="https://example.com/tokio.png">
+        </head><body></body></html>"#;
+        
+        let metadata = extract_metadata(html);
+        
+        assert_eq!(metadata.description.as_deref(), Some("Fast async runtime"));
+        assert_eq!(metadata.og_title.as_deref(), Some("tokio"));
+        assert_eq!(metadata.og_description, None);
+        assert_eq!(metadata.og_image.as_deref(), Some("https://example.com/tokio.png"));
+    }
+    
+    #[test]
+    fn pages_without_meta_tags_have_empty_metadata() {
+        let metadata = extract_metadata("<html><head><title>Plain</title></head><body>Hi</body></html>");
+        
+        assert!(metadata.is_empty());
+    }
+    
+    #[test]
+    fn json_pages_keep_their_body() {
+        let body = br#"{"name":"serde","downloads":42}"#;
+        let page = non_html_page("https://example.com/api/crates/serde", Some("application/json".to_string()), 200, body);
+        
+        assert_eq!(page.size, body.len());
+        assert_eq!(page.status_code, Some(200));
+        assert_eq!(page.body.as_deref(), Some(r#"{"name":"serde","downloads":42}"#));
+    }
+    
+    #[test]
+    fn pdf_pages_are_counted_without_a_body() {
+        let body = b"%PDF-1.7\n\x00\x01\x02binary";
+        let page = non_html_page("https://example.com/whitepaper.pdf", Some("application/pdf".to_string()), 200, body);
+        
+        assert_eq!(page.size, body.len());
+        assert_eq!(page.content_type.as_deref(), Some("application/pdf"));
+        assert!(page.body.is_none());
+    }
+    
+    #[test]
+    fn render_policy_renders_seed_patterns_and_a_stable_sample() {
+        let url = |path: &str| Url::parse(&format!("https://example.com{}", path)).unwrap();
+        let policy = RenderPolicy { render_patterns: vec!["/docs/".to_string()], ..RenderPolicy::default() };
+        
+        assert!(policy.should_render(&url("/"), 0));
+        assert!(policy.should_render(&url("/docs/intro"), 3));
+        assert!(!policy.should_render(&url("/blog/post"), 1));
+        
+        let sampled = RenderPolicy { render_seed: false, sample_percent: 50, ..RenderPolicy::default() };
+        let picked: Vec<bool> = (0..200).map(|i| sampled.should_render(&url(&format!("/p/{}", i)), 2)).collect();
+        let rendered = picked.iter().filter(|&&p| p).count();
+        assert!((50..150).contains(&rendered), "{} of 200 sampled", rendered);
+        assert_eq!(picked[7], sampled.should_render(&url("/p/7"), 2));
+        
+        let everything = RenderPolicy { sample_percent: 100, ..RenderPolicy::default() };
+        assert!((0..50).all(|i| everything.should_render(&url(&format!("/p/{}", i)), 2)));
+        
+        // The sample is the same whichever build or toolchain runs the crawl
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+    
+    #[test]
+    fn seed_files_skip_comments_and_invalid_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("seeds.txt");
+        std::fs::write(&path, "# entry points\nhttps://example.com/a#top\n\nnot a url\nftp://example.com/file\n  http://example.com/b  \n").unwrap();
+        
+        let seeds: Vec<String> = load_seed_urls(&path).unwrap().iter().map(Url::to_string).collect();
+        
+        assert_eq!(seeds, ["https://example.com/a", "http://example.com/b"]);
+        assert!(load_seed_urls(&dir.path().join("missing.txt")).is_err());
+    }
+    
+    #[test]
+    fn repeating_path_segments_are_suppressed() {
+        let mut traps = TrapTracker::new(TrapGuard::default());
+        
+        assert!(traps.allow(&Url::parse("https://example.com/a/a/a/").unwrap()));
+        assert!(!traps.allow(&Url::parse("https://example.com/a/a/a/a/").unwrap()));
+    }
+    
+    #[test]
+    fn query_permutations_are_capped_per_path() {
+        let mut traps = TrapTracker::new(TrapGuard { max_query_variants: 2, ..TrapGuard::default() });
+        
+        assert!(traps.allow(&Url::parse("https://example.com/search?q=a&page=1").unwrap()));
+        // Same parameters in a different order are not a new variant
+        assert!(traps.allow(&Url::parse("https://example.com/search?page=1&q=a").unwrap()));
+        assert!(traps.allow(&Url::parse("https://example.com/search?q=a&page=2").unwrap()));
+        assert!(!traps.allow(&Url::parse("https://example.com/search?q=a&page=3").unwrap()));
+        assert!(traps.allow(&Url::parse("https://example.com/other?q=a&page=3").unwrap()));
+    }
+    
+    #[test]
+    fn urls_per_prefix_are_capped() {
+        let mut traps = TrapTracker::new(TrapGuard { max_urls_per_prefix: 2, ..TrapGuard::default() });
+        
+        assert!(traps.allow(&Url::parse("https://example.com/calendar/2024/01").unwrap()));
+        assert!(traps.allow(&Url::parse("https://example.com/calendar/2024/02").unwrap()));
+        assert!(!traps.allow(&Url::parse("https://example.com/calendar/2024/03").unwrap()));
+        assert!(traps.allow(&Url::parse("https://example.com/calendar/2025/01").unwrap()));
+    }
+    
+    #[test]
+    fn resource_size_comes_from_content_length() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_TYPE, "application/zip".parse().unwrap());
+        headers.insert(reqwest::header::CONTENT_LENGTH, "52428800".parse().unwrap());
+        
+        let info = ResourceInfo::from_headers(&headers);
+        assert_eq!(info.content_type.as_deref(), Some("application/zip"));
+        assert_eq!(info.length, Some(52_428_800));
+    }
+    
+    #[test]
+    fn ranged_get_size_comes_from_content_range() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::CONTENT_LENGTH, "1".parse().unwrap());
+        headers.insert(reqwest::header::CONTENT_RANGE, "bytes 0-0/123456".parse().unwrap());
+        
+        assert_eq!(ResourceInfo::from_headers(&headers).length, Some(123_456));
+    }
+    
+    #[tokio::test]
+    async fn accept_language_changes_the_served_locale() {
+        use axum::{http::HeaderMap, routing::get, Router};
+        
+        // A locale-aware site serving German to German-speaking clients
+        async fn greeting(headers: HeaderMap) -> &'static str {
+            let language = headers.get("accept-language").and_then(|value| value.to_str().ok()).unwrap_or_default();
+            if language.starts_with("de") { "Hallo Welt" } else { "Hello world" }
+        }
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/", get(greeting))).await.unwrap();
+        });
+        
+        let client = Client::new();
+        let mut task = Task::new("task-1".to_string(), url.to_string(), 1, false, None, 0);
+        
+        let body = page_request(&client, &url, task.accept_language()).send().await.unwrap().text().await.unwrap();
+        assert_eq!(task.accept_language(), crate::models::DEFAULT_ACCEPT_LANGUAGE);
+        assert_eq!(body, "Hello world");
+        
+        task.accept_language = Some("de-DE".to_string());
+        let body = page_request(&client, &url, task.accept_language()).send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "Hallo Welt");
+    }
+    
+    #[tokio::test]
+    async fn flaky_pages_are_fetched_again_until_they_load() {
+        use axum::{extract::State, http::StatusCode, routing::get, Router};
+        
+        // A page that fails twice before loading
+        async fn flaky(State(failures_left): State<Arc<AtomicUsize>>) -> (StatusCode, &'static str) {
+            match failures_left.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)) {
+                Ok(_) => (StatusCode::SERVICE_UNAVAILABLE, "try again"),
+                Err(_) => (StatusCode::OK, "loaded"),
+            }
+        }
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let app = Router::new().route("/", get(flaky)).with_state(Arc::new(AtomicUsize::new(2)));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let client = Client::new();
+        let mut retries = RetryQueue::new(CrawlProfile::Normal.max_retries(), std::time::Duration::ZERO);
+        let mut outcomes = Vec::new();
+        let mut next = Some((url, 1));
+        let mut loaded = None;
+        while let Some((url, attempt)) = next.take() {
+            let response = page_request(&client, &url, crate::models::DEFAULT_ACCEPT_LANGUAGE).send().await.unwrap();
+            if response.status().is_server_error() {
+                outcomes.push(retries.record_failure(url, attempt));
+                next = retries.next_ready().map(|(url, attempts)| (url, attempts + 1));
+            } else {
+                loaded = Some((attempt, response.text().await.unwrap()));
+            }
+        }
+        
+        let delay = std::time::Duration::ZERO;
+        assert_eq!(outcomes, [RetryOutcome::Scheduled { delay }, RetryOutcome::Scheduled { delay }]);
+        assert_eq!(loaded, Some((3, "loaded".to_string())));
+        assert!(retries.is_empty());
+    }
+    
+    #[test]
+    fn retries_back_off_and_stop_at_the_limit() {
+        let url = Url::parse("https://example.com/flaky").unwrap();
+        let mut retries = RetryQueue::new(2, std::time::Duration::from_millis(100));
+        
+        assert_eq!(retries.record_failure(url.clone(), 1), RetryOutcome::Scheduled { delay: std::time::Duration::from_millis(100) });
+        assert_eq!(retries.record_failure(url.clone(), 2), RetryOutcome::Scheduled { delay: std::time::Duration::from_millis(200) });
+        assert_eq!(retries.record_failure(url, 3), RetryOutcome::GaveUp);
+        
+        // Nothing is ready before its backoff has passed
+        assert_eq!(retries.next_ready(), None);
+        assert!(!retries.is_empty());
+    }
+    
+    #[test]
+    fn progress_exposes_the_queues_and_visited_urls() {
+        let progress = CrawlProgress::new();
+        let url = Url::parse("https://example.com/").unwrap();
+        progress.state.visited.lock().unwrap().extend(["https://example.com/".to_string(), "https://example.com/a".to_string()]);
+        progress.state.important_queue.lock().unwrap().push_back(url.clone());
+        progress.state.regular_queue.lock().unwrap().extend([url.clone(), url]);
+        
+        assert_eq!(progress.queue_len(), 3);
+        assert_eq!(progress.visited_count(), 2);
+        let status = progress.live_status();
+        assert_eq!((status.important_queue, status.regular_queue), (1, 2));
+        
+        let crawler = Crawler::default().with_progress(progress.clone());
+        assert_eq!((crawler.queue_len(), crawler.visited_count(), crawler.pages_crawled()), (3, 2, 0));
+        
+        progress.state.clear();
+        assert_eq!((crawler.queue_len(), crawler.visited_count()), (0, 0));
+    }
+    
+    #[test]
+    fn external_links_are_deduplicated_and_capped() {
+        let mut external = ExternalLinks::new(2);
+        for url in ["https://a.test/", "https://a.test/", "mailto:x@a.test", "https://b.test/", "https://c.test/"] {
+            external.offer(&Url::parse(url).unwrap());
+        }
+        
+        let urls: Vec<String> = external.urls.iter().map(|url| url.to_string()).collect();
+        assert_eq!(urls, ["https://a.test/", "https://b.test/"]);
+    }
+    
+    #[test]
+    fn host_scope_matches_only_the_exact_host() {
+        let url = |u: &str| Url::parse(u).unwrap();
+        
+        assert!(in_scope(&url("https://example.com/a"), "example.com", CrawlScope::Host));
+        assert!(!in_scope(&url("https://a.example.com/"), "example.com", CrawlScope::Host));
+        assert!(!in_scope(&url("https://notexample.com/"), "example.com", CrawlScope::Host));
+    }
+    
+    #[test]
+    fn domain_scope_matches_subdomains_but_not_lookalikes() {
+        let url = |u: &str| Url::parse(u).unwrap();
+        
+        assert!(in_scope(&url("https://example.com/"), "example.com", CrawlScope::Domain));
+        assert!(in_scope(&url("https://a.example.com/"), "example.com", CrawlScope::Domain));
+        assert!(!in_scope(&url("https://notexample.com/"), "example.com", CrawlScope::Domain));
+        assert!(!in_scope(&url("https://evil-example.com/"), "example.com", CrawlScope::Domain));
+        // Siblings are out of scope for a subdomain target
+        assert!(!in_scope(&url("https://b.example.com/"), "a.example.com", CrawlScope::Domain));
+    }
+    
+    #[test]
+    fn registrable_domain_scope_uses_the_public_suffix_list() {
+        let url = |u: &str| Url::parse(u).unwrap();
+        
+        assert!(in_scope(&url("https://b.example.com/"), "a.example.com", CrawlScope::RegistrableDomain));
+        assert!(in_scope(&url("https://example.com/"), "www.example.com", CrawlScope::RegistrableDomain));
+        assert!(!in_scope(&url("https://notexample.com/"), "example.com", CrawlScope::RegistrableDomain));
+        
+        // Hosts sharing only a public suffix are different sites
+        assert!(in_scope(&url("https://docs.example.co.uk/"), "www.example.co.uk", CrawlScope::RegistrableDomain));
+        assert!(!in_scope(&url("https://other.co.uk/"), "example.co.uk", CrawlScope::RegistrableDomain));
+        assert!(!in_scope(&url("https://bob.github.io/"), "alice.github.io", CrawlScope::RegistrableDomain));
+        
+        assert_eq!(registrable_domain("a.b.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain("localhost"), None);
+    }
+    
+    #[tokio::test]
+    async fn redirect_chains_count_towards_depth_when_asked() {
+        use axum::{response::Redirect, routing::get, Router};
+        
+        // A link at depth 1 that takes two redirects to reach its page
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let app = Router::new()
+            .route("/old", get(|| async { Redirect::permanent("/moved") }))
+            .route("/moved", get(|| async { Redirect::temporary("/page") }))
+            .route("/page", get(|| async { "page" }));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let hops = RedirectHops::default();
+        let client = http_client("test", DEFAULT_MAX_REDIRECTS, false, &hops);
+        let response = client.get(base.join("old").unwrap()).send().await.unwrap();
+        assert_eq!(response.url().path(), "/page");
+        let redirects = hops.take(response.url());
+        assert_eq!(redirects, 2);
+        
+        // With a depth limit of 3 the page is in reach by links, but not by requests
+        let linked = Distance::default().next();
+        let distance = linked.redirected(redirects);
+        assert_eq!(distance, Distance { links: 1, hops: 3 });
+        let max_depth = 3;
+        assert!(distance.depth(false) < max_depth);
+        assert!(distance.depth(true) >= max_depth);
+        
+        // Its links are one more hop away in either case
+        assert_eq!(distance.next(), Distance { links: 2, hops: 4 });
+        
+        // Too long a chain is an error, as with the default redirect policy
+        let client = http_client("test", 1, false, &hops);
+        assert!(client.get(base.join("old").unwrap()).send().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn injected_client_survives_later_settings() {
+        use axum::{response::Redirect, routing::get, Router};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/old", listener.local_addr().unwrap())).unwrap();
+        let app = Router::new().route("/old", get(|| async { Redirect::permanent("/new") }));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        // A client that doesn't follow redirects stays in use after the redirect limit is set
+        let client = Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+        let crawler = Crawler::default()
+            .with_client(client)
+            .with_max_redirects(5);
+        
+        let response = crawler.client.get(url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PERMANENT_REDIRECT);
+    }
+}
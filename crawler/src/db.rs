@@ -1,20 +1,129 @@
-use crate::models::{Task, CrawlResult, CrawledPage, CrawlStatus, CrawlReport};
-use anyhow::{Result, Context};
-use rusqlite::{params, Connection};
+use crate::models::{Task, CrawlResult, CrawledPage, CrawlStatus, CrawlReport, CrawlStats, PageMetadata, StoredPage, default_allowed_content_types, is_html_content_type};
+use crate::storage::PageRecord;
+use anyhow::{anyhow, Result, Context};
+use rusqlite::{params, Connection, OptionalExtension};
 use log::{info, warn};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
+use url::Url;
 use std::fs;
+use std::io::{BufRead, BufReader};
+use flate2::read::MultiGzDecoder;
 use std::time::{SystemTime, UNIX_EPOCH};
 use regex;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
 
 /// Type alias for a wallet history entry
 pub type WalletHistoryEntry = (String, i64, u64, String, Option<String>);
 
+/// Type alias for a report waiting to be resent: (id, task_id, JSON payload)
+pub type PendingReport = (i64, String, String);
+
+/// Type alias for a URL the crawl gave up on: (url, attempts, last error)
+pub type FailedUrl = (String, u32, String);
+
+/// Type alias for a page a task already crawled: (url, depth, links found on it)
+pub type CrawledUrl = (String, Option<u32>, Vec<String>);
+
+/// Schema of `crawled_pages`. Each crawl keeps its own row per URL, so crawls of the same site can be compared.
+const CRAWLED_PAGES_TABLE: &str = "CREATE TABLE IF NOT EXISTS crawled_pages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    status INTEGER,
+    content_type TEXT,
+    title TEXT,
+    size INTEGER NOT NULL,
+    html TEXT,
+    fetched_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    is_javascript_dependent INTEGER DEFAULT 0,
+    javascript_dependency_reasons TEXT,
+    extracted_links TEXT,
+    depth INTEGER,
+    text_content TEXT,
+    charset TEXT,
+    metadata TEXT,
+    requested_url TEXT,
+    is_external INTEGER DEFAULT 0,
+    hops INTEGER,
+    asset_path TEXT,
+    soft_404 INTEGER DEFAULT 0,
+    FOREIGN KEY (task_id) REFERENCES tasks(id),
+    UNIQUE(task_id, url)
+)";
+
+/// Every column of `crawled_pages`, copied over when the table is rebuilt
+const CRAWLED_PAGES_COLUMNS: &str = "id, task_id, url, domain, status, content_type, title, size, html, fetched_at, \
+    is_javascript_dependent, javascript_dependency_reasons, extracted_links, depth, text_content, charset, metadata, \
+    requested_url, is_external, hops, asset_path, soft_404";
+
+/// URLs that differ between two crawls, see [`Database::diff_crawls`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrawlDiff {
+    /// URLs only the second crawl has, sorted
+    pub added: Vec<String>,
+    /// URLs only the first crawl has, sorted
+    pub removed: Vec<String>,
+    /// URLs in both crawls whose size changed: (url, bytes in the first, bytes in the second), sorted by URL
+    pub size_changed: Vec<(String, i64, i64)>,
+}
+
+impl CrawlDiff {
+    /// Whether both crawls have the same URLs with the same sizes
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.size_changed.is_empty()
+    }
+}
+
+/// Column the crawl history can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrawlSort {
+    /// When the crawl started
+    #[default]
+    Date,
+    /// Number of pages crawled
+    Pages,
+    /// Bytes downloaded
+    Size,
+}
+
+impl CrawlSort {
+    /// Name used in query strings
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CrawlSort::Date => "date",
+            CrawlSort::Pages => "pages",
+            CrawlSort::Size => "size",
+        }
+    }
+    
+    fn column(self) -> &'static str {
+        match self {
+            CrawlSort::Date => "start_time",
+            CrawlSort::Pages => "pages_count",
+            CrawlSort::Size => "total_size",
+        }
+    }
+}
+
+impl std::str::FromStr for CrawlSort {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "date" => Ok(CrawlSort::Date),
+            "pages" => Ok(CrawlSort::Pages),
+            "size" => Ok(CrawlSort::Size),
+            _ => Err(format!("Invalid sort column: {} (expected date, pages or size)", s)),
+        }
+    }
+}
+
 /// Database connection wrapper
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
     path: PathBuf,
@@ -67,10 +176,16 @@ impl Database {
                 max_links INTEGER,
                 created_at INTEGER NOT NULL,
                 assigned_at INTEGER,
-                incentive_amount INTEGER NOT NULL
+                incentive_amount INTEGER NOT NULL,
+                allowed_content_types TEXT
             )",
             [],
         )?;
+        add_column_if_missing(&conn, "tasks", "allowed_content_types", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "scope", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "accept_language", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "max_runtime_secs", "INTEGER")?;
+        add_column_if_missing(&conn, "tasks", "robots_user_agent", "TEXT")?;
         
         conn.execute(
             "CREATE TABLE IF NOT EXISTS crawl_results (
@@ -84,10 +199,14 @@ impl Database {
                 end_time INTEGER,
                 transaction_hash TEXT,
                 incentives_received INTEGER,
+                status_counts TEXT,
+                content_type_bytes TEXT,
                 FOREIGN KEY (task_id) REFERENCES tasks(id)
             )",
             [],
         )?;
+        add_column_if_missing(&conn, "crawl_results", "status_counts", "TEXT")?;
+        add_column_if_missing(&conn, "crawl_results", "content_type_bytes", "TEXT")?;
         
         conn.execute(
             "CREATE TABLE IF NOT EXISTS wallet_history (
@@ -102,6 +221,21 @@ impl Database {
             [],
         )?;
         
+        // Each (task, transaction) pair may only be recorded once so a re-run
+        // claim can't double-count. Drop any duplicates from older databases first.
+        conn.execute(
+            "DELETE FROM wallet_history WHERE rowid NOT IN (
+                SELECT MIN(rowid) FROM wallet_history GROUP BY task_id, transaction_hash
+            )",
+            [],
+        )?;
+        
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_wallet_history_task_tx
+             ON wallet_history(task_id, transaction_hash)",
+            [],
+        )?;
+        
         conn.execute(
             "CREATE TABLE IF NOT EXISTS crawl_reports (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -117,26 +251,19 @@ impl Database {
         )?;
         
         // Create crawled_pages table for storing individual pages with full content
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS crawled_pages (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                task_id TEXT NOT NULL,
-                url TEXT NOT NULL,
-                domain TEXT NOT NULL,
-                status INTEGER,
-                content_type TEXT,
-                title TEXT,
-                size INTEGER NOT NULL,
-                html TEXT,
-                fetched_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-                is_javascript_dependent INTEGER DEFAULT 0,
-                javascript_dependency_reasons TEXT,
-                extracted_links TEXT,
-                FOREIGN KEY (task_id) REFERENCES tasks(id),
-                UNIQUE(url)
-            )",
-            [],
-        )?;
+        conn.execute(CRAWLED_PAGES_TABLE, [])?;
+        
+        // Add columns introduced after the table was first created
+        add_column_if_missing(&conn, "crawled_pages", "depth", "INTEGER")?;
+        add_column_if_missing(&conn, "crawled_pages", "text_content", "TEXT")?;
+        add_column_if_missing(&conn, "crawled_pages", "charset", "TEXT")?;
+        add_column_if_missing(&conn, "crawled_pages", "metadata", "TEXT")?;
+        add_column_if_missing(&conn, "crawled_pages", "requested_url", "TEXT")?;
+        add_column_if_missing(&conn, "crawled_pages", "is_external", "INTEGER DEFAULT 0")?;
+        add_column_if_missing(&conn, "crawled_pages", "hops", "INTEGER")?;
+        add_column_if_missing(&conn, "crawled_pages", "asset_path", "TEXT")?;
+        add_column_if_missing(&conn, "crawled_pages", "soft_404", "INTEGER DEFAULT 0")?;
+        migrate_crawled_pages_key(&conn)?;
         
         // Create indexes for crawled_pages
         conn.execute(
@@ -163,6 +290,49 @@ impl Database {
             [],
         )?;
         
+        // Create pending_reports table for reports the manager could not be reached for
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pending_reports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        
+        // Create failed_urls table for URLs that still failed after every retry
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS failed_urls (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT NOT NULL,
+                failed_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        
+        // Create robots_cache and sitemap_cache tables so robots.txt and sitemaps survive restarts
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS robots_cache (
+                domain TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sitemap_cache (
+                domain TEXT PRIMARY KEY,
+                urls TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        
         info!("Database tables initialized successfully");
         Ok(())
     }
@@ -180,13 +350,15 @@ impl Database {
         
         // Convert boolean to integer
         let follow_subdomains_int: i32 = if task.follow_subdomains { 1 } else { 0 };
+        let allowed_content_types = serde_json::to_string(&task.allowed_content_types)?;
         
         // Insert task into database
         conn.execute(
             "INSERT OR REPLACE INTO tasks (
                 id, url, max_depth, follow_subdomains, max_links,
-                created_at, assigned_at, incentive_amount
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                created_at, assigned_at, incentive_amount, allowed_content_types, scope, accept_language,
+                max_runtime_secs, robots_user_agent
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 task.id,
                 task.target_url,
@@ -199,6 +371,11 @@ impl Database {
                 task.created_at,
                 task.assigned_at,
                 task.incentive_amount,
+                allowed_content_types,
+                task.scope.map(|scope| scope.to_string()),
+                task.accept_language,
+                task.max_runtime_secs,
+                task.robots_user_agent,
             ],
         ).with_context(|| format!("Failed to save task with ID: {}", task.id))?;
         
@@ -210,8 +387,9 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         
         let mut stmt = conn.prepare(
-            "SELECT id, target_url, max_depth, follow_subdomains, max_links, 
-                    created_at, assigned_at, incentive_amount
+            "SELECT id, url, max_depth, follow_subdomains, max_links, 
+                    created_at, assigned_at, incentive_amount, allowed_content_types, scope, accept_language,
+                    max_runtime_secs, robots_user_agent
              FROM tasks WHERE id = ?"
         )?;
         
@@ -230,10 +408,17 @@ impl Database {
                 target_url: row.get(1)?,
                 max_depth: row.get(2)?,
                 follow_subdomains: row.get::<_, i32>(3)? != 0,
+                scope: row.get::<_, Option<String>>(9)?.and_then(|scope| scope.parse().ok()),
                 max_links,
                 created_at: row.get(5)?,
                 assigned_at: row.get(6)?,
                 incentive_amount: row.get(7)?,
+                allowed_content_types: content_types_from_column(row.get(8)?),
+                accept_language: row.get(10)?,
+                max_runtime_secs: row.get(11)?,
+                robots_user_agent: row.get(12)?,
+                // Credentials are never stored
+                login: None,
             }))
         } else {
             Ok(None)
@@ -245,8 +430,9 @@ impl Database {
         let conn = self.conn.lock().unwrap();
         
         let mut stmt = conn.prepare(
-            "SELECT id, target_url, max_depth, follow_subdomains, max_links, 
-                    created_at, assigned_at, incentive_amount
+            "SELECT id, url, max_depth, follow_subdomains, max_links, 
+                    created_at, assigned_at, incentive_amount, allowed_content_types, scope, accept_language,
+                    max_runtime_secs, robots_user_agent
              FROM tasks
              ORDER BY created_at DESC"
         )?;
@@ -264,10 +450,17 @@ impl Database {
                 target_url: row.get(1)?,
                 max_depth: row.get(2)?,
                 follow_subdomains: row.get::<_, i32>(3)? != 0,
+                scope: row.get::<_, Option<String>>(9)?.and_then(|scope| scope.parse().ok()),
                 max_links,
                 created_at: row.get(5)?,
                 assigned_at: row.get(6)?,
                 incentive_amount: row.get(7)?,
+                allowed_content_types: content_types_from_column(row.get(8)?),
+                accept_language: row.get(10)?,
+                max_runtime_secs: row.get(11)?,
+                robots_user_agent: row.get(12)?,
+                // Credentials are never stored
+                login: None,
             })
         })?;
         
@@ -281,8 +474,10 @@ impl Database {
     
     /// Save a crawl result to the database
     pub fn save_crawl_result(&self, result: &CrawlResult) -> Result<()> {
-        // Serialize pages to JSON
+        // Serialize pages and the status/content type breakdown to JSON
         let pages_json = serde_json::to_string(&result.pages)?;
+        let status_counts_json = serde_json::to_string(&result.status_counts)?;
+        let content_type_bytes_json = serde_json::to_string(&result.content_type_bytes)?;
         
         let conn = self.conn.lock().unwrap();
         
@@ -290,8 +485,9 @@ impl Database {
         conn.execute(
             "INSERT INTO crawl_results (
                 task_id, domain, status, pages_count, pages, total_size,
-                start_time, end_time, transaction_hash, incentives_received
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                start_time, end_time, transaction_hash, incentives_received,
+                status_counts, content_type_bytes
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 result.task_id,
                 result.domain,
@@ -303,6 +499,8 @@ impl Database {
                 result.end_time,
                 result.transaction_hash,
                 result.incentives_received,
+                status_counts_json,
+                content_type_bytes_json,
             ],
         )?;
         
@@ -311,8 +509,10 @@ impl Database {
     
     /// Update an existing crawl result
     pub fn update_crawl_result(&self, result: &CrawlResult) -> Result<()> {
-        // Serialize pages to JSON
+        // Serialize pages and the status/content type breakdown to JSON
         let pages_json = serde_json::to_string(&result.pages)?;
+        let status_counts_json = serde_json::to_string(&result.status_counts)?;
+        let content_type_bytes_json = serde_json::to_string(&result.content_type_bytes)?;
         
         let conn = self.conn.lock().unwrap();
         
@@ -321,7 +521,8 @@ impl Database {
             "UPDATE crawl_results SET 
                 domain = ?, status = ?, pages_count = ?, pages = ?, 
                 total_size = ?, start_time = ?, end_time = ?,
-                transaction_hash = ?, incentives_received = ?
+                transaction_hash = ?, incentives_received = ?,
+                status_counts = ?, content_type_bytes = ?
              WHERE task_id = ?",
             params![
                 result.domain,
@@ -333,6 +534,8 @@ impl Database {
                 result.end_time,
                 result.transaction_hash,
                 result.incentives_received,
+                status_counts_json,
+                content_type_bytes_json,
                 result.task_id,
             ],
         )?;
@@ -350,7 +553,8 @@ impl Database {
         
         let mut stmt = conn.prepare(
             "SELECT task_id, domain, status, pages_count, pages, total_size,
-                    start_time, end_time, transaction_hash, incentives_received
+                    start_time, end_time, transaction_hash, incentives_received,
+                    status_counts, content_type_bytes
              FROM crawl_results WHERE task_id = ?"
         )?;
         
@@ -359,19 +563,14 @@ impl Database {
         if let Some(row) = rows.next()? {
             // Parse status
             let status_str: String = row.get(2)?;
-            let status = match status_str.as_str() {
-                "InProgress" => CrawlStatus::InProgress,
-                "Completed" => CrawlStatus::Completed,
-                "Failed" => CrawlStatus::Failed,
-                "Verified" => CrawlStatus::Verified,
-                "Rejected" => CrawlStatus::Rejected,
-                _ => return Err(anyhow::anyhow!("Invalid crawl status: {}", status_str)),
-            };
+            let status: CrawlStatus = status_str.parse().map_err(anyhow::Error::msg)?;
             
             // Parse pages
             let pages_json: String = row.get(4)?;
             let pages: Vec<CrawledPage> = serde_json::from_str(&pages_json)
                 .with_context(|| format!("Failed to parse pages JSON for task {}", task_id))?;
+            let stats = parse_crawl_stats(row.get(10)?, row.get(11)?)
+                .with_context(|| format!("Failed to parse crawl stats for task {}", task_id))?;
             
             Ok(Some(CrawlResult {
                 task_id: row.get(0)?,
@@ -384,34 +583,64 @@ impl Database {
                 end_time: row.get(7)?,
                 transaction_hash: row.get(8)?,
                 incentives_received: row.get(9)?,
+                sitemap_urls_found: None,
+                bytes_saved: 0,
+                status_counts: stats.status_counts,
+                content_type_bytes: stats.content_type_bytes,
+                rendered_pages: 0,
+                time_limited: false,
             }))
         } else {
             Ok(None)
         }
     }
     
-    /// Get all crawl results
+    /// Breakdown of a task's crawl by status code and content type, `None` if the task has no result
+    pub fn crawl_stats(&self, task_id: &str) -> Result<Option<CrawlStats>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let columns = conn.query_row(
+            "SELECT status_counts, content_type_bytes FROM crawl_results WHERE task_id = ?",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        
+        match columns {
+            Some((status_counts, content_type_bytes)) => Ok(Some(parse_crawl_stats(status_counts, content_type_bytes)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Get all crawl results, newest first
     pub fn get_all_crawl_results(&self) -> Result<Vec<CrawlResult>> {
+        self.get_crawl_results_filtered(None, None, CrawlSort::Date, false)
+    }
+    
+    /// Get crawl results whose domain contains `domain` and whose status is `status`, sorted by `sort`
+    pub fn get_crawl_results_filtered(
+        &self,
+        domain: Option<&str>,
+        status: Option<&CrawlStatus>,
+        sort: CrawlSort,
+        ascending: bool,
+    ) -> Result<Vec<CrawlResult>> {
         let conn = self.conn.lock().unwrap();
         
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare(&format!(
             "SELECT task_id, domain, status, pages_count, pages, total_size,
                     start_time, end_time, transaction_hash, incentives_received
              FROM crawl_results
-             ORDER BY start_time DESC"
-        )?;
-        
-        let result_iter = stmt.query_map([], |row| {
+             WHERE (?1 IS NULL OR domain LIKE '%' || ?1 || '%')
+               AND (?2 IS NULL OR status = ?2)
+             ORDER BY {} {}, start_time DESC",
+            sort.column(),
+            if ascending { "ASC" } else { "DESC" },
+        ))?;
+        
+        let result_iter = stmt.query_map(params![domain, status.map(|s| s.to_string())], |row| {
             // Parse status
             let status_str: String = row.get(2)?;
-            let status = match status_str.as_str() {
-                "InProgress" => CrawlStatus::InProgress,
-                "Completed" => CrawlStatus::Completed,
-                "Failed" => CrawlStatus::Failed,
-                "Verified" => CrawlStatus::Verified,
-                "Rejected" => CrawlStatus::Rejected,
-                _ => CrawlStatus::Failed, // Default to failed for unknown status
-            };
+            let status = status_str.parse().unwrap_or(CrawlStatus::Failed); // Default to failed for unknown status
             
             // Parse pages
             let pages_json: String = row.get(4)?;
@@ -431,6 +660,12 @@ impl Database {
                 end_time: row.get(7)?,
                 transaction_hash: row.get(8)?,
                 incentives_received: row.get(9)?,
+                sitemap_urls_found: None,
+                bytes_saved: 0,
+                status_counts: HashMap::new(),
+                content_type_bytes: HashMap::new(),
+                rendered_pages: 0,
+                time_limited: false,
             })
         })?;
         
@@ -443,13 +678,15 @@ impl Database {
     }
     
     /// Add wallet history entry
+    ///
+    /// Returns `false` if an entry for the same task and transaction already exists.
     pub fn add_wallet_history(
         &self,
         task_id: &str,
         amount: i64,
         transaction_hash: &str,
         description: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let id = Uuid::new_v4().to_string();
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -458,8 +695,8 @@ impl Database {
         
         let conn = self.conn.lock().unwrap();
         
-        conn.execute(
-            "INSERT INTO wallet_history (
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO wallet_history (
                 id, task_id, amount, timestamp, transaction_hash, description
             ) VALUES (?, ?, ?, ?, ?, ?)",
             params![
@@ -472,9 +709,101 @@ impl Database {
             ],
         )?;
         
+        if inserted == 0 {
+            warn!("Wallet history for task {} and transaction {} already recorded", task_id, transaction_hash);
+        }
+        
+        Ok(inserted > 0)
+    }
+    
+    /// Check whether incentives were already recorded for a task's transaction
+    pub fn has_wallet_history(&self, task_id: &str, transaction_hash: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM wallet_history WHERE task_id = ? AND transaction_hash = ?",
+            params![task_id, transaction_hash],
+            |row| row.get(0),
+        )?;
+        
+        Ok(count > 0)
+    }
+    
+    /// Queue a report the manager could not be reached for, to be resent later
+    pub fn save_pending_report(&self, task_id: &str, payload: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT INTO pending_reports (task_id, payload) VALUES (?, ?)",
+            params![task_id, payload],
+        )?;
+        
+        info!("Queued report for task {} to be resent", task_id);
+        Ok(())
+    }
+    
+    /// Get queued reports, oldest first
+    pub fn get_pending_reports(&self) -> Result<Vec<PendingReport>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, payload FROM pending_reports ORDER BY id ASC"
+        )?;
+        
+        let reports = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(reports)
+    }
+    
+    /// Remove a queued report once it has been delivered
+    pub fn delete_pending_report(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute("DELETE FROM pending_reports WHERE id = ?", params![id])?;
+        
+        Ok(())
+    }
+    
+    /// Record a URL the crawl gave up on after `attempts` tries
+    pub fn save_failed_url(&self, task_id: &str, url: &str, attempts: u32, last_error: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT INTO failed_urls (task_id, url, attempts, last_error, failed_at) VALUES (?, ?, ?, ?, ?)",
+            params![task_id, url, attempts, last_error, unix_now() as i64],
+        )?;
+        
         Ok(())
     }
     
+    /// URLs a task's crawl gave up on, in the order it gave up
+    pub fn get_failed_urls(&self, task_id: &str) -> Result<Vec<FailedUrl>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT url, attempts, last_error FROM failed_urls WHERE task_id = ? ORDER BY id ASC"
+        )?;
+        
+        let failed = stmt.query_map(params![task_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(failed)
+    }
+    
+    /// Forget the URLs a task's crawl gave up on, before retrying them. Returns how many there were.
+    pub fn delete_failed_urls(&self, task_id: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        
+        let deleted = conn.execute("DELETE FROM failed_urls WHERE task_id = ?", params![task_id])?;
+        
+        Ok(deleted)
+    }
+    
     /// Get wallet history entries
     pub fn get_wallet_history(&self, limit: Option<usize>) -> Result<Vec<WalletHistoryEntry>> {
         let limit_clause = match limit {
@@ -575,24 +904,20 @@ impl Database {
         }
     }
 
-    /// Save a crawled page to the database with full HTML content
-    pub fn save_crawled_page(
-        &self,
-        task_id: &str,
-        url: &str,
-        domain: &str,
-        status: i32,
-        content_type: Option<&str>,
-        size: i64,
-        html: Option<&str>,
-        is_javascript_dependent: bool,
-        javascript_dependency_reasons: Option<String>,
-    ) -> Result<()> {
+    /// Save a crawled page to the database with full HTML content.
+    /// `page.url` is where the content was found after redirects; `page.requested_url` is the URL that was fetched.
+    pub fn save_crawled_page(&self, page: &PageRecord) -> Result<()> {
         // Convert boolean to integer
-        let js_dependent_int: i32 = if is_javascript_dependent { 1 } else { 0 };
+        let js_dependent_int: i32 = if page.is_javascript_dependent { 1 } else { 0 };
+        
+        // Keep metadata as JSON, leaving it NULL when the page declared none
+        let metadata_json = match page.metadata.as_ref().filter(|metadata| !metadata.is_empty()) {
+            Some(metadata) => Some(serde_json::to_string(metadata)?),
+            None => None,
+        };
         
         // Extract title from HTML if available
-        let title = match html {
+        let title = match &page.html {
             Some(content) => self.extract_title_from_html(content),
             None => None,
         };
@@ -602,29 +927,35 @@ impl Database {
         // Insert the page using UPSERT logic (INSERT OR REPLACE)
         conn.execute(
             "INSERT OR REPLACE INTO crawled_pages (
-                task_id, url, domain, status, content_type, title, size, html,
-                fetched_at, is_javascript_dependent, javascript_dependency_reasons
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, datetime('now'), ?, ?)",
+                task_id, url, requested_url, domain, status, content_type, title, size, html, text_content,
+                charset, metadata, fetched_at, is_javascript_dependent, javascript_dependency_reasons, depth, hops
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'), ?, ?, ?, ?)",
             params![
-                task_id,
-                url,
-                domain,
-                status,
-                content_type,
+                page.task_id,
+                page.url,
+                page.requested_url,
+                page.domain,
+                page.status,
+                page.content_type,
                 title,
-                size,
-                html,
+                page.size,
+                page.html,
+                page.text_content,
+                page.charset,
+                metadata_json,
                 js_dependent_int,
-                javascript_dependency_reasons,
+                page.javascript_dependency_reasons,
+                page.depth,
+                page.hops,
             ],
         ).context("Failed to save crawled page")?;
         
-        info!("Saved page to database: {}", url);
+        info!("Saved page to database: {}", page.url);
         Ok(())
     }
     
-    /// Update extracted links for a crawled page
-    pub fn update_crawled_page_links(&self, url: &str, links: &[String]) -> Result<()> {
+    /// Update extracted links for a page crawled by a task
+    pub fn update_crawled_page_links(&self, task_id: &str, url: &str, links: &[String]) -> Result<()> {
         // Convert links to JSON
         let links_json = serde_json::to_string(links)
             .context("Failed to convert links to JSON")?;
@@ -633,8 +964,8 @@ impl Database {
         
         // Update the page with the extracted links
         let rows_updated = conn.execute(
-            "UPDATE crawled_pages SET extracted_links = ? WHERE url = ?",
-            params![links_json, url],
+            "UPDATE crawled_pages SET extracted_links = ? WHERE task_id = ? AND url = ?",
+            params![links_json, task_id, url],
         ).context("Failed to update page links")?;
         
         if rows_updated > 0 {
@@ -646,6 +977,34 @@ impl Database {
         Ok(())
     }
 
+    /// Flag a page crawled by a task as a soft 404: a "not found" page served with a 2xx status
+    pub fn mark_soft_404(&self, task_id: &str, url: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "UPDATE crawled_pages SET soft_404 = 1 WHERE task_id = ? AND url = ?",
+            params![task_id, url],
+        ).context("Failed to flag soft 404")?;
+        
+        Ok(())
+    }
+    
+    /// Record the file an asset crawled by a task was saved to
+    pub fn update_crawled_page_asset(&self, task_id: &str, url: &str, asset_path: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        let rows_updated = conn.execute(
+            "UPDATE crawled_pages SET asset_path = ? WHERE task_id = ? AND url = ?",
+            params![asset_path, task_id, url],
+        ).context("Failed to update page asset path")?;
+        
+        if rows_updated == 0 {
+            warn!("No page found to record the asset of for URL: {}", url);
+        }
+        
+        Ok(())
+    }
+
     /// Add a crawled page to the database
     pub fn add_crawled_page(&self, task_id: &str, url: &str, domain: &str, status: i32, 
                             content_type: Option<&str>, title: Option<&str>, 
@@ -708,16 +1067,880 @@ impl Database {
         Ok(())
     }
     
-    /// Check if a URL is already in the crawled_pages table
-    pub fn is_url_crawled(&self, url: &str) -> Result<bool> {
+    /// Export a task's link graph as (from_url, to_url) edges.
+    /// Only links to pages crawled for the task are included unless `include_external` is set.
+    pub fn export_link_graph(&self, task_id: &str, include_external: bool) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT cp.url, link.value
+             FROM crawled_pages cp, json_each(cp.extracted_links) link
+             WHERE cp.task_id = ?1
+               AND (?2 OR link.value IN (SELECT url FROM crawled_pages WHERE task_id = ?1))
+             ORDER BY cp.url, link.value"
+        )?;
+        
+        let edges = stmt.query_map(params![task_id, include_external], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to export link graph for task {}", task_id))?;
+        
+        Ok(edges)
+    }
+    
+    /// Get a domain's cached robots.txt content and when it was fetched (Unix timestamp)
+    pub fn get_cached_robots(&self, domain: &str) -> Result<Option<(String, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT content, fetched_at FROM robots_cache WHERE domain = ?"
+        )?;
+        let mut rows = stmt.query(params![domain])?;
+        
+        match rows.next()? {
+            Some(row) => Ok(Some((row.get(0)?, row.get::<_, i64>(1)? as u64))),
+            None => Ok(None),
+        }
+    }
+    
+    /// Cache a domain's robots.txt content
+    pub fn save_cached_robots(&self, domain: &str, content: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT OR REPLACE INTO robots_cache (domain, content, fetched_at) VALUES (?, ?, ?)",
+            params![domain, content, unix_now() as i64],
+        ).with_context(|| format!("Failed to cache robots.txt for {}", domain))?;
+        
+        Ok(())
+    }
+    
+    /// Get a domain's cached sitemap URLs and when they were fetched (Unix timestamp)
+    pub fn get_cached_sitemap_urls(&self, domain: &str) -> Result<Option<(HashSet<String>, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT urls, fetched_at FROM sitemap_cache WHERE domain = ?"
+        )?;
+        let mut rows = stmt.query(params![domain])?;
+        
+        match rows.next()? {
+            Some(row) => {
+                let urls_json: String = row.get(0)?;
+                let urls = serde_json::from_str(&urls_json)
+                    .context("Failed to parse cached sitemap URLs")?;
+                Ok(Some((urls, row.get::<_, i64>(1)? as u64)))
+            },
+            None => Ok(None),
+        }
+    }
+    
+    /// Cache the URLs found in a domain's sitemaps
+    pub fn save_cached_sitemap_urls(&self, domain: &str, urls: &HashSet<String>) -> Result<()> {
+        let urls_json = serde_json::to_string(urls)
+            .context("Failed to convert sitemap URLs to JSON")?;
+        
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT OR REPLACE INTO sitemap_cache (domain, urls, fetched_at) VALUES (?, ?, ?)",
+            params![domain, urls_json, unix_now() as i64],
+        ).with_context(|| format!("Failed to cache sitemap URLs for {}", domain))?;
+        
+        Ok(())
+    }
+    
+    /// Get the description and Open Graph metadata a task stored for a page.
+    /// Returns `None` if the task didn't crawl the page; missing tags are `None` fields.
+    pub fn get_page_metadata(&self, task_id: &str, url: &str) -> Result<Option<PageMetadata>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT metadata FROM crawled_pages WHERE task_id = ? AND url = ?")?;
+        let mut rows = stmt.query(params![task_id, url])?;
+        
+        match rows.next()? {
+            Some(row) => {
+                let metadata: Option<String> = row.get(0)?;
+                let metadata = match metadata {
+                    Some(json) => serde_json::from_str(&json)
+                        .with_context(|| format!("Failed to parse metadata for {}", url))?,
+                    None => PageMetadata::default(),
+                };
+                Ok(Some(metadata))
+            },
+            None => Ok(None),
+        }
+    }
+    
+    /// Get a page a task stored, with its HTML, text and metadata
+    pub fn get_stored_page(&self, task_id: &str, url: &str) -> Result<Option<StoredPage>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT url, requested_url, task_id, domain, status, content_type, title, size, charset,
+                    fetched_at, depth, is_javascript_dependent, metadata, html, text_content, hops, asset_path,
+                    soft_404
+             FROM crawled_pages WHERE task_id = ? AND url = ?"
+        )?;
+        let mut rows = stmt.query(params![task_id, url])?;
+        
+        let row = match rows.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        
+        let metadata = match row.get::<_, Option<String>>(12)? {
+            Some(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse metadata for {}", url))?,
+            None => PageMetadata::default(),
+        };
+        
+        Ok(Some(StoredPage {
+            url: row.get(0)?,
+            requested_url: row.get(1)?,
+            task_id: row.get(2)?,
+            domain: row.get(3)?,
+            status: row.get(4)?,
+            content_type: row.get(5)?,
+            title: row.get(6)?,
+            size: row.get(7)?,
+            charset: row.get(8)?,
+            fetched_at: row.get(9)?,
+            depth: row.get(10)?,
+            is_javascript_dependent: row.get::<_, Option<i32>>(11)?.unwrap_or(0) != 0,
+            metadata,
+            html: row.get(13)?,
+            text_content: row.get(14)?,
+            hops: row.get(15)?,
+            asset_path: row.get(16)?,
+            soft_404: row.get::<_, Option<i32>>(17)?.unwrap_or(0) != 0,
+        }))
+    }
+    
+    /// Count a task's crawled pages at each depth
+    pub fn pages_by_depth(&self, task_id: &str) -> Result<HashMap<u32, usize>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT depth, COUNT(*) FROM crawled_pages
+             WHERE task_id = ? AND depth IS NOT NULL
+             GROUP BY depth"
+        )?;
+        
+        let histogram = stmt.query_map(params![task_id], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, i64>(1)? as usize))
+        })?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+        
+        Ok(histogram)
+    }
+    
+    /// Domains with the most crawled pages across all tasks, as (domain, pages, bytes),
+    /// ties broken by bytes
+    pub fn top_domains(&self, limit: usize) -> Result<Vec<(String, usize, u64)>> {
+        let conn = self.conn.lock().unwrap();
+        
+        // Grouping walks the domain index instead of sorting the whole table
+        let mut stmt = conn.prepare(
+            "SELECT domain, COUNT(*), COALESCE(SUM(size), 0)
+             FROM crawled_pages INDEXED BY idx_crawled_pages_domain
+             GROUP BY domain
+             ORDER BY COUNT(*) DESC, SUM(size) DESC, domain
+             LIMIT ?"
+        )?;
+        
+        let domains = stmt.query_map(params![limit as i64], |row| {
+            Ok((row.get(0)?, row.get::<_, i64>(1)? as usize, row.get::<_, i64>(2)? as u64))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(domains)
+    }
+    
+    /// Save a page fetched one hop outside the task scope, marked as external
+    pub fn save_external_page(&self, task_id: &str, page: &CrawledPage, title: Option<&str>) -> Result<()> {
+        let domain = Url::parse(&page.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+        
+        let conn = self.conn.lock().unwrap();
+        
+        conn.execute(
+            "INSERT OR REPLACE INTO crawled_pages (
+                task_id, url, domain, status, content_type, title, size, fetched_at, is_external
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'), 1)",
+            params![
+                task_id,
+                page.url,
+                domain,
+                page.status_code.map(|status| status as i32),
+                page.content_type,
+                title,
+                page.size as i64,
+            ],
+        ).with_context(|| format!("Failed to save external page {}", page.url))?;
+        
+        Ok(())
+    }
+    
+    /// Titles of a task's stored pages, keyed by URL
+    pub fn page_titles(&self, task_id: &str) -> Result<HashMap<String, String>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT url, title FROM crawled_pages WHERE task_id = ? AND title IS NOT NULL"
+        )?;
+        
+        let titles = stmt.query_map(params![task_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+        
+        Ok(titles)
+    }
+    
+    /// Every page a task has crawled, with its depth and the links found on it, in one query
+    pub fn get_crawled_urls(&self, task_id: &str) -> Result<Vec<CrawledUrl>> {
+        let conn = self.conn.lock().unwrap();
+        
+        let mut stmt = conn.prepare(
+            "SELECT url, depth, extracted_links FROM crawled_pages WHERE task_id = ? AND is_external = 0"
+        )?;
+        
+        let pages = stmt.query_map(params![task_id], |row| {
+            let links: Option<String> = row.get(2)?;
+            Ok((row.get(0)?, row.get(1)?, links))
+        })?
+        .map(|row| -> Result<CrawledUrl> {
+            let (url, depth, links) = row?;
+            let links = match links {
+                Some(json) => serde_json::from_str(&json)
+                    .with_context(|| format!("Failed to parse extracted links for {}", url))?,
+                None => Vec::new(),
+            };
+            Ok((url, depth, links))
+        })
+        .collect::<Result<Vec<_>>>()?;
+        
+        Ok(pages)
+    }
+    
+    /// Check if a task already crawled a URL
+    pub fn is_url_crawled(&self, task_id: &str, url: &str) -> Result<bool> {
         let conn = self.conn.lock().unwrap();
         
         let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM crawled_pages WHERE url = ?",
-            params![url],
+            "SELECT COUNT(*) FROM crawled_pages WHERE task_id = ? AND url = ?",
+            params![task_id, url],
             |row| row.get(0),
         )?;
         
         Ok(count > 0)
     }
-} 
\ No newline at end of file
+    
+    /// Import a JSONL crawl dump (one `CrawledPage` per line, as written to `data/crawls`) into
+    /// `crawled_pages` under `task_id`, then recompute the task's crawl result from the imported pages.
+    /// The task is created from the first page's origin if it doesn't exist. Malformed lines are skipped.
+    /// Gzipped dumps (`--compress`) are recognized by their magic bytes and decompressed on the fly.
+    pub fn import_jsonl<P: AsRef<Path>>(&self, task_id: &str, path: P) -> Result<JsonlImport> {
+        let path = path.as_ref();
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut file = BufReader::new(file);
+        let is_gzip = file.fill_buf()
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .starts_with(&[0x1f, 0x8b]);
+        let reader: Box<dyn BufRead> = if is_gzip {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(file)
+        };
+        
+        // Step 1: Parse the dump, remembering which lines couldn't be read
+        let mut pages = Vec::new();
+        let mut skipped = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            match serde_json::from_str::<CrawledPage>(&line) {
+                Ok(page) if Url::parse(&page.url).is_ok() => pages.push(page),
+                Ok(page) => {
+                    warn!("Skipping line {} of {}: invalid URL {}", line_number, path.display(), page.url);
+                    skipped.push(line_number);
+                },
+                Err(e) => {
+                    warn!("Skipping line {} of {}: {}", line_number, path.display(), e);
+                    skipped.push(line_number);
+                },
+            }
+        }
+        
+        // Step 2: Make sure the task exists, since pages and results reference it
+        let task = match self.get_task(task_id)? {
+            Some(task) => task,
+            None => {
+                let target_url = pages.first()
+                    .and_then(|page| Url::parse(&page.url).ok())
+                    .map(|url| url.origin().ascii_serialization())
+                    .ok_or_else(|| anyhow!("Task {} doesn't exist and {} has no pages to create it from", task_id, path.display()))?;
+                let task = Task::new(task_id.to_string(), target_url, 1, false, None, 0);
+                self.save_task(&task)?;
+                task
+            },
+        };
+        
+        // Step 3: Insert the pages
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            
+            for page in &pages {
+                let domain = Url::parse(&page.url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(|host| host.to_string()))
+                    .unwrap_or_default();
+                let is_html = page.content_type.as_deref().map(is_html_content_type).unwrap_or(true);
+                let html = page.body.as_deref().filter(|_| is_html);
+                let title = html.and_then(|html| self.extract_title_from_html(html));
+                
+                tx.execute(
+                    "INSERT OR REPLACE INTO crawled_pages (
+                        task_id, url, domain, status, content_type, title, size, html, fetched_at, is_external
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, datetime(?, 'unixepoch'), ?)",
+                    params![
+                        task_id,
+                        page.url,
+                        domain,
+                        page.status_code.map(|status| status as i32),
+                        page.content_type,
+                        title,
+                        page.size as i64,
+                        html,
+                        page.timestamp as i64,
+                        page.is_external,
+                    ],
+                ).with_context(|| format!("Failed to import page {}", page.url))?;
+            }
+            
+            tx.commit()?;
+        }
+        
+        // Step 4: Recompute the crawl result from the imported pages
+        let mut result = CrawlResult::new(task_id, &task.target_url);
+        let mut stats = CrawlStats::default();
+        for page in &pages {
+            stats.record(page);
+            result.total_size += page.size as u64;
+            result.pages.push(CrawledPage { body: None, ..page.clone() });
+        }
+        result.pages_count = pages.len();
+        result.set_stats(stats);
+        result.start_time = pages.iter().map(|page| page.timestamp).min().unwrap_or(result.start_time);
+        result.end_time = pages.iter().map(|page| page.timestamp).max().or(Some(result.start_time));
+        result.status = CrawlStatus::Completed;
+        
+        if self.get_crawl_result(task_id)?.is_some() {
+            self.update_crawl_result(&result)?;
+        } else {
+            self.save_crawl_result(&result)?;
+        }
+        
+        info!("Imported {} pages from {} into task {} ({} lines skipped)",
+            pages.len(), path.display(), task_id, skipped.len());
+        Ok(JsonlImport { imported: pages.len(), skipped_lines: skipped })
+    }
+    
+    /// Compare the pages of two crawls by URL: which URLs `task_b` added or lost
+    /// relative to `task_a`, and which changed size. Only differing URLs are loaded,
+    /// so large crawls are compared inside SQLite.
+    pub fn diff_crawls(&self, task_a: &str, task_b: &str) -> Result<CrawlDiff> {
+        for task_id in [task_a, task_b] {
+            if self.get_task(task_id)?.is_none() {
+                return Err(anyhow!("Task {} not found", task_id));
+            }
+        }
+        
+        let conn = self.conn.lock().unwrap();
+        
+        let only_in = |task_id: &str, other: &str| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(
+                "SELECT url FROM crawled_pages p
+                 WHERE task_id = ?1
+                   AND NOT EXISTS (SELECT 1 FROM crawled_pages o WHERE o.task_id = ?2 AND o.url = p.url)
+                 ORDER BY url"
+            )?;
+            let urls = stmt.query_map(params![task_id, other], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(urls)
+        };
+        let added = only_in(task_b, task_a)?;
+        let removed = only_in(task_a, task_b)?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT a.url, a.size, b.size FROM crawled_pages a
+             JOIN crawled_pages b ON b.task_id = ?2 AND b.url = a.url
+             WHERE a.task_id = ?1 AND a.size != b.size
+             ORDER BY a.url"
+        )?;
+        let size_changed = stmt.query_map(params![task_a, task_b], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(CrawlDiff { added, removed, size_changed })
+    }
+    
+    /// Delete a crawl: its task, crawl result, crawled pages, failed URLs and crawl reports, in one transaction.
+    /// Wallet history and queued reports are kept. Returns the number of rows deleted.
+    pub fn delete_crawl(&self, task_id: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        
+        let mut deleted = 0;
+        for table in ["crawled_pages", "failed_urls", "crawl_reports", "crawl_results"] {
+            deleted += tx.execute(&format!("DELETE FROM {} WHERE task_id = ?", table), params![task_id])
+                .with_context(|| format!("Failed to delete {} of task {}", table, task_id))?;
+        }
+        deleted += tx.execute("DELETE FROM tasks WHERE id = ?", params![task_id])
+            .with_context(|| format!("Failed to delete task {}", task_id))?;
+        
+        tx.commit()?;
+        
+        info!("Deleted task {} ({} rows)", task_id, deleted);
+        Ok(deleted)
+    }
+}
+
+/// Outcome of [`Database::import_jsonl`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonlImport {
+    /// Pages inserted into `crawled_pages`
+    pub imported: usize,
+    /// 1-based numbers of the lines that couldn't be parsed
+    pub skipped_lines: Vec<usize>,
+}
+
+/// Parse a task's stored content types, falling back to HTML for older rows
+fn content_types_from_column(value: Option<String>) -> Vec<String> {
+    value
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_else(default_allowed_content_types)
+}
+
+/// Current time as a Unix timestamp
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Parse the JSON stats columns of a crawl result; results saved before they existed have none
+fn parse_crawl_stats(status_counts: Option<String>, content_type_bytes: Option<String>) -> Result<CrawlStats> {
+    Ok(CrawlStats {
+        status_counts: match status_counts {
+            Some(json) => serde_json::from_str(&json)?,
+            None => HashMap::new(),
+        },
+        content_type_bytes: match content_type_bytes {
+            Some(json) => serde_json::from_str(&json)?,
+            None => HashMap::new(),
+        },
+    })
+}
+
+/// Add a column to an existing table if an older database doesn't have it yet
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    
+    if !exists {
+        info!("Adding column {}.{}", table, column);
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        ).with_context(|| format!("Failed to add column {}.{}", table, column))?;
+    }
+    
+    Ok(())
+}
+
+/// Rebuild `crawled_pages` keyed by task and URL if it still has the old URL-only key,
+/// under which a later crawl of a page replaced the row of the earlier one
+fn migrate_crawled_pages_key(conn: &Connection) -> Result<()> {
+    let schema: Option<String> = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'crawled_pages'",
+        [],
+        |row| row.get(0),
+    ).optional()?;
+    if !schema.is_some_and(|schema| schema.contains("UNIQUE(url)")) {
+        return Ok(());
+    }
+    
+    info!("Keying crawled_pages by task and URL");
+    // The view and indexes are recreated by init_tables once the table is rebuilt
+    conn.execute_batch(&format!(
+        "BEGIN;
+         DROP VIEW IF EXISTS v_crawled_pages;
+         ALTER TABLE crawled_pages RENAME TO crawled_pages_old;
+         {table};
+         INSERT INTO crawled_pages ({columns}) SELECT {columns} FROM crawled_pages_old;
+         DROP TABLE crawled_pages_old;
+         COMMIT;",
+        table = CRAWLED_PAGES_TABLE,
+        columns = CRAWLED_PAGES_COLUMNS,
+    )).context("Failed to rebuild crawled_pages")?;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::output::JsonlWriter;
+    use tempfile::TempDir;
+    
+    /// A database with every table created, stored in `dir`
+    pub(crate) fn test_db(dir: &TempDir) -> Database {
+        let db = Database::new(dir.path().join("crawler.db")).unwrap();
+        db.init_tables().unwrap();
+        db
+    }
+    
+    /// An HTML page of `size` bytes crawled by a task at `depth`
+    fn page(task_id: &str, url: &str, size: i64, depth: u32) -> PageRecord {
+        PageRecord {
+            task_id: task_id.to_string(),
+            url: url.to_string(),
+            domain: Url::parse(url).unwrap().host_str().unwrap().to_string(),
+            status: 200,
+            content_type: Some("text/html".to_string()),
+            size,
+            depth,
+            hops: depth,
+            ..PageRecord::default()
+        }
+    }
+    
+    #[test]
+    fn delete_crawl_removes_all_rows_of_the_task() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        for task_id in ["doomed", "kept"] {
+            let conn = db.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO tasks (id, url, max_depth, follow_subdomains, max_links, created_at, incentive_amount)
+                 VALUES (?, 'https://example.com', 1, 0, -1, 0, 0)",
+                params![task_id],
+            ).unwrap();
+            conn.execute(
+                "INSERT INTO crawl_results (task_id, domain, status, pages_count, pages, total_size, start_time)
+                 VALUES (?, 'example.com', 'Completed', 2, '[]', 10, 0)",
+                params![task_id],
+            ).unwrap();
+            conn.execute(
+                "INSERT INTO crawl_reports (task_id, pages_crawled, total_size_bytes, crawl_duration_ms, timestamp)
+                 VALUES (?, 2, 10, 100, 0)",
+                params![task_id],
+            ).unwrap();
+            drop(conn);
+            
+            for name in ["a", "b"] {
+                let url = format!("https://example.com/{}/{}", task_id, name);
+                db.save_crawled_page(&page(task_id, &url, 5, 1)).unwrap();
+            }
+        }
+        
+        // Task, result, report and two pages
+        assert_eq!(db.delete_crawl("doomed").unwrap(), 5);
+        assert!(db.get_crawl_result("doomed").unwrap().is_none());
+        assert!(db.pages_by_depth("doomed").unwrap().is_empty());
+        
+        let conn = db.conn.lock().unwrap();
+        let reports: i64 = conn
+            .query_row("SELECT COUNT(*) FROM crawl_reports WHERE task_id = 'doomed'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(reports, 0);
+        drop(conn);
+        
+        // Other tasks are untouched
+        assert!(db.get_crawl_result("kept").unwrap().is_some());
+        assert_eq!(db.pages_by_depth("kept").unwrap().get(&1), Some(&2));
+        
+        // Deleting again finds nothing
+        assert_eq!(db.delete_crawl("doomed").unwrap(), 0);
+    }
+    
+    #[test]
+    fn crawl_stats_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        db.conn.lock().unwrap().execute(
+            "INSERT INTO tasks (id, url, max_depth, follow_subdomains, max_links, created_at, incentive_amount)
+             VALUES ('task', 'https://example.com', 1, 0, -1, 0, 0)",
+            [],
+        ).unwrap();
+        
+        let mut result = CrawlResult::new("task", "example.com");
+        for (status, content_type, size) in [
+            (Some(200), Some("text/html; charset=utf-8"), 100),
+            (Some(200), Some("application/pdf"), 40),
+            (Some(404), Some("text/html"), 10),
+            (None, None, 0),
+        ] {
+            result.add_page(CrawledPage {
+                url: "https://example.com/".to_string(),
+                size,
+                timestamp: 0,
+                content_type: content_type.map(str::to_string),
+                status_code: status,
+                body: None,
+                is_external: false,
+            });
+        }
+        db.save_crawl_result(&result).unwrap();
+        
+        let stats = db.crawl_stats("task").unwrap().unwrap();
+        assert_eq!(stats.status_counts, HashMap::from([(200, 2), (404, 1)]));
+        assert_eq!(stats.content_type_bytes, HashMap::from([
+            ("text/html".to_string(), 110),
+            ("application/pdf".to_string(), 40),
+            ("unknown".to_string(), 0),
+        ]));
+        let loaded = db.get_crawl_result("task").unwrap().unwrap();
+        assert_eq!(loaded.status_counts, stats.status_counts);
+        assert_eq!(loaded.content_type_bytes, stats.content_type_bytes);
+        
+        assert!(db.crawl_stats("missing").unwrap().is_none());
+    }
+    
+    #[test]
+    fn top_domains_rank_by_pages_then_bytes() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        for (domain, path, size) in [
+            ("a.test", "1", 10),
+            ("a.test", "2", 10),
+            ("b.test", "1", 500),
+            ("c.test", "1", 100),
+            ("c.test", "2", 100),
+        ] {
+            let url = format!("https://{}/{}", domain, path);
+            db.save_crawled_page(&page("task", &url, size, 0)).unwrap();
+        }
+        
+        assert_eq!(db.top_domains(2).unwrap(), [
+            ("c.test".to_string(), 2, 200),
+            ("a.test".to_string(), 2, 20),
+        ]);
+        assert_eq!(db.top_domains(10).unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn cancelled_status_round_trips() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        db.conn.lock().unwrap().execute(
+            "INSERT INTO tasks (id, url, max_depth, follow_subdomains, max_links, created_at, incentive_amount)
+             VALUES ('task', 'https://example.com', 1, 0, -1, 0, 0)",
+            [],
+        ).unwrap();
+        
+        let mut result = CrawlResult::new("task", "example.com");
+        result.set_cancelled();
+        db.save_crawl_result(&result).unwrap();
+        
+        let loaded = db.get_crawl_result("task").unwrap().unwrap();
+        assert_eq!(loaded.status, CrawlStatus::Cancelled);
+        assert!(loaded.end_time.is_some());
+        
+        let filtered = db.get_crawl_results_filtered(None, Some(&CrawlStatus::Cancelled), CrawlSort::default(), false).unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+    
+    #[test]
+    fn import_jsonl_skips_malformed_lines_and_summarizes_the_crawl() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        let dump = dir.path().join("crawl.jsonl");
+        fs::write(&dump, [
+            r#"{"url":"https://example.com/","size":40,"timestamp":100,"content_type":"text/html","status_code":200,"body":"<html><title>Home</title></html>"}"#,
+            "{not json",
+            "",
+            r#"{"url":"https://example.com/missing","size":0,"timestamp":160,"content_type":"text/html","status_code":404,"body":null}"#,
+            r#"{"url":"not a url","size":1,"timestamp":0,"content_type":null,"status_code":null,"body":null}"#,
+        ].join("\n")).unwrap();
+        
+        let import = db.import_jsonl("old", &dump).unwrap();
+        assert_eq!(import, JsonlImport { imported: 2, skipped_lines: vec![2, 5] });
+        
+        let task = db.get_task("old").unwrap().unwrap();
+        assert_eq!(task.target_url, "https://example.com");
+        assert_eq!(db.page_titles("old").unwrap().get("https://example.com/").map(String::as_str), Some("Home"));
+        
+        let result = db.get_crawl_result("old").unwrap().unwrap();
+        assert_eq!(result.pages_count, 2);
+        assert_eq!(result.total_size, 40);
+        assert_eq!((result.start_time, result.end_time), (100, Some(160)));
+        assert_eq!(result.status_counts, HashMap::from([(200, 1), (404, 1)]));
+        
+        // Importing again replaces the rows instead of duplicating them
+        db.import_jsonl("old", &dump).unwrap();
+        assert_eq!(db.get_crawl_result("old").unwrap().unwrap().pages_count, 2);
+        let rows: i64 = db.conn.lock().unwrap()
+            .query_row("SELECT COUNT(*) FROM crawled_pages WHERE task_id = 'old'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 2);
+    }
+    
+    #[test]
+    fn import_jsonl_reads_gzipped_dumps() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        let dump = dir.path().join("crawl.jsonl.gz");
+        let writer = JsonlWriter::create(&dump, true).unwrap();
+        for (url, status) in [("https://example.com/", 200), ("https://example.com/gone", 404)] {
+            writer.write_page(&CrawledPage {
+                url: url.to_string(),
+                size: 10,
+                timestamp: 100,
+                content_type: Some("text/html".to_string()),
+                status_code: Some(status),
+                body: None,
+                is_external: false,
+            }).unwrap();
+        }
+        writer.finish().unwrap();
+        
+        let import = db.import_jsonl("gz", &dump).unwrap();
+        assert_eq!(import, JsonlImport { imported: 2, skipped_lines: vec![] });
+        assert_eq!(db.get_crawl_result("gz").unwrap().unwrap().status_counts, HashMap::from([(200, 1), (404, 1)]));
+    }
+    
+    #[test]
+    fn failed_urls_are_kept_per_task_until_retried() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        db.save_failed_url("flaky", "https://example.com/a", 4, "HTTP 503").unwrap();
+        db.save_failed_url("flaky", "https://example.com/b", 4, "connection reset").unwrap();
+        db.save_failed_url("other", "https://example.com/c", 2, "HTTP 500").unwrap();
+        
+        assert_eq!(db.get_failed_urls("flaky").unwrap(), vec![
+            ("https://example.com/a".to_string(), 4, "HTTP 503".to_string()),
+            ("https://example.com/b".to_string(), 4, "connection reset".to_string()),
+        ]);
+        
+        assert_eq!(db.delete_failed_urls("flaky").unwrap(), 2);
+        assert!(db.get_failed_urls("flaky").unwrap().is_empty());
+        assert_eq!(db.get_failed_urls("other").unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn crawled_urls_come_with_their_depth_and_links() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        db.save_task(&Task::new("halfway".to_string(), "https://example.com".to_string(), 3, false, None, 0)).unwrap();
+        for (url, depth) in [("https://example.com/", 0), ("https://example.com/docs", 1)] {
+            db.save_crawled_page(&page("halfway", url, 10, depth)).unwrap();
+        }
+        db.update_crawled_page_links("halfway", "https://example.com/", &[
+            "https://example.com/docs".to_string(),
+            "https://example.com/blog".to_string(),
+        ]).unwrap();
+        
+        let mut crawled = db.get_crawled_urls("halfway").unwrap();
+        crawled.sort();
+        assert_eq!(crawled, vec![
+            ("https://example.com/".to_string(), Some(0), vec![
+                "https://example.com/docs".to_string(),
+                "https://example.com/blog".to_string(),
+            ]),
+            ("https://example.com/docs".to_string(), Some(1), Vec::new()),
+        ]);
+        assert!(db.get_crawled_urls("other").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn diff_crawls_compares_pages_by_url() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir);
+        
+        let crawls = [
+            ("monday", vec![("https://example.com/", 100), ("https://example.com/old", 20), ("https://example.com/same", 5)]),
+            ("tuesday", vec![("https://example.com/", 120), ("https://example.com/new", 30), ("https://example.com/same", 5)]),
+        ];
+        for (task_id, pages) in &crawls {
+            db.save_task(&Task::new(task_id.to_string(), "https://example.com".to_string(), 2, false, None, 0)).unwrap();
+            for (url, size) in pages {
+                db.save_crawled_page(&page(task_id, url, *size, 1)).unwrap();
+            }
+        }
+        
+        let diff = db.diff_crawls("monday", "tuesday").unwrap();
+        assert_eq!(diff.added, vec!["https://example.com/new"]);
+        assert_eq!(diff.removed, vec!["https://example.com/old"]);
+        assert_eq!(diff.size_changed, vec![("https://example.com/".to_string(), 100, 120)]);
+        
+        assert!(db.diff_crawls("monday", "monday").unwrap().is_empty());
+        assert!(db.diff_crawls("monday", "missing").is_err());
+    }
+    
+    #[test]
+    fn url_keyed_crawled_pages_are_rebuilt_per_task() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::new(dir.path().join("crawler.db")).unwrap();
+        db.conn.lock().unwrap().execute_batch(
+            "CREATE TABLE crawled_pages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                domain TEXT NOT NULL,
+                status INTEGER,
+                content_type TEXT,
+                title TEXT,
+                size INTEGER NOT NULL,
+                html TEXT,
+                fetched_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                is_javascript_dependent INTEGER DEFAULT 0,
+                javascript_dependency_reasons TEXT,
+                extracted_links TEXT,
+                UNIQUE(url)
+            );
+            INSERT INTO crawled_pages (task_id, url, domain, size) VALUES ('monday', 'https://example.com/', 'example.com', 100);"
+        ).unwrap();
+        
+        db.init_tables().unwrap();
+        db.save_crawled_page(&page("tuesday", "https://example.com/", 120, 0)).unwrap();
+        
+        // The second crawl no longer replaces the first one's row
+        let conn = db.conn.lock().unwrap();
+        let sizes: Vec<(String, i64)> = conn
+            .prepare("SELECT task_id, size FROM crawled_pages ORDER BY id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(sizes, vec![("monday".to_string(), 100), ("tuesday".to_string(), 120)]);
+        drop(conn);
+        
+        // Lookups find the page of the task asked for
+        assert_eq!(db.get_stored_page("monday", "https://example.com/").unwrap().unwrap().size, 100);
+        assert_eq!(db.get_stored_page("tuesday", "https://example.com/").unwrap().unwrap().size, 120);
+        assert!(db.get_page_metadata("tuesday", "https://example.com/").unwrap().is_some());
+        assert!(db.is_url_crawled("monday", "https://example.com/").unwrap());
+        assert!(!db.is_url_crawled("wednesday", "https://example.com/").unwrap());
+    }
+}
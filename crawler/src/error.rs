@@ -0,0 +1,87 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use thiserror::Error;
+use crate::solana::SolanaError;
+
+/// Errors returned by the crawler's HTTP API, each mapped to its own status code.
+/// Handlers keep using `anyhow` internally; errors are translated here at the boundary.
+#[derive(Debug, Error)]
+pub enum CrawlerError {
+    /// The request names a URL that can't be crawled
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    
+    /// The request is malformed
+    #[error("{0}")]
+    BadRequest(String),
+    
+    /// The requested task or page does not exist
+    #[error("{0}")]
+    NotFound(String),
+    
+    /// The Solana RPC endpoint failed or rejected a request
+    #[error(transparent)]
+    Solana(#[from] SolanaError),
+    
+    /// Anything else that went wrong on our side
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl CrawlerError {
+    /// HTTP status returned for this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            CrawlerError::InvalidUrl(_) | CrawlerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            CrawlerError::NotFound(_) => StatusCode::NOT_FOUND,
+            CrawlerError::Solana(_) => StatusCode::BAD_GATEWAY,
+            CrawlerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    
+    /// Machine-readable error type sent in the response body
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            CrawlerError::InvalidUrl(_) => "invalid_url",
+            CrawlerError::BadRequest(_) => "bad_request",
+            CrawlerError::NotFound(_) => "not_found",
+            CrawlerError::Solana(_) => "solana_error",
+            CrawlerError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl IntoResponse for CrawlerError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": {
+                "type": self.error_type(),
+                "message": self.to_string(),
+            }
+        });
+        
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+impl From<anyhow::Error> for CrawlerError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<SolanaError>() {
+            Ok(e) => return CrawlerError::Solana(e),
+            Err(err) => err,
+        };
+        
+        if let Some(e) = err.downcast_ref::<url::ParseError>() {
+            return CrawlerError::InvalidUrl(e.to_string());
+        }
+        if let Some(rusqlite::Error::QueryReturnedNoRows) = err.downcast_ref::<rusqlite::Error>() {
+            return CrawlerError::NotFound(err.to_string());
+        }
+        
+        CrawlerError::Internal(err.to_string())
+    }
+}
@@ -1,13 +1,18 @@
 use anyhow::{Result, anyhow};
 use chromiumoxide::{Browser, BrowserConfig, Element, Page};
+use chromiumoxide::cdp::browser_protocol::network::{Cookie, CookieParam, Headers, SetCookiesParams, SetExtraHttpHeadersParams, TimeSinceEpoch};
 use futures::StreamExt;
 use log::{info, warn, debug, error};
 use std::time::Duration;
 use tokio::time::timeout;
 use url::Url;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::collections::HashSet;
 use std::process::Command;
+use crate::models::{Credentials, LoginSelectors};
+
+/// Longest a login, from opening the form to the page after submitting it, may take
+const LOGIN_TIMEOUT_SECS: u64 = 30;
 
 /// HeadlessBrowser provides browser automation for JavaScript-heavy sites
 #[derive(Clone)]
@@ -16,6 +21,13 @@ pub struct HeadlessBrowser {
     browser: Option<Arc<Browser>>,
     /// Whether the browser is currently running
     is_running: bool,
+    /// Cookies set on every page this browser opens: those from `login` or `set_cookies`,
+    /// plus those pages set when `keep_cookies` is on
+    cookies: Arc<RwLock<Vec<CookieParam>>>,
+    /// Whether cookies set by one page are carried to the next; pages are incognito otherwise
+    keep_cookies: bool,
+    /// Whether Chrome is launched with `--ignore-certificate-errors`
+    ignore_certificate_errors: bool,
 }
 
 impl Default for HeadlessBrowser {
@@ -23,6 +35,9 @@ impl Default for HeadlessBrowser {
         Self {
             browser: None,
             is_running: false,
+            cookies: Arc::new(RwLock::new(Vec::new())),
+            keep_cookies: false,
+            ignore_certificate_errors: false,
         }
     }
 }
@@ -33,6 +48,55 @@ impl HeadlessBrowser {
         Self::default()
     }
     
+    /// Carry cookies set by each page to the pages opened after it, so session-based
+    /// sites don't re-challenge on every page
+    pub fn with_cookie_persistence(mut self, enabled: bool) -> Self {
+        self.keep_cookies = enabled;
+        self
+    }
+    
+    /// Launch Chrome with `--ignore-certificate-errors`, for internal sites with self-signed certificates
+    pub fn with_ignore_certificate_errors(mut self, enabled: bool) -> Self {
+        self.ignore_certificate_errors = enabled;
+        self
+    }
+    
+    /// Cookies currently sent with every page this browser opens
+    pub fn get_cookies(&self) -> Vec<CookieParam> {
+        self.cookies.read()
+            .map(|cookies| cookies.clone())
+            .unwrap_or_default()
+    }
+    
+    /// Replace the cookies sent with every page this browser opens
+    pub fn set_cookies(&self, cookies: Vec<CookieParam>) {
+        if let Ok(mut jar) = self.cookies.write() {
+            *jar = cookies;
+        }
+    }
+    
+    /// Add the cookies a page ended up with to the jar, replacing older ones with the same name, domain and path
+    async fn keep_page_cookies(&self, page: &Page) {
+        if !self.keep_cookies {
+            return;
+        }
+        
+        let cookies = match page.get_cookies().await {
+            Ok(cookies) => cookies,
+            Err(e) => {
+                debug!("Failed to read page cookies: {}", e);
+                return;
+            }
+        };
+        
+        if let Ok(mut jar) = self.cookies.write() {
+            for cookie in cookies.into_iter().map(cookie_param) {
+                jar.retain(|kept| !(kept.name == cookie.name && kept.domain == cookie.domain && kept.path == cookie.path));
+                jar.push(cookie);
+            }
+        }
+    }
+    
     /// Start the browser
     pub async fn start(&mut self) -> Result<()> {
         if self.is_running {
@@ -41,19 +105,25 @@ impl HeadlessBrowser {
         
         info!("Starting headless Chrome browser");
         
+        let mut args = vec![
+            "--disable-web-security", // Disable CORS for easier crawling
+            "--disable-extensions",   // No extensions needed
+            "--disable-gpu",          // Better compatibility
+            "--disable-dev-shm-usage", // Avoid crashes in constrained environments
+            "--disable-setuid-sandbox", // Additional sandbox flexibility
+            "--no-first-run",         // Skip first run tasks
+            "--no-zygote"             // More robust launching
+        ];
+        if self.ignore_certificate_errors {
+            warn!("Headless Chrome will ignore TLS certificate errors");
+            args.push("--ignore-certificate-errors");
+        }
+        
         // Create browser config with more robust settings
         let config = BrowserConfig::builder()
             .no_sandbox() // Often needed in Docker or CI environments
             .incognito() // Use incognito mode to avoid cache/cookies between sessions
-            .args(vec![
-                "--disable-web-security", // Disable CORS for easier crawling
-                "--disable-extensions",   // No extensions needed
-                "--disable-gpu",          // Better compatibility
-                "--disable-dev-shm-usage", // Avoid crashes in constrained environments
-                "--disable-setuid-sandbox", // Additional sandbox flexibility
-                "--no-first-run",         // Skip first run tasks
-                "--no-zygote"             // More robust launching
-            ])
+            .args(args)
             .build()
             .map_err(|e| anyhow!("Failed to build browser config: {}", e))?;
         
@@ -144,8 +214,77 @@ impl HeadlessBrowser {
         Ok(())
     }
     
+    /// Log in through the form at `url` and keep the session cookies, so pages this browser
+    /// opens afterwards are authenticated. Credentials are typed into the form and never logged.
+    pub async fn login(&self, url: &Url, selectors: &LoginSelectors, credentials: &Credentials) -> Result<()> {
+        let browser_instance = self.browser.as_ref()
+            .ok_or_else(|| anyhow!("Browser not started"))?;
+        
+        info!("Logging in at {}", url);
+        
+        let cookies = timeout(Duration::from_secs(LOGIN_TIMEOUT_SECS), async {
+            let page = browser_instance.new_page(url.as_str()).await
+                .map_err(|e| anyhow!("Failed to open login page: {}", e))?;
+            
+            let result = Self::submit_login_form(&page, selectors, credentials).await;
+            let cookies = match result {
+                Ok(()) => page.get_cookies().await
+                    .map_err(|e| anyhow!("Failed to read session cookies: {}", e)),
+                Err(e) => Err(e),
+            };
+            
+            if let Err(e) = page.close().await {
+                warn!("Error closing page: {}", e);
+            }
+            
+            cookies
+        }).await
+            .map_err(|_| anyhow!("Timeout logging in at {}", url))??;
+        
+        if cookies.is_empty() {
+            warn!("Login at {} set no cookies, later pages may not be authenticated", url);
+        }
+        info!("Logged in at {}, keeping {} session cookies", url, cookies.len());
+        
+        self.set_cookies(cookies.into_iter().map(cookie_param).collect());
+        
+        Ok(())
+    }
+    
+    /// Fill in and submit a login form on an open page, then wait for the page it leads to
+    async fn submit_login_form(page: &Page, selectors: &LoginSelectors, credentials: &Credentials) -> Result<()> {
+        if timeout(Duration::from_secs(10), page.wait_for_navigation()).await.is_err() {
+            warn!("Timeout waiting for the login page, will try to fill the form anyway");
+        }
+        
+        let username = page.find_element(selectors.username.as_str()).await
+            .map_err(|e| anyhow!("Username field '{}' not found: {}", selectors.username, e))?;
+        username.click().await
+            .map_err(|e| anyhow!("Failed to focus username field: {}", e))?;
+        username.type_str(&credentials.username).await
+            .map_err(|e| anyhow!("Failed to type username: {}", e))?;
+        
+        let password = page.find_element(selectors.password.as_str()).await
+            .map_err(|e| anyhow!("Password field '{}' not found: {}", selectors.password, e))?;
+        password.click().await
+            .map_err(|e| anyhow!("Failed to focus password field: {}", e))?;
+        password.type_str(&credentials.password).await
+            .map_err(|e| anyhow!("Failed to type password: {}", e))?;
+        
+        page.find_element(selectors.submit.as_str()).await
+            .map_err(|e| anyhow!("Submit button '{}' not found: {}", selectors.submit, e))?
+            .click().await
+            .map_err(|e| anyhow!("Failed to submit login form: {}", e))?;
+        
+        if timeout(Duration::from_secs(10), page.wait_for_navigation()).await.is_err() {
+            warn!("Timeout waiting for the page after login");
+        }
+        
+        Ok(())
+    }
+    
     /// Extract links from a JavaScript-heavy page
-    pub async fn extract_links(browser: Arc<HeadlessBrowser>, url: &Url, wait_time_secs: u64) -> Result<Vec<Url>> {
+    pub async fn extract_links(browser: Arc<HeadlessBrowser>, url: &Url, wait_time_secs: u64, accept_language: Option<&str>) -> Result<Vec<Url>> {
         let browser_instance = browser.browser.as_ref()
             .ok_or_else(|| anyhow!("Browser not started"))?;
             
@@ -154,10 +293,7 @@ impl HeadlessBrowser {
         // Use a shorter overall timeout
         let total_timeout = timeout(Duration::from_secs(wait_time_secs + 5), async {
             // Create a new page with error handling
-            let page = match browser_instance.new_page(url.as_str()).await {
-                Ok(page) => page,
-                Err(e) => return Err(anyhow!("Failed to create new page: {}", e)),
-            };
+            let page = browser.open_page(browser_instance, url, accept_language).await?;
             
             // Set a reasonable timeout for navigation
             let timeout_duration = Duration::from_secs(wait_time_secs.max(3));
@@ -189,6 +325,8 @@ impl HeadlessBrowser {
                 }
             };
             
+            browser.keep_page_cookies(&page).await;
+            
             // Close the page immediately to free resources
             if let Err(e) = page.close().await {
                 warn!("Error closing page: {}", e);
@@ -207,7 +345,7 @@ impl HeadlessBrowser {
     }
     
     /// Extract content from a JavaScript-heavy page
-    pub async fn extract_content(browser: Arc<HeadlessBrowser>, url: &Url, wait_time_secs: u64) -> Result<String> {
+    pub async fn extract_content(browser: Arc<HeadlessBrowser>, url: &Url, wait_time_secs: u64, accept_language: Option<&str>) -> Result<String> {
         let browser_instance = browser.browser.as_ref()
             .ok_or_else(|| anyhow!("Browser not started"))?;
             
@@ -216,10 +354,7 @@ impl HeadlessBrowser {
         // Use a shorter overall timeout
         let total_timeout = timeout(Duration::from_secs(wait_time_secs + 5), async {
             // Create a new page with error handling
-            let page = match browser_instance.new_page(url.as_str()).await {
-                Ok(page) => page,
-                Err(e) => return Err(anyhow!("Failed to create new page: {}", e)),
-            };
+            let page = browser.open_page(browser_instance, url, accept_language).await?;
             
             // Set a reasonable timeout for navigation
             let timeout_duration = Duration::from_secs(wait_time_secs.max(3));
@@ -267,6 +402,8 @@ impl HeadlessBrowser {
                 },
             };
             
+            browser.keep_page_cookies(&page).await;
+            
             // Close the page immediately to free resources
             if let Err(e) = page.close().await {
                 warn!("Error closing page: {}", e);
@@ -284,6 +421,35 @@ impl HeadlessBrowser {
         }
     }
     
+    /// Open a page at `url`, sending `accept_language` with its requests if set,
+    /// along with the cookies in the jar
+    async fn open_page(&self, browser: &Browser, url: &Url, accept_language: Option<&str>) -> Result<Page> {
+        let cookies = self.get_cookies();
+        if accept_language.is_none() && cookies.is_empty() {
+            return browser.new_page(url.as_str()).await
+                .map_err(|e| anyhow!("Failed to create new page: {}", e));
+        }
+        
+        // Set the header and cookies on a blank page before navigating so the first request carries them
+        let page = browser.new_page("about:blank").await
+            .map_err(|e| anyhow!("Failed to create new page: {}", e))?;
+        if let Some(accept_language) = accept_language {
+            let headers = Headers::new(serde_json::json!({ "Accept-Language": accept_language }));
+            if let Err(e) = page.execute(SetExtraHttpHeadersParams::new(headers)).await {
+                warn!("Failed to set Accept-Language for {}: {}", url, e);
+            }
+        }
+        if !cookies.is_empty() {
+            if let Err(e) = page.execute(SetCookiesParams::new(cookies)).await {
+                warn!("Failed to set cookies for {}: {}", url, e);
+            }
+        }
+        page.goto(url.as_str()).await
+            .map_err(|e| anyhow!("Failed to navigate to {}: {}", url, e))?;
+        
+        Ok(page)
+    }
+    
     /// Static version of extract_links_from_page that doesn't need &self
     async fn extract_links_from_page_static(page: &Page) -> Result<Vec<Url>> {
         let base_url_str = page.url().await
@@ -383,4 +549,18 @@ impl Drop for HeadlessBrowser {
             // We can't do async operations in Drop, so we just log a warning
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Turn a cookie read from a page into one that can be set on another
+fn cookie_param(cookie: Cookie) -> CookieParam {
+    let mut param = CookieParam::new(cookie.name, cookie.value);
+    param.domain = Some(cookie.domain);
+    param.path = Some(cookie.path);
+    param.secure = Some(cookie.secure);
+    param.http_only = Some(cookie.http_only);
+    param.same_site = cookie.same_site;
+    if !cookie.session {
+        param.expires = Some(TimeSinceEpoch::new(cookie.expires));
+    }
+    param
+}
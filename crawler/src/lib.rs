@@ -6,12 +6,26 @@ pub mod solana;
 pub mod service;
 pub mod robots;
 pub mod headless;
+pub mod error;
+pub mod metrics;
+pub mod logging;
+pub mod rate_limit;
+pub mod output;
+pub mod storage;
+pub mod soft404;
+pub mod visited;
 
 
 // Re-export important types
-pub use models::{Task, CrawlResult};
-pub use solana::SolanaIntegration;
-pub use crawler::Crawler;
-pub use service::CrawlerService;
-pub use robots::{RobotsManager, is_javascript_dependent};
-pub use headless::HeadlessBrowser; 
\ No newline at end of file
+pub use models::{Task, CrawlResult, CrawlPlan};
+pub use solana::{SolanaIntegration, SolanaError};
+pub use crawler::{Crawler, CrawlProfile, CrawlProgress, CrawlState, JsDetection, LinkSelector, RenderPolicy, RobotsEnforcement, TrapGuard};
+pub use service::{ActiveHours, CrawlerService};
+pub use robots::{JsAssessment, ROBOTS_PRODUCT_TOKEN, RobotsManager, assess_javascript_dependence, is_javascript_dependent};
+pub use headless::HeadlessBrowser;
+pub use rate_limit::RequestBudget;
+pub use output::JsonlWriter;
+pub use soft404::Soft404Detector;
+pub use visited::VisitedSet;
+pub use storage::{CompositeSink, FileSink, PageRecord, StorageRoute, StorageRouter, StorageSink};
+pub use error::CrawlerError;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use clap::ValueEnum;
+use log::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+/// How log lines are written to stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines prefixed with their task and worker spans
+    #[default]
+    Text,
+    /// One JSON object per line, with the current span and its parents, for log ingestion
+    Json,
+}
+
+/// Install the global `tracing` subscriber.
+///
+/// `RUST_LOG` takes precedence over `level` when set, with the usual `env_logger` syntax
+/// (e.g. `info,cryptocrawl_crawler::robots=debug`). Records from the `log` crate are
+/// forwarded, so they show up inside the task and worker spans as well.
+pub fn init(level: LevelFilter, format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(level.to_string().to_lowercase()));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+    }
+}
@@ -6,19 +6,29 @@ mod solana;
 mod ui;
 mod robots;
 mod headless;
+mod error;
+mod metrics;
+mod logging;
+mod rate_limit;
+mod output;
+mod storage;
+mod soft404;
+mod visited;
 
 use anyhow::{Result, Context};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use log::{info, error, LevelFilter};
 use std::path::{PathBuf, Path};
 use std::fs;
-use crawler::Crawler;
-use db::Database;
-use service::CrawlerService;
+use std::sync::Arc;
+use crawler::{Crawler, CrawlProfile, JsDetection, LinkSelector, RenderPolicy, RobotsEnforcement, TrapGuard};
+use db::{CrawlDiff, Database};
+use storage::{CompositeSink, FileSink, StorageRoute, StorageSink};
+use service::{ActiveHours, CrawlerService};
 use solana::SolanaIntegration;
 use uuid::Uuid;
 use reqwest::Client;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Command line arguments
 #[derive(Parser)]
@@ -32,10 +42,14 @@ struct Args {
     #[clap(short, long, default_value = "data/crawler.db")]
     db_path: PathBuf,
     
-    /// Log level
+    /// Log level (RUST_LOG overrides it when set)
     #[clap(short, long, default_value = "info")]
     log_level: LevelFilter,
     
+    /// Log output format
+    #[clap(long, value_enum, default_value = "text")]
+    log_format: logging::LogFormat,
+    
     /// Client ID (generates a new one if not provided)
     #[clap(short = 'i', long)]
     client_id: Option<String>,
@@ -67,6 +81,38 @@ struct Args {
     /// Poll interval in seconds
     #[clap(short = 't', long, default_value = "60")]
     poll_interval: u64,
+    
+    /// Retries after a failed submission of a crawl report to Solana (0 for none)
+    #[clap(long, default_value = "2")]
+    submit_retries: u32,
+    
+    /// API key used to register with the manager
+    #[clap(long)]
+    api_key: Option<String>,
+    
+    /// Maximum number of tasks the service crawls at the same time
+    #[clap(long, default_value = "1")]
+    max_concurrent_tasks: usize,
+    
+    /// Seconds between heartbeats sent to the manager
+    #[clap(long, default_value = "30")]
+    heartbeat_interval: u64,
+    
+    /// Number of attempts to reach the manager when submitting a crawl report
+    #[clap(long, default_value = "3")]
+    report_retries: u32,
+    
+    /// Local hours during which the service takes new tasks, e.g. 22-6; any time when unset
+    #[clap(long)]
+    active_hours: Option<ActiveHours>,
+    
+    /// Politeness profile: worker count, request delay, per-host concurrency and retries
+    #[clap(long, value_enum, default_value = "normal")]
+    profile: CrawlProfile,
+    
+    /// Most page requests per second across all the service's crawls; unlimited when unset
+    #[clap(long)]
+    max_requests_per_second: Option<u32>,
 }
 
 /// Subcommands
@@ -111,13 +157,255 @@ enum Command {
         #[clap(short, long)]
         follow_subdomains: bool,
         
+        /// Hosts to crawl: host, domain (host and subdomains) or registrable-domain; overrides --follow-subdomains
+        #[clap(long)]
+        scope: Option<models::CrawlScope>,
+        
         /// Maximum links to follow
         #[clap(short = 'l', long)]
         max_links: Option<usize>,
         
+        /// File of additional start URLs, one per line; invalid lines are skipped
+        #[clap(long)]
+        seeds: Option<PathBuf>,
+        
         /// Use headless Chrome for JavaScript sites
         #[clap(long)]
         use_headless_chrome: bool,
+        
+        /// Also render JavaScript-dependent pages whose URL contains this substring (repeatable)
+        #[clap(long = "render-pattern")]
+        render_patterns: Vec<String>,
+        
+        /// Percentage of the other JavaScript-dependent pages to render
+        #[clap(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100))]
+        render_sample: u8,
+        
+        /// JavaScript indicators a page needs before it counts as JavaScript-dependent
+        #[clap(long, default_value = "1")]
+        js_min_reasons: usize,
+        
+        /// Confidence (0-1) a JavaScript-dependent page needs before it is rendered
+        #[clap(long, default_value = "0.25")]
+        js_min_score: f64,
+        
+        /// Crawl pages even if robots.txt disallows them
+        #[clap(long)]
+        ignore_robots: bool,
+        
+        /// Ignore robots.txt only for this domain and its subdomains, e.g. a site you own (repeatable)
+        #[clap(long = "robots-override")]
+        robots_override_domains: Vec<String>,
+        
+        /// Store the readable text of each page
+        #[clap(long)]
+        extract_text: bool,
+        
+        /// Store the raw HTML of each page
+        #[clap(long)]
+        store_html: bool,
+        
+        /// Additional content type to store besides HTML, e.g. application/json (repeatable)
+        #[clap(long = "content-type")]
+        content_types: Vec<String>,
+        
+        /// Maximum number of redirects followed for a single page
+        #[clap(long, default_value = "10")]
+        max_redirects: usize,
+        
+        /// Count each redirect towards the depth limit, not just each followed link
+        #[clap(long)]
+        redirects_count_as_depth: bool,
+        
+        /// Elements to read links from (repeatable); anchors and rel="next" when unset
+        #[clap(long = "link-selector", value_enum)]
+        link_selectors: Vec<LinkSelector>,
+        
+        /// Accept invalid and self-signed TLS certificates (internal sites only)
+        #[clap(long)]
+        danger_accept_invalid_certs: bool,
+        
+        /// Times a page that failed to load is fetched again (default: set by --profile)
+        #[clap(long)]
+        max_retries: Option<usize>,
+        
+        /// Maximum URLs enqueued under the same two-segment path prefix
+        #[clap(long, default_value = "1000")]
+        max_urls_per_prefix: usize,
+        
+        /// Maximum times the same segment may repeat in a path
+        #[clap(long, default_value = "3")]
+        max_segment_repeats: usize,
+        
+        /// Maximum distinct query strings enqueued for the same path
+        #[clap(long, default_value = "50")]
+        max_query_variants: usize,
+        
+        /// Only fetch the URLs listed in the site's sitemaps and never follow links
+        #[clap(long)]
+        sitemap_only: bool,
+        
+        /// Most sitemap URLs queued as seeds when links are followed
+        #[clap(long, default_value = "100")]
+        sitemap_seed_limit: usize,
+        
+        /// Sitemap path tried when robots.txt lists none, in order (repeatable; replaces the defaults)
+        #[clap(long = "sitemap-location")]
+        sitemap_locations: Vec<String>,
+        
+        /// Send a HEAD request first and skip resources that are too large or of the wrong type
+        #[clap(long)]
+        head_precheck: bool,
+        
+        /// Flag pages that are the site's "not found" page served with a 200 status
+        #[clap(long)]
+        detect_soft_404: bool,
+        
+        /// Keep 64-bit fingerprints of visited URLs instead of the URLs, with room for this many
+        #[clap(long)]
+        fingerprint_visited: Option<usize>,
+        
+        /// Largest resource, in bytes, the HEAD pre-check lets through or saved to --asset-dir
+        #[clap(long, default_value = "10485760")]
+        max_page_size: u64,
+        
+        /// Save PDFs, images and other --asset-content-type resources to files in this directory
+        #[clap(long)]
+        asset_dir: Option<PathBuf>,
+        
+        /// Content type saved to --asset-dir, e.g. application/pdf or image/* (repeatable; PDFs and images by default)
+        #[clap(long = "asset-content-type", requires = "asset_dir")]
+        asset_content_types: Vec<String>,
+        
+        /// Store pages matching --page-dir-content-type and --page-dir-min-size as JSON files in this directory instead of the database
+        #[clap(long)]
+        page_dir: Option<PathBuf>,
+        
+        /// Content type stored in --page-dir, e.g. text/plain or text/* (repeatable; every type by default)
+        #[clap(long = "page-dir-content-type", requires = "page_dir")]
+        page_dir_content_types: Vec<String>,
+        
+        /// Smallest page, in bytes, stored in --page-dir
+        #[clap(long, requires = "page_dir")]
+        page_dir_min_size: Option<u64>,
+        
+        /// Also keep the pages stored in --page-dir in the database
+        #[clap(long, requires = "page_dir")]
+        page_dir_keep_in_db: bool,
+        
+        /// Accept-Language sent with every request, e.g. de-DE
+        #[clap(long)]
+        accept_language: Option<String>,
+        
+        /// Token matched against robots.txt User-agent lines instead of CryptoCrawl; the HTTP User-Agent is unchanged
+        #[clap(long)]
+        robots_user_agent: Option<String>,
+        
+        /// Stop the crawl after this many seconds and keep the pages crawled so far
+        #[clap(long)]
+        max_runtime: Option<u64>,
+        
+        /// Also fetch the targets of links leaving the crawl scope, without following their links
+        #[clap(long)]
+        external_hop: bool,
+        
+        /// Maximum out-of-scope pages fetched by --external-hop
+        #[clap(long, default_value = "100")]
+        max_external_links: usize,
+        
+        /// Carry cookies between the pages headless Chrome renders, for session-based sites
+        #[clap(long, requires = "use_headless_chrome")]
+        keep_browser_cookies: bool,
+        
+        /// JSON file with a form login to perform in headless Chrome before crawling
+        #[clap(long, requires = "use_headless_chrome")]
+        login: Option<PathBuf>,
+        
+        /// Continue an interrupted crawl under this task ID, skipping the pages it already stored
+        #[clap(long, value_name = "TASK_ID")]
+        resume: Option<String>,
+        
+        /// Gzip the JSONL dump under data/crawls, written as .jsonl.gz
+        #[clap(long)]
+        compress: bool,
+    },
+    
+    /// Preview which URLs a crawl would start from without downloading any pages
+    Plan {
+        /// URL to plan a crawl for
+        url: String,
+        
+        /// Maximum depth to crawl
+        #[clap(short, long, default_value = "2")]
+        max_depth: u32,
+        
+        /// Follow subdomains
+        #[clap(short, long)]
+        follow_subdomains: bool,
+        
+        /// Hosts to crawl: host, domain (host and subdomains) or registrable-domain; overrides --follow-subdomains
+        #[clap(long)]
+        scope: Option<models::CrawlScope>,
+        
+        /// Maximum links to follow
+        #[clap(short = 'l', long)]
+        max_links: Option<usize>,
+        
+        /// Print the plan as JSON
+        #[clap(long)]
+        json: bool,
+        
+        /// Plan as if robots.txt were not obeyed
+        #[clap(long)]
+        ignore_robots: bool,
+    },
+    
+    /// Export the link graph of a crawled task
+    Graph {
+        /// ID of the crawled task
+        #[clap(long)]
+        task_id: String,
+        
+        /// Output format
+        #[clap(long, value_enum, default_value = "dot")]
+        format: GraphFormat,
+        
+        /// Include links to pages that were not crawled
+        #[clap(long)]
+        include_external: bool,
+    },
+    
+    /// Compare two crawls: URLs added, removed or changed in size from the first to the second
+    Diff {
+        /// ID of the earlier crawl's task
+        task_a: String,
+        
+        /// ID of the later crawl's task
+        task_b: String,
+    },
+    
+    /// Show the domains that produced the most pages and bytes across all crawls
+    Stats {
+        /// Number of domains to show
+        #[clap(long, default_value = "20")]
+        limit: usize,
+    },
+    
+    /// Fetch the URLs a crawl gave up on again, storing them under the same task
+    RetryFailed {
+        /// ID of the crawled task
+        #[clap(long)]
+        task_id: String,
+    },
+    
+    /// Import a JSONL crawl dump (e.g. from data/crawls) into the database
+    Import {
+        /// Task the imported pages belong to; created if it doesn't exist
+        #[clap(long)]
+        task_id: String,
+        
+        /// JSONL file with one crawled page per line, optionally gzipped
+        file: PathBuf,
     },
     
     /// Crawl crates.io
@@ -141,12 +429,101 @@ enum Command {
         /// Use headless Chrome for JavaScript sites
         #[clap(long)]
         use_headless_chrome: bool,
+        
+        /// Gzip the JSONL output, written as <OUTPUT>.gz
+        #[clap(long)]
+        compress: bool,
     },
     
     /// Register as a crawler with the manager
     Register,
 }
 
+/// Map the `--ignore-robots` flag to a robots.txt enforcement mode
+fn robots_enforcement(ignore_robots: bool) -> RobotsEnforcement {
+    if ignore_robots {
+        RobotsEnforcement::Off
+    } else {
+        RobotsEnforcement::Strict
+    }
+}
+
+/// Output formats for the link graph
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// One JSON edge per line
+    Jsonl,
+}
+
+/// Render link graph edges in the requested format
+fn format_link_graph(edges: &[(String, String)], format: GraphFormat) -> String {
+    let mut output = String::new();
+    
+    match format {
+        GraphFormat::Dot => {
+            // Quote node names, escaping characters DOT treats specially
+            let quote = |url: &str| format!("\"{}\"", url.replace('\\', "\\\\").replace('"', "\\\""));
+            
+            output.push_str("digraph links {\n");
+            for (from, to) in edges {
+                output.push_str(&format!("    {} -> {};\n", quote(from), quote(to)));
+            }
+            output.push_str("}\n");
+        }
+        GraphFormat::Jsonl => {
+            for (from, to) in edges {
+                output.push_str(&serde_json::json!({ "from": from, "to": to }).to_string());
+                output.push('\n');
+            }
+        }
+    }
+    
+    output
+}
+
+/// Render a crawl diff as a report, one URL per line
+fn format_crawl_diff(diff: &CrawlDiff) -> String {
+    if diff.is_empty() {
+        return "No differences\n".to_string();
+    }
+    
+    let mut output = String::new();
+    
+    output.push_str(&format!("Added ({}):\n", diff.added.len()));
+    for url in &diff.added {
+        output.push_str(&format!("  + {}\n", url));
+    }
+    
+    output.push_str(&format!("Removed ({}):\n", diff.removed.len()));
+    for url in &diff.removed {
+        output.push_str(&format!("  - {}\n", url));
+    }
+    
+    output.push_str(&format!("Size changed ({}):\n", diff.size_changed.len()));
+    for (url, before, after) in &diff.size_changed {
+        output.push_str(&format!("  ~ {} ({} -> {} bytes)\n", url, before, after));
+    }
+    
+    output
+}
+
+/// Render the top domains as an aligned table
+fn format_top_domains(domains: &[(String, usize, u64)]) -> String {
+    if domains.is_empty() {
+        return "No pages crawled yet\n".to_string();
+    }
+    
+    let width = domains.iter().map(|(domain, _, _)| domain.len()).max().unwrap_or(0).max("Domain".len());
+    let mut output = format!("{:<width$}  {:>10}  {:>14}\n", "Domain", "Pages", "Bytes", width = width);
+    for (domain, pages, bytes) in domains {
+        output.push_str(&format!("{:<width$}  {:>10}  {:>14}\n", domain, pages, bytes, width = width));
+    }
+    
+    output
+}
+
 /// Ensure the directory for a file exists
 fn ensure_parent_dir(path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -203,6 +580,36 @@ fn load_config(args: &mut Args) -> Result<()> {
         if let Some(poll_interval) = config.get("poll_interval").and_then(|v| v.as_u64()) {
             args.poll_interval = poll_interval;
         }
+        
+        if let Some(submit_retries) = config.get("submit_retries").and_then(|v| v.as_u64()) {
+            args.submit_retries = submit_retries as u32;
+        }
+        
+        if let Some(api_key) = config.get("api_key").and_then(|v| v.as_str()) {
+            args.api_key = Some(api_key.to_string());
+        }
+        
+        if let Some(max_concurrent_tasks) = config.get("max_concurrent_tasks").and_then(|v| v.as_u64()) {
+            args.max_concurrent_tasks = max_concurrent_tasks as usize;
+        }
+        
+        if let Some(heartbeat_interval) = config.get("heartbeat_interval").and_then(|v| v.as_u64()) {
+            args.heartbeat_interval = heartbeat_interval;
+        }
+        
+        if let Some(report_retries) = config.get("report_retries").and_then(|v| v.as_u64()) {
+            args.report_retries = report_retries as u32;
+        }
+        
+        if let Some(active_hours) = config.get("active_hours").and_then(|v| v.as_str()) {
+            args.active_hours = Some(active_hours.parse()
+                .map_err(|e| anyhow::anyhow!("Invalid active_hours in config: {}", e))?);
+        }
+        
+        if let Some(profile) = config.get("profile").and_then(|v| v.as_str()) {
+            args.profile = CrawlProfile::from_str(profile, true)
+                .map_err(|e| anyhow::anyhow!("Invalid profile {:?} in config: {}", profile, e))?;
+        }
     }
     
     Ok(())
@@ -270,9 +677,7 @@ async fn main() -> Result<()> {
     load_config(&mut args).context("Failed to load configuration")?;
     
     // Set up logging
-    env_logger::Builder::new()
-        .filter_level(args.log_level)
-        .init();
+    logging::init(args.log_level, args.log_format);
     
     info!("Starting CryptoCrawl crawler v{}", env!("CARGO_PKG_VERSION"));
     
@@ -300,7 +705,8 @@ async fn main() -> Result<()> {
         &args.rpc_endpoint,
         Some(&args.keypair_path),
         &args.program_id,
-    ).context("Failed to initialize Solana integration")?;
+    ).context("Failed to initialize Solana integration")?
+    .with_submit_retry(args.submit_retries, Duration::from_millis(500));
     
     // Set manager pubkey if available
     solana.set_manager_pubkey(&args.manager_pubkey);
@@ -330,7 +736,14 @@ async fn main() -> Result<()> {
                 solana,
             )
             .context("Failed to create crawler service")?
-            .with_headless_chrome(use_headless_chrome);
+            .with_headless_chrome(use_headless_chrome)
+            .with_api_key(args.api_key.clone())
+            .with_max_concurrent_tasks(args.max_concurrent_tasks)
+            .with_heartbeat_interval(args.heartbeat_interval)
+            .with_report_retry(args.report_retries, Duration::from_secs(1))
+            .with_crawl_profile(args.profile)
+            .with_active_hours(args.active_hours)
+            .with_max_requests_per_second(args.max_requests_per_second);
             
             if use_headless_chrome {
                 info!("Headless Chrome is enabled for JavaScript-dependent sites");
@@ -343,19 +756,102 @@ async fn main() -> Result<()> {
                 .context("Failed to process tasks")?;
         },
         
-        Command::Crawl { url, max_depth, follow_subdomains, max_links, use_headless_chrome } => {
+        Command::Crawl {
+            url, max_depth, follow_subdomains, scope, max_links, seeds, use_headless_chrome,
+            render_patterns, render_sample, js_min_reasons, js_min_score, ignore_robots, robots_override_domains, extract_text, store_html, content_types, max_redirects, redirects_count_as_depth, link_selectors, danger_accept_invalid_certs, max_retries,
+            max_urls_per_prefix, max_segment_repeats, max_query_variants, sitemap_only, sitemap_seed_limit, sitemap_locations,
+            head_precheck, detect_soft_404, fingerprint_visited, max_page_size, asset_dir, asset_content_types, page_dir, page_dir_content_types, page_dir_min_size, page_dir_keep_in_db, accept_language, robots_user_agent, max_runtime, external_hop, max_external_links, keep_browser_cookies, login, resume, compress,
+        } => {
             // Create crawler
-            let mut crawler = Crawler::default().with_headless_chrome(use_headless_chrome);
+            let mut crawler = Crawler::default()
+                .with_headless_chrome(use_headless_chrome)
+                .with_browser_cookies(keep_browser_cookies)
+                .with_render_policy(RenderPolicy {
+                    render_patterns,
+                    sample_percent: render_sample,
+                    ..RenderPolicy::default()
+                })
+                .with_js_detection(JsDetection {
+                    min_reasons: js_min_reasons,
+                    min_score: js_min_score,
+                })
+                .with_profile(args.profile)
+                .with_robots_enforcement(robots_enforcement(ignore_robots))
+                .with_robots_override_domains(robots_override_domains.into_iter().collect())
+                .with_text_extraction(extract_text)
+                .with_html_storage(store_html)
+                .with_max_redirects(max_redirects)
+                .with_redirects_count_as_depth(redirects_count_as_depth)
+                .with_danger_accept_invalid_certs(danger_accept_invalid_certs)
+                .with_trap_guard(TrapGuard {
+                    max_urls_per_prefix,
+                    max_segment_repeats,
+                    max_query_variants,
+                    ..TrapGuard::default()
+                })
+                .with_sitemap_only(sitemap_only)
+                .with_sitemap_seed_limit(sitemap_seed_limit)
+                .with_head_precheck(head_precheck)
+                .with_soft_404_detection(detect_soft_404)
+                .with_max_page_size(max_page_size)
+                .with_external_hop(external_hop)
+                .with_max_external_links(max_external_links)
+                .with_skip_crawled(resume.is_some())
+                .with_compressed_output(compress);
+            if let Some(seeds) = seeds {
+                crawler = crawler.with_seed_urls_file(seeds);
+            }
+            if let Some(expected) = fingerprint_visited {
+                crawler = crawler.with_fingerprint_visited(expected);
+            }
+            if let Some(max_retries) = max_retries {
+                crawler = crawler.with_max_retries(max_retries);
+            }
+            if !link_selectors.is_empty() {
+                crawler = crawler.with_link_selectors(link_selectors);
+            }
+            if let Some(asset_dir) = asset_dir {
+                crawler = crawler.with_asset_dir(asset_dir);
+            }
+            if !asset_content_types.is_empty() {
+                crawler = crawler.with_asset_content_types(asset_content_types);
+            }
+            if let Some(page_dir) = page_dir {
+                let files: Arc<dyn StorageSink> = Arc::new(FileSink::new(page_dir));
+                let sink: Arc<dyn StorageSink> = if page_dir_keep_in_db {
+                    Arc::new(CompositeSink::new(vec![files, Arc::new(db.clone())]))
+                } else {
+                    files
+                };
+                let route = page_dir_content_types.iter().fold(StorageRoute::new(sink), |route, content_type| {
+                    route.content_type(content_type)
+                });
+                crawler = crawler.with_storage_route(route.min_size(page_dir_min_size.unwrap_or(0)));
+            }
+            if !sitemap_locations.is_empty() {
+                crawler = crawler.with_sitemap_locations(sitemap_locations);
+            }
             
             // Set database connection
             crawler.set_database(db.clone());
             
+            let login = match login {
+                Some(path) => {
+                    let config = std::fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read login file: {}", path.display()))?;
+                    Some(serde_json::from_str::<models::LoginConfig>(&config)
+                        .with_context(|| format!("Invalid login file: {}", path.display()))?)
+                }
+                None => None,
+            };
+            
             // Create a new task
             let task = models::Task {
-                id: Uuid::new_v4().to_string(),
+                id: resume.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
                 target_url: url.clone(),
                 max_depth,
                 follow_subdomains,
+                scope,
                 max_links,
                 created_at: SystemTime::now()
                     .duration_since(UNIX_EPOCH)
@@ -366,6 +862,14 @@ async fn main() -> Result<()> {
                     .unwrap_or_default()
                     .as_secs()),
                 incentive_amount: 0,
+                allowed_content_types: models::default_allowed_content_types()
+                    .into_iter()
+                    .chain(content_types)
+                    .collect(),
+                accept_language,
+                robots_user_agent,
+                max_runtime_secs: max_runtime,
+                login,
             };
             
             // Save task to database
@@ -381,18 +885,137 @@ async fn main() -> Result<()> {
                 .await
                 .with_context(|| format!("Failed to crawl URL: {}", url))?;
             
-            // Save results
-            db.save_crawl_result(&result)
-                .with_context(|| format!("Failed to save crawl result for task: {}", task.id))?;
+            // Save results, replacing those of the interrupted run when resuming
+            let saved = if db.get_crawl_result(&task.id)?.is_some() {
+                db.update_crawl_result(&result)
+            } else {
+                db.save_crawl_result(&result)
+            };
+            saved.with_context(|| format!("Failed to save crawl result for task: {}", task.id))?;
             
             // Print summary
             println!("Crawl complete!");
             println!("Domain: {}", result.domain);
             println!("Pages crawled: {}", result.pages_count);
+            if let Some(found) = result.sitemap_urls_found {
+                println!("Sitemap URLs: {} found, {} fetched", found, result.pages_count);
+            }
             println!("Total data size: {} bytes", result.total_size);
+            println!("URLs discovered: {} ({} left in the queue)", crawler.visited_count(), crawler.queue_len());
+            if result.time_limited {
+                println!("Stopped early: reached the {}s runtime limit", max_runtime.unwrap_or_default());
+            }
+            if head_precheck {
+                println!("Bytes saved by HEAD pre-check: {}", result.bytes_saved);
+            }
+            if use_headless_chrome {
+                println!("Pages rendered: {}, fetched plain: {}", result.rendered_pages, result.plain_pages());
+            }
+        }
+        
+        Command::Plan { url, max_depth, follow_subdomains, scope, max_links, json, ignore_robots } => {
+            let mut task = models::Task::new(
+                Uuid::new_v4().to_string(),
+                url.clone(),
+                max_depth,
+                follow_subdomains,
+                max_links,
+                0,
+            );
+            task.scope = scope;
+            
+            let plan = Crawler::default()
+                .with_robots_enforcement(robots_enforcement(ignore_robots))
+                .plan(&task)
+                .await
+                .with_context(|| format!("Failed to plan crawl of {}", url))?;
+            
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plan)?);
+            } else {
+                println!("Crawl plan for {}", plan.target_url);
+                for sitemap in &plan.sitemaps {
+                    println!("Sitemap: {}", sitemap);
+                }
+                for planned in &plan.urls {
+                    println!("{} {} ({:?}, {})",
+                        if planned.allowed { "ALLOW" } else { "DENY " },
+                        planned.url, planned.source, planned.reason);
+                }
+                println!("{} of {} URLs would be crawled", plan.allowed_count(), plan.urls.len());
+            }
+        }
+        
+        Command::Graph { task_id, format, include_external } => {
+            let edges = db.export_link_graph(&task_id, include_external)
+                .with_context(|| format!("Failed to export link graph for task {}", task_id))?;
+            
+            info!("Exporting {} links for task {}", edges.len(), task_id);
+            print!("{}", format_link_graph(&edges, format));
+        }
+        
+        Command::Diff { task_a, task_b } => {
+            let diff = db.diff_crawls(&task_a, &task_b)
+                .with_context(|| format!("Failed to compare tasks {} and {}", task_a, task_b))?;
+            
+            print!("{}", format_crawl_diff(&diff));
+        }
+        
+        Command::Stats { limit } => {
+            let domains = db.top_domains(limit)
+                .context("Failed to aggregate crawled pages by domain")?;
+            
+            print!("{}", format_top_domains(&domains));
+        }
+        
+        Command::RetryFailed { task_id } => {
+            let mut task = db.get_task(&task_id)
+                .with_context(|| format!("Failed to load task {}", task_id))?
+                .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
+            let failed = db.get_failed_urls(&task_id)
+                .with_context(|| format!("Failed to load failed URLs for task {}", task_id))?;
+            
+            let urls: Vec<url::Url> = failed.iter()
+                .filter_map(|(failed_url, _, _)| url::Url::parse(failed_url).ok())
+                .collect();
+            if urls.is_empty() {
+                println!("No failed URLs for task {}", task_id);
+            } else {
+                // URLs that fail again are recorded anew by the crawl
+                db.delete_failed_urls(&task_id)
+                    .with_context(|| format!("Failed to clear failed URLs for task {}", task_id))?;
+                
+                // Every URL is fetched, however deep the original crawl found it
+                let retried = urls.len();
+                task.max_depth = task.max_depth.max(1);
+                task.max_links = Some(retried);
+                
+                let mut crawler = Crawler::default()
+                    .with_profile(args.profile)
+                    .with_retry_urls(urls);
+                crawler.set_database(db.clone());
+                
+                let result = crawler.crawl(&task)
+                    .await
+                    .with_context(|| format!("Failed to retry task {}", task_id))?;
+                let still_failing = db.get_failed_urls(&task_id)?.len();
+                
+                println!("Retried {} URLs: {} fetched, {} still failing", retried, result.pages_count, still_failing);
+            }
+        }
+        
+        Command::Import { task_id, file } => {
+            let import = db.import_jsonl(&task_id, &file)
+                .with_context(|| format!("Failed to import {:?}", file))?;
+            
+            println!("Imported {} pages into task {}", import.imported, task_id);
+            if !import.skipped_lines.is_empty() {
+                let lines: Vec<String> = import.skipped_lines.iter().map(|line| line.to_string()).collect();
+                println!("Skipped {} malformed lines: {}", lines.len(), lines.join(", "));
+            }
         }
         
-        Command::CrawlCrates { max_depth, follow_subdomains, max_links, output, use_headless_chrome } => {
+        Command::CrawlCrates { max_depth, follow_subdomains, max_links, output, use_headless_chrome, compress } => {
             info!("Crawling crates.io with depth {}", max_depth);
             if use_headless_chrome {
                 info!("Headless Chrome is enabled for JavaScript-dependent sites");
@@ -413,17 +1036,18 @@ async fn main() -> Result<()> {
             
             // Prepare output file if provided
             let output_file = if let Some(output_path) = &output {
+                let output_path = if compress { output::gzip_path(output_path) } else { output_path.clone() };
                 info!("Setting up JSONL output to {:?}", output_path);
                 
                 // Ensure parent directory exists
-                ensure_parent_dir(output_path)?;
+                ensure_parent_dir(&output_path)?;
                 
                 // Open file for writing
                 let file = fs::OpenOptions::new()
                     .create(true)
                     .write(true)
                     .truncate(true)
-                    .open(output_path)
+                    .open(&output_path)
                     .context(format!("Failed to open output file {:?}", output_path))?;
                 
                 Some(file)
@@ -432,7 +1056,17 @@ async fn main() -> Result<()> {
             };
             
             // Create crawler and crawl crates.io with streaming results
-            let mut crawler = Crawler::new(task.clone()).with_headless_chrome(use_headless_chrome);
+            let mut crawler = Crawler::new(task.clone())
+                .with_headless_chrome(use_headless_chrome)
+                .with_render_policy(RenderPolicy {
+                    render_patterns: ["/crates/", "/keywords/", "/categories/", "/docs/"]
+                        .iter()
+                        .map(|pattern| pattern.to_string())
+                        .collect(),
+                    ..RenderPolicy::default()
+                })
+                .with_profile(args.profile)
+                .with_compressed_output(compress);
             
             // Set database connection
             crawler.set_database(db.clone());
@@ -477,7 +1111,8 @@ async fn main() -> Result<()> {
                 args.poll_interval,
                 db,
                 solana,
-            ).context("Failed to initialize crawler service")?;
+            ).context("Failed to initialize crawler service")?
+            .with_api_key(args.api_key.clone());
             
             // Register with the manager
             let client_id = service.client_id();
@@ -0,0 +1,67 @@
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Gauge, IntCounter, IntGauge, Registry, TextEncoder};
+
+/// Prometheus metrics for crawls run by this process, exposed at `GET /metrics`
+pub struct CrawlMetrics {
+    registry: Registry,
+    /// Pages crawled
+    pub pages_crawled: IntCounter,
+    /// Bytes downloaded
+    pub bytes_downloaded: IntCounter,
+    /// Crawl workers currently running
+    pub active_workers: IntGauge,
+    /// Pages per second of the crawl that last stored a page
+    pub pages_per_second: Gauge,
+}
+
+/// Metrics shared by the crawl workers and the UI server
+pub static METRICS: Lazy<CrawlMetrics> = Lazy::new(CrawlMetrics::new);
+
+/// Counts a crawl worker as active until dropped
+pub struct ActiveWorker(());
+
+impl Drop for ActiveWorker {
+    fn drop(&mut self) {
+        METRICS.active_workers.dec();
+    }
+}
+
+impl CrawlMetrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("cryptocrawl_crawler".to_string()), None)
+            .expect("valid metrics prefix");
+        
+        let pages_crawled = IntCounter::new("pages_crawled_total", "Pages crawled").expect("valid counter");
+        let bytes_downloaded = IntCounter::new("bytes_downloaded_total", "Bytes downloaded").expect("valid counter");
+        let active_workers = IntGauge::new("active_workers", "Crawl workers currently running").expect("valid gauge");
+        let pages_per_second = Gauge::new("pages_per_second", "Pages per second of the current crawl").expect("valid gauge");
+        
+        registry.register(Box::new(pages_crawled.clone())).expect("unique metric name");
+        registry.register(Box::new(bytes_downloaded.clone())).expect("unique metric name");
+        registry.register(Box::new(active_workers.clone())).expect("unique metric name");
+        registry.register(Box::new(pages_per_second.clone())).expect("unique metric name");
+        
+        Self {
+            registry,
+            pages_crawled,
+            bytes_downloaded,
+            active_workers,
+            pages_per_second,
+        }
+    }
+    
+    /// Mark a crawl worker as running until the returned guard is dropped
+    pub fn track_worker(&self) -> ActiveWorker {
+        self.active_workers.inc();
+        ActiveWorker(())
+    }
+    
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
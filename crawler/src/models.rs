@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 use log;
@@ -18,6 +19,10 @@ pub struct Task {
     /// Whether to follow subdomains
     pub follow_subdomains: bool,
     
+    /// Hosts the crawl may fetch from; when unset, `follow_subdomains` picks `Host` or `Domain`
+    #[serde(default)]
+    pub scope: Option<CrawlScope>,
+    
     /// Maximum number of links to follow (None for unlimited)
     pub max_links: Option<usize>,
     
@@ -29,6 +34,136 @@ pub struct Task {
     
     /// Amount of incentives for completing the task
     pub incentive_amount: u64,
+    
+    /// Content types that are stored; anything else is skipped
+    #[serde(default = "default_allowed_content_types")]
+    pub allowed_content_types: Vec<String>,
+    
+    /// Accept-Language sent with every request, e.g. `de-DE`; `DEFAULT_ACCEPT_LANGUAGE` when unset
+    #[serde(default)]
+    pub accept_language: Option<String>,
+    
+    /// Token matched against robots.txt `User-agent` lines, separate from the HTTP `User-Agent`;
+    /// the crawler's token (`ROBOTS_PRODUCT_TOKEN` by default) when unset
+    #[serde(default)]
+    pub robots_user_agent: Option<String>,
+    
+    /// Wall-clock limit for the crawl in seconds; the crawl stops early with a partial result
+    #[serde(default)]
+    pub max_runtime_secs: Option<u64>,
+    
+    /// Form login performed in the headless browser before the crawl; never serialized,
+    /// so credentials stay out of the database, reports and dumps
+    #[serde(default, skip_serializing)]
+    pub login: Option<LoginConfig>,
+}
+
+/// A form login for sites that gate their content behind a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginConfig {
+    /// Page with the login form
+    pub url: String,
+    
+    /// Where the form fields are on the login page
+    pub selectors: LoginSelectors,
+    
+    /// What to type into them
+    pub credentials: Credentials,
+}
+
+/// CSS selectors of a login form's fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginSelectors {
+    /// Username or email input
+    pub username: String,
+    
+    /// Password input
+    pub password: String,
+    
+    /// Button clicked to submit the form
+    pub submit: String,
+}
+
+/// Username and password for a login form. `Debug` redacts both so they never end up in logs.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &"<redacted>")
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Which hosts a crawl may fetch pages from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlScope {
+    /// Only the target's exact host
+    Host,
+    
+    /// The target's host and its subdomains
+    Domain,
+    
+    /// Any host under the target's registrable domain (eTLD+1), e.g. `docs.example.co.uk` for `www.example.co.uk`
+    RegistrableDomain,
+}
+
+impl fmt::Display for CrawlScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrawlScope::Host => write!(f, "host"),
+            CrawlScope::Domain => write!(f, "domain"),
+            CrawlScope::RegistrableDomain => write!(f, "registrable_domain"),
+        }
+    }
+}
+
+impl std::str::FromStr for CrawlScope {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "host" => Ok(CrawlScope::Host),
+            "domain" => Ok(CrawlScope::Domain),
+            "registrable_domain" => Ok(CrawlScope::RegistrableDomain),
+            other => Err(format!("Unknown crawl scope: {} (expected host, domain or registrable-domain)", other)),
+        }
+    }
+}
+
+/// Accept-Language sent when a task doesn't set one
+pub const DEFAULT_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.5";
+
+/// Content types crawled when a task doesn't say otherwise
+pub const HTML_CONTENT_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
+pub(crate) fn default_allowed_content_types() -> Vec<String> {
+    HTML_CONTENT_TYPES.iter().map(|ct| ct.to_string()).collect()
+}
+
+/// Media type of a Content-Type header value, without parameters such as the charset
+pub fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_lowercase()
+}
+
+/// Whether a media type is the allowed one, or of its kind for wildcards such as `image/*`
+pub fn content_type_matches(allowed: &str, media_type: &str) -> bool {
+    let allowed = allowed.trim().to_lowercase();
+    match allowed.strip_suffix("/*") {
+        Some(prefix) => media_type.split('/').next() == Some(prefix),
+        None => allowed == media_type,
+    }
+}
+
+/// Whether a Content-Type header value is an HTML document
+pub fn is_html_content_type(content_type: &str) -> bool {
+    HTML_CONTENT_TYPES.contains(&media_type(content_type).as_str())
 }
 
 impl Task {
@@ -46,6 +181,7 @@ impl Task {
             target_url,
             max_depth,
             follow_subdomains,
+            scope: None,
             max_links,
             created_at: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -53,8 +189,39 @@ impl Task {
                 .as_secs(),
             assigned_at: None,
             incentive_amount,
+            allowed_content_types: default_allowed_content_types(),
+            accept_language: None,
+            robots_user_agent: None,
+            max_runtime_secs: None,
+            login: None,
         }
     }
+    
+    /// Hosts the crawl may fetch from
+    pub fn scope(&self) -> CrawlScope {
+        match self.scope {
+            Some(scope) => scope,
+            None if self.follow_subdomains => CrawlScope::Domain,
+            None => CrawlScope::Host,
+        }
+    }
+    
+    /// Accept-Language sent with the task's requests
+    pub fn accept_language(&self) -> &str {
+        self.accept_language.as_deref().unwrap_or(DEFAULT_ACCEPT_LANGUAGE)
+    }
+    
+    /// Whether a response with this Content-Type should be stored.
+    /// Entries may be exact media types or wildcards such as `application/*`;
+    /// responses without a Content-Type are treated as HTML.
+    pub fn allows_content_type(&self, content_type: Option<&str>) -> bool {
+        let media_type = match content_type {
+            Some(content_type) => media_type(content_type),
+            None => return true,
+        };
+        
+        self.allowed_content_types.iter().any(|allowed| content_type_matches(allowed, &media_type))
+    }
 }
 
 /// Crawled page information
@@ -77,6 +244,141 @@ pub struct CrawledPage {
     
     /// HTML body content of the page
     pub body: Option<String>,
+    
+    /// Whether the page is outside the task scope, fetched one hop out to check the link target
+    #[serde(default)]
+    pub is_external: bool,
+}
+
+/// Breakdown of a crawl's pages by HTTP status code and content type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlStats {
+    /// Pages per HTTP status code; pages that got no response aren't counted
+    pub status_counts: HashMap<u16, usize>,
+    /// Bytes per media type, `unknown` for pages without a Content-Type
+    pub content_type_bytes: HashMap<String, u64>,
+}
+
+impl CrawlStats {
+    /// Count a crawled page
+    pub fn record(&mut self, page: &CrawledPage) {
+        if let Some(status) = page.status_code {
+            *self.status_counts.entry(status).or_default() += 1;
+        }
+        
+        let content_type = page.content_type.as_deref()
+            .map(media_type)
+            .filter(|ct| !ct.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        *self.content_type_bytes.entry(content_type).or_default() += page.size as u64;
+    }
+    
+    /// Undo `record` for a page counted twice
+    fn forget(&mut self, page: &CrawledPage) {
+        if let Some(status) = page.status_code {
+            if let Some(count) = self.status_counts.get_mut(&status) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.status_counts.remove(&status);
+                }
+            }
+        }
+        
+        let content_type = page.content_type.as_deref()
+            .map(media_type)
+            .filter(|ct| !ct.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        if let Some(bytes) = self.content_type_bytes.get_mut(&content_type) {
+            *bytes = bytes.saturating_sub(page.size as u64);
+            if *bytes == 0 {
+                self.content_type_bytes.remove(&content_type);
+            }
+        }
+    }
+}
+
+/// Link-preview metadata declared by a page; tags the page doesn't have are `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageMetadata {
+    /// `<meta name="description">`
+    pub description: Option<String>,
+    
+    /// `<meta property="og:title">`
+    pub og_title: Option<String>,
+    
+    /// `<meta property="og:description">`
+    pub og_description: Option<String>,
+    
+    /// `<meta property="og:image">`
+    pub og_image: Option<String>,
+}
+
+impl PageMetadata {
+    /// Whether the page declared none of the tags
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.og_title.is_none()
+            && self.og_description.is_none()
+            && self.og_image.is_none()
+    }
+}
+
+/// A page as stored in `crawled_pages`, with its content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPage {
+    /// URL the content was found at
+    pub url: String,
+    
+    /// URL that was requested, if it redirected
+    pub requested_url: Option<String>,
+    
+    /// Task that crawled the page
+    pub task_id: String,
+    
+    /// Domain of the page
+    pub domain: String,
+    
+    /// HTTP status code
+    pub status: Option<i32>,
+    
+    /// Content type of the page
+    pub content_type: Option<String>,
+    
+    /// Page title
+    pub title: Option<String>,
+    
+    /// Size of the page content in bytes
+    pub size: i64,
+    
+    /// Charset the page was decoded with
+    pub charset: Option<String>,
+    
+    /// When the page was fetched
+    pub fetched_at: Option<String>,
+    
+    /// Link depth from the start URL
+    pub depth: Option<u32>,
+    
+    /// Requests from the start URL, counting each link and each redirect
+    pub hops: Option<u32>,
+    
+    /// File the resource was saved to, for assets kept on disk instead of in the database
+    pub asset_path: Option<String>,
+    
+    /// Whether the page looked like the site's "not found" page despite a 2xx status
+    pub soft_404: bool,
+    
+    /// Whether the page needed JavaScript to render
+    pub is_javascript_dependent: bool,
+    
+    /// Link-preview metadata
+    pub metadata: PageMetadata,
+    
+    /// Raw HTML
+    pub html: Option<String>,
+    
+    /// Extracted text
+    pub text_content: Option<String>,
 }
 
 /// Status of a crawl
@@ -96,6 +398,26 @@ pub enum CrawlStatus {
     
     /// Crawl was rejected by the manager
     Rejected,
+    
+    /// Crawl was stopped before it finished
+    Cancelled,
+}
+
+impl std::str::FromStr for CrawlStatus {
+    type Err = String;
+    
+    /// Parse a status as stored in the database ("In Progress") or as named in code ("InProgress")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace(' ', "").to_ascii_lowercase().as_str() {
+            "inprogress" => Ok(CrawlStatus::InProgress),
+            "completed" => Ok(CrawlStatus::Completed),
+            "failed" => Ok(CrawlStatus::Failed),
+            "verified" => Ok(CrawlStatus::Verified),
+            "rejected" => Ok(CrawlStatus::Rejected),
+            "cancelled" | "canceled" => Ok(CrawlStatus::Cancelled),
+            _ => Err(format!("Invalid crawl status: {}", s)),
+        }
+    }
 }
 
 impl fmt::Display for CrawlStatus {
@@ -106,6 +428,7 @@ impl fmt::Display for CrawlStatus {
             CrawlStatus::Failed => write!(f, "Failed"),
             CrawlStatus::Verified => write!(f, "Verified"),
             CrawlStatus::Rejected => write!(f, "Rejected"),
+            CrawlStatus::Cancelled => write!(f, "Cancelled"),
         }
     }
 }
@@ -142,6 +465,30 @@ pub struct CrawlResult {
     
     /// Amount of incentives received
     pub incentives_received: Option<i64>,
+    
+    /// Number of URLs listed in the site's sitemaps (sitemap-only crawls)
+    #[serde(default)]
+    pub sitemap_urls_found: Option<usize>,
+    
+    /// Bytes not downloaded because a HEAD pre-check skipped the resource
+    #[serde(default)]
+    pub bytes_saved: u64,
+    
+    /// Pages per HTTP status code
+    #[serde(default)]
+    pub status_counts: HashMap<u16, usize>,
+    
+    /// Bytes per content type
+    #[serde(default)]
+    pub content_type_bytes: HashMap<String, u64>,
+    
+    /// Pages rendered with headless Chrome rather than stored as fetched
+    #[serde(default)]
+    pub rendered_pages: usize,
+    
+    /// Whether the crawl was stopped by the task's `max_runtime_secs` before it finished
+    #[serde(default)]
+    pub time_limited: bool,
 }
 
 /// Report of a crawl to submit to the manager
@@ -185,6 +532,12 @@ impl CrawlResult {
             end_time: None,
             transaction_hash: None,
             incentives_received: None,
+            sitemap_urls_found: None,
+            bytes_saved: 0,
+            status_counts: HashMap::new(),
+            content_type_bytes: HashMap::new(),
+            rendered_pages: 0,
+            time_limited: false,
         }
     }
     
@@ -211,11 +564,89 @@ impl CrawlResult {
         
         self.pages.push(page.clone());
         
-        // Update the total size and count
+        // Update the breakdown, total size and count
+        let mut stats = CrawlStats {
+            status_counts: std::mem::take(&mut self.status_counts),
+            content_type_bytes: std::mem::take(&mut self.content_type_bytes),
+        };
+        stats.record(&page);
+        self.set_stats(stats);
         self.total_size += page.size as u64;
         self.pages_count += 1;
     }
     
+    /// Combine another result for the same task, e.g. a resumed or partial crawl, into this one.
+    /// Pages are unioned by URL, keeping the more recently crawled copy, so a page in both
+    /// results is counted once. The crawl runs from the earliest start to the latest end, and
+    /// takes its status from the result that started last. Rendered pages and saved bytes are summed.
+    pub fn merge(&mut self, other: CrawlResult) {
+        let mut stats = CrawlStats {
+            status_counts: std::mem::take(&mut self.status_counts),
+            content_type_bytes: std::mem::take(&mut self.content_type_bytes),
+        };
+        for (status, count) in other.status_counts {
+            *stats.status_counts.entry(status).or_default() += count;
+        }
+        for (content_type, bytes) in other.content_type_bytes {
+            *stats.content_type_bytes.entry(content_type).or_default() += bytes;
+        }
+        self.pages_count += other.pages_count;
+        self.total_size += other.total_size;
+        
+        // Drop the older copy of each page crawled in both
+        let mut by_url: HashMap<String, usize> = self.pages.iter()
+            .enumerate()
+            .map(|(i, page)| (page.url.clone(), i))
+            .collect();
+        for page in other.pages {
+            match by_url.get(&page.url) {
+                Some(&i) => {
+                    let dropped = if page.timestamp >= self.pages[i].timestamp {
+                        std::mem::replace(&mut self.pages[i], page)
+                    } else {
+                        page
+                    };
+                    stats.forget(&dropped);
+                    self.pages_count = self.pages_count.saturating_sub(1);
+                    self.total_size = self.total_size.saturating_sub(dropped.size as u64);
+                },
+                None => {
+                    by_url.insert(page.url.clone(), self.pages.len());
+                    self.pages.push(page);
+                },
+            }
+        }
+        self.set_stats(stats);
+        
+        if other.start_time >= self.start_time {
+            self.status = other.status;
+            self.time_limited = other.time_limited;
+        }
+        self.start_time = self.start_time.min(other.start_time);
+        self.end_time = self.end_time.max(other.end_time);
+        
+        self.rendered_pages += other.rendered_pages;
+        self.bytes_saved += other.bytes_saved;
+        self.sitemap_urls_found = self.sitemap_urls_found.max(other.sitemap_urls_found);
+        if self.transaction_hash.is_none() {
+            self.transaction_hash = other.transaction_hash;
+        }
+        if self.incentives_received.is_none() {
+            self.incentives_received = other.incentives_received;
+        }
+    }
+    
+    /// Pages stored as fetched, without rendering
+    pub fn plain_pages(&self) -> usize {
+        self.pages_count.saturating_sub(self.rendered_pages)
+    }
+    
+    /// Replace the breakdown by status code and content type
+    pub fn set_stats(&mut self, stats: CrawlStats) {
+        self.status_counts = stats.status_counts;
+        self.content_type_bytes = stats.content_type_bytes;
+    }
+    
     /// Complete the crawl
     pub fn complete(&mut self) {
         self.status = CrawlStatus::Completed;
@@ -238,6 +669,17 @@ impl CrawlResult {
         );
     }
     
+    /// Set the crawl as cancelled
+    pub fn set_cancelled(&mut self) {
+        self.status = CrawlStatus::Cancelled;
+        self.end_time = Some(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        );
+    }
+    
     /// Set the transaction hash
     pub fn set_transaction(&mut self, tx_hash: String) {
         self.transaction_hash = Some(tx_hash);
@@ -278,4 +720,178 @@ impl CrawlResult {
               page.size, 
               page.status_code.unwrap_or(0));
     }
-} 
\ No newline at end of file
+} 
+
+/// Where a URL in a crawl plan came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeedSource {
+    /// The task's target URL
+    Target,
+    /// A well-known page added for the target site
+    WellKnown,
+    /// An entry in one of the site's sitemaps
+    Sitemap,
+}
+
+/// A candidate URL in a crawl plan and whether the crawl would fetch it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedUrl {
+    /// The candidate URL
+    pub url: String,
+    
+    /// Where the URL came from
+    pub source: SeedSource,
+    
+    /// Whether the crawl would fetch it
+    pub allowed: bool,
+    
+    /// Why it is allowed or denied
+    pub reason: String,
+}
+
+/// Preview of what a crawl would start from, built without downloading page bodies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlPlan {
+    /// ID of the planned task
+    pub task_id: String,
+    
+    /// Normalized target URL
+    pub target_url: String,
+    
+    /// Sitemaps that would be read
+    pub sitemaps: Vec<String>,
+    
+    /// Candidate URLs in queue order
+    pub urls: Vec<PlannedUrl>,
+}
+
+impl CrawlPlan {
+    /// Number of candidate URLs the crawl would fetch
+    pub fn allowed_count(&self) -> usize {
+        self.urls.iter().filter(|url| url.allowed).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn task_with_types(content_types: &[&str]) -> Task {
+        let mut task = Task::new("task-1".to_string(), "https://example.com".to_string(), 2, false, None, 0);
+        task.allowed_content_types = content_types.iter().map(|ct| ct.to_string()).collect();
+        task
+    }
+    
+    #[test]
+    fn html_is_allowed_by_default() {
+        let task = Task::new("task-1".to_string(), "https://example.com".to_string(), 2, false, None, 0);
+        
+        assert!(task.allows_content_type(Some("text/html; charset=utf-8")));
+        assert!(task.allows_content_type(Some("application/xhtml+xml")));
+        assert!(task.allows_content_type(None));
+        assert!(!task.allows_content_type(Some("application/json")));
+        assert!(!task.allows_content_type(Some("application/pdf")));
+    }
+    
+    #[test]
+    fn json_can_be_allowed() {
+        let task = task_with_types(&["text/html", "application/json"]);
+        
+        assert!(task.allows_content_type(Some("application/json")));
+        assert!(task.allows_content_type(Some("Application/JSON; charset=utf-8")));
+        assert!(!task.allows_content_type(Some("application/pdf")));
+        assert!(!is_html_content_type("application/json"));
+    }
+    
+    #[test]
+    fn pdf_can_be_allowed_with_a_wildcard() {
+        let task = task_with_types(&["application/*"]);
+        
+        assert!(task.allows_content_type(Some("application/pdf")));
+        assert!(task.allows_content_type(Some("application/json")));
+        assert!(!task.allows_content_type(Some("text/html")));
+        assert!(!is_html_content_type("application/pdf"));
+    }
+    
+    #[test]
+    fn tasks_without_content_types_default_to_html() {
+        let task: Task = serde_json::from_value(serde_json::json!({
+            "id": "task-1",
+            "target_url": "https://example.com",
+            "max_depth": 2,
+            "follow_subdomains": false,
+            "max_links": null,
+            "created_at": 0,
+            "assigned_at": null,
+            "incentive_amount": 0
+        })).unwrap();
+        
+        assert_eq!(task.allowed_content_types, vec!["text/html", "application/xhtml+xml"]);
+    }
+    
+    #[test]
+    fn login_credentials_are_never_logged_or_serialized() {
+        let mut task = Task::new("task-1".to_string(), "https://docs.example.com".to_string(), 2, false, None, 0);
+        task.login = Some(LoginConfig {
+            url: "https://docs.example.com/login".to_string(),
+            selectors: LoginSelectors {
+                username: "#user".to_string(),
+                password: "#pass".to_string(),
+                submit: "button[type=submit]".to_string(),
+            },
+            credentials: Credentials {
+                username: "alice".to_string(),
+                password: "hunter2".to_string(),
+            },
+        });
+        
+        let debug = format!("{:?}", task);
+        assert!(!debug.contains("alice"));
+        assert!(!debug.contains("hunter2"));
+        
+        let json = serde_json::to_value(&task).unwrap();
+        assert!(json.get("login").is_none());
+    }
+    
+    fn page(url: &str, size: usize, timestamp: u64) -> CrawledPage {
+        CrawledPage {
+            url: url.to_string(),
+            size,
+            timestamp,
+            content_type: Some("text/html".to_string()),
+            status_code: Some(200),
+            body: None,
+            is_external: false,
+        }
+    }
+    
+    #[test]
+    fn merging_results_counts_overlapping_pages_once() {
+        let mut first = CrawlResult::new("task-1", "example.com");
+        first.start_time = 100;
+        first.end_time = Some(200);
+        first.time_limited = true;
+        first.add_page(page("https://example.com/", 10, 150));
+        first.add_page(page("https://example.com/a", 20, 160));
+        
+        let mut resumed = CrawlResult::new("task-1", "example.com");
+        resumed.start_time = 300;
+        resumed.add_page(page("https://example.com/a", 25, 310));
+        resumed.add_page(page("https://example.com/b", 30, 320));
+        resumed.complete();
+        let resumed_end = resumed.end_time;
+        
+        first.merge(resumed);
+        
+        assert_eq!(first.pages_count, 3);
+        assert_eq!(first.pages.len(), 3);
+        assert_eq!(first.total_size, 10 + 25 + 30);
+        assert_eq!(first.pages.iter().find(|p| p.url.ends_with("/a")).unwrap().size, 25);
+        assert_eq!(first.status_counts.get(&200), Some(&3));
+        assert_eq!(first.content_type_bytes.get("text/html"), Some(&65));
+        assert_eq!(first.start_time, 100);
+        assert_eq!(first.end_time, resumed_end);
+        assert_eq!(first.status, CrawlStatus::Completed);
+        assert!(!first.time_limited);
+    }
+}
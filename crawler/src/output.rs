@@ -0,0 +1,127 @@
+use crate::models::CrawledPage;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where the JSONL lines end up
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(writer) => writer.write(buf),
+            Sink::Gzip(writer) => writer.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(writer) => writer.flush(),
+            Sink::Gzip(writer) => writer.flush(),
+        }
+    }
+}
+
+/// JSONL dump of a crawl, one `CrawledPage` per line, shared by all of its workers.
+///
+/// Writes are buffered, and gzip-compressed when created with `compress`.
+/// Call [`JsonlWriter::finish`] once the crawl is over so the buffer is flushed
+/// and the gzip trailer written.
+pub struct JsonlWriter {
+    sink: Mutex<Option<Sink>>,
+}
+
+impl JsonlWriter {
+    /// Create (or truncate) the dump at `path`
+    pub fn create<P: AsRef<Path>>(path: P, compress: bool) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let sink = if compress {
+            Sink::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Sink::Plain(file)
+        };
+        
+        Ok(Self { sink: Mutex::new(Some(sink)) })
+    }
+    
+    /// Append a page to the dump
+    pub fn write_page(&self, page: &CrawledPage) -> io::Result<()> {
+        let json = serde_json::to_string(page)?;
+        match self.sink.lock().unwrap().as_mut() {
+            Some(sink) => writeln!(sink, "{}", json),
+            None => Err(io::Error::new(io::ErrorKind::Other, "JSONL dump already finished")),
+        }
+    }
+    
+    /// Flush the dump and close it; later writes fail
+    pub fn finish(&self) -> io::Result<()> {
+        match self.sink.lock().unwrap().take() {
+            Some(Sink::Plain(mut writer)) => writer.flush(),
+            Some(Sink::Gzip(encoder)) => encoder.finish()?.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `path` with `.gz` appended, unless it already ends with it
+pub fn gzip_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if path.extension().map_or(false, |ext| ext == "gz") {
+        return path.to_path_buf();
+    }
+    
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+    use tempfile::TempDir;
+    
+    fn page(url: &str) -> CrawledPage {
+        CrawledPage {
+            url: url.to_string(),
+            size: 2048,
+            timestamp: 1_700_000_000,
+            content_type: Some("text/html".to_string()),
+            status_code: Some(200),
+            body: Some("<p>Hello, crawler.</p>".repeat(100)),
+            is_external: false,
+        }
+    }
+    
+    #[test]
+    fn compressed_dump_holds_the_same_lines() {
+        let dir = TempDir::new().unwrap();
+        let plain_path = dir.path().join("crawl.jsonl");
+        let gzip_path = gzip_path(&plain_path);
+        assert_eq!(gzip_path, dir.path().join("crawl.jsonl.gz"));
+        
+        let plain = JsonlWriter::create(&plain_path, false).unwrap();
+        let gzip = JsonlWriter::create(&gzip_path, true).unwrap();
+        for url in ["https://example.com/", "https://example.com/a", "https://example.com/b"] {
+            plain.write_page(&page(url)).unwrap();
+            gzip.write_page(&page(url)).unwrap();
+        }
+        plain.finish().unwrap();
+        gzip.finish().unwrap();
+        assert!(gzip.write_page(&page("https://example.com/c")).is_err());
+        
+        let expected = std::fs::read_to_string(&plain_path).unwrap();
+        let mut decompressed = String::new();
+        MultiGzDecoder::new(File::open(&gzip_path).unwrap()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, expected);
+        assert_eq!(expected.lines().count(), 3);
+        assert!(std::fs::metadata(&gzip_path).unwrap().len() < expected.len() as u64 / 10);
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Caps the requests made per second by every crawler holding a clone of it.
+///
+/// Each caller reserves the next free slot, so requests are spread evenly at the
+/// configured rate however many crawls share the budget. It composes with the
+/// per-host concurrency limit: a worker first waits for its host, then for a slot.
+#[derive(Debug, Clone)]
+pub struct RequestBudget {
+    /// Time between two consecutive requests
+    interval: Duration,
+    /// Earliest time the next request may be sent
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl RequestBudget {
+    /// Allow at most `requests_per_second` requests per second (at least one)
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+    
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            // An idle budget doesn't bank slots for a later burst
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn concurrent_callers_share_the_rate() {
+        let budget = RequestBudget::new(50);
+        let start = Instant::now();
+        
+        // Five crawls making five requests each
+        let crawls: Vec<_> = (0..5).map(|_| {
+            let budget = budget.clone();
+            tokio::spawn(async move {
+                for _ in 0..5 {
+                    budget.acquire().await;
+                }
+            })
+        }).collect();
+        for crawl in crawls {
+            crawl.await.unwrap();
+        }
+        
+        // The first request goes out at once and the other 24 are 20 ms apart
+        assert!(start.elapsed() >= Duration::from_millis(480), "took {:?}", start.elapsed());
+    }
+}
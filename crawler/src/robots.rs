@@ -1,11 +1,13 @@
 use anyhow::{Result, anyhow};
 use url::Url;
+use scraper::{Html, Selector};
 use log::{info, warn, debug};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use reqwest::Client;
 use std::sync::Arc;
 use std::sync::Mutex;
+use crate::db::Database;
 
 /// Simple robots.txt parser
 #[derive(Default, Clone, Debug)]
@@ -20,9 +22,64 @@ pub struct RobotsTxt {
 #[derive(Clone, Debug)]
 enum Rule {
     /// Allow a path
-    Allow(String),
+    Allow(PathPattern),
     /// Disallow a path
-    Disallow(String),
+    Disallow(PathPattern),
+}
+
+/// A robots.txt path pattern, split at its `*` wildcards when parsed so that matching
+/// a path needs no further work. Like a plain prefix, a pattern matches any path that
+/// starts with it, unless it ends with `$`.
+#[derive(Clone, Debug)]
+struct PathPattern {
+    /// Length of the pattern as written, which decides between competing rules
+    len: usize,
+    /// Literal pieces between the wildcards; never empty
+    parts: Vec<String>,
+    /// Whether the pattern ended with `$` and must match the path up to its end
+    anchored: bool,
+}
+
+impl PathPattern {
+    fn new(pattern: &str) -> Self {
+        let (body, anchored) = match pattern.strip_suffix('$') {
+            Some(body) => (body, true),
+            None => (pattern, false),
+        };
+        
+        Self {
+            len: pattern.len(),
+            parts: body.split('*').map(str::to_string).collect(),
+            anchored,
+        }
+    }
+    
+    /// Check if path matches the pattern
+    fn matches(&self, path: &str) -> bool {
+        // The first piece must start the path
+        let Some(mut rest) = path.strip_prefix(self.parts[0].as_str()) else {
+            return false;
+        };
+        
+        let Some((last, middle)) = self.parts[1..].split_last() else {
+            // No wildcards: a prefix, or with `$` the whole path
+            return !self.anchored || rest.is_empty();
+        };
+        
+        // Taking the earliest occurrence of each piece leaves the most room for the rest
+        for part in middle {
+            match rest.find(part.as_str()) {
+                Some(idx) => rest = &rest[idx + part.len()..],
+                None => return false,
+            }
+        }
+        
+        if self.anchored {
+            rest.ends_with(last.as_str())
+        } else {
+            rest.contains(last.as_str())
+        }
+    }
 }
 
 impl RobotsTxt {
@@ -56,7 +113,7 @@ impl RobotsTxt {
                         current_agents.push(value.to_lowercase());
                     },
                     "allow" => {
-                        let rule = Rule::Allow(value.to_string());
+                        let rule = Rule::Allow(PathPattern::new(value));
                         if current_agents.is_empty() {
                             // No user agent context, add to default rules
                             self.default_rules.push(rule);
@@ -70,12 +127,12 @@ impl RobotsTxt {
                         }
                     },
                     "disallow" => {
-                        if value.is_empty() {
-                            // Empty disallow = allow all
-                            continue;
-                        }
-                        
-                        let rule = Rule::Disallow(value.to_string());
+                        // Empty disallow = allow all, kept as a rule so it can outweigh a `Disallow: /`
+                        let rule = if value.is_empty() {
+                            Rule::Allow(PathPattern::new("/"))
+                        } else {
+                            Rule::Disallow(PathPattern::new(value))
+                        };
                         if current_agents.is_empty() {
                             // No user agent context, add to default rules
                             self.default_rules.push(rule);
@@ -133,100 +190,103 @@ impl RobotsTxt {
         true
     }
     
-    /// Check if path matches any rules
+    /// Check if path matches any rules. As RFC 9309 specifies, the longest matching
+    /// pattern decides, whatever the order of the rules, and Allow wins a tie.
     fn check_rules(&self, rules: &[Rule], path: &str) -> Option<bool> {
-        let mut matched = false;
-        let mut result = false;
+        let mut best: Option<(usize, bool)> = None;
         
         for rule in rules {
-            match rule {
-                Rule::Allow(pattern) => {
-                    if self.path_matches(pattern, path) {
-                        matched = true;
-                        result = true;
-                    }
-                },
-                Rule::Disallow(pattern) => {
-                    if self.path_matches(pattern, path) {
-                        matched = true;
-                        result = false;
-                    }
-                },
+            let (pattern, allowed) = match rule {
+                Rule::Allow(pattern) => (pattern, true),
+                Rule::Disallow(pattern) => (pattern, false),
+            };
+            
+            // Tuples compare by length first, then prefer `true`
+            if pattern.matches(path) && best.map_or(true, |best| (pattern.len, allowed) > best) {
+                best = Some((pattern.len, allowed));
             }
         }
         
-        if matched {
-            Some(result)
-        } else {
-            None
-        }
-    }
-    
-    /// Check if path matches pattern
-    fn path_matches(&self, pattern: &str, path: &str) -> bool {
-        if pattern == "/" {
-            // Special case: pattern "/" matches everything
-            return true;
-        }
-        
-        if pattern.ends_with('*') {
-            // Handle wildcard at the end
-            let prefix = &pattern[..pattern.len() - 1];
-            path.starts_with(prefix)
-        } else {
-            // Otherwise exact match or directory prefix
-            path == pattern || path.starts_with(&format!("{}/", pattern))
-        }
+        best.map(|(_, allowed)| allowed)
     }
 }
 
-/// Extracts URLs from XML content using simple string search
+/// A `<loc>` entry of a sitemap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SitemapLoc<'a> {
+    /// A nested sitemap listed by a sitemap index
+    Sitemap(&'a str),
+    /// A page URL
+    Page(&'a str),
+}
+
+/// Yields the `<loc>` entries of XML content one at a time using simple string search,
+/// so a large sitemap can be read only as far as needed.
 /// This avoids using scraper which is not Send-compatible
-fn extract_urls_from_sitemap(content: &str) -> (Vec<String>, Vec<String>) {
-    let mut sitemap_urls = Vec::new();
-    let mut page_urls = Vec::new();
-    
-    // Look for sitemap URLs (in sitemap index)
+fn sitemap_locs(content: &str) -> impl Iterator<Item = SitemapLoc<'_>> {
     let mut pos = 0;
-    while let Some(loc_start) = content[pos..].find("<loc>") {
-        pos += loc_start + 5; // 5 is the length of "<loc>"
-        if let Some(loc_end) = content[pos..].find("</loc>") {
-            let url = content[pos..pos + loc_end].trim();
-            
-            // Determine if this is a sitemap URL or a page URL
-            // by checking if it's inside a <sitemap> tag
-            let preceding = &content[..pos];
-            let last_open_tag = preceding.rfind('<').and_then(|idx| {
-                let tag_start = &preceding[idx..];
-                if tag_start.starts_with("<sitemap") || tag_start.starts_with("<sitemap>") {
-                    Some("sitemap")
-                } else if tag_start.starts_with("<url") || tag_start.starts_with("<url>") {
-                    Some("url")
-                } else {
-                    None
-                }
-            });
-            
-            if let Some(tag_type) = last_open_tag {
-                if tag_type == "sitemap" {
-                    sitemap_urls.push(url.to_string());
-                } else {
-                    page_urls.push(url.to_string());
-                }
-            } else {
-                // If we can't determine, assume it's a page URL
-                page_urls.push(url.to_string());
-            }
-            
-            pos += loc_end + 6; // 6 is the length of "</loc>"
-        } else {
-            break;
-        }
-    }
-    
-    (sitemap_urls, page_urls)
+    std::iter::from_fn(move || {
+        let loc_start = content[pos..].find("<loc>")?;
+        let tag_start = pos + loc_start;
+        pos = tag_start + 5; // 5 is the length of "<loc>"
+        let loc_end = content[pos..].find("</loc>")?;
+        let url = content[pos..pos + loc_end].trim();
+        pos += loc_end + 6; // 6 is the length of "</loc>"
+        
+        // Determine if this is a sitemap URL or a page URL
+        // by checking if it's inside a <sitemap> tag
+        let preceding = &content[..tag_start];
+        let in_sitemap = preceding.rfind('<')
+            .map(|idx| preceding[idx..].starts_with("<sitemap"))
+            .unwrap_or(false);
+        
+        // If we can't determine, assume it's a page URL
+        Some(if in_sitemap { SitemapLoc::Sitemap(url) } else { SitemapLoc::Page(url) })
+    })
 }
 
+/// Attempts at fetching a robots.txt that fails with a timeout, network error or 5xx
+const ROBOTS_FETCH_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a failed robots.txt fetch, doubled for each further retry
+const ROBOTS_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How long a domain whose robots.txt could not be fetched is crawled without it before trying again
+const ROBOTS_FAILURE_TTL: Duration = Duration::from_secs(300);
+
+/// Why a domain is crawled without robots.txt rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoRobots {
+    /// The server says there is no robots.txt (4xx), so everything is allowed
+    Absent,
+    /// Fetching robots.txt kept failing; retried once the entry expires
+    FetchFailed,
+}
+
+/// Outcome of a single robots.txt request
+enum RobotsFetch {
+    /// The file's content
+    Found(String),
+    /// The server answered with a 4xx status
+    Absent(reqwest::StatusCode),
+    /// Timeout, network error or 5xx; worth retrying
+    Failed(String),
+}
+
+/// Sitemap paths tried in order when robots.txt lists no sitemap
+pub const DEFAULT_SITEMAP_LOCATIONS: &[&str] = &[
+    "/sitemap.xml",
+    "/sitemap_index.xml",
+    "/sitemaps.xml",
+    "/sitemap/sitemap.xml",
+];
+
+/// Most sitemap files fetched for one domain, nested sitemap indexes included
+const DEFAULT_MAX_SITEMAP_FETCHES: usize = 200;
+
+/// Product token matched against robots.txt `User-agent` lines unless another one is set
+pub const ROBOTS_PRODUCT_TOKEN: &str = "CryptoCrawl";
+
 /// Manager for robots.txt handling and JavaScript detection
 #[derive(Debug, Clone)]
 pub struct RobotsManager {
@@ -234,16 +294,26 @@ pub struct RobotsManager {
     robots_cache: HashMap<String, (RobotsTxt, SystemTime)>,
     /// Cache of sitemaps by domain
     sitemap_cache: HashMap<String, (HashSet<String>, SystemTime)>,
-    /// User agent to use for robots.txt
+    /// Token matched against robots.txt `User-agent` lines; never sent as a header
     user_agent: String,
     /// Cache validity duration
     cache_validity: Duration,
     /// HTTP client for fetching robots.txt and sitemaps
     client: Client,
-    /// Negative cache - domains that don't have robots.txt
-    negative_cache: HashSet<String>,
+    /// Negative cache - domains crawled without robots.txt, with why and since when
+    negative_cache: HashMap<String, (NoRobots, SystemTime)>,
     /// Thread-local cache of allowed URLs - changed to Mutex for thread safety
     allowed_urls_cache: Option<Arc<Mutex<VecDeque<(String, bool, SystemTime)>>>>,
+    /// Database keeping robots.txt and sitemaps across restarts
+    persistent_cache: Option<Database>,
+    /// Domains (and their subdomains) whose robots.txt is never fetched nor obeyed
+    override_domains: HashSet<String>,
+    /// Override domains already reported, shared by clones so each is logged once
+    overrides_logged: Arc<Mutex<HashSet<String>>>,
+    /// Sitemap paths tried when robots.txt lists none
+    sitemap_locations: Vec<String>,
+    /// Most sitemap files fetched for one domain
+    max_sitemap_fetches: usize,
 }
 
 impl Default for RobotsManager {
@@ -251,29 +321,127 @@ impl Default for RobotsManager {
         Self {
             robots_cache: HashMap::new(),
             sitemap_cache: HashMap::new(),
-            user_agent: "CryptoCrawl/0.1 (https://github.com/yourusername/cryptocrawl)".to_string(),
+            user_agent: ROBOTS_PRODUCT_TOKEN.to_string(),
             cache_validity: Duration::from_secs(3600), // 1 hour
             client: Client::new(),
-            negative_cache: HashSet::new(),
+            negative_cache: HashMap::new(),
             allowed_urls_cache: Some(Arc::new(Mutex::new(VecDeque::with_capacity(100)))),
+            persistent_cache: None,
+            override_domains: HashSet::new(),
+            overrides_logged: Arc::new(Mutex::new(HashSet::new())),
+            sitemap_locations: DEFAULT_SITEMAP_LOCATIONS.iter().map(|path| path.to_string()).collect(),
+            max_sitemap_fetches: DEFAULT_MAX_SITEMAP_FETCHES,
         }
     }
 }
 
 impl RobotsManager {
-    /// Create a new robots manager with custom user agent
+    /// Create a new robots manager matching robots.txt rules against a custom token
     pub fn new(user_agent: &str) -> Self {
         let mut manager = Self::default();
         manager.user_agent = user_agent.to_string();
         manager
     }
     
+    /// Match robots.txt rules against this token instead, e.g. `MyBot` for a robots.txt
+    /// with a `User-agent: MyBot` group. The HTTP `User-Agent` header is set on the client.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        if self.user_agent != user_agent {
+            self.user_agent = user_agent.to_string();
+            // Cached verdicts were reached for the old token and are shared with other clones
+            if self.allowed_urls_cache.is_some() {
+                self.allowed_urls_cache = Some(Arc::new(Mutex::new(VecDeque::with_capacity(100))));
+            }
+        }
+        self
+    }
+    
     /// Set the cache validity duration
     pub fn with_cache_validity(mut self, duration: Duration) -> Self {
         self.cache_validity = duration;
         self
     }
     
+    /// Keep fetched robots.txt files and sitemap URLs in the database so they survive restarts
+    pub fn with_persistent_cache(mut self, db: Database) -> Self {
+        self.persistent_cache = Some(db);
+        self
+    }
+    
+    /// Always allow URLs on these domains and their subdomains without fetching robots.txt.
+    /// Meant for sites you own; every override is logged as a warning.
+    pub fn with_override_domains(mut self, domains: HashSet<String>) -> Self {
+        self.override_domains = domains.into_iter()
+            .map(|domain| domain.trim().trim_end_matches('.').to_lowercase())
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        self
+    }
+    
+    /// Sitemap paths to try, in order, when robots.txt lists no sitemap
+    pub fn with_sitemap_locations(mut self, locations: Vec<String>) -> Self {
+        self.sitemap_locations.clear();
+        for location in locations {
+            let location = location.trim();
+            if location.is_empty() {
+                continue;
+            }
+            let location = if location.starts_with('/') {
+                location.to_string()
+            } else {
+                format!("/{}", location)
+            };
+            if !self.sitemap_locations.contains(&location) {
+                self.sitemap_locations.push(location);
+            }
+        }
+        self
+    }
+    
+    /// Stop reading a domain's sitemaps after this many fetches, so sitemap indexes can't recurse forever
+    pub fn with_max_sitemap_fetches(mut self, max_fetches: usize) -> Self {
+        self.max_sitemap_fetches = max_fetches;
+        self
+    }
+    
+    /// The sitemap URLs tried for `domain` when its robots.txt lists none
+    pub fn fallback_sitemaps(&self, domain: &str) -> Vec<String> {
+        self.sitemap_locations.iter()
+            .map(|path| format!("http://{}{}", domain, path))
+            .collect()
+    }
+    
+    /// The override entry covering `host`, if any
+    fn override_for(&self, host: &str) -> Option<&String> {
+        self.override_domains.iter()
+            .find(|domain| host == domain.as_str() || host.ends_with(&format!(".{}", domain)))
+    }
+    
+    /// Whether the domain is in the negative cache and its entry hasn't expired
+    fn has_no_robots(&mut self, domain: &str) -> bool {
+        let (reason, since) = match self.negative_cache.get(domain) {
+            Some(entry) => *entry,
+            None => return false,
+        };
+        
+        let ttl = match reason {
+            NoRobots::Absent => self.cache_validity,
+            NoRobots::FetchFailed => ROBOTS_FAILURE_TTL,
+        };
+        if SystemTime::now().duration_since(since).unwrap_or_default() <= ttl {
+            return true;
+        }
+        
+        debug!("Negative robots.txt cache entry for {} expired", domain);
+        self.negative_cache.remove(domain);
+        false
+    }
+    
+    /// Whether an entry fetched at `fetched_at` is still within the cache validity
+    fn is_fresh(&self, fetched_at: SystemTime) -> bool {
+        SystemTime::now().duration_since(fetched_at).unwrap_or_default() <= self.cache_validity
+    }
+    
     /// Set the HTTP client
     pub fn with_client(mut self, client: Client) -> Self {
         self.client = client;
@@ -322,9 +490,20 @@ impl RobotsManager {
         let domain = url.host_str()
             .ok_or_else(|| anyhow!("URL has no host"))?
             .to_string();
+        
+        // Overridden domains are allowed without looking at robots.txt
+        if let Some(override_domain) = self.override_for(&domain) {
+            let first_use = self.overrides_logged.lock().unwrap().insert(domain.clone());
+            if first_use {
+                warn!("Ignoring robots.txt for {} (override for {})", domain, override_domain);
+            } else {
+                debug!("robots.txt override allows {}", url);
+            }
+            return Ok(true);
+        }
             
-        // Check negative cache - domains we know don't have robots.txt
-        if self.negative_cache.contains(&domain) {
+        // Check negative cache - domains without robots.txt or whose robots.txt is unreachable for now
+        if self.has_no_robots(&domain) {
             // Cache result
             if let Some(ref cache) = self.allowed_urls_cache {
                 let mut cache_guard = cache.lock().unwrap();
@@ -340,9 +519,9 @@ impl RobotsManager {
         let robots = match self.get_robots_parser(&domain).await {
             Ok(robots) => robots,
             Err(e) => {
-                // If we failed to get robots.txt, assume allowed and cache the domain
-                debug!("Failed to get robots.txt for {}: {}", domain, e);
-                self.negative_cache.insert(domain);
+                // If robots.txt stayed unreachable, assume allowed until the failure expires
+                warn!("{}; allowing {} for {:?}", e, domain, ROBOTS_FAILURE_TTL);
+                self.negative_cache.insert(domain, (NoRobots::FetchFailed, SystemTime::now()));
                 
                 // Cache result
                 if let Some(ref cache) = self.allowed_urls_cache {
@@ -378,40 +557,53 @@ impl RobotsManager {
             None => true,
         };
         
-        // Fetch and parse robots.txt if needed
+        // Fall back to the persistent cache before going to the network
+        if needs_refresh {
+            if let Some((content, fetched_at)) = self.load_persisted_robots(domain) {
+                debug!("Using persisted robots.txt for {}", domain);
+                let mut robots = RobotsTxt::default();
+                robots.parse(&content);
+                self.robots_cache.insert(domain.to_string(), (robots, fetched_at));
+                return Ok(&self.robots_cache.get(domain)
+                    .expect("Parser should exist at this point")
+                    .0);
+            }
+        }
+        
+        // Fetch and parse robots.txt if needed, retrying transient failures with backoff
         if needs_refresh {
             info!("Fetching robots.txt for domain: {}", domain);
             let robots_url = format!("http://{}/robots.txt", domain);
             
-            // Create a new parser
-            let mut robots = RobotsTxt::default();
-            
-            // Try to read the robots.txt file
-            match self.client.get(&robots_url).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let robots_content = match response.text().await {
-                            Ok(text) => text,
-                            Err(e) => {
-                                warn!("Failed to read robots.txt for {}: {}", domain, e);
-                                String::new() // Empty robots.txt (all allowed)
-                            }
-                        };
-                        
-                        // Parse the robots.txt content
-                        robots.parse(&robots_content);
-                    } else {
-                        debug!("No robots.txt found for {} (status: {})", domain, response.status());
-                        // Default parser (all allowed)
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to fetch robots.txt for {}: {}", domain, e);
-                    // Default parser (all allowed)
+            let mut delay = ROBOTS_RETRY_DELAY;
+            let mut attempt = 1;
+            let content = loop {
+                match self.fetch_robots(&robots_url).await {
+                    RobotsFetch::Found(content) => {
+                        self.persist_robots(domain, &content);
+                        break content;
+                    },
+                    RobotsFetch::Absent(status) => {
+                        debug!("No robots.txt found for {} (status: {})", domain, status);
+                        self.persist_robots(domain, "");
+                        self.negative_cache.insert(domain.to_string(), (NoRobots::Absent, SystemTime::now()));
+                        break String::new();
+                    },
+                    RobotsFetch::Failed(reason) if attempt < ROBOTS_FETCH_ATTEMPTS => {
+                        warn!("Failed to fetch robots.txt for {} ({}), retrying in {:?}", domain, reason, delay);
+                        tokio::time::sleep(delay).await;
+                        delay *= 2;
+                        attempt += 1;
+                    },
+                    RobotsFetch::Failed(reason) => {
+                        return Err(anyhow!("Failed to fetch robots.txt for {} after {} attempts: {}", domain, attempt, reason));
+                    },
                 }
             };
             
-            // Store in cache
+            // Parse and cache the rules
+            let mut robots = RobotsTxt::default();
+            robots.parse(&content);
             self.robots_cache.insert(domain.to_string(), (robots, SystemTime::now()));
         }
         
@@ -421,6 +613,52 @@ impl RobotsManager {
             .0)
     }
     
+    /// Request robots.txt once
+    async fn fetch_robots(&self, robots_url: &str) -> RobotsFetch {
+        let response = match self.client.get(robots_url).send().await {
+            Ok(response) => response,
+            Err(e) => return RobotsFetch::Failed(e.to_string()),
+        };
+        
+        let status = response.status();
+        if status.is_client_error() {
+            return RobotsFetch::Absent(status);
+        }
+        if !status.is_success() {
+            return RobotsFetch::Failed(format!("status {}", status));
+        }
+        
+        match response.text().await {
+            Ok(content) => RobotsFetch::Found(content),
+            Err(e) => RobotsFetch::Failed(e.to_string()),
+        }
+    }
+    
+    /// Load a domain's robots.txt from the persistent cache if it is still valid
+    fn load_persisted_robots(&self, domain: &str) -> Option<(String, SystemTime)> {
+        let db = self.persistent_cache.as_ref()?;
+        match db.get_cached_robots(domain) {
+            Ok(Some((content, fetched_at))) => {
+                let fetched_at = UNIX_EPOCH + Duration::from_secs(fetched_at);
+                self.is_fresh(fetched_at).then_some((content, fetched_at))
+            },
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to read cached robots.txt for {}: {}", domain, e);
+                None
+            }
+        }
+    }
+    
+    /// Store a domain's robots.txt in the persistent cache
+    fn persist_robots(&self, domain: &str, content: &str) {
+        if let Some(db) = &self.persistent_cache {
+            if let Err(e) = db.save_cached_robots(domain, content) {
+                warn!("Failed to cache robots.txt for {}: {}", domain, e);
+            }
+        }
+    }
+    
     /// Extract sitemap URLs from robots.txt
     pub async fn get_sitemaps_from_robots(&mut self, domain: &str) -> Result<Vec<String>> {
         // Extract sitemaps
@@ -463,42 +701,106 @@ impl RobotsManager {
     
     /// Get all URLs from a domain's sitemaps
     pub async fn get_sitemap_urls(&mut self, domain: &str) -> Result<HashSet<String>> {
+        self.collect_sitemap_urls(domain, usize::MAX).await
+    }
+    
+    /// Get at most `limit` URLs from a domain's sitemaps, reading no further than needed.
+    /// Sitemaps are read in order, so the same sitemaps always yield the same URLs.
+    pub async fn get_sitemap_urls_up_to(&mut self, domain: &str, limit: usize) -> Result<HashSet<String>> {
+        self.collect_sitemap_urls(domain, limit).await
+    }
+    
+    /// Read a domain's sitemaps until `limit` URLs are found; only complete results are cached
+    async fn collect_sitemap_urls(&mut self, domain: &str, limit: usize) -> Result<HashSet<String>> {
         // Check cache
         if let Some((urls, timestamp)) = self.sitemap_cache.get(domain) {
             let now = SystemTime::now();
             if now.duration_since(*timestamp).unwrap_or_default() <= self.cache_validity {
-                return Ok(urls.clone());
+                return Ok(first_urls(urls, limit));
+            }
+        }
+        
+        // Then the persistent cache
+        if let Some(db) = &self.persistent_cache {
+            match db.get_cached_sitemap_urls(domain) {
+                Ok(Some((urls, fetched_at))) => {
+                    let fetched_at = UNIX_EPOCH + Duration::from_secs(fetched_at);
+                    if self.is_fresh(fetched_at) {
+                        debug!("Using persisted sitemap URLs for {}", domain);
+                        let limited = first_urls(&urls, limit);
+                        self.sitemap_cache.insert(domain.to_string(), (urls, fetched_at));
+                        return Ok(limited);
+                    }
+                },
+                Ok(None) => {},
+                Err(e) => warn!("Failed to read cached sitemap URLs for {}: {}", domain, e),
             }
         }
         
         // Get sitemaps from robots.txt
         let sitemap_urls = self.get_sitemaps_from_robots(domain).await?;
         
-        // If no sitemaps in robots.txt, try the standard location
-        let sitemap_urls = if sitemap_urls.is_empty() {
-            vec![format!("http://{}/sitemap.xml", domain)]
-        } else {
-            sitemap_urls
-        };
-        
         // Process each sitemap
         let mut all_urls = HashSet::new();
         let mut visited_sitemaps = HashSet::new();
+        let mut fetches = 0;
         
         // Create an Arc<Client> to share across async tasks
         let client = Arc::new(self.client.clone());
         
-        // Process initial sitemaps
-        for sitemap_url in sitemap_urls {
-            self.process_sitemap_non_recursive(
-                &sitemap_url, 
-                client.clone(), 
-                &mut all_urls, 
-                &mut visited_sitemaps
-            ).await?;
+        if sitemap_urls.is_empty() {
+            // No sitemaps in robots.txt, so try the common locations until one lists URLs
+            for sitemap_url in self.fallback_sitemaps(domain) {
+                if fetches >= self.max_sitemap_fetches {
+                    break;
+                }
+                self.process_sitemap_non_recursive(
+                    &sitemap_url,
+                    client.clone(),
+                    &mut all_urls,
+                    &mut visited_sitemaps,
+                    &mut fetches,
+                    limit
+                ).await?;
+                if !all_urls.is_empty() {
+                    info!("Found sitemap for {} at {}", domain, sitemap_url);
+                    break;
+                }
+            }
+            if all_urls.is_empty() {
+                info!("No sitemap found for {} at {} common locations", domain, self.sitemap_locations.len());
+            }
+        } else {
+            // Process the sitemaps robots.txt lists, each once
+            for sitemap_url in sitemap_urls {
+                if all_urls.len() >= limit {
+                    break;
+                }
+                if visited_sitemaps.contains(&sitemap_url) {
+                    debug!("Skipping duplicate sitemap {}", sitemap_url);
+                    continue;
+                }
+                self.process_sitemap_non_recursive(
+                    &sitemap_url, 
+                    client.clone(), 
+                    &mut all_urls, 
+                    &mut visited_sitemaps,
+                    &mut fetches,
+                    limit
+                ).await?;
+            }
         }
         
-        // Cache the results
+        // Cache the results, unless the limit cut them short
+        if all_urls.len() >= limit {
+            debug!("Stopped reading sitemaps for {} at {} URLs", domain, limit);
+            return Ok(all_urls);
+        }
+        if let Some(db) = &self.persistent_cache {
+            if let Err(e) = db.save_cached_sitemap_urls(domain, &all_urls) {
+                warn!("Failed to cache sitemap URLs for {}: {}", domain, e);
+            }
+        }
         self.sitemap_cache.insert(
             domain.to_string(), 
             (all_urls.clone(), SystemTime::now())
@@ -514,7 +816,9 @@ impl RobotsManager {
         initial_sitemap_url: &str, 
         client: Arc<Client>,
         all_urls: &mut HashSet<String>,
-        visited_sitemaps: &mut HashSet<String>
+        visited_sitemaps: &mut HashSet<String>,
+        fetches: &mut usize,
+        limit: usize
     ) -> Result<()> {
         // Stack of sitemaps to process
         let mut sitemap_stack = vec![initial_sitemap_url.to_string()];
@@ -523,6 +827,14 @@ impl RobotsManager {
         visited_sitemaps.insert(initial_sitemap_url.to_string());
         
         while let Some(sitemap_url) = sitemap_stack.pop() {
+            if all_urls.len() >= limit {
+                break;
+            }
+            if *fetches >= self.max_sitemap_fetches {
+                warn!("Stopped reading sitemaps after {} fetches; {} left unread", fetches, sitemap_stack.len() + 1);
+                break;
+            }
+            *fetches += 1;
             info!("Processing sitemap: {}", sitemap_url);
             
             // Fetch sitemap
@@ -548,119 +860,454 @@ impl RobotsManager {
                 }
             };
             
-            // Extract URLs using a simpler method that doesn't use scraper
-            let (sub_sitemaps, page_urls) = extract_urls_from_sitemap(&content);
-            
-            // Add all page URLs to the result set
-            for url in page_urls {
-                all_urls.insert(url);
-            }
-            
-            // Add all sub-sitemaps to the stack if not visited yet
-            for sub_sitemap in sub_sitemaps {
-                if !visited_sitemaps.contains(&sub_sitemap) {
-                    visited_sitemaps.insert(sub_sitemap.clone());
-                    sitemap_stack.push(sub_sitemap);
+            // Add page URLs to the result set until the limit, and sub-sitemaps
+            // to the stack if not visited yet
+            let mut sub_sitemaps = Vec::new();
+            for loc in sitemap_locs(&content) {
+                match loc {
+                    SitemapLoc::Page(url) => {
+                        all_urls.insert(url.to_string());
+                        if all_urls.len() >= limit {
+                            break;
+                        }
+                    },
+                    SitemapLoc::Sitemap(url) => {
+                        if visited_sitemaps.insert(url.to_string()) {
+                            sub_sitemaps.push(url.to_string());
+                        }
+                    },
                 }
             }
+            
+            // Pushed in reverse so nested sitemaps are read in the order they are listed
+            sitemap_stack.extend(sub_sitemaps.into_iter().rev());
         }
         
         Ok(())
     }
 }
 
-/// Check if a site is likely JavaScript-dependent
+/// The first `limit` URLs of a cached set, in sorted order so repeated calls agree
+fn first_urls(urls: &HashSet<String>, limit: usize) -> HashSet<String> {
+    if urls.len() <= limit {
+        return urls.clone();
+    }
+    let mut sorted: Vec<&String> = urls.iter().collect();
+    sorted.sort();
+    sorted.into_iter().take(limit).cloned().collect()
+}
+
+/// Outcome of checking a page for signs that it needs JavaScript to show its content
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsAssessment {
+    /// Whether at least the required number of indicators was found
+    pub is_dependent: bool,
+    /// Confidence from 0 to 1, combining the weights of the indicators found
+    pub score: f64,
+    /// Descriptions of the indicators found
+    pub reasons: Vec<&'static str>,
+}
+
+/// A sign that a page relies on JavaScript, and how strongly it suggests so
+struct JsIndicator {
+    reason: &'static str,
+    weight: f64,
+    detect: fn(&Html, &str) -> bool,
+}
+
+/// The indicators checked, from the most telling to the least
+const JS_INDICATORS: [JsIndicator; 9] = [
+    JsIndicator { reason: "noscript warning found", weight: 0.5, detect: |document, _| has_noscript_warning(document) },
+    JsIndicator { reason: "JavaScript framework root element found", weight: 0.4, detect: |document, _| has_framework_root(document) },
+    JsIndicator { reason: "JavaScript framework script found", weight: 0.3, detect: |document, _| has_framework_script(document) },
+    JsIndicator { reason: "Ember.js application detected", weight: 0.4, detect: |document, _| has_ember_config(document) },
+    JsIndicator { reason: "Lazy-loaded images found", weight: 0.1, detect: |document, _| has_lazy_images(document) },
+    JsIndicator { reason: "Web components found", weight: 0.2, detect: |document, _| has_web_components(document) },
+    JsIndicator { reason: "Empty content container found", weight: 0.5, detect: |document, _| has_empty_content_container(document) },
+    JsIndicator { reason: "Loading indicator found", weight: 0.2, detect: |document, _| has_loading_indicator(document) },
+    JsIndicator { reason: "Dynamic content initialization found", weight: 0.05, detect: |_, html| has_dynamic_initialization(html) },
+];
+
+/// Whether any element matches the CSS selector
+fn matches_any(document: &Html, selector: &str) -> bool {
+    Selector::parse(selector)
+        .map(|selector| document.select(&selector).next().is_some())
+        .unwrap_or(false)
+}
+
+/// A `<noscript>` telling the visitor to enable JavaScript
+fn has_noscript_warning(document: &Html) -> bool {
+    let Ok(selector) = Selector::parse("noscript") else {
+        return false;
+    };
+    document.select(&selector).any(|noscript| {
+        let content = noscript.inner_html().to_lowercase();
+        content.contains("javascript") || content.contains("enable") || content.contains("script")
+    })
+}
+
+/// A mount point of a common JavaScript framework
+fn has_framework_root(document: &Html) -> bool {
+    ["#app", "#root", "[ng-app]", "[data-reactroot]", ".vue-app", ".ember-view", ".ember-application"]
+        .iter()
+        .any(|selector| matches_any(document, selector))
+}
+
+/// A script whose source names a framework or bundler
+fn has_framework_script(document: &Html) -> bool {
+    let Ok(selector) = Selector::parse("script[src]") else {
+        return false;
+    };
+    document.select(&selector)
+        .filter_map(|script| script.value().attr("src"))
+        .any(|src| {
+            let src = src.to_lowercase();
+            ["react", "vue", "angular", "ember", "webpack", "chunk"].iter().any(|keyword| src.contains(keyword))
+        })
+}
+
+/// The config meta tag Ember CLI emits for crates.io
+fn has_ember_config(document: &Html) -> bool {
+    matches_any(document, "meta[name='crates-io/config/environment']")
+}
+
+/// Images whose source is only filled in by a script
+fn has_lazy_images(document: &Html) -> bool {
+    matches_any(document, "img[loading='lazy'], img[data-src]")
+}
+
+/// Customized built-in or custom elements
+fn has_web_components(document: &Html) -> bool {
+    matches_any(document, "*[is], *[custom-element]")
+}
+
+/// A main content element with nothing in it
+fn has_empty_content_container(document: &Html) -> bool {
+    let Ok(selector) = Selector::parse("main, #content, .content, article") else {
+        return false;
+    };
+    document.select(&selector).any(|content| content.inner_html().trim().is_empty())
+}
+
+/// A spinner or loading placeholder
+fn has_loading_indicator(document: &Html) -> bool {
+    matches_any(document, "[class*='loading'], [id*='loading'], [class*='spinner']")
+}
+
+/// Scripts touching the DOM once the page loads
+fn has_dynamic_initialization(html: &str) -> bool {
+    ["window.", "document.", "addEventListener", "DOMContentLoaded"].iter().any(|trigger| html.contains(trigger))
+}
+
+/// Check if a site is likely JavaScript-dependent: it is when at least `min_reasons`
+/// indicators are found. The score combines the indicators' weights so that strong ones
+/// (a noscript warning, an empty content container) count for more than a stray
+/// `window.` reference.
+pub fn assess_javascript_dependence(html: &str, min_reasons: usize) -> JsAssessment {
+    let document = Html::parse_document(html);
+    let found: Vec<&JsIndicator> = JS_INDICATORS.iter()
+        .filter(|indicator| (indicator.detect)(&document, html))
+        .collect();
+    
+    // Each indicator independently suggests JavaScript, so together they're more convincing
+    let score = 1.0 - found.iter().map(|indicator| 1.0 - indicator.weight).product::<f64>();
+    let reasons: Vec<&'static str> = found.iter().map(|indicator| indicator.reason).collect();
+    
+    JsAssessment {
+        is_dependent: reasons.len() >= min_reasons.max(1),
+        score,
+        reasons,
+    }
+}
+
+/// Check if a site is likely JavaScript-dependent: any indicator counts.
+/// [`assess_javascript_dependence`] also weighs the indicators into a confidence score.
 pub fn is_javascript_dependent(html: &str) -> (bool, Vec<&str>) {
-    use scraper::{Html, Selector};
+    let assessment = assess_javascript_dependence(html, 1);
+    (assessment.is_dependent, assessment.reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     
-    let document = Html::parse_document(html);
-    let mut reasons = Vec::new();
-    
-    // 1. Check for noscript warnings
-    if let Ok(noscript_selector) = Selector::parse("noscript") {
-        for noscript in document.select(&noscript_selector) {
-            let content = noscript.inner_html().to_lowercase();
-            if content.contains("javascript") || 
-               content.contains("enable") || 
-               content.contains("script") {
-                reasons.push("noscript warning found");
-                break;
-            }
+    #[tokio::test]
+    async fn override_domains_are_allowed_without_fetching_robots() {
+        let mut manager = RobotsManager::default()
+            .with_override_domains(HashSet::from(["Example.invalid".to_string()]));
+        
+        for url in ["http://example.invalid/private", "http://www.example.invalid/"] {
+            assert!(manager.is_allowed(&Url::parse(url).unwrap()).await.unwrap());
         }
+        assert!(manager.robots_cache.is_empty());
+        
+        assert!(manager.override_for("notexample.invalid").is_none());
     }
     
-    // 2. Check for common JS framework root elements
-    for selector_str in &["#app", "#root", "[ng-app]", "[data-reactroot]", ".vue-app", ".ember-view", ".ember-application"] {
-        if let Ok(selector) = Selector::parse(selector_str) {
-            if document.select(&selector).next().is_some() {
-                reasons.push("JavaScript framework root element found");
-                break;
-            }
-        }
+    #[tokio::test]
+    async fn robots_rules_match_the_token_not_the_http_user_agent() {
+        let mut robots = RobotsTxt::default();
+        robots.parse("User-agent: CryptoCrawl\nDisallow: /private\n\nUser-agent: *\nAllow: /\n");
+        let private = Url::parse("http://example.com/private/page").unwrap();
+        let public = Url::parse("http://example.com/docs").unwrap();
+        
+        // The HTTP client says it's a browser; rules are still matched against the product token
+        let browser = Client::builder().user_agent("Mozilla/5.0 (X11; Linux x86_64)").build().unwrap();
+        let mut manager = RobotsManager::default().with_client(browser);
+        manager.robots_cache.insert("example.com".to_string(), (robots, SystemTime::now()));
+        assert!(!manager.is_allowed(&private).await.unwrap());
+        assert!(manager.is_allowed(&public).await.unwrap());
+        
+        // Another token falls under the `*` group, and verdicts for the old one aren't reused
+        let mut manager = manager.with_user_agent("OtherBot");
+        assert!(manager.is_allowed(&private).await.unwrap());
+        let mut manager = manager.with_user_agent(ROBOTS_PRODUCT_TOKEN);
+        assert!(!manager.is_allowed(&private).await.unwrap());
     }
     
-    // 3. Check for script tags with framework keywords
-    if let Ok(script_selector) = Selector::parse("script[src]") {
-        for script in document.select(&script_selector) {
-            if let Some(src) = script.value().attr("src") {
-                let src_lower = src.to_lowercase();
-                if src_lower.contains("react") || 
-                   src_lower.contains("vue") || 
-                   src_lower.contains("angular") ||
-                   src_lower.contains("ember") ||
-                   src_lower.contains("webpack") ||
-                   src_lower.contains("chunk") {
-                    reasons.push("JavaScript framework script found");
-                    break;
-                }
+    #[tokio::test]
+    async fn transient_robots_failures_are_retried_and_expire() {
+        use axum::{http::StatusCode, routing::get, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        // Fail twice with a 503, then serve the real file
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route("/robots.txt", get(move || async move {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(StatusCode::SERVICE_UNAVAILABLE)
+            } else {
+                Ok("User-agent: *\nDisallow: /private\n")
             }
-        }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let domain = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let mut manager = RobotsManager::default();
+        let robots = manager.get_robots_parser(&domain).await.unwrap();
+        assert!(!robots.can_fetch("*", &Url::parse("http://example.com/private/page").unwrap()));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(manager.negative_cache.is_empty());
+        
+        // A failure only keeps the domain out of robots.txt checks until its entry expires
+        let stale = SystemTime::now() - ROBOTS_FAILURE_TTL - Duration::from_secs(1);
+        manager.negative_cache.insert("down.invalid".to_string(), (NoRobots::FetchFailed, SystemTime::now()));
+        assert!(manager.has_no_robots("down.invalid"));
+        manager.negative_cache.insert("down.invalid".to_string(), (NoRobots::FetchFailed, stale));
+        assert!(!manager.has_no_robots("down.invalid"));
+        assert!(manager.negative_cache.is_empty());
     }
     
-    // 4. Check for meta tags indicating JS frameworks
-    if let Ok(meta_selector) = Selector::parse("meta[name='crates-io/config/environment']") {
-        if document.select(&meta_selector).next().is_some() {
-            reasons.push("Ember.js application detected");
-        }
+    #[tokio::test]
+    async fn sitemaps_fall_back_to_common_locations_with_a_fetch_cap() {
+        use axum::{extract::State, routing::get, Router};
+        
+        // No robots.txt and no /sitemap.xml; the index links to itself and two sitemaps
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let domain = listener.local_addr().unwrap().to_string();
+        let app = Router::new()
+            .route("/sitemap_index.xml", get(|State(domain): State<String>| async move {
+                format!(
+                    "<sitemapindex><sitemap><loc>http://{d}/sitemap_index.xml</loc></sitemap>\
+                     <sitemap><loc>http://{d}/a.xml</loc></sitemap>\
+                     <sitemap><loc>http://{d}/b.xml</loc></sitemap></sitemapindex>",
+                    d = domain
+                )
+            }))
+            .route("/a.xml", get(|| async { "<urlset><url><loc>http://example.com/a</loc></url></urlset>" }))
+            .route("/b.xml", get(|| async { "<urlset><url><loc>http://example.com/b</loc></url></urlset>" }))
+            .with_state(domain.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let mut manager = RobotsManager::default();
+        let urls = manager.get_sitemap_urls(&domain).await.unwrap();
+        assert_eq!(urls, HashSet::from(["http://example.com/a".to_string(), "http://example.com/b".to_string()]));
+        
+        // /sitemap.xml and the index use up the budget before either sitemap is read
+        let mut manager = RobotsManager::default().with_max_sitemap_fetches(2);
+        assert!(manager.get_sitemap_urls(&domain).await.unwrap().is_empty());
+        
+        let manager = RobotsManager::default()
+            .with_sitemap_locations(vec!["a.xml".to_string(), "/a.xml".to_string(), " ".to_string()]);
+        assert_eq!(manager.fallback_sitemaps("example.com"), vec!["http://example.com/a.xml".to_string()]);
     }
     
-    // 5. Check for lazy loaded images
-    if let Ok(img_selector) = Selector::parse("img[loading='lazy'], img[data-src]") {
-        if document.select(&img_selector).next().is_some() {
-            reasons.push("Lazy-loaded images found");
-        }
+    #[tokio::test]
+    async fn sitemap_reading_stops_at_the_limit() {
+        use axum::{routing::get, Router};
+        
+        let urlset: String = (0..10)
+            .map(|i| format!("<url><loc>http://example.com/{}</loc></url>", i))
+            .collect();
+        let urlset = format!("<urlset>{}</urlset>", urlset);
+        let app = Router::new().route("/sitemap.xml", get(move || async move { urlset }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let domain = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        // A partial read yields the first entries and isn't cached as the whole sitemap
+        let mut manager = RobotsManager::default();
+        let urls = manager.get_sitemap_urls_up_to(&domain, 3).await.unwrap();
+        assert_eq!(urls, (0..3).map(|i| format!("http://example.com/{}", i)).collect());
+        assert!(manager.sitemap_cache.is_empty());
+        
+        assert_eq!(manager.get_sitemap_urls(&domain).await.unwrap().len(), 10);
+        assert_eq!(manager.get_sitemap_urls_up_to(&domain, 3).await.unwrap().len(), 3);
     }
     
-    // 6. Check for web components
-    if let Ok(component_selector) = Selector::parse("*[is], *[custom-element]") {
-        if document.select(&component_selector).next().is_some() {
-            reasons.push("Web components found");
-        }
+    #[test]
+    fn sitemap_locs_tell_nested_sitemaps_from_pages() {
+        let index = "<sitemapindex><sitemap><loc> http://example.com/a.xml </loc></sitemap></sitemapindex>\
+                     <urlset><url><loc>http://example.com/page</loc></url></urlset>";
+        assert_eq!(sitemap_locs(index).collect::<Vec<_>>(), vec![
+            SitemapLoc::Sitemap("http://example.com/a.xml"),
+            SitemapLoc::Page("http://example.com/page"),
+        ]);
     }
     
-    // 7. Check for empty content containers
-    if let Ok(content_selector) = Selector::parse("main, #content, .content, article") {
-        for content in document.select(&content_selector) {
-            if content.inner_html().trim().is_empty() {
-                reasons.push("Empty content container found");
-                break;
-            }
+    #[test]
+    fn strong_javascript_indicators_outweigh_weak_ones() {
+        let weak = r#"<html><body><p>Hello</p><script>window.onload = () => {};</script></body></html>"#;
+        let weak_assessment = assess_javascript_dependence(weak, 1);
+        assert!(weak_assessment.is_dependent);
+        assert_eq!(weak_assessment.reasons, ["Dynamic content initialization found"]);
+        assert!(weak_assessment.score < 0.1);
+        
+        let strong = r#"<html><body><noscript>Please enable JavaScript</noscript><main></main></body></html>"#;
+        let strong_assessment = assess_javascript_dependence(strong, 2);
+        assert!(strong_assessment.is_dependent);
+        assert_eq!(strong_assessment.reasons.len(), 2);
+        assert!((strong_assessment.score - 0.75).abs() < 1e-9);
+        
+        // Requiring more indicators than found leaves the page static
+        assert!(!assess_javascript_dependence(weak, 2).is_dependent);
+        
+        // The tuple form counts any indicator
+        assert_eq!(is_javascript_dependent(weak), (true, vec!["Dynamic content initialization found"]));
+        assert_eq!(is_javascript_dependent("<p>static</p>"), (false, Vec::new()));
+    }
+    
+    /// Whether robots.txt `content` lets any crawler fetch `path` on example.com
+    fn allows(content: &str, path: &str) -> bool {
+        let mut robots = RobotsTxt::default();
+        robots.parse(content);
+        robots.can_fetch("*", &Url::parse("http://example.com").unwrap().join(path).unwrap())
+    }
+    
+    #[test]
+    fn empty_disallow_allows_everything() {
+        for content in [
+            "User-agent: *\nDisallow: /\nDisallow:\n",
+            "User-agent: *\nDisallow:\nDisallow: /\n",
+        ] {
+            assert!(allows(content, "/"), "{:?}", content);
+            assert!(allows(content, "/private/page"), "{:?}", content);
         }
+        
+        // A longer Disallow still outweighs it
+        let content = "User-agent: *\nDisallow:\nDisallow: /private\n";
+        assert!(allows(content, "/public"));
+        assert!(!allows(content, "/private/page"));
     }
     
-    // 8. Check for loading indicators
-    if let Ok(loading_selector) = Selector::parse("[class*='loading'], [id*='loading'], [class*='spinner']") {
-        if document.select(&loading_selector).next().is_some() {
-            reasons.push("Loading indicator found");
+    #[test]
+    fn longest_matching_rule_wins_regardless_of_order() {
+        for content in [
+            "User-agent: *\nDisallow: /docs\nAllow: /docs/public\n",
+            "User-agent: *\nAllow: /docs/public\nDisallow: /docs\n",
+        ] {
+            assert!(allows(content, "/docs/public/page"), "{:?}", content);
+            assert!(!allows(content, "/docs/private"), "{:?}", content);
         }
     }
     
-    // 9. Check for specific dynamic content triggers
-    if html.contains("window.") || html.contains("document.") || 
-       html.contains("addEventListener") || html.contains("DOMContentLoaded") {
-        reasons.push("Dynamic content initialization found");
+    #[test]
+    fn wildcards_match_anywhere_and_dollar_anchors_the_end() {
+        let pdf = PathPattern::new("/*.pdf$");
+        assert!(pdf.matches("/report.pdf"));
+        assert!(pdf.matches("/docs/2024/report.pdf"));
+        assert!(!pdf.matches("/report.pdf.html"));
+        assert!(!pdf.matches("/report.PDF"));
+        
+        let nested = PathPattern::new("/a/*/b");
+        assert!(nested.matches("/a/x/b"));
+        assert!(nested.matches("/a/x/y/b/c"));
+        assert!(!nested.matches("/a/b"));
+        assert!(!nested.matches("/b/x/b"));
+        
+        let both = PathPattern::new("/files/*/draft-*.pdf$");
+        assert!(both.matches("/files/2024/draft-1.pdf"));
+        assert!(both.matches("/files/a/draft-b/draft-2.pdf"));
+        assert!(!both.matches("/files/2024/final.pdf"));
+        assert!(!both.matches("/files/2024/draft-1.pdf/view"));
+        
+        // Without wildcards a pattern is a prefix, or the whole path with `$`
+        assert!(PathPattern::new("/private").matches("/private/page"));
+        assert!(PathPattern::new("/exact$").matches("/exact"));
+        assert!(!PathPattern::new("/exact$").matches("/exact/page"));
+        
+        assert!(!allows("User-agent: *\nDisallow: /*.pdf$\n", "/docs/guide.pdf"));
+        assert!(allows("User-agent: *\nDisallow: /*.pdf$\n", "/docs/guide.html"));
+    }
+    
+    #[test]
+    fn javascript_heuristics_detect_their_indicator() {
+        let parse = Html::parse_document;
+        
+        assert!(has_noscript_warning(&parse("<noscript>Please enable JavaScript</noscript>")));
+        assert!(!has_noscript_warning(&parse("<noscript><img src=\"/pixel.gif\"></noscript>")));
+        assert!(has_framework_root(&parse("<div id=\"app\"></div>")));
+        assert!(has_framework_script(&parse("<script src=\"/js/vendor.chunk.js\"></script>")));
+        assert!(!has_framework_script(&parse("<script src=\"/js/analytics.js\"></script>")));
+        assert!(has_lazy_images(&parse("<img data-src=\"/a.png\">")));
+        assert!(has_web_components(&parse("<button is=\"fancy-button\">Go</button>")));
+        assert!(has_empty_content_container(&parse("<main>  </main>")));
+        assert!(!has_empty_content_container(&parse("<main><p>Text</p></main>")));
+        assert!(has_loading_indicator(&parse("<div class=\"spinner-border\"></div>")));
+        assert!(has_dynamic_initialization("document.addEventListener('DOMContentLoaded', init);"));
+    }
+    
+    #[test]
+    fn static_page_is_not_javascript_dependent() {
+        let assessment = assess_javascript_dependence(include_str!("../tests/fixtures/js/static.html"), 1);
+        assert_eq!(assessment, JsAssessment { is_dependent: false, score: 0.0, reasons: Vec::new() });
+    }
+    
+    #[test]
+    fn react_shell_is_javascript_dependent() {
+        let assessment = assess_javascript_dependence(include_str!("../tests/fixtures/js/react-spa.html"), 3);
+        assert!(assessment.is_dependent);
+        assert_eq!(assessment.reasons, [
+            "noscript warning found",
+            "JavaScript framework root element found",
+            "JavaScript framework script found",
+        ]);
+        assert!((assessment.score - 0.79).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn ember_app_is_javascript_dependent() {
+        let assessment = assess_javascript_dependence(include_str!("../tests/fixtures/js/ember-app.html"), 2);
+        assert!(assessment.is_dependent);
+        assert_eq!(assessment.reasons, ["noscript warning found", "Ember.js application detected"]);
+        assert!((assessment.score - 0.7).abs() < 1e-9);
     }
     
-    // Consider JS-dependent if 1 or more indicators are present (lowered from 2)
-    (reasons.len() >= 1, reasons)
-} 
\ No newline at end of file
+    #[test]
+    fn lazy_images_alone_are_a_weak_indicator() {
+        let html = include_str!("../tests/fixtures/js/lazy-images.html");
+        let assessment = assess_javascript_dependence(html, 1);
+        assert!(assessment.is_dependent);
+        assert_eq!(assessment.reasons, ["Lazy-loaded images found"]);
+        assert!((assessment.score - 0.1).abs() < 1e-9);
+        
+        assert!(!assess_javascript_dependence(html, 2).is_dependent);
+    }
+}
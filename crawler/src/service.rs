@@ -1,14 +1,95 @@
-use crate::models::{Task, CrawlResult};
-use crate::crawler::Crawler;
+use crate::models::{Task, CrawlResult, CrawledPage};
+use crate::crawler::{Crawler, CrawlProfile, CrawlProgress};
 use crate::db::Database;
-use crate::solana::SolanaIntegration;
+use crate::solana::{ConfirmationStatus, SolanaIntegration};
+use crate::rate_limit::RequestBudget;
 use anyhow::{Result, Context, anyhow};
-use log::{info, warn, error, debug};
-use reqwest::Client;
+use tracing::{info, warn, error, debug};
+use reqwest::{Client, RequestBuilder, StatusCode};
 use serde_json::{json, Value};
 use tokio::time::{sleep, Duration};
 use tokio::sync::Mutex;
-use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{NaiveTime, Timelike};
+use futures::stream::{FuturesUnordered, StreamExt};
+
+/// Outcome of a single attempt to deliver a report to the manager
+enum ReportDelivery {
+    /// The manager accepted the report and returned its verification
+    Accepted(Value),
+    /// The manager refused the report, or may have processed it; sending it again won't help
+    Rejected(String),
+    /// The manager could not be reached, so it never saw the report
+    Unavailable(String),
+}
+
+/// A crawl in progress, as known to the heartbeat and partial reports
+#[derive(Debug, Clone)]
+struct ActiveTask {
+    /// Domain or base URL being crawled
+    domain: String,
+    /// When the crawl started (Unix timestamp)
+    start_time: u64,
+    /// Live counters and pages not yet reported
+    progress: CrawlProgress,
+}
+
+/// Hours of the day, in local time, during which the service takes new tasks.
+/// The window may wrap past midnight, e.g. `22-6` for nightly crawls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHours {
+    /// Hour the window opens (0-23)
+    pub start: u32,
+    /// Hour the window closes (0-23), exclusive
+    pub end: u32,
+}
+
+impl ActiveHours {
+    /// Whether `hour` falls inside the window
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+    
+    /// Time from `now` until the window next opens
+    pub fn until_open(&self, now: NaiveTime) -> Duration {
+        const DAY: u64 = 24 * 60 * 60;
+        let start = u64::from(self.start) * 60 * 60;
+        Duration::from_secs((start + DAY - u64::from(now.num_seconds_from_midnight())) % DAY)
+    }
+}
+
+impl FromStr for ActiveHours {
+    type Err = String;
+    
+    /// Parse `start-end` in whole hours, e.g. `22-6`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-')
+            .ok_or_else(|| format!("Invalid active hours {:?} (expected start-end, e.g. 22-6)", s))?;
+        let hour = |value: &str| value.trim().parse::<u32>().ok().filter(|hour| *hour < 24)
+            .ok_or_else(|| format!("Invalid hour {:?} in active hours (expected 0-23)", value));
+        let hours = ActiveHours { start: hour(start)?, end: hour(end)? };
+        
+        if hours.start == hours.end {
+            return Err(format!("Active hours {:?} are empty", s));
+        }
+        Ok(hours)
+    }
+}
+
+impl fmt::Display for ActiveHours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:00-{:02}:00", self.start, self.end)
+    }
+}
 
 /// Service to integrate crawler with the crypto manager
 pub struct CrawlerService {
@@ -32,6 +113,42 @@ pub struct CrawlerService {
     
     /// Whether to use headless Chrome for JavaScript-heavy sites
     use_headless_chrome: bool,
+    
+    /// Maximum number of tasks crawled at the same time
+    max_concurrent_tasks: usize,
+    
+    /// Seconds between heartbeats sent to the manager
+    heartbeat_interval: u64,
+    
+    /// Maximum number of attempts when submitting a report to the manager
+    report_attempts: u32,
+    
+    /// Delay before the first report retry; doubled after each failed attempt
+    report_backoff: Duration,
+    
+    /// Politeness profile used for every crawl
+    crawl_profile: CrawlProfile,
+    
+    /// Hours during which new tasks are taken; any time when unset
+    active_hours: Option<ActiveHours>,
+    
+    /// Requests per second shared by every crawl; unlimited when unset
+    request_budget: Option<RequestBudget>,
+    
+    /// Tasks being crawled, keyed by task ID
+    active_tasks: std::sync::Mutex<HashMap<String, ActiveTask>>,
+    
+    /// API key used to register with the manager
+    api_key: Option<String>,
+    
+    /// Token issued by the manager at registration
+    token: RwLock<Option<String>>,
+    
+    /// Cancelled to stop taking new tasks once the current one is finished
+    shutdown: CancellationToken,
+    
+    /// Cancelled to abandon an in-progress crawl
+    abort: CancellationToken,
 }
 
 impl CrawlerService {
@@ -62,9 +179,129 @@ impl CrawlerService {
             manager_url: manager_url.to_string(),
             poll_interval,
             use_headless_chrome: false,
+            max_concurrent_tasks: 1,
+            heartbeat_interval: 30,
+            report_attempts: 3,
+            report_backoff: Duration::from_secs(1),
+            crawl_profile: CrawlProfile::Normal,
+            active_hours: None,
+            request_budget: None,
+            active_tasks: std::sync::Mutex::new(HashMap::new()),
+            api_key: None,
+            token: RwLock::new(None),
+            shutdown: CancellationToken::new(),
+            abort: CancellationToken::new(),
         })
     }
     
+    /// Stop the service after the current task has been crawled and reported
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+    
+    /// Stop the service immediately, abandoning any in-progress crawl
+    pub fn abort(&self) {
+        self.shutdown.cancel();
+        self.abort.cancel();
+    }
+    
+    /// Listen for Ctrl+C: the first press finishes the current task, the second abandons it
+    fn listen_for_shutdown(&self) {
+        let shutdown = self.shutdown.clone();
+        let abort = self.abort.clone();
+        
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            info!("Shutdown requested, finishing the current task (press Ctrl+C again to abort)");
+            shutdown.cancel();
+            
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            warn!("Aborting the current task");
+            abort.cancel();
+        });
+    }
+    
+    /// Sleep for the poll interval, waking early on shutdown
+    async fn wait_for_next_poll(&self) {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(self.poll_interval)) => {},
+            _ = self.shutdown.cancelled() => {},
+        }
+    }
+    
+    /// Sleep until the active hours begin, waking early on shutdown
+    async fn wait_for_active_hours(&self) {
+        let wait = match self.active_hours {
+            Some(hours) => hours.until_open(chrono::Local::now().time()),
+            None => return,
+        };
+        tokio::select! {
+            _ = sleep(wait) => {},
+            _ = self.shutdown.cancelled() => {},
+        }
+    }
+    
+    /// Whether new tasks may be taken right now
+    fn within_active_hours(&self) -> bool {
+        self.active_hours.map_or(true, |hours| hours.contains(chrono::Local::now().hour()))
+    }
+    
+    /// Only take new tasks during these hours; crawls already running are finished
+    pub fn with_active_hours(mut self, active_hours: Option<ActiveHours>) -> Self {
+        self.active_hours = active_hours;
+        self
+    }
+    
+    /// Cap the page requests per second of all concurrent crawls together
+    pub fn with_max_requests_per_second(mut self, requests_per_second: Option<u32>) -> Self {
+        self.request_budget = requests_per_second.map(RequestBudget::new);
+        self
+    }
+    
+    /// Set the maximum number of tasks crawled at the same time
+    pub fn with_max_concurrent_tasks(mut self, max_concurrent_tasks: usize) -> Self {
+        self.max_concurrent_tasks = max_concurrent_tasks.max(1);
+        self
+    }
+    
+    /// Set the number of seconds between heartbeats sent to the manager
+    pub fn with_heartbeat_interval(mut self, seconds: u64) -> Self {
+        self.heartbeat_interval = seconds.max(1);
+        self
+    }
+    
+    /// Set the number of report submission attempts and the initial retry backoff
+    pub fn with_report_retry(mut self, attempts: u32, initial_backoff: Duration) -> Self {
+        self.report_attempts = attempts.max(1);
+        self.report_backoff = initial_backoff;
+        self
+    }
+    
+    /// Set the politeness profile used for every crawl
+    pub fn with_crawl_profile(mut self, profile: CrawlProfile) -> Self {
+        self.crawl_profile = profile;
+        self
+    }
+    
+    /// Set the API key used to register with the manager
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+    
+    /// Attach the registration token (or the API key before registration) to a manager request
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let token = self.token.read().ok().and_then(|token| token.clone());
+        match token.or_else(|| self.api_key.clone()) {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+    
     /// Enable headless Chrome for JavaScript-heavy sites
     pub fn with_headless_chrome(mut self, enabled: bool) -> Self {
         self.use_headless_chrome = enabled;
@@ -78,17 +315,10 @@ impl CrawlerService {
     
     /// Start the crawler service
     pub async fn start(&self) -> Result<()> {
-        info!("Starting CryptoCrawl crawler service with client ID: {}", self.client_id);
-        info!("Connected to manager at: {}", self.manager_url);
-        
-        // Register with the manager
-        self.register().await?;
-        
-        // Run the service
         self.run().await
     }
     
-    /// Start the crawler service loop
+    /// Start the crawler service loop, returning once a shutdown is requested
     pub async fn run(&self) -> Result<()> {
         info!("Starting crawler service with client ID {}", self.client_id);
         info!("Connecting to manager at {}", self.manager_url);
@@ -96,35 +326,197 @@ impl CrawlerService {
         // Register with the manager
         self.register().await?;
         
+        self.service_loop().await
+    }
+    
+    /// Fetch and crawl tasks until a shutdown is requested, sending heartbeats meanwhile
+    async fn service_loop(&self) -> Result<()> {
+        self.listen_for_shutdown();
+        
+        // The heartbeat loop never ends, so this returns when the task loop does
+        tokio::select! {
+            result = self.task_loop() => result,
+            _ = self.heartbeat_loop() => Ok(()),
+        }
+    }
+    
+    /// Fetch and crawl tasks until a shutdown is requested.
+    /// Up to `max_concurrent_tasks` tasks are fetched and crawled at the same time.
+    async fn task_loop(&self) -> Result<()> {
+        // The crawls' workers are spawned by the crawler, so polling the crawls from this
+        // task is enough to keep them going
+        let mut running = FuturesUnordered::new();
+        let mut completed = 0;
+        let mut errors = 0;
+        let mut paused = false;
+        
         // Start the main service loop
         loop {
-            match self.process_next_task().await {
-                Ok(true) => {
-                    // Successfully processed a task, continue immediately
-                    continue;
+            // Outside the active hours no new tasks are taken
+            let active = self.within_active_hours();
+            if let Some(hours) = self.active_hours.filter(|_| active == paused) {
+                if active {
+                    info!("Active hours {} began, taking tasks again", hours);
+                } else {
+                    info!("Outside active hours {}, pausing until they begin", hours);
+                }
+                paused = !active;
+            }
+            
+            // Fetch tasks until every slot is busy or the manager has nothing for us
+            let mut connected = false;
+            while active && !self.shutdown.is_cancelled() && running.len() < self.max_concurrent_tasks {
+                match self.fetch_task().await {
+                    Ok(Some(task)) => {
+                        connected = true;
+                        running.push(self.process_assigned_task(task));
+                    }
+                    Ok(None) => {
+                        connected = true;
+                        info!("No task available, waiting for {} seconds", self.poll_interval);
+                        break;
+                    }
+                    Err(e) => {
+                        errors += 1;
+                        error!("Error fetching task: {}", e);
+                        break;
+                    }
                 }
-                Ok(false) => {
-                    // No task was available, wait before polling again
-                    info!("No task available, waiting for {} seconds", self.poll_interval);
-                    sleep(Duration::from_secs(self.poll_interval)).await;
+            }
+            
+            // The manager is reachable again, so resend reports it missed
+            if connected {
+                if let Err(e) = self.flush_pending_reports().await {
+                    warn!("Failed to resend queued reports: {}", e);
                 }
-                Err(e) => {
-                    // Error occurred, log and wait before retrying
-                    error!("Error processing task: {}", e);
-                    sleep(Duration::from_secs(self.poll_interval)).await;
+            }
+            
+            if running.is_empty() {
+                if self.shutdown.is_cancelled() {
+                    break;
+                }
+                if paused {
+                    self.wait_for_active_hours().await;
+                } else {
+                    self.wait_for_next_poll().await;
                 }
+                continue;
+            }
+            
+            // Wait for a crawl to finish, or poll again if a slot is free
+            let can_poll = running.len() < self.max_concurrent_tasks && !self.shutdown.is_cancelled();
+            tokio::select! {
+                Some(result) = running.next() => match result {
+                    Ok(()) => completed += 1,
+                    Err(e) => {
+                        errors += 1;
+                        error!("Error processing task: {}", e);
+                    }
+                },
+                _ = self.wait_for_next_poll(), if can_poll && !paused => {},
+                _ = self.wait_for_active_hours(), if can_poll && paused => {},
             }
         }
+        
+        info!("Crawler service stopped: {} tasks completed, {} errors", completed, errors);
+        Ok(())
     }
     
-    /// Process the next available task
-    async fn process_next_task(&self) -> Result<bool> {
-        // Fetch a task from the manager
-        let task = match self.fetch_task().await? {
-            Some(task) => task,
-            None => return Ok(false),
-        };
+    /// Periodically tell the manager we are alive and how far along our tasks are
+    async fn heartbeat_loop(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.heartbeat_interval));
+        
+        loop {
+            interval.tick().await;
+            
+            if let Err(e) = self.send_heartbeat().await {
+                warn!("Failed to send heartbeat: {}", e);
+            }
+            
+            if let Err(e) = self.send_progress().await {
+                warn!("Failed to send task progress: {}", e);
+            }
+            
+            for (task_id, active) in self.active_snapshot() {
+                if let Err(e) = self.flush_partial_report(&task_id, &active).await {
+                    warn!("Failed to send partial report for task {}: {}", task_id, e);
+                }
+            }
+        }
+    }
+    
+    /// Snapshot the tasks being crawled
+    fn active_snapshot(&self) -> Vec<(String, ActiveTask)> {
+        match self.active_tasks.lock() {
+            Ok(active) => active.iter()
+                .map(|(task_id, task)| (task_id.clone(), task.clone()))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Snapshot the progress of the tasks being crawled
+    fn active_progress(&self) -> Vec<(String, CrawlProgress)> {
+        self.active_snapshot().into_iter()
+            .map(|(task_id, task)| (task_id, task.progress))
+            .collect()
+    }
+    
+    /// Stop tracking a task, returning it if it was being crawled
+    fn remove_active_task(&self, task_id: &str) -> Option<ActiveTask> {
+        self.active_tasks.lock().ok()?.remove(task_id)
+    }
+    
+    /// Send a single heartbeat to the manager
+    async fn send_heartbeat(&self) -> Result<()> {
+        let tasks: Vec<Value> = self.active_progress().iter()
+            .map(|(task_id, progress)| json!({
+                "task_id": task_id,
+                "pages_crawled": progress.pages.load(Ordering::Relaxed),
+            }))
+            .collect();
+        
+        let url = format!("{}/api/crawlers/{}/heartbeat", self.manager_url, self.client_id);
+        let response = self.authorize(self.client.post(&url))
+            .json(&json!({ "tasks": tasks }))
+            .send()
+            .await
+            .context("Failed to reach manager")?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("Manager returned {}", response.status()));
+        }
         
+        debug!("Heartbeat sent for {} active tasks", tasks.len());
+        Ok(())
+    }
+    
+    /// Send crawl statistics for each task in progress to the manager
+    async fn send_progress(&self) -> Result<()> {
+        for (task_id, progress) in self.active_progress() {
+            let url = format!("{}/api/tasks/{}/progress", self.manager_url, task_id);
+            let response = self.authorize(self.client.post(&url))
+                .json(&json!({
+                    "client_id": self.client_id,
+                    "pages_crawled": progress.pages.load(Ordering::Relaxed),
+                    "bytes": progress.bytes.load(Ordering::Relaxed),
+                    "elapsed_ms": progress.elapsed_ms(),
+                }))
+                .send()
+                .await
+                .context("Failed to reach manager")?;
+            
+            if !response.status().is_success() {
+                return Err(anyhow!("Manager returned {} for task {}", response.status(), task_id));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Crawl a task received from the manager and submit the report, inside a span carrying its ID
+    #[tracing::instrument(name = "task", skip_all, fields(task_id = %task.id))]
+    async fn process_assigned_task(&self, task: Task) -> Result<()> {
         info!("Processing task {}: {}", task.id, task.target_url);
         
         // Ensure the task exists in the database
@@ -136,26 +528,62 @@ impl CrawlerService {
         }
         drop(db); // Release the lock before the long-running crawl
         
-        // Execute the crawl using our process_task method
-        let crawl_result = match self.process_task(&task).await {
-            Ok(result) => result,
-            Err(e) => {
-                error!("Crawl failed: {}", e);
-                return Err(anyhow!("Crawl failed: {}", e));
+        // Execute the crawl using our process_task method, unless it gets aborted
+        let crawl_result = tokio::select! {
+            result = self.process_task(&task) => match result {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Crawl failed: {}", e);
+                    return Err(anyhow!("Crawl failed: {}", e));
+                }
+            },
+            _ = self.abort.cancelled() => {
+                warn!("Crawl of task {} aborted", task.id);
+                
+                // Hand the manager whatever was crawled before the abort
+                if let Some(active) = self.remove_active_task(&task.id) {
+                    if let Err(e) = self.flush_partial_report(&task.id, &active).await {
+                        warn!("Failed to send partial report for task {}: {}", task.id, e);
+                    }
+                    
+                    // Record the crawl as cancelled rather than leaving no trace of it
+                    let mut cancelled = CrawlResult::new(&task.id, &active.domain);
+                    cancelled.start_time = active.start_time;
+                    cancelled.set_cancelled();
+                    let db = self.db.lock().await;
+                    let saved = match db.get_crawl_result(&task.id) {
+                        Ok(Some(_)) => db.update_crawl_result(&cancelled),
+                        Ok(None) => db.save_crawl_result(&cancelled),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = saved {
+                        warn!("Failed to record cancellation of task {}: {}", task.id, e);
+                    }
+                }
+                return Err(anyhow!("Crawl of task {} aborted", task.id));
             }
         };
         
         info!("Crawl completed: {} pages, {} bytes total",
             crawl_result.pages_count, crawl_result.total_size);
         
-        // Save result to database
+        // Save result to database, replacing the record of an earlier cancelled attempt
         let db = self.db.lock().await;
-        db.save_crawl_result(&crawl_result)?;
+        if db.get_crawl_result(&task.id)?.is_some() {
+            db.update_crawl_result(&crawl_result)?;
+        } else {
+            db.save_crawl_result(&crawl_result)?;
+        }
+        
+        drop(db);
         
-        // Convert to report and submit to manager
+        // Convert to report and submit to manager, even if a shutdown was requested meanwhile
+        if self.shutdown.is_cancelled() {
+            info!("Submitting report for task {} before shutting down", task.id);
+        }
         self.submit_report(&crawl_result).await?;
         
-        Ok(true)
+        Ok(())
     }
     
     /// Register with the manager
@@ -163,13 +591,19 @@ impl CrawlerService {
         info!("Registering crawler with manager");
         
         let url = format!("{}/api/crawlers/register", self.manager_url);
-        let response = self.client.post(&url)
+        let mut request = self.client.post(&url);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        
+        let response = request
             .json(&json!({
                 "client_id": self.client_id,
                 "capabilities": {
                     "max_depth": 10,
                     "follow_subdomains": true,
-                    "smart_mode": true
+                    "smart_mode": true,
+                    "max_concurrent_tasks": self.max_concurrent_tasks
                 }
             }))
             .send()
@@ -177,6 +611,15 @@ impl CrawlerService {
             .context("Failed to register with manager")?;
         
         if response.status().is_success() {
+            // Keep the issued token for task and report calls
+            let registration: Value = response.json().await.unwrap_or_default();
+            
+            if let Some(token) = registration["token"].as_str() {
+                if let Ok(mut current) = self.token.write() {
+                    *current = Some(token.to_string());
+                }
+            }
+            
             info!("Successfully registered with manager");
             Ok(())
         } else {
@@ -196,7 +639,7 @@ impl CrawlerService {
         debug!("Fetching task from manager");
         
         let url = format!("{}/api/tasks/assign", self.manager_url);
-        let response = self.client.post(&url)
+        let response = self.authorize(self.client.post(&url))
             .json(&json!({
                 "client_id": self.client_id
             }))
@@ -230,7 +673,7 @@ impl CrawlerService {
                 .unwrap_or(0);
             
             // Create task
-            let task = Task::new(
+            let mut task = Task::new(
                 id,
                 target_url,
                 max_depth,
@@ -238,6 +681,15 @@ impl CrawlerService {
                 max_links,
                 incentive_amount,
             );
+            task.scope = task_data["scope"].as_str().and_then(|scope| scope.parse().ok());
+            task.accept_language = task_data["accept_language"].as_str().map(|language| language.to_string());
+            task.robots_user_agent = task_data["robots_user_agent"].as_str().map(|token| token.to_string());
+            task.max_runtime_secs = task_data["max_runtime_secs"].as_u64();
+            task.login = match task_data.get("login").filter(|login| !login.is_null()) {
+                Some(login) => Some(serde_json::from_value(login.clone())
+                    .map_err(|e| anyhow!("Task has an invalid login field: {}", e))?),
+                None => None,
+            };
             
             info!("Received task: id={}, url={}", task.id, task.target_url);
             
@@ -252,6 +704,14 @@ impl CrawlerService {
         } else if response.status().as_u16() == 404 {
             debug!("No tasks available from manager");
             Ok(None)
+        } else if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            warn!("Manager is rate limiting task requests, backing off");
+            Ok(None)
+        } else if response.status() == StatusCode::UNAUTHORIZED {
+            // The manager no longer recognises our token, so register again
+            warn!("Manager rejected our token, re-registering");
+            self.register().await?;
+            Ok(None)
         } else {
             let status = response.status();
             let error_text = response.text().await
@@ -269,74 +729,221 @@ impl CrawlerService {
         // Create the report
         let report = result.clone().to_report();
         
-        // Generate a transaction on Solana as proof of submission
-        let tx_signature = match self.solana.submit_crawl_report(
+        // Generate a transaction on Solana as proof of submission; only confirmed
+        // signatures, or simulated ones labelled as such, are reported to the manager
+        let (tx_signature, tx_status) = match self.solana.submit_crawl_report(
             &result.task_id,
             result,
         ).await {
-            Ok(sig) => {
-                info!("Generated Solana transaction: {}", sig);
-                Some(sig)
+            Ok(receipt) if receipt.is_confirmed() => {
+                info!("Solana transaction confirmed: {}", receipt.signature);
+                (Some(receipt.signature), "confirmed")
+            },
+            Ok(receipt) if receipt.confirmation == ConfirmationStatus::Simulated => {
+                info!("Solana transaction simulated: {}", receipt.signature);
+                (Some(receipt.signature), "simulated")
+            },
+            Ok(receipt) => {
+                warn!("Solana transaction {} is unconfirmed, not reporting its signature", receipt.signature);
+                (None, "unconfirmed")
             },
             Err(e) => {
                 warn!("Failed to generate Solana transaction: {}", e);
-                None
+                (None, "unconfirmed")
             }
         };
         
         // Submit the report to the manager
         info!("Submitting crawl report for task {} to manager", result.task_id);
         
-        let url = format!("{}/api/reports", self.manager_url);
-        let response = self.client.post(&url)
+        let pages: Vec<Value> = report.pages.iter().map(page_submission).collect();
+        let payload = json!({
+            "task_id": report.task_id,
+            "client_id": self.client_id,
+            "domain": result.domain,
+            "pages": pages,
+            "start_time": result.start_time,
+            "end_time": result.end_time.unwrap_or_default(),
+            "transaction_signature": tx_signature,
+            "transaction_status": tx_status,
+        });
+        
+        self.submit_payload(&result.task_id, &payload).await
+    }
+    
+    /// Send a batch of pages for a task that is still being crawled. The manager appends them
+    /// to the task's report until `submit_report` completes it.
+    /// Returns how many pages the manager now holds for the task.
+    pub async fn submit_partial_report(&self, task_id: &str, domain: &str, start_time: u64, pages: &[CrawledPage]) -> Result<usize> {
+        let url = format!("{}/api/reports/partial", self.manager_url);
+        let response = self.authorize(self.client.post(&url))
             .json(&json!({
-                "task_id": report.task_id,
+                "task_id": task_id,
                 "client_id": self.client_id,
-                "domain": result.domain,
-                "pages": report.pages,
-                "start_time": result.start_time,
-                "end_time": result.end_time.unwrap_or_default(),
-                "transaction_signature": tx_signature,
+                "domain": domain,
+                "start_time": start_time,
+                "pages": pages.iter().map(page_submission).collect::<Vec<_>>(),
             }))
             .send()
             .await
-            .context("Failed to submit report to manager")?;
+            .context("Failed to reach manager")?;
         
-        if response.status().is_success() {
-            info!("Crawl report submitted successfully");
-            
-            // Parse verification result
-            let verification: Value = response.json().await
-                .context("Failed to parse verification response")?;
-            
-            let verified = verification["verified"].as_bool().unwrap_or(false);
-            let score = verification["score"].as_f64().unwrap_or(0.0);
-            let transaction_hash = verification["transaction_hash"].as_str()
-                .unwrap_or("none").to_string();
-            
-            if verified {
-                info!("Report verified with score {}, transaction: {}", score, transaction_hash);
-            } else {
-                warn!("Report was not verified, score: {}", score);
-            }
-            
-            Ok(())
-        } else {
-            let status = response.status();
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("Manager returned {} - {}", status, error_text));
+        }
+        
+        let body: Value = response.json().await
+            .context("Failed to parse partial report response")?;
+        Ok(body["pages_count"].as_u64().unwrap_or_default() as usize)
+    }
+    
+    /// Send the pages a crawl has found since its last partial report.
+    /// If the manager can't take them they are kept for the next batch.
+    async fn flush_partial_report(&self, task_id: &str, active: &ActiveTask) -> Result<()> {
+        let pages = active.progress.take_pages();
+        if pages.is_empty() {
+            return Ok(());
+        }
+        
+        match self.submit_partial_report(task_id, &active.domain, active.start_time, &pages).await {
+            Ok(total) => {
+                debug!("Sent {} pages for task {}, {} in total", pages.len(), task_id, total);
+                Ok(())
+            },
+            Err(e) => {
+                active.progress.restore_pages(pages);
+                Err(e)
+            },
+        }
+    }
+    
+    /// Deliver a report payload, queueing it for later if the manager stays unavailable
+    async fn submit_payload(&self, task_id: &str, payload: &Value) -> Result<()> {
+        match self.deliver_report(payload).await {
+            ReportDelivery::Accepted(verification) => {
+                info!("Crawl report submitted successfully");
+                log_verification(&verification);
+                Ok(())
+            },
+            ReportDelivery::Rejected(e) => {
+                error!("Error submitting report: {}", e);
+                Err(anyhow!("Error submitting report: {}", e))
+            },
+            ReportDelivery::Unavailable(e) => {
+                warn!("Manager unavailable, keeping report for task {} to resend later: {}", task_id, e);
+                let db = self.db.lock().await;
+                db.save_pending_report(task_id, &payload.to_string())
+                    .context("Failed to queue report")?;
+                Ok(())
+            },
+        }
+    }
+    
+    /// Send a report to the manager, retrying with exponential backoff while it can't be reached.
+    /// Submitting a report pays the incentive, so a report the manager may have seen is never resent.
+    async fn deliver_report(&self, payload: &Value) -> ReportDelivery {
+        let mut backoff = self.report_backoff;
+        let mut attempt = 1;
+        
+        loop {
+            match self.send_report(payload).await {
+                ReportDelivery::Unavailable(e) if attempt < self.report_attempts => {
+                    warn!("Report submission attempt {}/{} failed: {}, retrying in {:?}",
+                          attempt, self.report_attempts, e, backoff);
+                    sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                },
+                ReportDelivery::Unavailable(e) => {
+                    return ReportDelivery::Unavailable(format!("{} (after {} attempts)", e, attempt));
+                },
+                delivery => return delivery,
+            }
+        }
+    }
+    
+    /// Make a single attempt to send a report to the manager
+    async fn send_report(&self, payload: &Value) -> ReportDelivery {
+        let url = format!("{}/api/reports", self.manager_url);
+        let response = match self.authorize(self.client.post(&url)).json(payload).send().await {
+            Ok(response) => response,
+            Err(e) if e.is_connect() => return ReportDelivery::Unavailable(format!("Failed to connect to manager: {}", e)),
+            Err(e) => return ReportDelivery::Rejected(format!("Failed to submit report to manager: {}", e)),
+        };
+        
+        let status = response.status();
+        if status.is_success() {
+            return match response.json().await {
+                Ok(verification) => ReportDelivery::Accepted(verification),
+                Err(e) => ReportDelivery::Rejected(format!("Failed to parse verification response: {}", e)),
+            };
+        }
+        
+        let error_text = response.text().await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        
+        if status == StatusCode::UNAUTHORIZED {
+            // The manager no longer recognises our token, so register again for the next task
+            warn!("Manager rejected our token, re-registering");
+            if let Err(e) = self.register().await {
+                warn!("Failed to re-register with manager: {}", e);
+            }
+        }
+        
+        ReportDelivery::Rejected(format!("{} - {}", status, error_text))
+    }
+    
+    /// Resend reports queued while the manager was unavailable, oldest first.
+    /// Stops at the first report the manager still can't take; returns how many were delivered.
+    pub async fn flush_pending_reports(&self) -> Result<usize> {
+        let pending = self.db.lock().await.get_pending_reports()?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        
+        info!("Resending {} queued reports", pending.len());
+        
+        let mut delivered = 0;
+        for (id, task_id, payload) in pending {
+            let payload: Value = match serde_json::from_str(&payload) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    warn!("Dropping unreadable queued report for task {}: {}", task_id, e);
+                    self.db.lock().await.delete_pending_report(id)?;
+                    continue;
+                }
+            };
             
-            error!("Error submitting report: {} - {}", status, error_text);
-            Err(anyhow!("Error submitting report: {} - {}", status, error_text))
+            match self.send_report(&payload).await {
+                ReportDelivery::Accepted(verification) => {
+                    info!("Queued report for task {} submitted successfully", task_id);
+                    log_verification(&verification);
+                    self.db.lock().await.delete_pending_report(id)?;
+                    delivered += 1;
+                },
+                ReportDelivery::Rejected(e) => {
+                    warn!("Manager rejected queued report for task {}, dropping it: {}", task_id, e);
+                    self.db.lock().await.delete_pending_report(id)?;
+                },
+                ReportDelivery::Unavailable(e) => {
+                    warn!("Manager still unavailable, keeping queued reports: {}", e);
+                    break;
+                },
+            }
         }
+        
+        Ok(delivered)
     }
     
     /// Get list of currently available tasks
     pub async fn get_available_tasks(&self) -> Result<Vec<Task>> {
         debug!("Fetching available tasks from manager");
         
-        let url = format!("{}/api/tasks", self.manager_url);
-        let response = self.client.get(&url)
+        let url = format!("{}/api/tasks?status=Pending", self.manager_url);
+        let response = self.authorize(self.client.get(&url))
             .send()
             .await
             .context("Failed to request tasks from manager")?;
@@ -391,31 +998,7 @@ impl CrawlerService {
     
     /// Process tasks using the provided crawler
     pub async fn process_tasks(&self) -> Result<()> {
-        info!("Starting crawler service with client ID {}", self.client_id);
-        info!("Connecting to manager at {}", self.manager_url);
-        
-        // Register with the manager
-        self.register().await?;
-        
-        // Start the main service loop
-        loop {
-            match self.process_next_task().await {
-                Ok(true) => {
-                    // Successfully processed a task, continue immediately
-                    continue;
-                }
-                Ok(false) => {
-                    // No task was available, wait before polling again
-                    info!("No task available, waiting for {} seconds", self.poll_interval);
-                    sleep(Duration::from_secs(self.poll_interval)).await;
-                }
-                Err(e) => {
-                    // Error occurred, log and wait before retrying
-                    error!("Error processing task: {}", e);
-                    sleep(Duration::from_secs(self.poll_interval)).await;
-                }
-            }
-        }
+        self.run().await
     }
     
     /// Process a single task
@@ -425,11 +1008,34 @@ impl CrawlerService {
         // Clone the task for the crawler
         let task_clone = task.clone();
         
+        // Track progress for heartbeats, progress updates and partial reports while the crawl runs
+        let progress = CrawlProgress::new();
+        if let Ok(mut active) = self.active_tasks.lock() {
+            active.insert(task.id.clone(), ActiveTask {
+                domain: task.target_url.clone(),
+                start_time: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                progress: progress.clone(),
+            });
+        }
+        
         // Create a new crawler for this task with headless chrome if enabled
-        let mut crawler = Crawler::new(task_clone).with_headless_chrome(self.use_headless_chrome);
+        let mut crawler = Crawler::new(task_clone)
+            .with_headless_chrome(self.use_headless_chrome)
+            .with_profile(self.crawl_profile)
+            .with_progress(progress.clone());
+        if let Some(budget) = &self.request_budget {
+            crawler = crawler.with_request_budget(budget.clone());
+        }
         
         // Try to crawl the URL
-        let crawl_result = match crawler.crawl(task).await {
+        let crawl_result = crawler.crawl(task).await;
+        
+        self.remove_active_task(&task.id);
+        
+        let mut crawl_result = match crawl_result {
             Ok(result) => result,
             Err(e) => {
                 error!("Failed to crawl URL {}: {}", task.target_url, e);
@@ -439,6 +1045,227 @@ impl CrawlerService {
         
         info!("Crawled {} pages from {}", crawl_result.pages_count, task.target_url);
         
+        // Pages not sent in a partial report go with the final one
+        crawl_result.pages.extend(progress.take_pages());
+        
         Ok(crawl_result)
     }
-} 
\ No newline at end of file
+}
+
+/// A page in the shape the manager's report endpoints expect
+fn page_submission(page: &CrawledPage) -> Value {
+    json!({
+        "url": page.url,
+        "status": page.status_code,
+        "content_type": page.content_type,
+        "size": page.size,
+        "timestamp": page.timestamp,
+    })
+}
+
+/// Log the manager's verification of a submitted report
+fn log_verification(verification: &Value) {
+    let verified = verification["verified"].as_bool().unwrap_or(false);
+    let score = verification["score"].as_f64().unwrap_or(0.0);
+    let transaction_hash = verification["transaction_hash"].as_str()
+        .unwrap_or("none");
+    
+    if verified {
+        info!("Report verified with score {}, transaction: {}", score, transaction_hash);
+    } else {
+        warn!("Report was not verified, score: {}", score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::StatusCode as HttpStatus, routing::post, Json, Router};
+    use crate::db::tests::test_db;
+    use std::sync::atomic::AtomicUsize;
+    use tempfile::TempDir;
+    
+    /// A manager whose report endpoint fails a number of times before accepting reports
+    #[derive(Clone, Default)]
+    struct FlakyManager {
+        failures_left: Arc<AtomicUsize>,
+        received: Arc<AtomicUsize>,
+    }
+    
+    async fn receive_report(State(manager): State<FlakyManager>) -> (HttpStatus, Json<Value>) {
+        manager.received.fetch_add(1, Ordering::SeqCst);
+        
+        let failing = manager.failures_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+            .is_ok();
+        
+        if failing {
+            (HttpStatus::SERVICE_UNAVAILABLE, Json(json!({ "error": "unavailable" })))
+        } else {
+            (HttpStatus::OK, Json(json!({ "verified": true, "score": 1.0 })))
+        }
+    }
+    
+    /// Start a flaky manager failing `failures` times, returning its URL
+    async fn start_manager(manager: FlakyManager, failures: usize) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        serve_manager(manager, failures, listener)
+    }
+    
+    /// Serve a flaky manager failing `failures` times on `listener`, returning its URL
+    fn serve_manager(manager: FlakyManager, failures: usize, listener: tokio::net::TcpListener) -> String {
+        manager.failures_left.store(failures, Ordering::SeqCst);
+        
+        let app = Router::new()
+            .route("/api/reports", post(receive_report))
+            .route("/api/reports/partial", post(receive_report))
+            .with_state(manager);
+        
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        format!("http://{}", addr)
+    }
+    
+    fn test_service(dir: &TempDir, manager_url: &str, attempts: u32) -> CrawlerService {
+        let db = test_db(dir);
+        
+        let keypair_path = dir.path().join("keypair.json");
+        let solana = SolanaIntegration::new(
+            "http://127.0.0.1:1",
+            Some(keypair_path.to_str().unwrap()),
+            "CrawL4Lvdx5hGZ9S9xABCzAvta8P9R4W7Z4BM7nKTsKw",
+        ).unwrap();
+        
+        CrawlerService::new("test-client".to_string(), manager_url, 1, db, solana)
+            .unwrap()
+            .with_report_retry(attempts, Duration::from_millis(10))
+    }
+    
+    fn test_payload() -> Value {
+        json!({ "task_id": "task-1", "client_id": "test-client", "pages": [] })
+    }
+    
+    /// An address nothing listens on, standing in for a manager that is down
+    async fn unused_addr() -> std::net::SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+    
+    #[tokio::test]
+    async fn unreachable_manager_report_is_queued_and_flushed_later() {
+        let dir = TempDir::new().unwrap();
+        let addr = unused_addr().await;
+        let service = test_service(&dir, &format!("http://{}", addr), 2);
+        
+        // Every connection is refused, so the report is kept for later
+        service.submit_payload("task-1", &test_payload()).await.unwrap();
+        
+        let pending = service.db.lock().await.get_pending_reports().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1, "task-1");
+        
+        // Still unreachable: the report stays queued
+        assert_eq!(service.flush_pending_reports().await.unwrap(), 0);
+        assert_eq!(service.db.lock().await.get_pending_reports().unwrap().len(), 1);
+        
+        // Once the manager is up the queued report is delivered, once
+        let manager = FlakyManager::default();
+        serve_manager(manager.clone(), 0, tokio::net::TcpListener::bind(addr).await.unwrap());
+        assert_eq!(service.flush_pending_reports().await.unwrap(), 1);
+        assert_eq!(manager.received.load(Ordering::SeqCst), 1);
+        assert!(service.db.lock().await.get_pending_reports().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn report_is_not_resent_after_a_server_error() {
+        let dir = TempDir::new().unwrap();
+        let manager = FlakyManager::default();
+        let url = start_manager(manager.clone(), 1).await;
+        let service = test_service(&dir, &url, 3);
+        
+        // The manager may have saved the report and paid for it before failing
+        assert!(service.submit_payload("task-1", &test_payload()).await.is_err());
+        assert_eq!(manager.received.load(Ordering::SeqCst), 1);
+        assert!(service.db.lock().await.get_pending_reports().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn rejected_report_is_not_queued() {
+        let dir = TempDir::new().unwrap();
+        // Nothing serves reports under /missing, so the manager answers 404
+        let manager = FlakyManager::default();
+        let url = start_manager(manager.clone(), 0).await;
+        let service = test_service(&dir, &format!("{}/missing", url), 3);
+        
+        assert!(service.submit_payload("task-1", &test_payload()).await.is_err());
+        assert_eq!(manager.received.load(Ordering::SeqCst), 0);
+        assert!(service.db.lock().await.get_pending_reports().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn partial_report_is_kept_until_the_manager_takes_it() {
+        let dir = TempDir::new().unwrap();
+        let manager = FlakyManager::default();
+        let url = start_manager(manager.clone(), 1).await;
+        let service = test_service(&dir, &url, 1);
+        
+        let active = ActiveTask {
+            domain: "https://example.com".to_string(),
+            start_time: 0,
+            progress: CrawlProgress::new(),
+        };
+        active.progress.restore_pages(vec![CrawledPage {
+            url: "https://example.com/".to_string(),
+            size: 10,
+            timestamp: 0,
+            content_type: Some("text/html".to_string()),
+            status_code: Some(200),
+            body: None,
+            is_external: false,
+        }]);
+        
+        // The first batch fails and its pages go back into the buffer
+        assert!(service.flush_partial_report("task-1", &active).await.is_err());
+        assert_eq!(active.progress.take_pages().len(), 1);
+        
+        active.progress.restore_pages(vec![CrawledPage {
+            url: "https://example.com/about".to_string(),
+            size: 20,
+            timestamp: 0,
+            content_type: Some("text/html".to_string()),
+            status_code: Some(200),
+            body: None,
+            is_external: false,
+        }]);
+        service.flush_partial_report("task-1", &active).await.unwrap();
+        
+        assert_eq!(manager.received.load(Ordering::SeqCst), 2);
+        assert!(active.progress.take_pages().is_empty());
+    }
+    
+    #[test]
+    fn active_hours_wrap_past_midnight() {
+        let nightly: ActiveHours = "22-6".parse().unwrap();
+        assert!(nightly.contains(23));
+        assert!(nightly.contains(0));
+        assert!(nightly.contains(5));
+        assert!(!nightly.contains(6));
+        assert!(!nightly.contains(12));
+        
+        let office: ActiveHours = "9-17".parse().unwrap();
+        assert!(office.contains(9));
+        assert!(!office.contains(17));
+        assert!(!office.contains(22));
+        
+        let noon = NaiveTime::from_hms_opt(12, 30, 0).unwrap();
+        assert_eq!(nightly.until_open(noon), Duration::from_secs(9 * 60 * 60 + 30 * 60));
+        assert_eq!(office.until_open(noon), Duration::from_secs(20 * 60 * 60 + 30 * 60));
+        
+        assert!("6".parse::<ActiveHours>().is_err());
+        assert!("22-24".parse::<ActiveHours>().is_err());
+        assert!("8-8".parse::<ActiveHours>().is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::crawler::{extract_text, fnv1a, page_request};
+use log::{debug, info};
+use reqwest::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use url::Url;
+
+/// Share of word shingles a page must have in common with the not-found page
+pub const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// How far a page's size may be from the not-found page's, as a share of the latter
+pub const SIZE_TOLERANCE: f64 = 0.2;
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 3;
+
+/// What a host serves for a URL that can't exist
+#[derive(Debug)]
+struct Baseline {
+    size: usize,
+    shingles: HashSet<u64>,
+}
+
+impl Baseline {
+    fn new(html: &str) -> Self {
+        Self { size: html.len(), shingles: shingles(&extract_text(html)) }
+    }
+    
+    /// Whether a page is about as large as the not-found page and nearly the same text
+    fn matches(&self, html: &str) -> bool {
+        let size_difference = (html.len() as f64 - self.size as f64).abs();
+        if size_difference > self.size as f64 * SIZE_TOLERANCE {
+            return false;
+        }
+        
+        similarity(&self.shingles, &shingles(&extract_text(html))) >= SIMILARITY_THRESHOLD
+    }
+}
+
+/// Flags pages served with a 2xx status that are really a "not found" page.
+///
+/// Once per origin, a random URL that can't exist is fetched. Hosts answering it with an
+/// error status report missing pages properly and are never flagged. For hosts answering
+/// with a 2xx, a page is a soft 404 when its size is within [`SIZE_TOLERANCE`] of that
+/// response and at least [`SIMILARITY_THRESHOLD`] of their word shingles match.
+#[derive(Debug)]
+pub struct Soft404Detector {
+    client: Client,
+    accept_language: String,
+    /// Not-found page per origin; `None` for origins that return real 404s
+    baselines: Mutex<HashMap<String, Option<Arc<Baseline>>>>,
+}
+
+impl Soft404Detector {
+    pub fn new(client: Client, accept_language: &str) -> Self {
+        Self {
+            client,
+            accept_language: accept_language.to_string(),
+            baselines: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Whether `html`, served with a 2xx status from `url`, looks like its host's not-found page
+    pub async fn is_soft_404(&self, url: &Url, html: &str) -> bool {
+        match self.baseline(url).await {
+            Some(baseline) => baseline.matches(html),
+            None => false,
+        }
+    }
+    
+    /// Not-found page of the URL's origin, fetched the first time the origin is seen
+    async fn baseline(&self, url: &Url) -> Option<Arc<Baseline>> {
+        let origin = url.origin().ascii_serialization();
+        
+        // Held while fetching, so each origin is only probed once
+        let mut baselines = self.baselines.lock().await;
+        if let Some(baseline) = baselines.get(&origin) {
+            return baseline.clone();
+        }
+        
+        let baseline = self.fetch_baseline(url).await.map(Arc::new);
+        baselines.insert(origin, baseline.clone());
+        baseline
+    }
+    
+    async fn fetch_baseline(&self, url: &Url) -> Option<Baseline> {
+        let probe = url.join(&format!("/cryptocrawl-missing-{:016x}", rand::random::<u64>())).ok()?;
+        let response = match page_request(&self.client, &probe, &self.accept_language).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("Soft 404 probe {} failed: {}", probe, e);
+                return None;
+            }
+        };
+        
+        let status = response.status();
+        if !status.is_success() {
+            debug!("{} answers missing pages with {}", url.origin().ascii_serialization(), status);
+            return None;
+        }
+        
+        let html = response.text().await.ok()?;
+        info!("{} answers missing pages with {}; checking its pages for soft 404s",
+              url.origin().ascii_serialization(), status);
+        Some(Baseline::new(&html))
+    }
+}
+
+/// FNV-1a hashes of each run of `SHINGLE_WORDS` consecutive words, lowercased
+fn shingles(text: &str) -> HashSet<u64> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    
+    words.windows(SHINGLE_WORDS.min(words.len()).max(1))
+        .map(|window| fnv1a(window.join(" ").as_bytes()))
+        .collect()
+}
+
+/// Jaccard similarity of two shingle sets; two empty sets are identical
+fn similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const NOT_FOUND: &str = "<html><head><title>Example Docs</title></head><body>\
+        <nav>Home Guides Reference Blog</nav>\
+        <main><h1>Page not found</h1><p>Sorry, we couldn't find the page you were looking for. \
+        It may have moved, or the link you followed may be broken. Try the search box above \
+        or head back to the home page.</p></main>\
+        <footer>Copyright Example Inc. All rights reserved.</footer></body></html>";
+    
+    #[test]
+    fn pages_like_the_not_found_page_are_flagged() {
+        let baseline = Baseline::new(NOT_FOUND);
+        
+        // The same page served for another missing URL, with the path echoed back
+        let echoed = NOT_FOUND.replace("<h1>Page not found</h1>", "<h1>Page not found: /guides/old</h1>");
+        assert!(baseline.matches(&echoed));
+        
+        // A real page sharing the navigation and footer
+        let real = NOT_FOUND.replace(
+            "<h1>Page not found</h1><p>Sorry, we couldn't find the page you were looking for. \
+            It may have moved, or the link you followed may be broken. Try the search box above \
+            or head back to the home page.</p>",
+            "<h1>Installing</h1><p>Download the installer for your platform, run it and follow the \
+            prompts. The command line tools are added to your path, so open a new terminal and \
+            check the version to make sure everything works.</p>",
+        );
+        assert!(!baseline.matches(&real));
+        
+        // Much larger pages are never flagged
+        assert!(!baseline.matches(&NOT_FOUND.repeat(2)));
+    }
+}
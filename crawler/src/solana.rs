@@ -1,11 +1,41 @@
 use anyhow::Result;
-use log::info;
+use log::{info, warn, debug};
+use reqwest::Client;
+use serde_json::{json, Value};
 use std::path::Path;
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use rand::Rng;
+use thiserror::Error;
 use crate::models::CrawlResult;
 
+/// Placeholder wallet address used when the keypair file is not a real Solana keypair
+const PLACEHOLDER_WALLET_ADDRESS: &str = "FJpDxheFBVPnQqGzZWvVFJxq7xKGBHtJNbSA6D7PUcfr";
+
+/// Prefix of the signatures of simulated transactions. It isn't base58, so a simulated
+/// signature can't be mistaken for a real one.
+const SIMULATED_SIGNATURE_PREFIX: &str = "simulated-";
+
+/// Errors returned by Solana RPC operations
+#[derive(Debug, Error)]
+pub enum SolanaError {
+    /// The RPC endpoint could not be reached (connection refused, DNS failure, timeout)
+    #[error("Solana RPC endpoint {endpoint} is unreachable: {reason}")]
+    RpcUnreachable { endpoint: String, reason: String },
+    
+    /// The RPC endpoint returned an error response
+    #[error("Solana RPC error: {0}")]
+    Rpc(String),
+    
+    /// The transaction was processed but failed on-chain
+    #[error("Transaction {signature} failed: {reason}")]
+    TransactionFailed { signature: String, reason: String },
+    
+    /// The transaction was not confirmed before the timeout elapsed
+    #[error("Timed out after {timeout:?} waiting for confirmation of transaction {signature}")]
+    ConfirmationTimeout { signature: String, timeout: Duration },
+}
+
 /// Represents a connection to the Solana blockchain
 #[derive(Debug, Clone)]
 pub struct SolanaIntegration {
@@ -13,13 +43,51 @@ pub struct SolanaIntegration {
     #[allow(dead_code)]
     keypair_path: String,
     /// RPC endpoint
-    #[allow(dead_code)]
     rpc_endpoint: String,
     /// Program ID for the CryptoCrawl program
     #[allow(dead_code)]
     program_id: String,
     /// Manager's public key (for submitting reports)
     manager_pubkey: Option<String>,
+    /// Wallet address derived from the keypair
+    wallet_address: String,
+    /// HTTP client for JSON-RPC requests
+    client: Client,
+    /// How long to wait for a transaction to be confirmed
+    confirmation_timeout: Duration,
+    /// Retries after a failed crawl report submission
+    submit_retries: u32,
+    /// Delay before the first retry; doubled after each failed attempt
+    submit_backoff: Duration,
+}
+
+/// Confirmation state of a submitted transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationStatus {
+    /// The cluster confirmed the transaction
+    Confirmed,
+    /// The transaction was not confirmed (failed or timed out)
+    Unconfirmed,
+    /// The RPC endpoint was unreachable, so confirmation could not be checked
+    RpcUnavailable,
+    /// The transaction was simulated rather than sent, so there is nothing to confirm
+    Simulated,
+}
+
+/// Result of submitting a crawl report on-chain
+#[derive(Debug, Clone)]
+pub struct SubmissionReceipt {
+    /// Transaction signature
+    pub signature: String,
+    /// Whether the transaction was confirmed
+    pub confirmation: ConfirmationStatus,
+}
+
+impl SubmissionReceipt {
+    /// Whether the transaction was confirmed by the cluster
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmation == ConfirmationStatus::Confirmed
+    }
 }
 
 impl SolanaIntegration {
@@ -52,42 +120,214 @@ impl SolanaIntegration {
             fs::write(&keypair_path, dummy_keypair)?;
         }
         
+        // Derive the wallet address from the keypair if it is a real Solana keypair
+        let wallet_address = Self::load_wallet_address(&keypair_path)
+            .unwrap_or_else(|| {
+                warn!("Keypair at {} is not a valid Solana keypair, using placeholder wallet address", keypair_path);
+                PLACEHOLDER_WALLET_ADDRESS.to_string()
+            });
+        
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        
         Ok(Self {
             keypair_path,
             rpc_endpoint: rpc_endpoint.to_string(),
             program_id: program_id.to_string(),
             manager_pubkey: None,
+            wallet_address,
+            client,
+            confirmation_timeout: Duration::from_secs(30),
+            submit_retries: 2,
+            submit_backoff: Duration::from_millis(500),
         })
     }
     
+    /// Set how long `confirm_transaction` waits before giving up
+    pub fn with_confirmation_timeout(mut self, timeout: Duration) -> Self {
+        self.confirmation_timeout = timeout;
+        self
+    }
+    
+    /// Set how many times a failed submission is retried (0 for none) and the initial retry backoff
+    pub fn with_submit_retry(mut self, retries: u32, initial_backoff: Duration) -> Self {
+        self.submit_retries = retries;
+        self.submit_backoff = initial_backoff;
+        self
+    }
+    
+    /// Read the public key from a Solana CLI keypair file (JSON array of 64 bytes)
+    fn load_wallet_address(keypair_path: &str) -> Option<String> {
+        let content = fs::read_to_string(keypair_path).ok()?;
+        let bytes: Vec<u8> = serde_json::from_str(&content).ok()?;
+        if bytes.len() != 64 {
+            return None;
+        }
+        
+        // The last 32 bytes of the keypair are the public key
+        Some(bs58::encode(&bytes[32..]).into_string())
+    }
+    
+    /// Send a JSON-RPC request to the configured endpoint and return the `result` field
+    async fn rpc_call(&self, method: &str, params: Value) -> Result<Value, SolanaError> {
+        debug!("Solana RPC call: {} {}", method, params);
+        
+        let response = self.client.post(&self.rpc_endpoint)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() || e.is_timeout() {
+                    SolanaError::RpcUnreachable {
+                        endpoint: self.rpc_endpoint.clone(),
+                        reason: e.to_string(),
+                    }
+                } else {
+                    SolanaError::Rpc(format!("{} request failed: {}", method, e))
+                }
+            })?;
+        
+        if !response.status().is_success() {
+            return Err(SolanaError::Rpc(format!("{} returned HTTP {}", method, response.status())));
+        }
+        
+        let mut body: Value = response.json().await
+            .map_err(|e| SolanaError::Rpc(format!("Failed to parse {} response: {}", method, e)))?;
+        
+        if let Some(error) = body.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(SolanaError::Rpc(format!("{} failed: {}", method, message)));
+        }
+        
+        Ok(body["result"].take())
+    }
+    
     /// Set manager's public key
     pub fn set_manager_pubkey(&mut self, pubkey: &str) {
         self.manager_pubkey = Some(pubkey.to_string());
     }
     
+    /// RPC endpoint the integration talks to
+    pub fn rpc_endpoint(&self) -> &str {
+        &self.rpc_endpoint
+    }
+    
     /// Get wallet address (public key)
     pub fn get_wallet_address(&self) -> String {
-        // In a real implementation, we would read the keypair and return the public key
-        // For now, just simulate a wallet address
-        "FJpDxheFBVPnQqGzZWvVFJxq7xKGBHtJNbSA6D7PUcfr".to_string()
+        self.wallet_address.clone()
     }
     
-    /// Get wallet balance
-    pub fn get_balance(&self) -> Result<u64> {
-        // In a real implementation, we would query the Solana RPC endpoint
-        // For now, just return a simulated balance
-        let mut rng = rand::thread_rng();
-        Ok(rng.gen_range(10_000_000..100_000_000))
+    /// Get wallet balance in lamports from the RPC endpoint
+    pub async fn get_balance(&self) -> Result<u64, SolanaError> {
+        let result = self.rpc_call("getBalance", json!([self.wallet_address])).await?;
+        
+        result["value"].as_u64()
+            .ok_or_else(|| SolanaError::Rpc(format!("Unexpected getBalance response: {}", result)))
     }
     
-    /// Submit crawl report to the blockchain
-    pub async fn submit_crawl_report(&self, task_id: &str, crawl_result: &CrawlResult) -> Result<String> {
+    /// Poll the RPC endpoint until the transaction is confirmed or the timeout elapses
+    pub async fn confirm_transaction(&self, signature: &str) -> Result<(), SolanaError> {
+        info!("Waiting for confirmation of transaction {}", signature);
+        
+        let started = Instant::now();
+        loop {
+            let result = self.rpc_call(
+                "getSignatureStatuses",
+                json!([[signature], { "searchTransactionHistory": true }]),
+            ).await?;
+            
+            // A null status means the cluster has not seen the transaction yet
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(SolanaError::TransactionFailed {
+                        signature: signature.to_string(),
+                        reason: status["err"].to_string(),
+                    });
+                }
+                
+                match status["confirmationStatus"].as_str() {
+                    Some("confirmed") | Some("finalized") => {
+                        info!("Transaction {} confirmed", signature);
+                        return Ok(());
+                    }
+                    other => debug!("Transaction {} status: {:?}", signature, other),
+                }
+            }
+            
+            if started.elapsed() >= self.confirmation_timeout {
+                return Err(SolanaError::ConfirmationTimeout {
+                    signature: signature.to_string(),
+                    timeout: self.confirmation_timeout,
+                });
+            }
+            
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+    
+    /// Submit crawl report to the blockchain, retrying transient failures with
+    /// exponential backoff, then wait for the transaction to be confirmed
+    pub async fn submit_crawl_report(&self, task_id: &str, crawl_result: &CrawlResult) -> Result<SubmissionReceipt> {
         // Log the submission
         info!(
             "Submitting crawl report to Solana: task={}, domain={}, pages={}, size={}",
             task_id, crawl_result.domain, crawl_result.pages_count, crawl_result.total_size
         );
         
+        let mut backoff = self.submit_backoff;
+        let mut attempt = 1;
+        let signature = loop {
+            match self.send_crawl_report(task_id, crawl_result).await {
+                Ok(signature) => break signature,
+                Err(e) if attempt <= self.submit_retries => {
+                    warn!("Crawl report submission attempt {}/{} failed: {}, retrying in {:?}",
+                          attempt, self.submit_retries + 1, e, backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.context(format!(
+                    "Failed to submit crawl report after {} attempts", attempt))),
+            }
+        };
+        
+        if signature.starts_with(SIMULATED_SIGNATURE_PREFIX) {
+            info!("Transaction {} was simulated, skipping confirmation", signature);
+            return Ok(SubmissionReceipt { signature, confirmation: ConfirmationStatus::Simulated });
+        }
+        
+        // Wait for the cluster to confirm the submission
+        let confirmation = match self.confirm_transaction(&signature).await {
+            Ok(()) => ConfirmationStatus::Confirmed,
+            Err(e @ SolanaError::RpcUnreachable { .. }) => {
+                warn!("Cannot confirm transaction {}: {}", signature, e);
+                ConfirmationStatus::RpcUnavailable
+            }
+            Err(e) => {
+                warn!("Transaction {} is unconfirmed: {}", signature, e);
+                ConfirmationStatus::Unconfirmed
+            }
+        };
+        
+        Ok(SubmissionReceipt { signature, confirmation })
+    }
+    
+    /// Build and send the crawl report transaction, returning its signature.
+    /// The transaction is only simulated for now, so the signature is a labelled placeholder.
+    async fn send_crawl_report(&self, task_id: &str, crawl_result: &CrawlResult) -> Result<String> {
+        // The transaction needs a recent blockhash; an unreachable or failing RPC endpoint
+        // fails the attempt here
+        let blockhash = self.rpc_call("getLatestBlockhash", json!([{ "commitment": "finalized" }])).await?;
+        debug!("Building crawl report transaction with blockhash {}", blockhash["value"]["blockhash"]);
+        
         // In a real implementation, we would build and submit a Solana transaction
         // For now, just simulate a transaction hash
         let timestamp = SystemTime::now()
@@ -96,7 +336,8 @@ impl SolanaIntegration {
             .as_secs();
         
         let tx_hash = format!(
-            "5{}{}{:x}{}",
+            "{}{}{}{:x}{}",
+            SIMULATED_SIGNATURE_PREFIX,
             crawl_result.domain.chars().take(3).collect::<String>(),
             task_id.chars().take(4).collect::<String>(),
             timestamp,
@@ -107,7 +348,7 @@ impl SolanaIntegration {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
         
         // Simulate success
-        info!("Crawl report submitted successfully: {}", tx_hash);
+        info!("Crawl report transaction simulated: {}", tx_hash);
         Ok(tx_hash)
     }
     
@@ -196,4 +437,66 @@ impl SolanaIntegration {
         info!("Successfully updated crawler status");
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    
+    /// RPC endpoint failing the first `failures` blockhash requests with a 503
+    async fn flaky_rpc(failures: usize) -> (String, Arc<AtomicUsize>) {
+        use axum::{http::StatusCode, routing::post, Json, Router};
+        
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route("/", post(move |Json(request): Json<Value>| async move {
+            match request["method"].as_str() {
+                Some("getLatestBlockhash") if counter.fetch_add(1, Ordering::SeqCst) < failures => {
+                    Err(StatusCode::SERVICE_UNAVAILABLE)
+                }
+                Some("getLatestBlockhash") => Ok(Json(json!({
+                    "jsonrpc": "2.0", "id": 1,
+                    "result": { "value": { "blockhash": "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N" } },
+                }))),
+                _ => Ok(Json(json!({
+                    "jsonrpc": "2.0", "id": 1,
+                    "result": { "value": [{ "err": null, "confirmationStatus": "confirmed" }] },
+                }))),
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        (endpoint, hits)
+    }
+    
+    #[tokio::test]
+    async fn failed_submissions_are_retried() {
+        let dir = TempDir::new().unwrap();
+        let keypair = dir.path().join("wallet.json");
+        let keypair = keypair.to_str().unwrap();
+        let result = CrawlResult::new("task", "example.com");
+        
+        // Two failures are absorbed by two retries
+        let (endpoint, hits) = flaky_rpc(2).await;
+        let solana = SolanaIntegration::new(&endpoint, Some(keypair), "program").unwrap()
+            .with_submit_retry(2, Duration::from_millis(1));
+        let receipt = solana.submit_crawl_report("task", &result).await.unwrap();
+        assert_eq!(receipt.confirmation, ConfirmationStatus::Simulated);
+        assert!(receipt.signature.starts_with(SIMULATED_SIGNATURE_PREFIX));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        
+        // One retry isn't enough
+        let (endpoint, hits) = flaky_rpc(2).await;
+        let solana = SolanaIntegration::new(&endpoint, Some(keypair), "program").unwrap()
+            .with_submit_retry(1, Duration::from_millis(1));
+        assert!(solana.submit_crawl_report("task", &result).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::crawler::fnv1a;
+use crate::db::Database;
+use crate::models::{PageMetadata, content_type_matches, media_type};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A crawled page and what was extracted from it, as handed to a [`StorageSink`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageRecord {
+    pub task_id: String,
+    /// URL the page was served from, after redirects
+    pub url: String,
+    /// URL the crawler asked for, when it differs
+    pub requested_url: Option<String>,
+    pub domain: String,
+    pub status: i32,
+    pub content_type: Option<String>,
+    /// Body size in bytes, whether or not the body is kept
+    pub size: i64,
+    pub html: Option<String>,
+    pub text_content: Option<String>,
+    pub charset: Option<String>,
+    pub metadata: Option<PageMetadata>,
+    pub is_javascript_dependent: bool,
+    pub javascript_dependency_reasons: Option<String>,
+    pub depth: u32,
+    pub hops: u32,
+    /// Looks like the site's "not found" page despite a 2xx status
+    pub soft_404: bool,
+}
+
+/// Somewhere crawled pages are kept
+pub trait StorageSink: Send + Sync {
+    /// Store a page, replacing what the task stored for the same URL before
+    fn store(&self, page: &PageRecord) -> Result<()>;
+}
+
+/// The `crawled_pages` table
+impl StorageSink for Database {
+    fn store(&self, page: &PageRecord) -> Result<()> {
+        self.save_crawled_page(page)?;
+        if page.soft_404 {
+            self.mark_soft_404(&page.task_id, &page.url)?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Writes each page as a JSON file, `<dir>/<task id>/<hash of the URL>.json`
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    dir: PathBuf,
+}
+
+impl FileSink {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+    
+    /// File a page of a task is written to
+    pub fn path_for(&self, task_id: &str, url: &str) -> PathBuf {
+        self.dir.join(task_id).join(format!("{:016x}.json", fnv1a(url.as_bytes())))
+    }
+}
+
+impl StorageSink for FileSink {
+    fn store(&self, page: &PageRecord) -> Result<()> {
+        let path = self.path_for(&page.task_id, &page.url);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        
+        let json = serde_json::to_vec(page)?;
+        fs::write(&path, json)
+            .with_context(|| format!("Failed to write {} to {}", page.url, path.display()))
+    }
+}
+
+/// Stores every page in each of its sinks. All of them are tried; the first error is returned.
+#[derive(Clone, Default)]
+pub struct CompositeSink {
+    sinks: Vec<Arc<dyn StorageSink>>,
+}
+
+impl CompositeSink {
+    pub fn new(sinks: Vec<Arc<dyn StorageSink>>) -> Self {
+        Self { sinks }
+    }
+}
+
+impl StorageSink for CompositeSink {
+    fn store(&self, page: &PageRecord) -> Result<()> {
+        let mut first_error = None;
+        for sink in &self.sinks {
+            if let Err(e) = sink.store(page) {
+                first_error.get_or_insert(e);
+            }
+        }
+        
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// Sends the pages matching a content type and size to a sink
+#[derive(Clone)]
+pub struct StorageRoute {
+    /// Media types routed, such as `text/plain` or `image/*`; any type when empty
+    pub content_types: Vec<String>,
+    /// Smallest page routed, in bytes
+    pub min_size: u64,
+    pub sink: Arc<dyn StorageSink>,
+}
+
+impl StorageRoute {
+    /// Route every page to `sink`; narrow it with [`StorageRoute::content_type`] and [`StorageRoute::min_size`]
+    pub fn new(sink: Arc<dyn StorageSink>) -> Self {
+        Self { content_types: Vec::new(), min_size: 0, sink }
+    }
+    
+    /// Only route pages of this media type (repeatable; wildcards such as `text/*` work)
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_types.push(content_type.to_string());
+        self
+    }
+    
+    /// Only route pages of at least this many bytes
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+    
+    /// Whether a page with this Content-Type and size takes the route
+    fn matches(&self, content_type: Option<&str>, size: u64) -> bool {
+        if size < self.min_size {
+            return false;
+        }
+        if self.content_types.is_empty() {
+            return true;
+        }
+        
+        let media_type = content_type.map(media_type).unwrap_or_default();
+        self.content_types.iter().any(|allowed| content_type_matches(allowed, &media_type))
+    }
+}
+
+impl fmt::Debug for StorageRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageRoute")
+            .field("content_types", &self.content_types)
+            .field("min_size", &self.min_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Picks the sink of a page: the first route it matches, or the fallback
+#[derive(Clone, Default)]
+pub struct StorageRouter {
+    routes: Vec<StorageRoute>,
+    fallback: Option<Arc<dyn StorageSink>>,
+}
+
+impl StorageRouter {
+    pub fn new(routes: Vec<StorageRoute>, fallback: Option<Arc<dyn StorageSink>>) -> Self {
+        Self { routes, fallback }
+    }
+    
+    /// Sink for a page with this Content-Type and size; `None` when the page isn't stored
+    pub fn sink_for(&self, content_type: Option<&str>, size: u64) -> Option<&Arc<dyn StorageSink>> {
+        self.routes.iter()
+            .find(|route| route.matches(content_type, size))
+            .map(|route| &route.sink)
+            .or(self.fallback.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::test_db;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+    
+    /// Remembers the URLs it was given
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+    
+    impl StorageSink for Recorder {
+        fn store(&self, page: &PageRecord) -> Result<()> {
+            self.0.lock().unwrap().push(page.url.clone());
+            Ok(())
+        }
+    }
+    
+    fn page(url: &str, content_type: &str, size: i64) -> PageRecord {
+        PageRecord {
+            task_id: "task".to_string(),
+            url: url.to_string(),
+            domain: "example.com".to_string(),
+            status: 200,
+            content_type: Some(content_type.to_string()),
+            size,
+            ..PageRecord::default()
+        }
+    }
+    
+    #[test]
+    fn pages_go_to_the_first_matching_route() {
+        let database = Arc::new(Recorder::default());
+        let files = Arc::new(Recorder::default());
+        let router = StorageRouter::new(
+            vec![StorageRoute::new(files.clone()).content_type("text/plain").min_size(1_000_000)],
+            Some(database.clone()),
+        );
+        
+        for page in [
+            page("https://example.com/", "text/html; charset=utf-8", 50_000),
+            page("https://example.com/dump.txt", "text/plain", 8_000_000),
+            page("https://example.com/notes.txt", "text/plain", 2_000),
+        ] {
+            let sink = router.sink_for(page.content_type.as_deref(), page.size as u64).unwrap();
+            sink.store(&page).unwrap();
+        }
+        
+        assert_eq!(*files.0.lock().unwrap(), ["https://example.com/dump.txt"]);
+        assert_eq!(*database.0.lock().unwrap(), ["https://example.com/", "https://example.com/notes.txt"]);
+        assert!(StorageRouter::default().sink_for(Some("text/html"), 1).is_none());
+    }
+    
+    #[test]
+    fn composite_sink_writes_to_files_and_the_database() {
+        let dir = TempDir::new().unwrap();
+        let database = Arc::new(test_db(&dir));
+        let files = FileSink::new(dir.path().join("pages"));
+        let both = CompositeSink::new(vec![database.clone(), Arc::new(files.clone())]);
+        
+        let mut dump = page("https://example.com/dump.txt", "text/plain", 11);
+        dump.text_content = Some("hello world".to_string());
+        both.store(&dump).unwrap();
+        
+        let stored = database.get_stored_page("task", "https://example.com/dump.txt").unwrap().unwrap();
+        assert_eq!(stored.text_content.as_deref(), Some("hello world"));
+        
+        let json = fs::read_to_string(files.path_for("task", "https://example.com/dump.txt")).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["text_content"], "hello world");
+        assert_eq!(json["content_type"], "text/plain");
+    }
+}
@@ -1,18 +1,22 @@
 use axum::{
     routing::{get, post},
-    Router, extract::{State, Path, Json}, http::StatusCode,
-    response::{IntoResponse, Response, Html},
+    Router, extract::{State, Path, Json, Query, ws::{Message, WebSocket, WebSocketUpgrade}}, http::StatusCode,
+    response::{IntoResponse, Html},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::collections::HashMap;
 use tokio::sync::Mutex;
 use uuid::Uuid;
-use crate::db::Database;
-use crate::models::{Task, CrawlResult, CrawlStatus};
-use crate::crawler::Crawler;
-use crate::solana::SolanaIntegration;
+use url::Url;
+use crate::db::{Database, CrawlSort};
+use crate::error::CrawlerError;
+use crate::metrics::METRICS;
+use crate::models::{Task, CrawlResult, CrawlStats, CrawlStatus, StoredPage, default_allowed_content_types};
+use crate::crawler::{Crawler, CrawlProgress};
+use crate::solana::{SolanaIntegration, SolanaError, ConfirmationStatus};
 use std::sync::atomic::{AtomicBool, Ordering};
-use log::{info, error};
+use log::{info, warn, error};
 use anyhow::Result;
 
 /// Application state
@@ -27,38 +31,121 @@ pub struct AppState {
     client_id: String,
     /// Whether the system is running
     running: Arc<AtomicBool>,
+    /// Counters of the crawl started from the UI, if any
+    live: Arc<Mutex<Option<CrawlProgress>>>,
 }
 
-// API Error handling
-#[derive(Debug)]
-pub enum ApiError {
-    InternalError(String),
-    NotFound(String),
-    BadRequest(String),
+// Request and response types
+#[derive(Serialize, Deserialize)]
+pub struct TaskAssignRequest {
+    pub url: String,
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-        };
+/// Query string of the page preview routes, e.g. `?task_id=abc&url=https://example.com/`
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub task_id: String,
+    pub url: String,
+}
 
-        (status, error_message).into_response()
-    }
+/// Response of `DELETE /api/tasks/:id`
+#[derive(Serialize, Deserialize)]
+pub struct DeleteTaskResponse {
+    pub task_id: String,
+    /// Rows removed across the task, crawl result, report and page tables
+    pub deleted_rows: usize,
+}
+
+/// Query string of the task history page, e.g. `?domain=crates.io&status=Completed&sort=pages&order=desc`
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskListQuery {
+    pub domain: Option<String>,
+    pub status: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
 }
 
-impl From<anyhow::Error> for ApiError {
-    fn from(err: anyhow::Error) -> Self {
-        ApiError::InternalError(err.to_string())
+/// Filters and sort order applied to the task history
+#[derive(Debug, Default)]
+struct TaskFilter {
+    domain: Option<String>,
+    status: Option<CrawlStatus>,
+    sort: CrawlSort,
+    ascending: bool,
+}
+
+impl TaskFilter {
+    /// Parse the query string; empty form fields mean "no filter"
+    fn from_query(query: TaskListQuery) -> Result<Self, CrawlerError> {
+        let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        
+        let status = match non_empty(query.status) {
+            Some(status) => Some(status.parse().map_err(CrawlerError::BadRequest)?),
+            None => None,
+        };
+        let sort = match non_empty(query.sort) {
+            Some(sort) => sort.parse().map_err(CrawlerError::BadRequest)?,
+            None => CrawlSort::default(),
+        };
+        let ascending = match non_empty(query.order).as_deref() {
+            Some("asc") => true,
+            Some("desc") | None => false,
+            Some(order) => return Err(CrawlerError::BadRequest(format!("Invalid sort order: {} (expected asc or desc)", order))),
+        };
+        
+        Ok(Self {
+            domain: non_empty(query.domain),
+            status,
+            sort,
+            ascending,
+        })
+    }
+    
+    /// Link to the task history with these filters, sorted by `sort`
+    fn href(&self, sort: CrawlSort, ascending: bool) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(domain) = &self.domain {
+            query.append_pair("domain", domain);
+        }
+        if let Some(status) = &self.status {
+            query.append_pair("status", &format!("{:?}", status));
+        }
+        query.append_pair("sort", sort.as_str());
+        query.append_pair("order", if ascending { "asc" } else { "desc" });
+        
+        format!("/tasks?{}", query.finish())
+    }
+    
+    /// Clickable column header; clicking the current sort column flips its order
+    fn sort_header(&self, sort: CrawlSort, label: &str) -> String {
+        let (ascending, arrow) = if self.sort == sort {
+            (!self.ascending, if self.ascending { " &#9650;" } else { " &#9660;" })
+        } else {
+            (false, "")
+        };
+        
+        format!(r#"<a href="{}" class="text-info text-decoration-none">{}{}</a>"#,
+                escape_html(&self.href(sort, ascending)), label, arrow)
     }
 }
 
-// Request and response types
-#[derive(Serialize, Deserialize)]
-pub struct TaskAssignRequest {
-    pub url: String,
+/// Link to the stored content preview of a page crawled by a task
+fn page_link(task_id: &str, url: &str) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("task_id", task_id)
+        .append_pair("url", url)
+        .finish();
+    
+    format!(r#"<a href="/pages?{}" class="text-info">{}</a>"#, escape_html(&query), escape_html(url))
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
 }
 
 #[derive(Serialize)]
@@ -74,9 +161,35 @@ pub struct WalletHistoryItem {
     pub amount: i64,
     pub timestamp: u64,
     pub transaction_hash: String,
+    /// The transaction on the Solana explorer, for the cluster the RPC endpoint belongs to
+    pub explorer_url: String,
     pub description: Option<String>,
 }
 
+/// Solana explorer page of a transaction. The cluster is guessed from the RPC endpoint:
+/// devnet and testnet by name, mainnet by default, and anything local as a custom cluster.
+fn explorer_url(rpc_endpoint: &str, signature: &str) -> String {
+    let base = format!("https://explorer.solana.com/tx/{}", signature);
+    let host = Url::parse(rpc_endpoint)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_default();
+    
+    if host.contains("devnet") {
+        format!("{}?cluster=devnet", base)
+    } else if host.contains("testnet") {
+        format!("{}?cluster=testnet", base)
+    } else if host == "localhost" || host == "127.0.0.1" || host == "[::1]" {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("cluster", "custom")
+            .append_pair("customUrl", rpc_endpoint)
+            .finish();
+        format!("{}?{}", base, query)
+    } else {
+        base
+    }
+}
+
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub client_id: String,
@@ -111,7 +224,7 @@ fn index_template(status: &StatusResponse) -> String {
                 </div>
             </div>
             "#,
-            task.id, task.url, task.status, task.pages_crawled, task.data_size
+            escape_html(&task.id), escape_html(&task.url), escape_html(&task.status), task.pages_crawled, task.data_size
         ),
         None => r#"
             <div class="card bg-dark text-white mb-4">
@@ -170,27 +283,249 @@ fn index_template(status: &StatusResponse) -> String {
                     </div>
                 </div>
                 
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">Live Workers <span id="live-state" class="badge bg-secondary">connecting</span></div>
+                    <div class="card-body">
+                        <div class="row text-center">
+                            <div class="col"><h3 id="live-workers">-</h3>Active Workers</div>
+                            <div class="col"><h3 id="live-important">-</h3>Important Queue</div>
+                            <div class="col"><h3 id="live-regular">-</h3>Regular Queue</div>
+                            <div class="col"><h3 id="live-pages">-</h3>Pages</div>
+                            <div class="col"><h3 id="live-rate">-</h3>Pages/sec</div>
+                        </div>
+                    </div>
+                </div>
+                
                 <div class="card bg-dark text-white">
                     <div class="card-header">Task History</div>
                     <div class="card-body">
                         <a href="/tasks" class="btn btn-primary">View Task History</a>
+                        <a href="/wallet" class="btn btn-secondary">View Wallet History</a>
+                        <a href="/analytics" class="btn btn-secondary">View Analytics</a>
                     </div>
                 </div>
             </div>
             
             <script src="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0-alpha1/dist/js/bootstrap.bundle.min.js"></script>
+            <script>
+                // Live worker status; keeps the last values and reconnects if the socket closes
+                (function connect(delay) {{
+                    const state = document.getElementById('live-state');
+                    const scheme = location.protocol === 'https:' ? 'wss' : 'ws';
+                    const socket = new WebSocket(`${{scheme}}://${{location.host}}/ws/status`);
+                    
+                    socket.onopen = () => {{
+                        delay = 1000;
+                        state.textContent = 'live';
+                        state.className = 'badge bg-success';
+                    }};
+                    socket.onmessage = event => {{
+                        const status = JSON.parse(event.data);
+                        document.getElementById('live-workers').textContent = status.active_workers;
+                        document.getElementById('live-important').textContent = status.important_queue;
+                        document.getElementById('live-regular').textContent = status.regular_queue;
+                        document.getElementById('live-pages').textContent = status.pages;
+                        document.getElementById('live-rate').textContent = status.pages_per_second.toFixed(1);
+                    }};
+                    socket.onclose = () => {{
+                        state.textContent = 'disconnected, retrying';
+                        state.className = 'badge bg-warning text-dark';
+                        setTimeout(() => connect(Math.min(delay * 2, 30000)), delay);
+                    }};
+                }})(1000);
+            </script>
         </body>
         </html>
         "#,
-        status.client_id,
-        status.wallet_address,
+        escape_html(&status.client_id),
+        escape_html(&status.wallet_address),
         status.wallet_balance,
         status.completed_tasks,
         active_task_html
     )
 }
 
-fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
+fn wallet_template(wallet: &WalletResponse) -> String {
+    let history_rows = wallet.history
+        .iter()
+        .map(|item| {
+            let date = chrono::DateTime::from_timestamp(item.timestamp as i64, 0)
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            
+            format!(
+                r#"
+                <tr>
+                    <td>{}</td>
+                    <td><a href="/tasks/{}" class="text-info">{}</a></td>
+                    <td>{} tokens</td>
+                    <td>{}</td>
+                    <td><a href="{}" class="text-info" target="_blank" rel="noopener">{}</a></td>
+                </tr>
+                "#,
+                date,
+                escape_html(&item.task_id),
+                escape_html(&item.task_id),
+                item.amount,
+                escape_html(item.description.as_deref().unwrap_or_default()),
+                escape_html(&item.explorer_url),
+                escape_html(&item.transaction_hash)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
+    
+    format!(
+        r#"
+        <!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Wallet - CryptoCrawl Client</title>
+            <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0-alpha1/dist/css/bootstrap.min.css" rel="stylesheet">
+            <style>
+                body {{ background-color: #121212; color: #e0e0e0; }}
+                .card {{ background-color: #1e1e1e; border-color: #333; }}
+                .card-header {{ background-color: #252525; border-color: #333; }}
+                .navbar {{ background-color: #252525; }}
+                th, td {{ color: #e0e0e0; }}
+                .table {{ color: #e0e0e0; }}
+            </style>
+        </head>
+        <body>
+            <nav class="navbar navbar-expand-lg navbar-dark mb-4">
+                <div class="container">
+                    <a class="navbar-brand" href="/">CryptoCrawl Client</a>
+                </div>
+            </nav>
+            
+            <div class="container">
+                <h2 class="mb-4">Wallet</h2>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-body">
+                        <p><strong>Address:</strong> {}</p>
+                        <p><strong>Balance:</strong> {} tokens</p>
+                    </div>
+                </div>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">History</div>
+                    <div class="card-body">
+                        <div class="table-responsive">
+                            <table class="table table-dark">
+                                <thead>
+                                    <tr>
+                                        <th>Date</th>
+                                        <th>Task</th>
+                                        <th>Amount</th>
+                                        <th>Description</th>
+                                        <th>Transaction</th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {}
+                                </tbody>
+                            </table>
+                        </div>
+                    </div>
+                </div>
+                
+                <a href="/" class="btn btn-primary">Back to Dashboard</a>
+            </div>
+        </body>
+        </html>
+        "#,
+        escape_html(&wallet.address),
+        wallet.balance,
+        history_rows
+    )
+}
+
+/// Domains to show on the analytics page
+const ANALYTICS_DOMAIN_LIMIT: usize = 50;
+
+fn analytics_template(domains: &[(String, usize, u64)]) -> String {
+    let domain_rows = domains
+        .iter()
+        .enumerate()
+        .map(|(rank, (domain, pages, bytes))| format!(
+            r#"
+            <tr>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{}</td>
+                <td>{} bytes</td>
+            </tr>
+            "#,
+            rank + 1,
+            escape_html(domain),
+            pages,
+            bytes
+        ))
+        .collect::<Vec<String>>()
+        .join("");
+    
+    format!(
+        r#"
+        <!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Analytics - CryptoCrawl Client</title>
+            <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0-alpha1/dist/css/bootstrap.min.css" rel="stylesheet">
+            <style>
+                body {{ background-color: #121212; color: #e0e0e0; }}
+                .card {{ background-color: #1e1e1e; border-color: #333; }}
+                .card-header {{ background-color: #252525; border-color: #333; }}
+                .navbar {{ background-color: #252525; }}
+                th, td {{ color: #e0e0e0; }}
+                .table {{ color: #e0e0e0; }}
+            </style>
+        </head>
+        <body>
+            <nav class="navbar navbar-expand-lg navbar-dark mb-4">
+                <div class="container">
+                    <a class="navbar-brand" href="/">CryptoCrawl Client</a>
+                </div>
+            </nav>
+            
+            <div class="container">
+                <h2 class="mb-4">Analytics</h2>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">Top Domains</div>
+                    <div class="card-body">
+                        <div class="table-responsive">
+                            <table class="table table-dark">
+                                <thead>
+                                    <tr>
+                                        <th>#</th>
+                                        <th>Domain</th>
+                                        <th>Pages</th>
+                                        <th>Size</th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {}
+                                </tbody>
+                            </table>
+                        </div>
+                    </div>
+                </div>
+                
+                <a href="/" class="btn btn-primary">Back to Dashboard</a>
+            </div>
+        </body>
+        </html>
+        "#,
+        domain_rows
+    )
+}
+
+fn tasks_template(tasks: &[CrawlResult], filter: &TaskFilter) -> String {
     let task_rows = tasks
         .iter()
         .map(|task| {
@@ -199,6 +534,7 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
                 CrawlStatus::Failed => "text-danger",
                 CrawlStatus::Verified => "text-primary",
                 CrawlStatus::Rejected => "text-warning",
+                CrawlStatus::Cancelled => "text-muted",
                 _ => "text-secondary",
             };
             
@@ -215,6 +551,10 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
                 None => "In progress".to_string(),
             };
             
+            let started = chrono::DateTime::from_timestamp(task.start_time as i64, 0)
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            
             format!(
                 r#"
                 <tr>
@@ -225,21 +565,52 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
                     <td>{}</td>
                     <td>{}</td>
                     <td>{}</td>
+                    <td>{}</td>
+                    <td><button class="btn btn-sm btn-outline-danger delete-task" data-task-id="{}">Delete</button></td>
                 </tr>
                 "#,
-                task.task_id,
-                task.task_id,
-                task.domain,
+                escape_html(&task.task_id),
+                escape_html(&task.task_id),
+                escape_html(&task.domain),
                 status_class,
                 task.status,
                 task.pages_count,
                 task.total_size as usize,
+                started,
                 end_time,
-                incentives
+                incentives,
+                escape_html(&task.task_id)
             )
         })
         .collect::<Vec<String>>()
         .join("");
+    
+    // Filter form, keeping the current sort order
+    let status_options = [CrawlStatus::InProgress, CrawlStatus::Completed, CrawlStatus::Failed, CrawlStatus::Verified, CrawlStatus::Rejected, CrawlStatus::Cancelled]
+        .iter()
+        .map(|status| format!(
+            r#"<option value="{:?}"{}>{}</option>"#,
+            status,
+            if filter.status.as_ref() == Some(status) { " selected" } else { "" },
+            status
+        ))
+        .collect::<Vec<String>>()
+        .join("");
+    
+    let mut active_filters = Vec::new();
+    if let Some(domain) = &filter.domain {
+        active_filters.push(format!("domain contains <strong>{}</strong>", escape_html(domain)));
+    }
+    if let Some(status) = &filter.status {
+        active_filters.push(format!("status is <strong>{}</strong>", status));
+    }
+    let summary = format!(
+        "Showing {} crawls{}, sorted by {} ({})",
+        tasks.len(),
+        if active_filters.is_empty() { String::new() } else { format!(" where {}", active_filters.join(" and ")) },
+        filter.sort.as_str(),
+        if filter.ascending { "ascending" } else { "descending" },
+    );
 
     format!(
         r#"
@@ -269,6 +640,25 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
             <div class="container">
                 <h2 class="mb-4">Task History</h2>
                 
+                <form class="row g-2 mb-3" method="get" action="/tasks">
+                    <div class="col-md-5">
+                        <input type="text" name="domain" class="form-control" placeholder="Domain contains..." value="{}">
+                    </div>
+                    <div class="col-md-3">
+                        <select name="status" class="form-select">
+                            <option value="">Any status</option>
+                            {}
+                        </select>
+                    </div>
+                    <input type="hidden" name="sort" value="{}">
+                    <input type="hidden" name="order" value="{}">
+                    <div class="col-auto">
+                        <button type="submit" class="btn btn-primary">Filter</button>
+                        <a href="/tasks" class="btn btn-secondary">Clear</a>
+                    </div>
+                </form>
+                <p class="text-muted">{}</p>
+                
                 <div class="card bg-dark text-white mb-4">
                     <div class="card-body">
                         <div class="table-responsive">
@@ -278,10 +668,12 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
                                         <th>Task ID</th>
                                         <th>Domain</th>
                                         <th>Status</th>
-                                        <th>Pages</th>
-                                        <th>Data Size</th>
+                                        <th>{}</th>
+                                        <th>{}</th>
+                                        <th>{}</th>
                                         <th>Duration</th>
                                         <th>Incentives</th>
+                                        <th></th>
                                     </tr>
                                 </thead>
                                 <tbody>
@@ -296,19 +688,51 @@ fn tasks_template(tasks: &Vec<CrawlResult>) -> String {
             </div>
             
             <script src="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0-alpha1/dist/js/bootstrap.bundle.min.js"></script>
+            <script>
+                document.querySelectorAll('.delete-task').forEach(button => {{
+                    button.addEventListener('click', async () => {{
+                        const taskId = button.dataset.taskId;
+                        if (!confirm(`Delete task ${{taskId}} and all of its pages?`)) {{
+                            return;
+                        }}
+                        const response = await fetch(`/api/tasks/${{encodeURIComponent(taskId)}}`, {{ method: 'DELETE' }});
+                        if (response.ok) {{
+                            button.closest('tr').remove();
+                        }} else {{
+                            const body = await response.json().catch(() => null);
+                            alert(body?.error?.message ?? `Failed to delete task ${{taskId}}`);
+                        }}
+                    }});
+                }});
+            </script>
         </body>
         </html>
         "#,
+        escape_html(filter.domain.as_deref().unwrap_or_default()),
+        status_options,
+        filter.sort.as_str(),
+        if filter.ascending { "asc" } else { "desc" },
+        summary,
+        filter.sort_header(CrawlSort::Pages, "Pages"),
+        filter.sort_header(CrawlSort::Size, "Data Size"),
+        filter.sort_header(CrawlSort::Date, "Started"),
         task_rows
     )
 }
 
-fn task_detail_template(task: &CrawlResult) -> String {
+fn task_detail_template(
+    task: &CrawlResult,
+    depths: &HashMap<u32, usize>,
+    titles: &HashMap<String, String>,
+    stats: &CrawlStats,
+    failed_urls: usize,
+) -> String {
     let status_class = match task.status {
         CrawlStatus::Completed => "text-success",
         CrawlStatus::Failed => "text-danger",
         CrawlStatus::Verified => "text-primary",
         CrawlStatus::Rejected => "text-warning",
+        CrawlStatus::Cancelled => "text-muted",
         _ => "text-secondary",
     };
     
@@ -331,16 +755,73 @@ fn task_detail_template(task: &CrawlResult) -> String {
                     <td>{}</td>
                     <td>{}</td>
                     <td>{}</td>
+                    <td>{}</td>
                 </tr>
                 "#,
                 i + 1,
-                page.url,
+                page_link(&task.task_id, &page.url),
+                escape_html(titles.get(&page.url).map(String::as_str).unwrap_or_default()),
                 page.size,
                 page.timestamp
             )
         })
         .collect::<Vec<String>>()
         .join("");
+    
+    let mut status_counts: Vec<(&u16, &usize)> = stats.status_counts.iter().collect();
+    status_counts.sort();
+    let status_rows = status_counts.into_iter()
+        .map(|(status, count)| {
+            format!(
+                r#"
+                <tr>
+                    <td>{}</td>
+                    <td>{}</td>
+                </tr>
+                "#,
+                status,
+                count
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
+    
+    // Largest content types first
+    let mut content_types: Vec<(&String, &u64)> = stats.content_type_bytes.iter().collect();
+    content_types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let content_type_rows = content_types.into_iter()
+        .map(|(content_type, bytes)| {
+            format!(
+                r#"
+                <tr>
+                    <td>{}</td>
+                    <td>{}</td>
+                </tr>
+                "#,
+                escape_html(content_type),
+                bytes
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
+    
+    let mut depth_counts: Vec<(&u32, &usize)> = depths.iter().collect();
+    depth_counts.sort();
+    let depth_rows = depth_counts.into_iter()
+        .map(|(depth, count)| {
+            format!(
+                r#"
+                <tr>
+                    <td>{}</td>
+                    <td>{}</td>
+                </tr>
+                "#,
+                depth,
+                count
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
 
     format!(
         r#"
@@ -381,6 +862,7 @@ fn task_detail_template(task: &CrawlResult) -> String {
                                 <p><strong>Domain:</strong> {}</p>
                                 <p><strong>Status:</strong> <span class="{}">{:?}</span></p>
                                 <p><strong>Pages Crawled:</strong> {}</p>
+                                <p><strong>Failed URLs:</strong> <span class="{}">{}</span></p>
                             </div>
                             <div class="col-md-6">
                                 <p><strong>Data Size:</strong> {} bytes</p>
@@ -391,6 +873,70 @@ fn task_detail_template(task: &CrawlResult) -> String {
                     </div>
                 </div>
                 
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">
+                        <h4>Pages by Depth</h4>
+                    </div>
+                    <div class="card-body">
+                        <div class="table-responsive">
+                            <table class="table table-dark">
+                                <thead>
+                                    <tr>
+                                        <th>Depth</th>
+                                        <th>Pages</th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {}
+                                </tbody>
+                            </table>
+                        </div>
+                    </div>
+                </div>
+                
+                <div class="row">
+                    <div class="col-md-6">
+                        <div class="card bg-dark text-white mb-4">
+                            <div class="card-header">
+                                <h4>Status Codes</h4>
+                            </div>
+                            <div class="card-body">
+                                <table class="table table-dark">
+                                    <thead>
+                                        <tr>
+                                            <th>Status</th>
+                                            <th>Pages</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {}
+                                    </tbody>
+                                </table>
+                            </div>
+                        </div>
+                    </div>
+                    <div class="col-md-6">
+                        <div class="card bg-dark text-white mb-4">
+                            <div class="card-header">
+                                <h4>Content Types</h4>
+                            </div>
+                            <div class="card-body">
+                                <table class="table table-dark">
+                                    <thead>
+                                        <tr>
+                                            <th>Content Type</th>
+                                            <th>Bytes</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {}
+                                    </tbody>
+                                </table>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+                
                 <div class="card bg-dark text-white mb-4">
                     <div class="card-header">
                         <h4>Crawled Pages</h4>
@@ -402,6 +948,7 @@ fn task_detail_template(task: &CrawlResult) -> String {
                                     <tr>
                                         <th>#</th>
                                         <th>URL</th>
+                                        <th>Title</th>
                                         <th>Size</th>
                                         <th>Timestamp</th>
                                     </tr>
@@ -421,18 +968,141 @@ fn task_detail_template(task: &CrawlResult) -> String {
         </body>
         </html>
         "#,
-        task.task_id,
-        task.domain,
+        escape_html(&task.task_id),
+        escape_html(&task.domain),
         status_class,
         task.status,
         task.pages_count,
+        if failed_urls > 0 { "text-danger" } else { "" },
+        failed_urls,
         task.total_size as usize,
-        transaction_hash,
+        escape_html(transaction_hash),
         incentives,
+        depth_rows,
+        status_rows,
+        content_type_rows,
         page_rows
     )
 }
 
+fn page_template(page: &StoredPage) -> String {
+    let optional = |value: &Option<String>| escape_html(value.as_deref().unwrap_or("N/A"));
+    
+    let metadata_rows = [
+        ("Description", &page.metadata.description),
+        ("og:title", &page.metadata.og_title),
+        ("og:description", &page.metadata.og_description),
+        ("og:image", &page.metadata.og_image),
+    ]
+        .iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!(
+            "<p><strong>{}:</strong> {}</p>", name, escape_html(value)
+        )))
+        .collect::<Vec<String>>()
+        .join("");
+    
+    // The HTML is shown in a sandboxed iframe without scripts; the text is escaped
+    let html_preview = match &page.html {
+        Some(html) => format!(
+            r#"<iframe sandbox srcdoc="{}" style="width: 100%; height: 600px; background-color: #fff; border: 0;"></iframe>"#,
+            escape_html(html)
+        ),
+        None => "<p>No HTML stored for this page.</p>".to_string(),
+    };
+    let text_preview = match &page.text_content {
+        Some(text) => format!(r#"<pre style="white-space: pre-wrap; color: #e0e0e0;">{}</pre>"#, escape_html(text)),
+        None => "<p>No text stored for this page.</p>".to_string(),
+    };
+    
+    format!(
+        r#"
+        <!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Page Preview - CryptoCrawl Client</title>
+            <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.0-alpha1/dist/css/bootstrap.min.css" rel="stylesheet">
+            <style>
+                body {{ background-color: #121212; color: #e0e0e0; }}
+                .card {{ background-color: #1e1e1e; border-color: #333; }}
+                .card-header {{ background-color: #252525; border-color: #333; }}
+                .navbar {{ background-color: #252525; }}
+            </style>
+        </head>
+        <body>
+            <nav class="navbar navbar-expand-lg navbar-dark mb-4">
+                <div class="container">
+                    <a class="navbar-brand" href="/">CryptoCrawl Client</a>
+                </div>
+            </nav>
+            
+            <div class="container">
+                <h2 class="mb-4">Page Preview</h2>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">
+                        <h4>Page Information</h4>
+                    </div>
+                    <div class="card-body">
+                        <p><strong>URL:</strong> {}</p>
+                        <p><strong>Requested URL:</strong> {}</p>
+                        <p><strong>Title:</strong> {}</p>
+                        <p><strong>Status:</strong> {}</p>
+                        <p><strong>Content Type:</strong> {}</p>
+                        <p><strong>Size:</strong> {} bytes</p>
+                        <p><strong>Depth:</strong> {}</p>
+                        <p><strong>Hops (with redirects):</strong> {}</p>
+                        <p><strong>Saved To:</strong> {}</p>
+                        <p><strong>Fetched At:</strong> {}</p>
+                        <p><strong>Soft 404:</strong> {}</p>
+                        <p><strong>JavaScript Dependent:</strong> {}</p>
+                        {}
+                    </div>
+                </div>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">
+                        <h4>HTML</h4>
+                    </div>
+                    <div class="card-body">
+                        {}
+                    </div>
+                </div>
+                
+                <div class="card bg-dark text-white mb-4">
+                    <div class="card-header">
+                        <h4>Text</h4>
+                    </div>
+                    <div class="card-body">
+                        {}
+                    </div>
+                </div>
+                
+                <a href="/tasks/{}" class="btn btn-primary">Back to Task</a>
+            </div>
+        </body>
+        </html>
+        "#,
+        escape_html(&page.url),
+        optional(&page.requested_url),
+        optional(&page.title),
+        page.status.map(|status| status.to_string()).unwrap_or_else(|| "N/A".to_string()),
+        optional(&page.content_type),
+        page.size,
+        page.depth.map(|depth| depth.to_string()).unwrap_or_else(|| "N/A".to_string()),
+        page.hops.map(|hops| hops.to_string()).unwrap_or_else(|| "N/A".to_string()),
+        optional(&page.asset_path),
+        optional(&page.fetched_at),
+        if page.soft_404 { "Yes" } else { "No" },
+        if page.is_javascript_dependent { "Yes" } else { "No" },
+        metadata_rows,
+        html_preview,
+        text_preview,
+        escape_html(&page.task_id)
+    )
+}
+
 // Start the UI server
 pub async fn start_ui_server(
     db: Database,
@@ -448,6 +1118,7 @@ pub async fn start_ui_server(
         solana: Arc::new(solana),
         client_id: client_id.to_string(),
         running: Arc::new(AtomicBool::new(true)),
+        live: Arc::new(Mutex::new(None)),
     });
 
     // Build router with routes and state
@@ -455,20 +1126,39 @@ pub async fn start_ui_server(
         .route("/", get(index_page))
         .route("/tasks", get(tasks_page))
         .route("/tasks/:id", get(task_detail_page))
+        .route("/pages", get(page_preview_page))
+        .route("/wallet", get(wallet_page))
+        .route("/analytics", get(analytics_page))
         .route("/api/tasks/assign", post(assign_task))
+        .route("/api/tasks/:id", get(get_task).delete(delete_task))
+        .route("/api/pages", get(get_page))
         .route("/api/wallet", get(get_wallet))
         .route("/api/status", get(get_status))
         .route("/api/health", get(health_check))
+        .route("/metrics", get(get_metrics))
+        .route("/ws/status", get(ws_status))
         .with_state(state);
 
     // Start server
     info!("Starting UI server on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
 
+    info!("UI server stopped");
     Ok(())
 }
 
+/// Resolve when the process receives Ctrl+C
+async fn shutdown_signal() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for shutdown signal: {}", e);
+        std::future::pending::<()>().await;
+    }
+    info!("Shutdown signal received, stopping UI server");
+}
+
 // Route handlers
 async fn health_check(
     State(state): State<Arc<AppState>>,
@@ -480,9 +1170,51 @@ async fn health_check(
     }
 }
 
+/// Prometheus crawl metrics in the text exposition format
+async fn get_metrics() -> impl IntoResponse {
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.render())
+}
+
+/// Push a live snapshot of the workers and queues every second
+async fn ws_status(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| push_live_status(socket, state))
+}
+
+async fn push_live_status(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+    
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let status = state.live.lock().await
+                    .as_ref()
+                    .map(|progress| progress.live_status())
+                    .unwrap_or_default();
+                let text = match serde_json::to_string(&status) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Failed to serialize live status: {}", e);
+                        break;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {},
+            },
+        }
+    }
+}
+
 async fn index_page(
     State(state): State<Arc<AppState>>,
-) -> Result<Html<String>, ApiError> {
+) -> Result<Html<String>, CrawlerError> {
     let status = get_status_data(state).await?;
     let html = index_template(&status);
     Ok(Html(html))
@@ -490,36 +1222,113 @@ async fn index_page(
 
 async fn tasks_page(
     State(state): State<Arc<AppState>>,
-) -> Result<Html<String>, ApiError> {
+    Query(query): Query<TaskListQuery>,
+) -> Result<Html<String>, CrawlerError> {
+    let filter = TaskFilter::from_query(query)?;
+    
     let db = state.db.lock().await;
-    let tasks = db.get_all_crawl_results()?;
-    let html = tasks_template(&tasks);
+    let tasks = db.get_crawl_results_filtered(
+        filter.domain.as_deref(),
+        filter.status.as_ref(),
+        filter.sort,
+        filter.ascending,
+    )?;
+    let html = tasks_template(&tasks, &filter);
     Ok(Html(html))
 }
 
+async fn analytics_page(
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, CrawlerError> {
+    let db = state.db.lock().await;
+    let domains = db.top_domains(ANALYTICS_DOMAIN_LIMIT)?;
+    Ok(Html(analytics_template(&domains)))
+}
+
 async fn task_detail_page(
     State(state): State<Arc<AppState>>,
     Path(task_id): Path<String>,
-) -> Result<Html<String>, ApiError> {
+) -> Result<Html<String>, CrawlerError> {
     let db = state.db.lock().await;
     let task = db.get_crawl_result(&task_id)?
-        .ok_or_else(|| ApiError::NotFound(format!("Task {} not found", task_id)))?;
+        .ok_or_else(|| CrawlerError::NotFound(format!("Task {} not found", task_id)))?;
+    let depths = db.pages_by_depth(&task_id)?;
+    let titles = db.page_titles(&task_id)?;
+    let stats = db.crawl_stats(&task_id)?.unwrap_or_default();
+    let failed_urls = db.get_failed_urls(&task_id)?.len();
     
-    let html = task_detail_template(&task);
+    let html = task_detail_template(&task, &depths, &titles, &stats, failed_urls);
     Ok(Html(html))
 }
 
+/// Crawl result of a task as JSON
+async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<CrawlResult>, CrawlerError> {
+    let db = state.db.lock().await;
+    let result = db.get_crawl_result(&task_id)?
+        .ok_or_else(|| CrawlerError::NotFound(format!("Task {} not found", task_id)))?;
+    
+    Ok(Json(result))
+}
+
+async fn page_preview_page(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PageQuery>,
+) -> Result<Html<String>, CrawlerError> {
+    let db = state.db.lock().await;
+    let page = db.get_stored_page(&query.task_id, &query.url)?
+        .ok_or_else(|| CrawlerError::NotFound(format!("Page {} not stored by task {}", query.url, query.task_id)))?;
+    
+    Ok(Html(page_template(&page)))
+}
+
+/// Stored content and metadata of a crawled page as JSON
+async fn get_page(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<StoredPage>, CrawlerError> {
+    let db = state.db.lock().await;
+    let page = db.get_stored_page(&query.task_id, &query.url)?
+        .ok_or_else(|| CrawlerError::NotFound(format!("Page {} not stored by task {}", query.url, query.task_id)))?;
+    
+    Ok(Json(page))
+}
+
+/// Delete a task with its crawl result and pages
+async fn delete_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<DeleteTaskResponse>, CrawlerError> {
+    let db = state.db.lock().await;
+    let deleted_rows = db.delete_crawl(&task_id)?;
+    if deleted_rows == 0 {
+        return Err(CrawlerError::NotFound(format!("Task {} not found", task_id)));
+    }
+    
+    Ok(Json(DeleteTaskResponse { task_id, deleted_rows }))
+}
+
 async fn assign_task(
     State(state): State<Arc<AppState>>,
     form: axum::extract::Form<TaskAssignRequest>,
-) -> Result<impl IntoResponse, ApiError> {
+) -> Result<impl IntoResponse, CrawlerError> {
+    // Only http(s) URLs can be crawled
+    let url = Url::parse(form.url.trim())
+        .map_err(|e| CrawlerError::InvalidUrl(format!("{}: {}", form.url, e)))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(CrawlerError::InvalidUrl(format!("{}: only http and https URLs can be crawled", form.url)));
+    }
+    
     // Create a new task
     let task_id = Uuid::new_v4().to_string();
     let task = Task {
         id: task_id,
-        target_url: form.url.clone(),
+        target_url: url.to_string(),
         max_depth: 2,
         follow_subdomains: false,
+        scope: None,
         max_links: Some(100),
         created_at: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -530,6 +1339,11 @@ async fn assign_task(
             .unwrap_or_default()
             .as_secs()),
         incentive_amount: 25_000_000,
+        allowed_content_types: default_allowed_content_types(),
+        accept_language: None,
+        robots_user_agent: None,
+        max_runtime_secs: None,
+        login: None,
     };
     
     // Save task to database
@@ -543,7 +1357,8 @@ async fn assign_task(
     tokio::spawn(async move {
         let crawl_result = {
             let mut crawler = state_clone.crawler.lock().await;
-            *crawler = Crawler::new(task.clone());
+            *crawler = Crawler::new(task.clone()).with_progress(CrawlProgress::new());
+            *state_clone.live.lock().await = crawler.progress().cloned();
             match crawler.crawl_current().await {
                 Ok(result) => result,
                 Err(e) => {
@@ -577,13 +1392,39 @@ async fn assign_task(
             
             // Submit to blockchain
             match solana_clone.submit_crawl_report(&task_id, &crawl_result).await {
-                Ok(tx_hash) => {
+                Ok(receipt) => {
+                    let tx_hash = receipt.signature.clone();
                     info!("Submitted crawl data to blockchain: {}", tx_hash);
                     
                     // Update result with transaction hash
                     let mut updated_result = crawl_result.clone();
                     updated_result.set_transaction(tx_hash.clone());
                     
+                    // Only claim incentives once the submission is confirmed on-chain
+                    match receipt.confirmation {
+                        ConfirmationStatus::Confirmed => {},
+                        ConfirmationStatus::RpcUnavailable => {
+                            warn!("Solana RPC unreachable, claiming incentives without on-chain confirmation");
+                        },
+                        ConfirmationStatus::Simulated => {
+                            info!("Transaction {} was simulated, claiming incentives without on-chain confirmation", tx_hash);
+                        },
+                        ConfirmationStatus::Unconfirmed => {
+                            error!("Not claiming incentives for task {}: transaction {} is unconfirmed", task_id, tx_hash);
+                            return;
+                        }
+                    }
+                    
+                    // Skip the claim if this transaction was already paid out
+                    match db_clone.lock().await.has_wallet_history(&task_id, &tx_hash) {
+                        Ok(true) => {
+                            warn!("Incentives for task {} already claimed, skipping", task_id);
+                            return;
+                        },
+                        Ok(false) => {},
+                        Err(e) => error!("Failed to check wallet history: {}", e),
+                    }
+                    
                     // Claim incentives
                     match solana_clone.claim_incentives(&tx_hash) {
                         Ok(amount) => {
@@ -595,13 +1436,15 @@ async fn assign_task(
                                 error!("Failed to update crawl result with transaction: {}", e);
                             }
                             
-                            if let Err(e) = db_guard.add_wallet_history(
+                            match db_guard.add_wallet_history(
                                 &task_id, 
                                 amount, 
                                 &tx_hash, 
                                 Some("Incentive claim")
                             ) {
-                                error!("Failed to add wallet history entry: {}", e);
+                                Ok(true) => {},
+                                Ok(false) => warn!("Duplicate incentive claim for task {} ignored", task_id),
+                                Err(e) => error!("Failed to add wallet history entry: {}", e),
                             }
                         }
                         Err(e) => error!("Failed to claim incentives: {}", e),
@@ -616,42 +1459,65 @@ async fn assign_task(
     Ok((StatusCode::SEE_OTHER, [("Location", "/")]))
 }
 
-async fn get_wallet(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<WalletResponse>, ApiError> {
+/// Fetch the wallet balance, falling back to zero when the RPC endpoint is unreachable
+async fn wallet_balance(solana: &SolanaIntegration) -> Result<u64, CrawlerError> {
+    match solana.get_balance().await {
+        Ok(balance) => Ok(balance),
+        Err(e @ SolanaError::RpcUnreachable { .. }) => {
+            warn!("{}, showing zero balance", e);
+            Ok(0)
+        },
+        Err(e) => Err(CrawlerError::Solana(e)),
+    }
+}
+
+/// Wallet address, balance and up to `limit` of the latest history entries
+async fn wallet_data(state: &AppState, limit: usize) -> Result<WalletResponse, CrawlerError> {
     let solana = &state.solana;
     let wallet_address = solana.get_wallet_address();
-    let balance = solana.get_balance()?;
+    let balance = wallet_balance(solana).await?;
     
     let db = state.db.lock().await;
-    let history = db.get_wallet_history(Some(10))?;
+    let history = db.get_wallet_history(Some(limit))?;
     
     let history_items = history.into_iter()
         .map(|(task_id, amount, timestamp, tx_hash, description)| WalletHistoryItem {
             task_id,
             amount,
             timestamp,
+            explorer_url: explorer_url(solana.rpc_endpoint(), &tx_hash),
             transaction_hash: tx_hash,
             description,
         })
         .collect();
     
-    let response = WalletResponse {
+    Ok(WalletResponse {
         address: wallet_address,
         balance,
         history: history_items,
-    };
-    
-    Ok(Json(response))
+    })
+}
+
+async fn get_wallet(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<WalletResponse>, CrawlerError> {
+    Ok(Json(wallet_data(&state, 10).await?))
+}
+
+async fn wallet_page(
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, CrawlerError> {
+    let wallet = wallet_data(&state, 100).await?;
+    Ok(Html(wallet_template(&wallet)))
 }
 
 async fn get_status_data(
     state: Arc<AppState>,
-) -> Result<StatusResponse, ApiError> {
+) -> Result<StatusResponse, CrawlerError> {
     // Get wallet info
     let solana = &state.solana;
     let wallet_address = solana.get_wallet_address();
-    let wallet_balance = solana.get_balance()?;
+    let wallet_balance = wallet_balance(solana).await?;
     
     // Get active task if any
     let crawler_guard = state.crawler.lock().await;
@@ -695,7 +1561,72 @@ async fn get_status_data(
 
 async fn get_status(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<StatusResponse>, ApiError> {
+) -> Result<Json<StatusResponse>, CrawlerError> {
     let status = get_status_data(state).await?;
     Ok(Json(status))
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CrawledPage;
+    
+    #[test]
+    fn malicious_titles_and_urls_are_rendered_inert() {
+        let url = "https://example.com/\"><script>alert('url')</script>";
+        let task = CrawlResult {
+            task_id: "task-1".to_string(),
+            domain: "example.com<img src=x onerror=alert(1)>".to_string(),
+            status: CrawlStatus::Completed,
+            pages_count: 1,
+            pages: vec![CrawledPage {
+                url: url.to_string(),
+                size: 10,
+                timestamp: 0,
+                content_type: Some("text/html".to_string()),
+                status_code: Some(200),
+                body: None,
+                is_external: false,
+            }],
+            total_size: 10,
+            start_time: 0,
+            end_time: Some(1),
+            transaction_hash: None,
+            incentives_received: None,
+            sitemap_urls_found: None,
+            bytes_saved: 0,
+            status_counts: HashMap::new(),
+            content_type_bytes: HashMap::new(),
+            rendered_pages: 0,
+            time_limited: false,
+        };
+        let titles = HashMap::from([(url.to_string(), "<script>alert('title')</script>".to_string())]);
+        
+        let stats = CrawlStats {
+            content_type_bytes: HashMap::from([("<img src=x>".to_string(), 1)]),
+            ..CrawlStats::default()
+        };
+        
+        let html = task_detail_template(&task, &HashMap::new(), &titles, &stats, 2);
+        
+        assert!(html.contains(r#"<strong>Failed URLs:</strong> <span class="text-danger">2</span>"#));
+        assert!(!html.contains("<script>alert"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;title&#39;)&lt;/script&gt;"));
+        assert!(html.contains("&quot;&gt;&lt;script&gt;alert(&#39;url&#39;)"));
+        
+        let html = tasks_template(&[task], &TaskFilter::default());
+        assert!(!html.contains("<img"));
+    }
+    
+    #[test]
+    fn explorer_links_follow_the_rpc_cluster() {
+        assert_eq!(explorer_url("https://api.devnet.solana.com", "sig"), "https://explorer.solana.com/tx/sig?cluster=devnet");
+        assert_eq!(explorer_url("https://api.testnet.solana.com", "sig"), "https://explorer.solana.com/tx/sig?cluster=testnet");
+        assert_eq!(explorer_url("https://api.mainnet-beta.solana.com", "sig"), "https://explorer.solana.com/tx/sig");
+        assert_eq!(
+            explorer_url("http://localhost:8899", "sig"),
+            "https://explorer.solana.com/tx/sig?cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899"
+        );
+    }
+}
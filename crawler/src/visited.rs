@@ -0,0 +1,103 @@
+use crate::crawler::fnv1a;
+use std::collections::HashSet;
+
+/// URLs a crawl has queued or fetched, so none is fetched twice.
+///
+/// The exact set keeps every URL and never errs, but takes gigabytes on crawls of millions
+/// of pages, as each entry holds the whole URL. The fingerprint variant keeps only a 64-bit
+/// FNV-1a fingerprint of each URL: 8 bytes plus a control byte per hash table slot, so
+/// between 10 and 18 bytes per URL depending on how full the table is. Two URLs sharing a
+/// fingerprint would make the second look visited; the chance of any such pair is about
+/// three in a million on a crawl of ten million URLs.
+#[derive(Debug, Clone)]
+pub enum VisitedSet {
+    Exact(HashSet<String>),
+    Fingerprints {
+        fingerprints: HashSet<u64>,
+        expected: usize,
+    },
+}
+
+impl Default for VisitedSet {
+    fn default() -> Self {
+        VisitedSet::Exact(HashSet::new())
+    }
+}
+
+impl VisitedSet {
+    /// A set of URL fingerprints with room reserved for `expected` URLs; it grows past that
+    pub fn fingerprints(expected: usize) -> Self {
+        VisitedSet::Fingerprints {
+            fingerprints: HashSet::with_capacity(expected),
+            expected,
+        }
+    }
+    
+    /// Record a URL; `false` when it was seen before
+    pub fn insert(&mut self, url: String) -> bool {
+        match self {
+            VisitedSet::Exact(urls) => urls.insert(url),
+            VisitedSet::Fingerprints { fingerprints, .. } => fingerprints.insert(fnv1a(url.as_bytes())),
+        }
+    }
+    
+    /// Whether a URL was seen
+    pub fn contains(&self, url: &str) -> bool {
+        match self {
+            VisitedSet::Exact(urls) => urls.contains(url),
+            VisitedSet::Fingerprints { fingerprints, .. } => fingerprints.contains(&fnv1a(url.as_bytes())),
+        }
+    }
+    
+    /// URLs recorded so far
+    pub fn len(&self) -> usize {
+        match self {
+            VisitedSet::Exact(urls) => urls.len(),
+            VisitedSet::Fingerprints { fingerprints, .. } => fingerprints.len(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Forget every URL, keeping the kind of set and its sizing
+    pub fn clear(&mut self) {
+        match self {
+            VisitedSet::Exact(urls) => urls.clear(),
+            VisitedSet::Fingerprints { expected, .. } => *self = VisitedSet::fingerprints(*expected),
+        }
+    }
+}
+
+impl Extend<String> for VisitedSet {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, urls: I) {
+        for url in urls {
+            self.insert(url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn fingerprints_tell_seen_urls_from_new_ones() {
+        let mut visited = VisitedSet::fingerprints(100);
+        
+        let seen: Vec<String> = (0..5_000).map(|i| format!("https://example.com/page/{}", i)).collect();
+        visited.extend(seen.iter().cloned());
+        assert!(seen.iter().all(|url| visited.contains(url)));
+        assert!(!visited.insert(seen[0].clone()));
+        
+        let new: Vec<String> = (0..5_000).map(|i| format!("https://example.com/other/{}", i)).collect();
+        assert!(new.iter().all(|url| !visited.contains(url)));
+        assert!(new.into_iter().all(|url| visited.insert(url)));
+        assert_eq!(visited.len(), 10_000);
+        
+        visited.clear();
+        assert!(visited.is_empty() && !visited.contains(&seen[0]));
+        assert!(matches!(visited, VisitedSet::Fingerprints { expected: 100, .. }));
+    }
+}
@@ -1,19 +1,27 @@
 use axum::{
-    routing::{get, post},
-    Router, extract::{State, Path, Json}, http::StatusCode,
+    routing::{get, patch, post},
+    Router, Extension, extract::{State, Path, Json, Query, Request},
+    http::{header::{AUTHORIZATION, CONTENT_TYPE}, HeaderName, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
 };
+use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 use crate::db::Database;
-use crate::models::{Task, TaskStatus, CrawlReport};
-use crate::evaluator::Evaluator;
+use crate::models::{Task, TaskStatus, TaskProgress, TaskTemplate, CrawlReport, CrawlerInfo, Config, Schedule, Stats, validate_webhook_url};
+use crate::evaluator::{DocLanguage, Evaluator, PackageNotFound, VersionUnavailable};
+use crate::metrics::METRICS;
+use crate::rate_limit::RateLimiter;
+use crate::scheduler::{self, DEFAULT_TIMEZONE};
 use crate::solana::SolanaIntegration;
+use crate::webhook::{VerificationEvent, WebhookNotifier};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tower_http::cors::{CorsLayer, Any};
-use log::info;
+use log::{debug, error, info, warn};
 use url::Url;
 
 /// Application state
@@ -26,6 +34,14 @@ pub struct AppState {
     solana: Arc<SolanaIntegration>,
     /// Whether the system is running
     running: Arc<AtomicBool>,
+    /// Manager configuration
+    config: Config,
+    /// Rate limiter for report submissions
+    report_limiter: RateLimiter,
+    /// Rate limiter for task assignment requests
+    assignment_limiter: RateLimiter,
+    /// Sends verification results to webhooks
+    webhooks: WebhookNotifier,
 }
 
 // API Error handling
@@ -34,26 +50,160 @@ pub enum ApiError {
     InternalError(String),
     NotFound(String),
     BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    TooManyRequests(String),
+    PayloadTooLarge(String),
+}
+
+impl ApiError {
+    /// Machine-readable error type sent in the response body
+    fn error_type(&self) -> &'static str {
+        match self {
+            ApiError::InternalError(_) => "internal_error",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::TooManyRequests(_) => "too_many_requests",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let error_type = self.error_type();
         let (status, error_message) = match self {
             ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            ApiError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+            ApiError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
         };
-
-        (status, error_message).into_response()
+        
+        let body = serde_json::json!({
+            "error": {
+                "type": error_type,
+                "message": error_message,
+            }
+        });
+        
+        (status, Json(body)).into_response()
     }
 }
 
+/// Errors from the database and evaluator stay `anyhow` internally and are
+/// translated here, so a missing row or a bad URL isn't reported as a 500
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
+        if let Some(e) = err.downcast_ref::<url::ParseError>() {
+            return ApiError::BadRequest(format!("Invalid URL: {}", e));
+        }
+        if let Some(rusqlite::Error::QueryReturnedNoRows) = err.downcast_ref::<rusqlite::Error>() {
+            return ApiError::NotFound(err.to_string());
+        }
+        if let Some(e) = err.downcast_ref::<PackageNotFound>() {
+            return ApiError::NotFound(e.to_string());
+        }
+        if let Some(e) = err.downcast_ref::<VersionUnavailable>() {
+            return ApiError::NotFound(e.to_string());
+        }
+        
         ApiError::InternalError(err.to_string())
     }
 }
 
+/// Identity of an authenticated API caller
+#[derive(Debug, Clone)]
+pub enum Caller {
+    /// Authentication is disabled
+    Anonymous,
+    /// Holder of a configured API key
+    Admin,
+    /// Crawler authenticated with its registration token
+    Crawler(String),
+}
+
+impl Caller {
+    /// Require an API key
+    fn require_admin(&self) -> Result<(), ApiError> {
+        match self {
+            Caller::Anonymous | Caller::Admin => Ok(()),
+            Caller::Crawler(_) => Err(ApiError::Forbidden("This endpoint requires an API key".to_string())),
+        }
+    }
+    
+    /// Require the registration token of the given crawler
+    fn require_client(&self, client_id: &str) -> Result<(), ApiError> {
+        match self {
+            Caller::Anonymous => Ok(()),
+            Caller::Crawler(id) if id == client_id => Ok(()),
+            Caller::Crawler(_) => Err(ApiError::Forbidden(format!("Token does not belong to client {}", client_id))),
+            Caller::Admin => Err(ApiError::Forbidden("This endpoint requires a crawler token".to_string())),
+        }
+    }
+}
+
+/// Hash a crawler token for storage
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Authenticate `Authorization: Bearer` credentials against the configured API keys
+/// and issued crawler tokens, recording the caller for the handlers
+async fn require_auth(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let auth = &state.config.auth;
+    
+    let caller = if !auth.enabled {
+        Caller::Anonymous
+    } else {
+        let token = req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))?;
+        
+        if auth.api_keys.iter().any(|key| key == token) {
+            Caller::Admin
+        } else {
+            let db = state.db.lock().await;
+            match db.get_client_for_token(&hash_token(token))? {
+                Some(client_id) => Caller::Crawler(client_id),
+                None => return Err(ApiError::Unauthorized("Invalid bearer token".to_string())),
+            }
+        }
+    };
+    
+    req.extensions_mut().insert(caller);
+    Ok(next.run(req).await)
+}
+
+/// Share of reported pages allowed to fall outside the task domain (redirects, canonical hosts)
+const OFF_DOMAIN_TOLERANCE: f64 = 0.05;
+
+/// Weight of the spot-check score in the final verification score
+const SPOT_CHECK_WEIGHT: f64 = 0.3;
+
+/// Default number of items returned by list endpoints
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Largest page a list endpoint will return
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Most tasks accepted by one batch creation request
+const MAX_TASK_BATCH_SIZE: usize = 1000;
+
+/// Header carrying the total number of items matching a list query
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
 // Request and response types
 #[derive(Serialize, Deserialize)]
 pub struct TaskRequest {
@@ -62,6 +212,58 @@ pub struct TaskRequest {
     pub follow_subdomains: bool,
     pub max_links: Option<u32>,
     pub incentive_amount: Option<u64>,
+    pub priority: Option<i32>,
+    /// Webhook notified when the report is verified, instead of the configured one
+    pub webhook_url: Option<String>,
+}
+
+impl TaskRequest {
+    /// Validate the request and build a new queued task from it
+    fn into_task(self) -> Result<Task, String> {
+        if let Err(e) = Url::parse(&self.target_url) {
+            return Err(format!("Invalid URL: {}", e));
+        }
+        if let Some(webhook_url) = &self.webhook_url {
+            validate_webhook_url(webhook_url).map_err(|e| format!("Webhook URL {}", e))?;
+        }
+        
+        Ok(Task::new(
+            Uuid::new_v4().to_string(),
+            self.target_url,
+            self.max_depth,
+            self.follow_subdomains,
+            self.max_links,
+            self.incentive_amount.unwrap_or(25_000_000),
+        ).with_priority(self.priority.unwrap_or(0))
+            .with_webhook_url(self.webhook_url))
+    }
+}
+
+/// A recurring crawl to create
+#[derive(Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    /// Cron expression, such as `0 2 * * *` for every night at 02:00
+    pub cron: String,
+    /// IANA time zone the expression is evaluated in; defaults to UTC
+    pub timezone: Option<String>,
+    /// Task created each time the schedule fires
+    pub template: TaskRequest,
+}
+
+/// A task of a batch that failed validation
+#[derive(Serialize)]
+pub struct BatchTaskFailure {
+    /// Position of the task in the submitted batch
+    pub index: usize,
+    pub target_url: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct BatchTaskResponse {
+    /// Ids of the created tasks, in submission order
+    pub created: Vec<String>,
+    pub failed: Vec<BatchTaskFailure>,
 }
 
 #[derive(Serialize)]
@@ -74,6 +276,115 @@ pub struct TaskResponse {
     pub created_at: u64,
     pub status: String,
     pub incentive_amount: u64,
+    pub priority: i32,
+    /// Live crawl progress, when the assigned crawler has reported any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgress>,
+}
+
+impl From<Task> for TaskResponse {
+    fn from(task: Task) -> Self {
+        TaskResponse {
+            id: task.id,
+            target_url: task.target_url,
+            max_depth: task.max_depth,
+            follow_subdomains: task.follow_subdomains,
+            max_links: task.max_links,
+            created_at: task.created_at,
+            status: format!("{:?}", task.status),
+            incentive_amount: task.incentive_amount,
+            priority: task.priority,
+            progress: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HeartbeatRequest {
+    /// Tasks the crawler is currently working on
+    #[serde(default)]
+    pub tasks: Vec<HeartbeatTask>,
+}
+
+#[derive(Deserialize)]
+pub struct HeartbeatTask {
+    pub task_id: String,
+    pub pages_crawled: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ProgressUpdate {
+    pub client_id: String,
+    pub pages_crawled: u64,
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Serialize)]
+pub struct RegistrationResponse {
+    pub client_id: String,
+    /// Bearer token for the crawler's task and report calls
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct TaskPriorityRequest {
+    pub priority: i32,
+}
+
+#[derive(Deserialize)]
+pub struct ListQuery {
+    /// Only include items whose task has this status
+    pub status: Option<String>,
+    /// Maximum number of items to return
+    pub limit: Option<u32>,
+    /// Number of items to skip
+    pub offset: Option<u32>,
+}
+
+impl ListQuery {
+    /// Parse the status filter, rejecting unknown statuses
+    fn status(&self) -> Result<Option<TaskStatus>, ApiError> {
+        self.status.as_deref()
+            .map(|status| status.parse().map_err(ApiError::BadRequest))
+            .transpose()
+    }
+    
+    /// Page size, clamped to `MAX_PAGE_SIZE`
+    fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    /// Maximum number of items to return
+    pub limit: Option<u32>,
+    /// Number of items to skip
+    pub offset: Option<u32>,
+}
+
+impl PageQuery {
+    /// Page size, clamped to `MAX_PAGE_SIZE`
+    fn limit(&self) -> u32 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ApiDocsQuery {
+    /// Package version to document; defaults to the latest release
+    pub version: Option<String>,
+    /// Package ecosystem (`rust`, `python` or `javascript`); defaults to `rust`
+    pub language: Option<String>,
+    /// Re-extract even when cached documentation is still fresh
+    pub refresh: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct StaleTasksQuery {
+    /// Override the configured assignment timeout (seconds)
+    pub max_age_secs: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -91,6 +402,23 @@ pub struct CrawlReportSubmission {
     pub end_time: u64,
 }
 
+/// A batch of pages sent while a crawl is still running
+#[derive(Serialize, Deserialize)]
+pub struct PartialReportSubmission {
+    pub task_id: String,
+    pub client_id: String,
+    pub domain: String,
+    pub pages: Vec<PageSubmission>,
+    pub start_time: u64,
+}
+
+#[derive(Serialize)]
+pub struct PartialReportResponse {
+    pub task_id: String,
+    /// Pages received for the task so far
+    pub pages_count: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PageSubmission {
     pub url: String,
@@ -100,6 +428,18 @@ pub struct PageSubmission {
     pub timestamp: u64,
 }
 
+impl From<&PageSubmission> for crate::models::CrawledPage {
+    fn from(page: &PageSubmission) -> Self {
+        Self {
+            url: page.url.clone(),
+            status: page.status,
+            content_type: page.content_type.clone(),
+            size: page.size,
+            timestamp: page.timestamp,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct VerificationResult {
     pub task_id: String,
@@ -107,12 +447,15 @@ pub struct VerificationResult {
     pub score: f64,
     pub notes: String,
     pub transaction_hash: String,
+    /// Amount computed by the incentive policy and transferred, if the report was verified
     pub incentive_amount: Option<u64>,
 }
 
 #[derive(Serialize)]
 pub struct ApiDocResponse {
     pub package: String,
+    pub version: String,
+    pub language: String,
     pub documentation: String,
 }
 
@@ -121,6 +464,7 @@ pub async fn start_api_server(
     db: Arc<Mutex<Database>>,
     evaluator: Arc<Evaluator>,
     solana: SolanaIntegration,
+    config: Config,
     addr: &str,
 ) -> Result<(), anyhow::Error> {
     // Create shared state
@@ -129,36 +473,81 @@ pub async fn start_api_server(
         evaluator: evaluator.clone(),
         solana: Arc::new(solana),
         running: Arc::new(AtomicBool::new(true)),
+        report_limiter: RateLimiter::new(
+            config.rate_limits.reports_per_minute,
+            config.rate_limits.report_burst,
+        ),
+        assignment_limiter: RateLimiter::new(
+            config.rate_limits.assignments_per_minute,
+            config.rate_limits.assignment_burst,
+        ),
+        webhooks: WebhookNotifier::new(&config.webhooks),
+        config,
     });
-
+    
+    if !state.config.auth.enabled {
+        warn!("Manager API authentication is DISABLED (auth.enabled = false): anyone who can reach the API can create tasks and submit reports");
+    } else if state.config.auth.api_keys.is_empty() {
+        error!("No API keys configured in auth.api_keys: only /api/health is reachable until one is added");
+    }
+    
+    if state.config.webhooks.secret.is_none() {
+        warn!("No webhooks.secret configured: webhook payloads are sent unsigned, so receivers can't tell them from forged ones");
+    }
+    
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
-
+        .allow_headers(Any)
+        .expose_headers([HeaderName::from_static(TOTAL_COUNT_HEADER)]);
+    
     // Build router
     let app = Router::new()
         .route("/api/tasks/assign", post(assign_next_task))
         .route("/api/tasks", get(get_all_tasks).post(create_task))
+        .route("/api/tasks/batch", post(create_tasks_batch))
+        .route("/api/tasks/stale", get(get_stale_tasks))
         .route("/api/tasks/:id", get(get_task))
         .route("/api/tasks/:id/assign", post(assign_task))
-        .route("/api/reports", post(submit_report))
+        .route("/api/tasks/:id/priority", patch(update_task_priority))
+        .route("/api/tasks/:id/progress", post(update_task_progress))
+        .route("/api/schedules", get(get_schedules).post(create_schedule))
+        .route("/api/reports", get(get_reports).post(submit_report))
+        .route("/api/reports/partial", post(submit_partial_report))
         .route("/api/reports/:task_id", get(get_report))
+        .route("/api/reports/:task_id/pages", get(get_report_pages))
+        .route("/api/stats", get(get_stats))
+        .route("/api/crawlers", get(get_crawlers))
         .route("/api/crawlers/register", post(register_crawler))
+        .route("/api/crawlers/:id/heartbeat", post(crawler_heartbeat))
         .route("/api/docs/:package", get(get_api_docs))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
         .route("/api/health", get(health_check))
+        .route("/metrics", get(get_metrics))
         .layer(cors)
         .with_state(state);
-
+    
     // Start server
     info!("Starting API server on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::Server::from_tcp(listener.into_std()?)?.serve(app.into_make_service()).await?;
-
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+    
+    info!("API server stopped");
     Ok(())
 }
 
+/// Resolve when the process receives Ctrl+C
+async fn shutdown_signal() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for shutdown signal: {}", e);
+        std::future::pending::<()>().await;
+    }
+    info!("Shutdown signal received, stopping API server");
+}
+
 // API route handlers
 async fn health_check(
     State(state): State<Arc<AppState>>,
@@ -170,26 +559,42 @@ async fn health_check(
     }
 }
 
+/// Prometheus metrics in the text exposition format
+async fn get_metrics() -> impl IntoResponse {
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.render())
+}
+
 async fn get_all_tasks(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<TaskResponse>>, ApiError> {
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let status = query.status()?;
+    
     let db = state.db.lock().await;
-    let tasks = db.get_pending_tasks()?;
+    let (tasks, total) = db.get_tasks_filtered(status.as_ref(), query.limit(), query.offset.unwrap_or(0))?;
     
-    let task_responses = tasks.into_iter()
-        .map(|task| TaskResponse {
-            id: task.id,
-            target_url: task.target_url,
-            max_depth: task.max_depth,
-            follow_subdomains: task.follow_subdomains,
-            max_links: task.max_links,
-            created_at: task.created_at,
-            status: format!("{:?}", task.status),
-            incentive_amount: task.incentive_amount,
-        })
+    let task_responses: Vec<TaskResponse> = tasks.into_iter()
+        .map(TaskResponse::from)
         .collect();
     
-    Ok(Json(task_responses))
+    Ok(([(HeaderName::from_static(TOTAL_COUNT_HEADER), total.to_string())], Json(task_responses)))
+}
+
+/// List tasks whose assignment has timed out without a report
+async fn get_stale_tasks(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Query(query): Query<StaleTasksQuery>,
+) -> Result<Json<Vec<TaskResponse>>, ApiError> {
+    caller.require_admin()?;
+    
+    let max_age = query.max_age_secs
+        .unwrap_or(state.config.tasks.assignment_timeout_secs);
+    
+    let db = state.db.lock().await;
+    let tasks = db.get_stale_assignments(Duration::from_secs(max_age))?;
+    
+    Ok(Json(tasks.into_iter().map(TaskResponse::from).collect()))
 }
 
 async fn get_task(
@@ -200,66 +605,176 @@ async fn get_task(
     let task = db.get_task(&task_id)?
         .ok_or_else(|| ApiError::NotFound(format!("Task {} not found", task_id)))?;
     
-    let task_response = TaskResponse {
-        id: task.id,
-        target_url: task.target_url,
-        max_depth: task.max_depth,
-        follow_subdomains: task.follow_subdomains,
-        max_links: task.max_links,
-        created_at: task.created_at,
-        status: format!("{:?}", task.status),
-        incentive_amount: task.incentive_amount,
+    // Include live progress while the task is being crawled
+    let progress = match task.status {
+        TaskStatus::Assigned | TaskStatus::InProgress => db.get_task_progress(&task_id)?,
+        _ => None,
     };
     
+    let mut task_response = TaskResponse::from(task);
+    task_response.progress = progress;
+    
     Ok(Json(task_response))
 }
 
+/// Record progress reported by the crawler working on a task
+async fn update_task_progress(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(task_id): Path<String>,
+    Json(update): Json<ProgressUpdate>,
+) -> Result<StatusCode, ApiError> {
+    caller.require_client(&update.client_id)?;
+    
+    let db = state.db.lock().await;
+    let task = db.get_task(&task_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Task {} not found", task_id)))?;
+    
+    if task.assigned_to.as_deref() != Some(update.client_id.as_str()) {
+        return Err(ApiError::Forbidden(format!("Task {} is not assigned to client {}", task_id, update.client_id)));
+    }
+    
+    db.save_task_progress(&TaskProgress {
+        task_id,
+        client_id: update.client_id,
+        pages_crawled: update.pages_crawled,
+        bytes: update.bytes,
+        elapsed_ms: update.elapsed_ms,
+        updated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    })?;
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
 async fn create_task(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Json(task_req): Json<TaskRequest>,
 ) -> Result<Json<TaskResponse>, ApiError> {
-    // Validate URL
-    if let Err(e) = Url::parse(&task_req.target_url) {
-        return Err(ApiError::BadRequest(format!("Invalid URL: {}", e)));
-    }
+    caller.require_admin()?;
     
-    // Create task
-    let task_id = Uuid::new_v4().to_string();
-    let incentive_amount = task_req.incentive_amount.unwrap_or(25_000_000);
-    
-    let task = Task::new(
-        task_id,
-        task_req.target_url.clone(),
-        task_req.max_depth,
-        task_req.follow_subdomains,
-        task_req.max_links,
-        incentive_amount,
-    );
+    // Validate URL and create task
+    let task = task_req.into_task().map_err(ApiError::BadRequest)?;
     
     // Save to database
     let db = state.db.lock().await;
     db.create_task(&task)?;
+    METRICS.tasks_created.inc();
     
-    // Create response
-    let task_response = TaskResponse {
-        id: task.id,
-        target_url: task.target_url,
-        max_depth: task.max_depth,
-        follow_subdomains: task.follow_subdomains,
-        max_links: task.max_links,
-        created_at: task.created_at,
-        status: format!("{:?}", task.status),
-        incentive_amount: task.incentive_amount,
-    };
+    Ok(Json(TaskResponse::from(task)))
+}
+
+/// Create many tasks at once. Valid tasks are inserted together;
+/// invalid ones are reported back without failing the batch.
+async fn create_tasks_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(task_reqs): Json<Vec<TaskRequest>>,
+) -> Result<Json<BatchTaskResponse>, ApiError> {
+    caller.require_admin()?;
     
-    Ok(Json(task_response))
+    if task_reqs.len() > MAX_TASK_BATCH_SIZE {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Batch of {} tasks exceeds the limit of {}", task_reqs.len(), MAX_TASK_BATCH_SIZE
+        )));
+    }
+    
+    let mut tasks = Vec::with_capacity(task_reqs.len());
+    let mut failed = Vec::new();
+    for (index, task_req) in task_reqs.into_iter().enumerate() {
+        let target_url = task_req.target_url.clone();
+        match task_req.into_task() {
+            Ok(task) => tasks.push(task),
+            Err(error) => failed.push(BatchTaskFailure { index, target_url, error }),
+        }
+    }
+    
+    let db = state.db.lock().await;
+    db.create_tasks(&tasks)?;
+    METRICS.tasks_created.inc_by(tasks.len() as u64);
+    info!("Created {} tasks in a batch, {} rejected", tasks.len(), failed.len());
+    
+    Ok(Json(BatchTaskResponse {
+        created: tasks.into_iter().map(|task| task.id).collect(),
+        failed,
+    }))
+}
+
+/// Create a schedule that queues a fresh task from its template every time its cron expression fires
+async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(req): Json<ScheduleRequest>,
+) -> Result<Json<Schedule>, ApiError> {
+    caller.require_admin()?;
+    
+    // Validate the template the same way as a task created directly
+    let template = TaskTemplate::from(&req.template.into_task().map_err(ApiError::BadRequest)?);
+    let timezone = req.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let schedule = scheduler::new_schedule(&req.cron, timezone, template, now)
+        .map_err(ApiError::BadRequest)?;
+    
+    let db = state.db.lock().await;
+    db.create_schedule(&schedule)?;
+    info!("Created schedule {} ({} {}) for {}", schedule.id, schedule.cron, schedule.timezone, schedule.template.target_url);
+    
+    Ok(Json(schedule))
+}
+
+/// List all schedules, the next one due first
+async fn get_schedules(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<Schedule>>, ApiError> {
+    caller.require_admin()?;
+    
+    let db = state.db.lock().await;
+    Ok(Json(db.get_schedules()?))
+}
+
+/// Change the priority of a task that is still waiting to be assigned
+async fn update_task_priority(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(task_id): Path<String>,
+    Json(req): Json<TaskPriorityRequest>,
+) -> Result<Json<TaskResponse>, ApiError> {
+    caller.require_admin()?;
+    
+    let db = state.db.lock().await;
+    
+    // Get the task
+    let mut task = db.get_task(&task_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Task {} not found", task_id)))?;
+    
+    // Only queued tasks can be reprioritized
+    if task.status != TaskStatus::Pending {
+        return Err(ApiError::BadRequest(format!("Task {} is no longer queued", task_id)));
+    }
+    
+    task.priority = req.priority;
+    db.update_task(&task)?;
+    
+    info!("Set priority of task {} to {}", task_id, req.priority);
+    
+    Ok(Json(TaskResponse::from(task)))
 }
 
 async fn assign_task(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Path(task_id): Path<String>,
     Json(req): Json<TaskAssignmentRequest>,
 ) -> Result<Json<TaskResponse>, ApiError> {
+    caller.require_client(&req.client_id)?;
+    
     let db = state.db.lock().await;
     
     // Get the task
@@ -271,41 +786,49 @@ async fn assign_task(
         return Err(ApiError::BadRequest(format!("Task {} is not available for assignment", task_id)));
     }
     
+    // Record that the crawler is alive
+    db.touch_crawler(&req.client_id)?;
+    
     // Assign the task
     task.assign(req.client_id);
     
     // Update in database
     db.update_task(&task)?;
+    METRICS.tasks_assigned.inc();
     
-    // Create response
-    let task_response = TaskResponse {
-        id: task.id,
-        target_url: task.target_url,
-        max_depth: task.max_depth,
-        follow_subdomains: task.follow_subdomains,
-        max_links: task.max_links,
-        created_at: task.created_at,
-        status: format!("{:?}", task.status),
-        incentive_amount: task.incentive_amount,
-    };
-    
-    Ok(Json(task_response))
+    Ok(Json(TaskResponse::from(task)))
 }
 
 /// Assign the next available task
 async fn assign_next_task(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Json(req): Json<TaskAssignmentRequest>,
 ) -> Result<Json<TaskResponse>, ApiError> {
+    caller.require_client(&req.client_id)?;
+    
+    if !state.assignment_limiter.check(&req.client_id) {
+        return Err(ApiError::TooManyRequests(format!("Too many task requests from client {}", req.client_id)));
+    }
+    
     let db = state.db.lock().await;
     
+    // Record that the crawler is alive
+    db.touch_crawler(&req.client_id)?;
+    
+    // Look up the crawler's declared capabilities
+    let crawler = db.get_crawler(&req.client_id)?;
+    
     // Get all pending tasks
     let tasks = db.get_pending_tasks()?;
     
-    // Find the first available task
-    let mut task = match tasks.into_iter().next() {
+    // Find the first task the crawler is able to handle
+    let mut task = match tasks.into_iter().find(|task| match &crawler {
+        Some(crawler) => crawler.can_handle(task),
+        None => true,
+    }) {
         Some(task) => task,
-        None => return Err(ApiError::NotFound("No tasks available for assignment".to_string())),
+        None => return Err(ApiError::NotFound(format!("No compatible tasks available for client {}", req.client_id))),
     };
     
     // Assign the task
@@ -313,65 +836,160 @@ async fn assign_next_task(
     
     // Update in database
     db.update_task(&task)?;
+    METRICS.tasks_assigned.inc();
     
-    // Create response
-    let task_response = TaskResponse {
-        id: task.id,
-        target_url: task.target_url,
-        max_depth: task.max_depth,
-        follow_subdomains: task.follow_subdomains,
-        max_links: task.max_links,
-        created_at: task.created_at,
-        status: format!("{:?}", task.status),
-        incentive_amount: task.incentive_amount,
-    };
+    Ok(Json(TaskResponse::from(task)))
+}
+
+/// Check that reported pages belong to the task's domain, allowing a small share of strays
+fn validate_report_domain(task: &Task, pages: &[PageSubmission]) -> Result<(), ApiError> {
+    let task_host = Url::parse(&task.target_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        .ok_or_else(|| ApiError::InternalError(format!("Task {} has an invalid target URL", task.id)))?;
+    let task_host = task_host.trim_start_matches("www.");
     
-    Ok(Json(task_response))
+    let off_domain: Vec<&str> = pages.iter()
+        .filter(|page| {
+            let host = Url::parse(&page.url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()));
+            
+            match host {
+                Some(host) => {
+                    let host = host.trim_start_matches("www.");
+                    let on_domain = host == task_host
+                        || (task.follow_subdomains && host.ends_with(&format!(".{}", task_host)));
+                    !on_domain
+                },
+                None => true,
+            }
+        })
+        .map(|page| page.url.as_str())
+        .collect();
+    
+    if !pages.is_empty() && off_domain.len() as f64 / pages.len() as f64 > OFF_DOMAIN_TOLERANCE {
+        return Err(ApiError::BadRequest(format!(
+            "{} of {} pages are outside the task domain {} (e.g. {})",
+            off_domain.len(),
+            pages.len(),
+            task_host,
+            off_domain[0]
+        )));
+    }
+    
+    Ok(())
 }
 
 async fn submit_report(
     State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Json(submission): Json<CrawlReportSubmission>,
 ) -> Result<Json<VerificationResult>, ApiError> {
+    caller.require_client(&submission.client_id)?;
+    
+    // Each report costs an LLM call and a blockchain transaction
+    if !state.report_limiter.check(&submission.client_id) {
+        return Err(ApiError::TooManyRequests(format!("Too many reports from client {}", submission.client_id)));
+    }
+    
     // Get task
     let db = state.db.lock().await;
     let mut task = db.get_task(&submission.task_id)?
         .ok_or_else(|| ApiError::NotFound(format!("Task not found: {}", submission.task_id)))?;
     
+    if task.assigned_to.as_deref() != Some(submission.client_id.as_str()) {
+        return Err(ApiError::Forbidden(format!("Task {} is not assigned to client {}", submission.task_id, submission.client_id)));
+    }
+    
+    // A resubmitted report gets the verification it already has, so it is never paid for twice
+    if db.report_is_partial(&submission.task_id)? == Some(false) {
+        let stored = db.get_report_verification(&submission.task_id)?
+            .ok_or_else(|| ApiError::BadRequest(format!("Report for task {} is still being verified", submission.task_id)))?;
+        info!("Report for task {} was already submitted, returning its verification", submission.task_id);
+        
+        return Ok(Json(VerificationResult {
+            task_id: submission.task_id,
+            verified: stored.verified,
+            score: stored.score,
+            notes: stored.notes,
+            transaction_hash: stored.transaction_hash,
+            incentive_amount: stored.incentive_paid,
+        }));
+    }
+    
+    // Only a task still being crawled can be reported on
+    if !matches!(task.status, TaskStatus::Assigned | TaskStatus::InProgress) {
+        return Err(ApiError::BadRequest(format!("Task {} is {:?}, not awaiting a report", submission.task_id, task.status)));
+    }
+    
+    // Make sure the pages were actually crawled from the task's domain
+    validate_report_domain(&task, &submission.pages)?;
+    
     // Create report
-    let report = CrawlReport {
+    let mut report = CrawlReport {
         task_id: submission.task_id.clone(),
         client_id: submission.client_id.clone(),
         domain: submission.domain.clone(),
-        pages_count: submission.pages.len(),
-        total_size: submission.pages.iter().map(|p| p.size).sum(),
-        pages: submission.pages.iter().map(|p| crate::models::CrawledPage {
-            url: p.url.clone(),
-            status: p.status,
-            content_type: p.content_type.clone(),
-            size: p.size,
-            timestamp: p.timestamp,
-        }).collect(),
+        pages_count: 0,
+        total_size: 0,
+        pages: Vec::new(),
         start_time: submission.start_time,
         end_time: Some(submission.end_time),
         verified: false,
         verification_score: None,
         verification_notes: None,
+        partial: false,
     };
     
-    // Save report
+    // Pages sent in partial reports while the crawl ran count towards the final report
+    if let Some(partial) = db.get_report_by_task(&submission.task_id)? {
+        if partial.partial {
+            info!("Finalizing partial report for task {} ({} pages so far)", submission.task_id, partial.pages_count);
+            report.start_time = report.start_time.min(partial.start_time);
+            report.add_pages(partial.pages);
+        }
+    }
+    report.add_pages(submission.pages.iter().map(Into::into));
+    
+    // Save report, replacing the partial one
     db.save_report(&report)?;
     
-    // Mark task as completed
+    // Mark task as completed, so a second submission can't start another verification
     task.complete();
     db.update_task(&task)?;
+    METRICS.tasks_completed.inc();
+    
+    // Verification calls the LLM and fetches pages, so other requests may use the database meanwhile
+    drop(db);
     
     // Verify the report
+    let verification_timer = METRICS.verification_seconds.start_timer();
     let evaluator = state.evaluator.clone();
-    let (verified, score, notes) = evaluator.verify_report(&report).await?;
+    let (mut verified, mut score, mut notes) = evaluator.verify_report(&report).await?;
     
-    // Update verification status
-    db.update_report_verification(&submission.task_id, verified, Some(score), Some(notes.clone()))?;
+    // Re-fetch a few of the reported URLs to catch fabricated pages
+    let spot_check_size = state.config.evaluator.spot_check_size;
+    if spot_check_size > 0 {
+        let spot_check = evaluator.spot_check(&task, &report, spot_check_size).await;
+        if let Some(spot_score) = spot_check.score() {
+            score = (1.0 - SPOT_CHECK_WEIGHT) * score + SPOT_CHECK_WEIGHT * spot_score;
+            if spot_score < 0.5 {
+                verified = false;
+            }
+            
+            if !spot_check.mismatches.is_empty() {
+                notes = format!("{}; spot check mismatches: {}", notes, spot_check.mismatches.join(", "));
+            }
+        }
+    }
+    
+    verification_timer.observe_duration();
+    if verified {
+        METRICS.reports_verified.inc();
+    } else {
+        METRICS.reports_rejected.inc();
+    }
     
     // Record verification on blockchain
     let solana = state.solana.clone();
@@ -382,15 +1000,33 @@ async fn submit_report(
         score,
     )?;
     
-    // If verified, transfer incentives
+    // If verified, transfer incentives scaled to the crawl's size
     let incentive_amount = if verified {
-        let incentive = task.incentive_amount;
+        let incentive = state.config.incentives.payout(task.incentive_amount, &report.pages);
+        info!("Computed incentive of {} for task {} ({} pages, base {})",
+              incentive, submission.task_id, report.pages_count, task.incentive_amount);
         solana.transfer_incentives(&submission.client_id, incentive)?;
         Some(incentive)
     } else {
         None
     };
     
+    // Update verification status
+    let db = state.db.lock().await;
+    db.update_report_verification(&submission.task_id, verified, Some(score), Some(notes.clone()), &tx_hash)?;
+    if let Some(incentive) = incentive_amount {
+        db.record_incentive_paid(&submission.task_id, incentive)?;
+    }
+    drop(db);
+    
+    // Let whoever is waiting on the crawl know, without holding up the response
+    state.webhooks.notify(task.webhook_url.as_deref(), VerificationEvent {
+        task_id: submission.task_id.clone(),
+        verified,
+        score,
+        incentive_amount,
+    });
+    
     // Create response
     let result = VerificationResult {
         task_id: submission.task_id,
@@ -404,6 +1040,58 @@ async fn submit_report(
     Ok(Json(result))
 }
 
+/// Append a batch of pages to the report of a task that is still being crawled,
+/// so the work isn't lost if the crawl is interrupted
+async fn submit_partial_report(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(submission): Json<PartialReportSubmission>,
+) -> Result<Json<PartialReportResponse>, ApiError> {
+    caller.require_client(&submission.client_id)?;
+    
+    let db = state.db.lock().await;
+    let task = db.get_task(&submission.task_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Task not found: {}", submission.task_id)))?;
+    
+    if task.assigned_to.as_deref() != Some(submission.client_id.as_str()) {
+        return Err(ApiError::Forbidden(format!("Task {} is not assigned to client {}", submission.task_id, submission.client_id)));
+    }
+    
+    if db.report_is_partial(&submission.task_id)? == Some(false) {
+        return Err(ApiError::BadRequest(format!("Report for task {} is already complete", submission.task_id)));
+    }
+    
+    validate_report_domain(&task, &submission.pages)?;
+    
+    let pages_count = db.append_partial_report(
+        &submission.task_id,
+        &submission.client_id,
+        &submission.domain,
+        submission.start_time,
+        submission.pages.iter().map(Into::into).collect(),
+    )?;
+    
+    debug!("Partial report for task {} now has {} pages", submission.task_id, pages_count);
+    
+    Ok(Json(PartialReportResponse {
+        task_id: submission.task_id,
+        pages_count,
+    }))
+}
+
+/// List reports, optionally filtered by their task's status
+async fn get_reports(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let status = query.status()?;
+    
+    let db = state.db.lock().await;
+    let (reports, total) = db.get_reports_filtered(status.as_ref(), query.limit(), query.offset.unwrap_or(0))?;
+    
+    Ok(([(HeaderName::from_static(TOTAL_COUNT_HEADER), total.to_string())], Json(reports)))
+}
+
 async fn get_report(
     State(state): State<Arc<AppState>>,
     Path(task_id): Path<String>,
@@ -417,16 +1105,51 @@ async fn get_report(
     Ok(Json(report))
 }
 
+/// List a report's crawled pages a page at a time, for reports too big to fetch whole
+async fn get_report_pages(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = state.db.lock().await;
+    let (pages, total) = db.get_report_pages(&task_id, query.limit(), query.offset.unwrap_or(0))?
+        .ok_or_else(|| ApiError::NotFound(format!("Report for task {} not found", task_id)))?;
+    
+    Ok(([(HeaderName::from_static(TOTAL_COUNT_HEADER), total.to_string())], Json(pages)))
+}
+
+/// Totals across every task and report
+async fn get_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Stats>, ApiError> {
+    let db = state.db.lock().await;
+    Ok(Json(db.aggregate_stats()?))
+}
+
 async fn get_api_docs(
     State(state): State<Arc<AppState>>,
     Path(package): Path<String>,
+    Query(query): Query<ApiDocsQuery>,
 ) -> Result<Json<ApiDocResponse>, ApiError> {
+    let language = match query.language.as_deref() {
+        Some(language) => language.parse::<DocLanguage>()
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?,
+        None => DocLanguage::default(),
+    };
+    
     // Get API documentation
     let evaluator = state.evaluator.clone();
-    let docs = evaluator.get_api_documentation(&package).await?;
+    let (version, docs) = evaluator.get_api_documentation(
+        &package,
+        language,
+        query.version.as_deref(),
+        query.refresh.unwrap_or(false),
+    ).await?;
     
     let response = ApiDocResponse {
         package,
+        version,
+        language: language.to_string(),
         documentation: docs,
     };
     
@@ -435,16 +1158,110 @@ async fn get_api_docs(
 
 /// Handle crawler registration
 async fn register_crawler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
     Json(request): Json<serde_json::Value>,
 ) -> Result<impl IntoResponse, ApiError> {
+    caller.require_admin()?;
+    
     let client_id = request.get("client_id")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ApiError::BadRequest("Missing client_id".to_string()))?;
     
-    // In a real implementation, we'd store the crawler in the database
-    // For now, we'll just log it
+    let capabilities = request.get("capabilities")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    
+    // Persist the registration and issue a fresh token for the crawler
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let db = state.db.lock().await;
+    db.upsert_crawler(client_id, &capabilities)?;
+    db.set_crawler_token(client_id, &hash_token(&token))?;
+    
     info!("Registered crawler with client ID: {}", client_id);
     
-    Ok(StatusCode::OK)
-} 
\ No newline at end of file
+    Ok(Json(RegistrationResponse {
+        client_id: client_id.to_string(),
+        token,
+    }))
+}
+
+/// Record that a crawler is alive and still working on its tasks
+async fn crawler_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(client_id): Path<String>,
+    Json(heartbeat): Json<HeartbeatRequest>,
+) -> Result<StatusCode, ApiError> {
+    caller.require_client(&client_id)?;
+    
+    let db = state.db.lock().await;
+    db.touch_crawler(&client_id)?;
+    
+    for progress in &heartbeat.tasks {
+        if db.record_task_heartbeat(&progress.task_id, &client_id)? {
+            debug!("Crawler {} has crawled {} pages of task {}", client_id, progress.pages_crawled, progress.task_id);
+        } else {
+            warn!("Crawler {} sent a heartbeat for task {} which is not assigned to it", client_id, progress.task_id);
+        }
+    }
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List registered crawlers
+async fn get_crawlers(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<CrawlerInfo>>, ApiError> {
+    caller.require_admin()?;
+    
+    let db = state.db.lock().await;
+    let crawlers = db.get_crawlers()?;
+    
+    Ok(Json(crawlers))
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    
+    #[tokio::test]
+    async fn not_found_is_returned_as_json() {
+        let response = ApiError::NotFound("Task 42 not found".to_string()).into_response();
+        
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], "application/json");
+        
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "error": {
+                "type": "not_found",
+                "message": "Task 42 not found",
+            }
+        }));
+    }
+    
+    #[test]
+    fn task_requests_are_validated_before_creation() {
+        let request = |target_url: &str| TaskRequest {
+            target_url: target_url.to_string(),
+            max_depth: 2,
+            follow_subdomains: false,
+            max_links: None,
+            incentive_amount: None,
+            priority: Some(3),
+            webhook_url: None,
+        };
+        
+        let task = request("https://example.com").into_task().unwrap();
+        assert_eq!(task.target_url, "https://example.com");
+        assert_eq!(task.incentive_amount, 25_000_000);
+        assert_eq!(task.priority, 3);
+        
+        let error = request("not a url").into_task().unwrap_err();
+        assert!(error.starts_with("Invalid URL"), "{}", error);
+    }
+}
@@ -1,10 +1,25 @@
-use crate::models::{Task, TaskStatus, CrawlReport, CrawledPage};
+use crate::models::{Task, TaskStatus, TaskProgress, TaskTemplate, CrawlReport, CrawledPage, CrawlerInfo, ReportVerification, Schedule, Stats};
 use anyhow::{anyhow, Result, Context};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use log::info;
 
+/// Columns selected for every task query, in the order `task_from_row` expects
+const TASK_COLUMNS: &str = "id, target_url, max_depth, follow_subdomains, max_links, \
+    created_at, assigned_at, completed_at, status, assigned_to, incentive_amount, priority, webhook_url";
+
+/// Columns selected for every report query, in the order `report_from_row` expects
+const REPORT_COLUMNS: &str = "task_id, client_id, domain, pages_count, total_size, reports.id, \
+    start_time, end_time, verified, verification_score, verification_notes, partial";
+
+/// Columns selected for every report page query, in the order `report_page_from_row` expects
+const REPORT_PAGE_COLUMNS: &str = "url, status, content_type, size, timestamp";
+
+/// Columns selected for every schedule query, in the order `schedule_from_row` expects
+const SCHEDULE_COLUMNS: &str = "id, cron, timezone, template, next_run, last_run, last_task_id, created_at";
+
 /// Manages the database for the manager
 #[derive(Debug)]
 pub struct Database {
@@ -79,11 +94,19 @@ impl Database {
                 completed_at INTEGER,
                 status TEXT NOT NULL,
                 assigned_to TEXT,
-                incentive_amount INTEGER NOT NULL
+                incentive_amount INTEGER NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 0,
+                heartbeat_at INTEGER,
+                webhook_url TEXT
             )",
             [],
         ).context("Failed to create tasks table")?;
         
+        // Databases created before task priorities existed lack the column
+        self.add_column_if_missing("tasks", "priority", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("tasks", "heartbeat_at", "INTEGER")?;
+        self.add_column_if_missing("tasks", "webhook_url", "TEXT")?;
+        
         // Create reports table
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS reports (
@@ -104,17 +127,176 @@ impl Database {
             [],
         ).context("Failed to create reports table")?;
         
+        self.add_column_if_missing("reports", "partial", "INTEGER NOT NULL DEFAULT 0")?;
+        self.add_column_if_missing("reports", "incentive_paid", "INTEGER")?;
+        self.add_column_if_missing("reports", "verification_tx", "TEXT")?;
+        
+        // Create report pages table; a report's pages are kept in insertion order
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS report_pages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                report_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                status INTEGER,
+                content_type TEXT,
+                size INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                FOREIGN KEY (report_id) REFERENCES reports(id)
+            )",
+            [],
+        ).context("Failed to create report_pages table")?;
+        
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_report_pages_report ON report_pages (report_id)",
+            [],
+        ).context("Failed to create report_pages index")?;
+        
+        // Partial reports look pages up by URL when a batch replaces them
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_report_pages_report_url ON report_pages (report_id, url)",
+            [],
+        ).context("Failed to create report_pages URL index")?;
+        
+        self.migrate_report_pages()?;
+        
+        // Create task progress table
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_progress (
+                task_id TEXT PRIMARY KEY,
+                client_id TEXT NOT NULL,
+                pages_crawled INTEGER NOT NULL,
+                bytes INTEGER NOT NULL,
+                elapsed_ms INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (task_id) REFERENCES tasks(id)
+            )",
+            [],
+        ).context("Failed to create task_progress table")?;
+        
+        // Create crawlers table
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS crawlers (
+                client_id TEXT PRIMARY KEY,
+                capabilities TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                token_hash TEXT
+            )",
+            [],
+        ).context("Failed to create crawlers table")?;
+        
+        self.add_column_if_missing("crawlers", "token_hash", "TEXT")?;
+        
+        // Create schedules table; the task template is stored as JSON
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS schedules (
+                id TEXT PRIMARY KEY,
+                cron TEXT NOT NULL,
+                timezone TEXT NOT NULL,
+                template TEXT NOT NULL,
+                next_run INTEGER NOT NULL,
+                last_run INTEGER,
+                last_task_id TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        ).context("Failed to create schedules table")?;
+        
         info!("Database tables initialized successfully");
         Ok(())
     }
     
+    /// Move pages still serialized in `reports.pages` by databases created before
+    /// the `report_pages` table existed into it
+    fn migrate_report_pages(&mut self) -> Result<()> {
+        let legacy: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare("SELECT id, pages FROM reports WHERE pages != '[]'")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        if legacy.is_empty() {
+            return Ok(());
+        }
+        
+        info!("Moving the pages of {} reports into the report_pages table", legacy.len());
+        let tx = self.conn.transaction()?;
+        for (report_id, pages_json) in legacy {
+            let pages: Vec<CrawledPage> = serde_json::from_str(&pages_json)
+                .with_context(|| format!("Failed to parse the pages of report {}", report_id))?;
+            Self::insert_report_pages(&tx, report_id, &pages)?;
+            tx.execute("UPDATE reports SET pages = '[]' WHERE id = ?", params![report_id])?;
+        }
+        tx.commit().context("Failed to migrate report pages")?;
+        
+        Ok(())
+    }
+    
+    /// Add a column to an existing table unless it is already present
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        
+        if !exists {
+            info!("Adding column {}.{}", table, column);
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            ).with_context(|| format!("Failed to add column {}.{}", table, column))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Build a task from a row selected with `TASK_COLUMNS`
+    fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+        let status_str: String = row.get(8)?;
+        let status = status_str.parse().unwrap_or(TaskStatus::Pending);
+        
+        Ok(Task {
+            id: row.get(0)?,
+            target_url: row.get(1)?,
+            max_depth: row.get(2)?,
+            follow_subdomains: row.get::<_, i32>(3)? != 0,
+            max_links: row.get(4)?,
+            created_at: row.get(5)?,
+            assigned_at: row.get(6)?,
+            completed_at: row.get(7)?,
+            status,
+            assigned_to: row.get(9)?,
+            incentive_amount: row.get(10)?,
+            priority: row.get(11)?,
+            webhook_url: row.get(12)?,
+        })
+    }
+    
     /// Create a new task
     pub fn create_task(&self, task: &Task) -> Result<()> {
-        self.conn.execute(
+        Self::insert_task(&self.conn, task)
+    }
+    
+    /// Create several tasks in one transaction, so either all of them are queued or none
+    pub fn create_tasks(&self, tasks: &[Task]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for task in tasks {
+            Self::insert_task(&tx, task)?;
+        }
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    /// Insert one task row
+    fn insert_task(conn: &Connection, task: &Task) -> Result<()> {
+        conn.prepare_cached(
             "INSERT INTO tasks (
                 id, target_url, max_depth, follow_subdomains, max_links,
-                created_at, assigned_at, completed_at, status, assigned_to, incentive_amount
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                created_at, assigned_at, completed_at, status, assigned_to, incentive_amount,
+                priority, webhook_url
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )?.execute(
             params![
                 task.id,
                 task.target_url,
@@ -127,6 +309,8 @@ impl Database {
                 format!("{:?}", task.status),
                 task.assigned_to,
                 task.incentive_amount,
+                task.priority,
+                task.webhook_url,
             ],
         )?;
         
@@ -136,40 +320,10 @@ impl Database {
     /// Get a task by ID
     pub fn get_task(&self, task_id: &str) -> Result<Option<Task>> {
         let mut stmt = self.conn.prepare(
-            "SELECT 
-                id, target_url, max_depth, follow_subdomains, max_links,
-                created_at, assigned_at, completed_at, status, assigned_to, incentive_amount
-            FROM tasks
-            WHERE id = ?"
+            &format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS)
         )?;
         
-        let task_result = stmt.query_row(params![task_id], |row| {
-            let status_str: String = row.get(8)?;
-            let status = match status_str.as_str() {
-                "Pending" => TaskStatus::Pending,
-                "Assigned" => TaskStatus::Assigned,
-                "InProgress" => TaskStatus::InProgress,
-                "Completed" => TaskStatus::Completed,
-                "Failed" => TaskStatus::Failed,
-                "Verified" => TaskStatus::Verified,
-                "Rejected" => TaskStatus::Rejected,
-                _ => TaskStatus::Pending,
-            };
-            
-            Ok(Task {
-                id: row.get(0)?,
-                target_url: row.get(1)?,
-                max_depth: row.get(2)?,
-                follow_subdomains: row.get::<_, i32>(3)? != 0,
-                max_links: row.get(4)?,
-                created_at: row.get(5)?,
-                assigned_at: row.get(6)?,
-                completed_at: row.get(7)?,
-                status,
-                assigned_to: row.get(9)?,
-                incentive_amount: row.get(10)?,
-            })
-        });
+        let task_result = stmt.query_row(params![task_id], Self::task_from_row);
         
         match task_result {
             Ok(task) => Ok(Some(task)),
@@ -190,7 +344,9 @@ impl Database {
                 completed_at = ?,
                 status = ?,
                 assigned_to = ?,
-                incentive_amount = ?
+                incentive_amount = ?,
+                priority = ?,
+                webhook_url = ?
             WHERE id = ?",
             params![
                 task.target_url,
@@ -202,6 +358,8 @@ impl Database {
                 format!("{:?}", task.status),
                 task.assigned_to,
                 task.incentive_amount,
+                task.priority,
+                task.webhook_url,
                 task.id,
             ],
         )?;
@@ -209,43 +367,18 @@ impl Database {
         Ok(())
     }
     
-    /// Get all pending tasks
+    /// Get all pending tasks, highest priority first and oldest first within a priority
     pub fn get_pending_tasks(&self) -> Result<Vec<Task>> {
         let mut stmt = self.conn.prepare(
-            "SELECT 
-                id, target_url, max_depth, follow_subdomains, max_links,
-                created_at, assigned_at, completed_at, status, assigned_to, incentive_amount
-            FROM tasks
-            WHERE status = 'Pending'"
+            &format!(
+                "SELECT {} FROM tasks
+                WHERE status = 'Pending'
+                ORDER BY priority DESC, created_at ASC",
+                TASK_COLUMNS
+            )
         )?;
         
-        let task_iter = stmt.query_map([], |row| {
-            let status_str: String = row.get(8)?;
-            let status = match status_str.as_str() {
-                "Pending" => TaskStatus::Pending,
-                "Assigned" => TaskStatus::Assigned,
-                "InProgress" => TaskStatus::InProgress,
-                "Completed" => TaskStatus::Completed,
-                "Failed" => TaskStatus::Failed,
-                "Verified" => TaskStatus::Verified,
-                "Rejected" => TaskStatus::Rejected,
-                _ => TaskStatus::Pending,
-            };
-            
-            Ok(Task {
-                id: row.get(0)?,
-                target_url: row.get(1)?,
-                max_depth: row.get(2)?,
-                follow_subdomains: row.get::<_, i32>(3)? != 0,
-                max_links: row.get(4)?,
-                created_at: row.get(5)?,
-                assigned_at: row.get(6)?,
-                completed_at: row.get(7)?,
-                status,
-                assigned_to: row.get(9)?,
-                incentive_amount: row.get(10)?,
-            })
-        })?;
+        let task_iter = stmt.query_map([], Self::task_from_row)?;
         
         let mut tasks = Vec::new();
         for task in task_iter {
@@ -255,104 +388,759 @@ impl Database {
         Ok(tasks)
     }
     
-    /// Save a crawl report
-    pub fn save_report(&self, report: &CrawlReport) -> Result<i64> {
-        // Serialize pages to JSON
-        let pages_json = serde_json::to_string(&report.pages)?;
+    /// Get a page of tasks, highest priority first and oldest first within a priority,
+    /// optionally filtered by status.
+    /// Returns the page along with the total number of matching tasks.
+    pub fn get_tasks_filtered(&self, status: Option<&TaskStatus>, limit: u32, offset: u32) -> Result<(Vec<Task>, usize)> {
+        let status = status.map(|s| format!("{:?}", s));
+        
+        let total: usize = self.conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE ?1 IS NULL OR status = ?1",
+            params![status],
+            |row| row.get(0),
+        )?;
+        
+        let mut stmt = self.conn.prepare(
+            &format!(
+                "SELECT {} FROM tasks
+                WHERE ?1 IS NULL OR status = ?1
+                ORDER BY priority DESC, created_at ASC
+                LIMIT ?2 OFFSET ?3",
+                TASK_COLUMNS
+            )
+        )?;
+        
+        let task_iter = stmt.query_map(params![status, limit, offset], Self::task_from_row)?;
+        
+        let mut tasks = Vec::new();
+        for task in task_iter {
+            tasks.push(task?);
+        }
+        
+        Ok((tasks, total))
+    }
+    
+    /// Get tasks with no assignment or heartbeat within `max_age` that were never reported back
+    pub fn get_stale_assignments(&self, max_age: Duration) -> Result<Vec<Task>> {
+        let cutoff = Self::stale_cutoff(max_age);
+        
+        let mut stmt = self.conn.prepare(
+            &format!(
+                "SELECT {} FROM tasks
+                WHERE status IN ('Assigned', 'InProgress')
+                  AND assigned_at IS NOT NULL
+                  AND COALESCE(heartbeat_at, assigned_at) < ?
+                  AND NOT EXISTS (SELECT 1 FROM reports WHERE reports.task_id = tasks.id)",
+                TASK_COLUMNS
+            )
+        )?;
+        
+        let task_iter = stmt.query_map(params![cutoff], Self::task_from_row)?;
+        
+        let mut tasks = Vec::new();
+        for task in task_iter {
+            tasks.push(task?);
+        }
+        
+        Ok(tasks)
+    }
+    
+    /// Return stale assignments to the pending pool, returning how many were reclaimed
+    pub fn reclaim_stale_assignments(&self, max_age: Duration) -> Result<usize> {
+        let cutoff = Self::stale_cutoff(max_age);
+        
+        let reclaimed = self.conn.execute(
+            "UPDATE tasks SET
+                status = 'Pending',
+                assigned_to = NULL,
+                assigned_at = NULL,
+                heartbeat_at = NULL
+            WHERE status IN ('Assigned', 'InProgress')
+              AND assigned_at IS NOT NULL
+              AND COALESCE(heartbeat_at, assigned_at) < ?
+              AND NOT EXISTS (SELECT 1 FROM reports WHERE reports.task_id = tasks.id)",
+            params![cutoff],
+        ).context("Failed to reclaim stale task assignments")?;
+        
+        Ok(reclaimed)
+    }
+    
+    /// Record a heartbeat for a task the crawler is working on, marking it in progress.
+    /// Returns false if the task is not currently assigned to the crawler.
+    pub fn record_task_heartbeat(&self, task_id: &str, client_id: &str) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE tasks SET
+                heartbeat_at = ?,
+                status = 'InProgress'
+            WHERE id = ?
+              AND assigned_to = ?
+              AND status IN ('Assigned', 'InProgress')",
+            params![Self::now(), task_id, client_id],
+        ).with_context(|| format!("Failed to record heartbeat for task {}", task_id))?;
+        
+        Ok(updated > 0)
+    }
+    
+    /// Store the latest progress reported for a task
+    pub fn save_task_progress(&self, progress: &TaskProgress) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO task_progress (
+                task_id, client_id, pages_crawled, bytes, elapsed_ms, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                progress.task_id,
+                progress.client_id,
+                progress.pages_crawled,
+                progress.bytes,
+                progress.elapsed_ms,
+                progress.updated_at,
+            ],
+        ).with_context(|| format!("Failed to save progress for task {}", progress.task_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get the latest progress reported for a task
+    pub fn get_task_progress(&self, task_id: &str) -> Result<Option<TaskProgress>> {
+        let result = self.conn.query_row(
+            "SELECT task_id, client_id, pages_crawled, bytes, elapsed_ms, updated_at
+             FROM task_progress WHERE task_id = ?",
+            params![task_id],
+            |row| Ok(TaskProgress {
+                task_id: row.get(0)?,
+                client_id: row.get(1)?,
+                pages_crawled: row.get(2)?,
+                bytes: row.get(3)?,
+                elapsed_ms: row.get(4)?,
+                updated_at: row.get(5)?,
+            }),
+        );
+        
+        match result {
+            Ok(progress) => Ok(Some(progress)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+    
+    /// Register a crawler, or refresh its capabilities and last-seen time if already known
+    pub fn upsert_crawler(&self, client_id: &str, capabilities: &serde_json::Value) -> Result<()> {
+        let now = Self::now();
+        
+        self.conn.execute(
+            "INSERT INTO crawlers (client_id, capabilities, first_seen, last_seen)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(client_id) DO UPDATE SET
+                capabilities = excluded.capabilities,
+                last_seen = excluded.last_seen",
+            params![client_id, capabilities.to_string(), now, now],
+        ).with_context(|| format!("Failed to register crawler {}", client_id))?;
+        
+        Ok(())
+    }
+    
+    /// Update a crawler's last-seen time, registering it with no capabilities if unknown
+    pub fn touch_crawler(&self, client_id: &str) -> Result<()> {
+        let now = Self::now();
+        
+        self.conn.execute(
+            "INSERT INTO crawlers (client_id, capabilities, first_seen, last_seen)
+             VALUES (?, '{}', ?, ?)
+             ON CONFLICT(client_id) DO UPDATE SET last_seen = excluded.last_seen",
+            params![client_id, now, now],
+        ).with_context(|| format!("Failed to update last_seen for crawler {}", client_id))?;
+        
+        Ok(())
+    }
+    
+    /// Store the hash of a crawler's API token, replacing any previous token
+    pub fn set_crawler_token(&self, client_id: &str, token_hash: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE crawlers SET token_hash = ? WHERE client_id = ?",
+            params![token_hash, client_id],
+        ).with_context(|| format!("Failed to store token for crawler {}", client_id))?;
+        
+        Ok(())
+    }
+    
+    /// Find the crawler that owns a token hash
+    pub fn get_client_for_token(&self, token_hash: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT client_id FROM crawlers WHERE token_hash = ?",
+            params![token_hash],
+            |row| row.get(0),
+        );
+        
+        match result {
+            Ok(client_id) => Ok(Some(client_id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+    
+    /// Get a registered crawler by client ID
+    pub fn get_crawler(&self, client_id: &str) -> Result<Option<CrawlerInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT client_id, capabilities, first_seen, last_seen
+             FROM crawlers
+             WHERE client_id = ?"
+        )?;
+        
+        let mut rows = stmt.query(params![client_id])?;
+        
+        if let Some(row) = rows.next()? {
+            let capabilities: String = row.get(1)?;
+            Ok(Some(CrawlerInfo {
+                client_id: row.get(0)?,
+                capabilities: serde_json::from_str(&capabilities).unwrap_or_default(),
+                first_seen: row.get(2)?,
+                last_seen: row.get(3)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Get all registered crawlers, most recently seen first
+    pub fn get_crawlers(&self) -> Result<Vec<CrawlerInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT client_id, capabilities, first_seen, last_seen
+             FROM crawlers
+             ORDER BY last_seen DESC"
+        )?;
         
+        let rows = stmt.query_map([], |row| {
+            let capabilities: String = row.get(1)?;
+            Ok((row.get::<_, String>(0)?, capabilities, row.get::<_, u64>(2)?, row.get::<_, u64>(3)?))
+        })?;
+        
+        let mut crawlers = Vec::new();
+        for row in rows {
+            let (client_id, capabilities, first_seen, last_seen) = row?;
+            crawlers.push(CrawlerInfo {
+                client_id,
+                capabilities: serde_json::from_str(&capabilities).unwrap_or_default(),
+                first_seen,
+                last_seen,
+            });
+        }
+        
+        Ok(crawlers)
+    }
+    
+    /// Build a `Schedule` from a row selected with `SCHEDULE_COLUMNS`
+    fn schedule_from_row(row: &rusqlite::Row) -> rusqlite::Result<Schedule> {
+        let template: String = row.get(3)?;
+        let template: TaskTemplate = serde_json::from_str(&template)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?;
+        
+        Ok(Schedule {
+            id: row.get(0)?,
+            cron: row.get(1)?,
+            timezone: row.get(2)?,
+            template,
+            next_run: row.get(4)?,
+            last_run: row.get(5)?,
+            last_task_id: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    }
+    
+    /// Create a new schedule
+    pub fn create_schedule(&self, schedule: &Schedule) -> Result<()> {
         self.conn.execute(
+            "INSERT INTO schedules (id, cron, timezone, template, next_run, last_run, last_task_id, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                schedule.id,
+                schedule.cron,
+                schedule.timezone,
+                serde_json::to_string(&schedule.template)?,
+                schedule.next_run,
+                schedule.last_run,
+                schedule.last_task_id,
+                schedule.created_at,
+            ],
+        ).with_context(|| format!("Failed to create schedule {}", schedule.id))?;
+        
+        Ok(())
+    }
+    
+    /// Get all schedules, the next one due first
+    pub fn get_schedules(&self) -> Result<Vec<Schedule>> {
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT {} FROM schedules ORDER BY next_run, id", SCHEDULE_COLUMNS)
+        )?;
+        let schedules = stmt.query_map([], Self::schedule_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        
+        Ok(schedules)
+    }
+    
+    /// Get the schedules whose next run is at or before `now`
+    pub fn get_due_schedules(&self, now: u64) -> Result<Vec<Schedule>> {
+        let mut stmt = self.conn.prepare(
+            &format!("SELECT {} FROM schedules WHERE next_run <= ? ORDER BY next_run, id", SCHEDULE_COLUMNS)
+        )?;
+        let schedules = stmt.query_map(params![now], Self::schedule_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        
+        Ok(schedules)
+    }
+    
+    /// Queue the task a schedule created and move the schedule to its next run, in one
+    /// transaction, so a crash between the two can't create the task twice
+    pub fn record_schedule_run(&self, schedule_id: &str, task: &Task, ran_at: u64, next_run: u64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        Self::insert_task(&tx, task)?;
+        tx.execute(
+            "UPDATE schedules SET next_run = ?, last_run = ?, last_task_id = ? WHERE id = ?",
+            params![next_run, ran_at, task.id, schedule_id],
+        ).with_context(|| format!("Failed to update schedule {}", schedule_id))?;
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    /// Current Unix timestamp in seconds
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+    
+    /// Unix timestamp before which an assignment counts as stale
+    fn stale_cutoff(max_age: Duration) -> u64 {
+        Self::now().saturating_sub(max_age.as_secs())
+    }
+    
+    /// Save a crawl report, replacing the task's partial report if there is one.
+    /// The summary goes to `reports` and each page to `report_pages`, in one transaction.
+    pub fn save_report(&self, report: &CrawlReport) -> Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        tx.execute(
+            "DELETE FROM report_pages WHERE report_id IN
+                (SELECT id FROM reports WHERE task_id = ? AND partial = 1)",
+            params![report.task_id],
+        )?;
+        tx.execute(
+            "DELETE FROM reports WHERE task_id = ? AND partial = 1",
+            params![report.task_id],
+        )?;
+        
+        tx.execute(
             "INSERT INTO reports (
                 task_id, client_id, domain, pages_count, total_size,
-                pages, start_time, end_time, verified, verification_score, verification_notes
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                pages, start_time, end_time, verified, verification_score, verification_notes, partial
+            ) VALUES (?, ?, ?, ?, ?, '[]', ?, ?, ?, ?, ?, ?)",
             params![
                 report.task_id,
                 report.client_id,
                 report.domain,
                 report.pages_count,
                 report.total_size,
-                pages_json,
                 report.start_time,
                 report.end_time,
                 if report.verified { 1 } else { 0 },
                 report.verification_score,
                 report.verification_notes,
+                if report.partial { 1 } else { 0 },
             ],
         )?;
+        let report_id = tx.last_insert_rowid();
+        
+        Self::insert_report_pages(&tx, report_id, &report.pages)?;
+        tx.commit().context("Failed to save report")?;
+        
+        Ok(report_id)
+    }
+    
+    /// Write a report's pages to `report_pages`
+    fn insert_report_pages(conn: &Connection, report_id: i64, pages: &[CrawledPage]) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "INSERT INTO report_pages (report_id, url, status, content_type, size, timestamp)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )?;
+        for page in pages {
+            stmt.execute(params![
+                report_id,
+                page.url,
+                page.status,
+                page.content_type,
+                page.size as i64,
+                page.timestamp as i64,
+            ])?;
+        }
         
-        Ok(self.conn.last_insert_rowid())
+        Ok(())
+    }
+    
+    /// Whether the report of a task is still partial, or `None` if it has no report yet
+    pub fn report_is_partial(&self, task_id: &str) -> Result<Option<bool>> {
+        let partial = self.conn.query_row(
+            "SELECT partial FROM reports WHERE task_id = ?",
+            params![task_id],
+            |row| row.get(0),
+        ).optional()?;
+        
+        Ok(partial)
+    }
+    
+    /// Append a batch of pages to the partial report of a task, starting one on the first batch.
+    /// Only the batch is written: a page replaces the row of an earlier one with the same URL,
+    /// and the summary is recounted from `report_pages`, all in one transaction.
+    /// Returns the number of pages now in the report.
+    pub fn append_partial_report(&self, task_id: &str, client_id: &str, domain: &str, start_time: u64, pages: Vec<CrawledPage>) -> Result<usize> {
+        let now = Self::now();
+        let tx = self.conn.unchecked_transaction()?;
+        
+        let existing: Option<(i64, bool)> = tx.query_row(
+            "SELECT id, partial FROM reports WHERE task_id = ?",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        
+        let report_id = match existing {
+            Some((_, false)) => {
+                return Err(anyhow!("Report for task {} is already complete", task_id));
+            },
+            Some((report_id, true)) => report_id,
+            None => {
+                tx.execute(
+                    "INSERT INTO reports (task_id, client_id, domain, pages_count, total_size, pages, start_time, end_time, verified, partial)
+                     VALUES (?, ?, ?, 0, 0, '[]', ?, ?, 0, 1)",
+                    params![task_id, client_id, domain, start_time, now],
+                )?;
+                tx.last_insert_rowid()
+            },
+        };
+        
+        {
+            let mut update = tx.prepare(
+                "UPDATE report_pages SET status = ?, content_type = ?, size = ?, timestamp = ?
+                 WHERE report_id = ? AND url = ?"
+            )?;
+            let mut insert = tx.prepare(
+                "INSERT INTO report_pages (report_id, url, status, content_type, size, timestamp)
+                 VALUES (?, ?, ?, ?, ?, ?)"
+            )?;
+            for page in &pages {
+                let replaced = update.execute(params![
+                    page.status,
+                    page.content_type,
+                    page.size as i64,
+                    page.timestamp as i64,
+                    report_id,
+                    page.url,
+                ])?;
+                if replaced == 0 {
+                    insert.execute(params![
+                        report_id,
+                        page.url,
+                        page.status,
+                        page.content_type,
+                        page.size as i64,
+                        page.timestamp as i64,
+                    ])?;
+                }
+            }
+        }
+        
+        tx.execute(
+            "UPDATE reports SET
+                pages_count = (SELECT COUNT(*) FROM report_pages WHERE report_id = ?1),
+                total_size = (SELECT COALESCE(SUM(size), 0) FROM report_pages WHERE report_id = ?1),
+                end_time = ?2
+             WHERE id = ?1",
+            params![report_id, now],
+        )?;
+        let pages_count: i64 = tx.query_row(
+            "SELECT pages_count FROM reports WHERE id = ?",
+            params![report_id],
+            |row| row.get(0),
+        )?;
+        tx.commit().with_context(|| format!("Failed to append to the partial report of task {}", task_id))?;
+        
+        Ok(pages_count as usize)
+    }
+    
+    /// Build a report without its pages from a row selected with `REPORT_COLUMNS`,
+    /// along with the report's row ID to load them with
+    fn report_from_row(row: &rusqlite::Row) -> rusqlite::Result<(CrawlReport, i64)> {
+        let report = CrawlReport {
+            task_id: row.get(0)?,
+            client_id: row.get(1)?,
+            domain: row.get(2)?,
+            pages_count: row.get(3)?,
+            total_size: row.get(4)?,
+            pages: Vec::new(),
+            start_time: row.get(6)?,
+            end_time: row.get(7)?,
+            verified: row.get(8)?,
+            verification_score: row.get(9)?,
+            verification_notes: row.get(10)?,
+            partial: row.get(11)?,
+        };
+        
+        Ok((report, row.get(5)?))
+    }
+    
+    /// Build a page from a row selected with `REPORT_PAGE_COLUMNS`
+    fn report_page_from_row(row: &rusqlite::Row) -> rusqlite::Result<CrawledPage> {
+        Ok(CrawledPage {
+            url: row.get(0)?,
+            status: row.get(1)?,
+            content_type: row.get(2)?,
+            size: row.get::<_, i64>(3)? as usize,
+            timestamp: row.get::<_, i64>(4)? as u64,
+        })
+    }
+    
+    /// Reassemble a report with its pages from `report_pages`
+    fn load_report(&self, (mut report, report_id): (CrawlReport, i64)) -> Result<CrawlReport> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM report_pages WHERE report_id = ? ORDER BY id",
+            REPORT_PAGE_COLUMNS
+        ))?;
+        
+        for page in stmt.query_map(params![report_id], Self::report_page_from_row)? {
+            report.pages.push(page?);
+        }
+        
+        Ok(report)
     }
     
     /// Get a report by task ID
     pub fn get_report_by_task(&self, task_id: &str) -> Result<Option<CrawlReport>> {
         let mut stmt = self.conn.prepare(
-            "SELECT task_id, client_id, domain, pages_count, total_size, pages, 
-             start_time, end_time, verified, verification_score, verification_notes 
-             FROM reports WHERE task_id = ?"
+            &format!("SELECT {} FROM reports WHERE task_id = ?", REPORT_COLUMNS)
         )?;
         
-        let report_result = stmt.query_row(params![task_id], |row| {
-            let task_id: String = row.get(0)?;
-            let client_id: String = row.get(1)?;
-            let domain: String = row.get(2)?;
-            let pages_count: usize = row.get(3)?;
-            let total_size: usize = row.get(4)?;
-            let pages_json: String = row.get(5)?;
-            let start_time: u64 = row.get(6)?;
-            let end_time: Option<u64> = row.get(7)?;
-            let verified: bool = row.get(8)?;
-            let verification_score: Option<f64> = row.get(9)?;
-            let verification_notes: Option<String> = row.get(10)?;
-            
-            Ok((
-                task_id, client_id, domain, pages_count, total_size, pages_json,
-                start_time, end_time, verified, verification_score, verification_notes
-            ))
-        });
-        
-        match report_result {
-            Ok((task_id, client_id, domain, pages_count, total_size, pages_json,
-                start_time, end_time, verified, verification_score, verification_notes)) => {
-                
-                // Parse pages JSON outside the query_row closure
-                let pages: Vec<CrawledPage> = serde_json::from_str(&pages_json)
-                    .context("Failed to parse pages JSON")?;
-                
-                Ok(Some(CrawlReport {
-                    task_id,
-                    client_id,
-                    domain,
-                    pages_count,
-                    total_size,
-                    pages,
-                    start_time,
-                    end_time,
-                    verified,
-                    verification_score,
-                    verification_notes,
-                }))
-            },
+        match stmt.query_row(params![task_id], Self::report_from_row) {
+            Ok(row) => Ok(Some(self.load_report(row)?)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(anyhow!("Database error: {}", e)),
         }
     }
     
+    /// Get a page of a report's crawled pages, in the order they were reported,
+    /// without loading the rest of the report.
+    /// Returns the pages along with the report's total page count, or `None` if the task has no report.
+    pub fn get_report_pages(&self, task_id: &str, limit: u32, offset: u32) -> Result<Option<(Vec<CrawledPage>, usize)>> {
+        let report_id: i64 = match self.conn.query_row(
+            "SELECT id FROM reports WHERE task_id = ?",
+            params![task_id],
+            |row| row.get(0),
+        ) {
+            Ok(report_id) => report_id,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(anyhow!("Database error: {}", e)),
+        };
+        
+        let total: usize = self.conn.query_row(
+            "SELECT COUNT(*) FROM report_pages WHERE report_id = ?",
+            params![report_id],
+            |row| row.get(0),
+        )?;
+        
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM report_pages WHERE report_id = ?1 ORDER BY id LIMIT ?2 OFFSET ?3",
+            REPORT_PAGE_COLUMNS
+        ))?;
+        
+        let page_iter = stmt.query_map(params![report_id, limit, offset], Self::report_page_from_row)?;
+        
+        let mut pages = Vec::new();
+        for page in page_iter {
+            pages.push(page?);
+        }
+        
+        Ok(Some((pages, total)))
+    }
+    
+    /// Get a page of reports, newest first, optionally filtered by their task's status.
+    /// Returns the page along with the total number of matching reports.
+    pub fn get_reports_filtered(&self, status: Option<&TaskStatus>, limit: u32, offset: u32) -> Result<(Vec<CrawlReport>, usize)> {
+        let status = status.map(|s| format!("{:?}", s));
+        
+        let total: usize = self.conn.query_row(
+            "SELECT COUNT(*) FROM reports
+             JOIN tasks ON tasks.id = reports.task_id
+             WHERE ?1 IS NULL OR tasks.status = ?1",
+            params![status],
+            |row| row.get(0),
+        )?;
+        
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM reports
+             JOIN tasks ON tasks.id = reports.task_id
+             WHERE ?1 IS NULL OR tasks.status = ?1
+             ORDER BY reports.id DESC
+             LIMIT ?2 OFFSET ?3",
+            REPORT_COLUMNS
+        ))?;
+        
+        let rows = stmt.query_map(params![status, limit, offset], Self::report_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        let mut reports = Vec::new();
+        for row in rows {
+            reports.push(self.load_report(row)?);
+        }
+        
+        Ok((reports, total))
+    }
+    
+    /// Record the incentive transferred for a task's report
+    pub fn record_incentive_paid(&self, task_id: &str, amount: u64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE reports SET incentive_paid = ? WHERE task_id = ?",
+            params![amount as i64, task_id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Totals across all tasks and reports, computed in SQL without loading any rows
+    pub fn aggregate_stats(&self) -> Result<Stats> {
+        let mut stmt = self.conn.prepare("SELECT status, COUNT(*) FROM tasks GROUP BY status")?;
+        let tasks_by_status = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        
+        let (reports, total_pages, total_bytes, total_incentives_paid, average_verification_score) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(pages_count), 0), COALESCE(SUM(total_size), 0),
+                    COALESCE(SUM(incentive_paid), 0), AVG(verification_score)
+             FROM reports",
+            [],
+            |row| Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Option<f64>>(4)?,
+            )),
+        )?;
+        
+        Ok(Stats {
+            tasks_by_status,
+            reports: reports as u64,
+            total_pages: total_pages as u64,
+            total_bytes: total_bytes as u64,
+            total_incentives_paid: total_incentives_paid as u64,
+            average_verification_score,
+        })
+    }
+    
     /// Update a report's verification status
-    pub fn update_report_verification(&self, task_id: &str, verified: bool, score: Option<f64>, notes: Option<String>) -> Result<()> {
+    pub fn update_report_verification(&self, task_id: &str, verified: bool, score: Option<f64>, notes: Option<String>, transaction_hash: &str) -> Result<()> {
         self.conn.execute(
             "UPDATE reports SET
                 verified = ?,
                 verification_score = ?,
-                verification_notes = ?
+                verification_notes = ?,
+                verification_tx = ?
             WHERE task_id = ?",
             params![
                 if verified { 1 } else { 0 },
                 score,
                 notes,
+                transaction_hash,
                 task_id,
             ],
         )?;
         
         Ok(())
     }
-} 
\ No newline at end of file
+    
+    /// Verification recorded for a task's complete report; `None` until it has been verified
+    pub fn get_report_verification(&self, task_id: &str) -> Result<Option<ReportVerification>> {
+        let verification = self.conn.query_row(
+            "SELECT verified, verification_score, verification_notes, verification_tx, incentive_paid
+            FROM reports WHERE task_id = ? AND partial = 0 AND verification_tx IS NOT NULL",
+            params![task_id],
+            |row| Ok(ReportVerification {
+                verified: row.get(0)?,
+                score: row.get::<_, Option<f64>>(1)?.unwrap_or_default(),
+                notes: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                transaction_hash: row.get(3)?,
+                incentive_paid: row.get::<_, Option<i64>>(4)?.map(|amount| amount as u64),
+            }),
+        ).optional()?;
+        
+        Ok(verification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn page(url: &str, size: usize) -> CrawledPage {
+        CrawledPage { url: url.to_string(), status: Some(200), content_type: None, size, timestamp: 0 }
+    }
+    
+    #[test]
+    fn partial_reports_grow_batch_by_batch() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::new(dir.path().join("manager.db")).unwrap();
+        db.create_task(&Task::new("task".to_string(), "https://example.com".to_string(), 2, false, None, 0)).unwrap();
+        assert_eq!(db.report_is_partial("task").unwrap(), None);
+        
+        let first = vec![page("https://example.com/", 100), page("https://example.com/a", 10)];
+        assert_eq!(db.append_partial_report("task", "client", "example.com", 5, first).unwrap(), 2);
+        
+        // A page sent again replaces its earlier row
+        let second = vec![page("https://example.com/a", 20), page("https://example.com/b", 30)];
+        assert_eq!(db.append_partial_report("task", "client", "example.com", 5, second).unwrap(), 3);
+        
+        let report = db.get_report_by_task("task").unwrap().unwrap();
+        assert!(report.partial);
+        assert_eq!(report.total_size, 150);
+        assert_eq!(report.start_time, 5);
+        let urls: Vec<&str> = report.pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/", "https://example.com/a", "https://example.com/b"]);
+        
+        // Complete reports take no more batches
+        db.save_report(&CrawlReport { partial: false, ..report }).unwrap();
+        assert_eq!(db.report_is_partial("task").unwrap(), Some(false));
+        assert!(db.append_partial_report("task", "client", "example.com", 5, vec![page("https://example.com/c", 1)]).is_err());
+    }
+    
+    #[test]
+    fn verification_is_stored_for_resubmitted_reports() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::new(dir.path().join("manager.db")).unwrap();
+        db.create_task(&Task::new("task".to_string(), "https://example.com".to_string(), 2, false, None, 0)).unwrap();
+        
+        let report = CrawlReport {
+            task_id: "task".to_string(),
+            client_id: "client".to_string(),
+            domain: "example.com".to_string(),
+            pages_count: 0,
+            total_size: 0,
+            pages: Vec::new(),
+            start_time: 0,
+            end_time: Some(1),
+            verified: false,
+            verification_score: None,
+            verification_notes: None,
+            partial: false,
+        };
+        db.save_report(&report).unwrap();
+        assert!(db.get_report_verification("task").unwrap().is_none());
+        
+        db.update_report_verification("task", true, Some(0.9), Some("fine".to_string()), "tx").unwrap();
+        db.record_incentive_paid("task", 42).unwrap();
+        
+        let stored = db.get_report_verification("task").unwrap().unwrap();
+        assert!(stored.verified);
+        assert_eq!(stored.score, 0.9);
+        assert_eq!(stored.notes, "fine");
+        assert_eq!(stored.transaction_hash, "tx");
+        assert_eq!(stored.incentive_paid, Some(42));
+    }
+}
@@ -1,14 +1,327 @@
 use anyhow::{Result, anyhow, Context};
 use log::{info, warn, debug, error};
-use crate::models::CrawlReport;
+use crate::models::{CrawlReport, CrawledPage, LlmOptions, Task};
+use crate::metrics::METRICS;
 use reqwest::Client;
-use std::time::Duration;
-use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::process::Output;
+use tokio::process::Command;
 use std::fs;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use url::{Host, Url};
 
 /// Available Ollama models
 const FALLBACK_MODELS: [&str; 3] = ["deepseek-r1:14b", "llama3", "mistral"];
 
+/// Default number of pages shown to the LLM
+pub const DEFAULT_SAMPLE_SIZE: usize = 25;
+
+/// Largest page size considered plausible (50 MiB)
+const MAX_PAGE_SIZE: usize = 50 * 1024 * 1024;
+
+/// Minimum heuristic score for a report to pass on heuristics alone
+const HEURISTIC_PASS_SCORE: f64 = 0.8;
+
+/// Weight of the LLM verdict when blended with the heuristic score
+const LLM_WEIGHT: f64 = 0.6;
+
+/// Content type prefixes a crawler can plausibly encounter
+const PLAUSIBLE_CONTENT_TYPES: [&str; 6] = ["text/", "application/", "image/", "font/", "audio/", "video/"];
+
+/// Default number of URLs the manager re-fetches per report
+pub const DEFAULT_SPOT_CHECK_SIZE: usize = 5;
+
+/// Timeout for each spot-check fetch
+const SPOT_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Live/reported size ratio beyond which a page counts as mismatched
+const SPOT_CHECK_SIZE_RATIO: f64 = 5.0;
+
+/// Longest gap allowed between streamed chunks before the generation is abandoned
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Upper bound on a whole streamed generation
+const STREAM_TOTAL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Upper bound on one API documentation extraction command
+const API_DOCS_EXTRACT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Number of streamed tokens between progress log lines
+const STREAM_PROGRESS_INTERVAL: usize = 200;
+
+/// Where extracted API documentation is cached, one directory per language
+/// and one file per package
+const API_DOCS_CACHE_DIR: &str = "cache/api_docs";
+
+/// How long cached API documentation is served before it is extracted again
+const API_DOCS_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Packages kept per language in the API documentation cache; the least recently used go first
+const API_DOCS_CACHE_MAX_ENTRIES: usize = 200;
+
+/// Package ecosystems daipendency can extract documentation for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocLanguage {
+    #[default]
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl DocLanguage {
+    pub const ALL: [DocLanguage; 3] = [DocLanguage::Rust, DocLanguage::Python, DocLanguage::JavaScript];
+    
+    /// Name passed to daipendency's `--language` and used in the cache path
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocLanguage::Rust => "rust",
+            DocLanguage::Python => "python",
+            DocLanguage::JavaScript => "javascript",
+        }
+    }
+    
+    /// Name of the language in prose
+    fn display_name(&self) -> &'static str {
+        match self {
+            DocLanguage::Rust => "Rust",
+            DocLanguage::Python => "Python",
+            DocLanguage::JavaScript => "JavaScript",
+        }
+    }
+    
+    /// What the language's ecosystem calls a package
+    fn package_noun(&self) -> &'static str {
+        match self {
+            DocLanguage::Rust => "crate",
+            DocLanguage::Python | DocLanguage::JavaScript => "package",
+        }
+    }
+    
+    /// Registry that packages of this language are resolved against
+    fn registry(&self) -> &'static str {
+        match self {
+            DocLanguage::Rust => "crates.io",
+            DocLanguage::Python => "PyPI",
+            DocLanguage::JavaScript => "npm",
+        }
+    }
+    
+    /// Registry API URL describing a package
+    fn package_url(&self, package: &str) -> String {
+        match self {
+            DocLanguage::Rust => format!("https://crates.io/api/v1/crates/{}", package),
+            DocLanguage::Python => format!("https://pypi.org/pypi/{}/json", package),
+            DocLanguage::JavaScript => format!("https://registry.npmjs.org/{}", package.replace('/', "%2F")),
+        }
+    }
+    
+    /// Latest release listed in the registry's description of a package
+    fn latest_version(&self, info: &serde_json::Value) -> Option<String> {
+        let version = match self {
+            DocLanguage::Rust => info["crate"]["max_stable_version"].as_str()
+                .or_else(|| info["crate"]["max_version"].as_str()),
+            DocLanguage::Python => info["info"]["version"].as_str(),
+            DocLanguage::JavaScript => info["dist-tags"]["latest"].as_str(),
+        };
+        version.map(str::to_string)
+    }
+    
+    /// Whether a package name is one the registry could hold, including npm's `@scope/name`.
+    /// Names end up in cache file names, so anything else is rejected up front.
+    fn is_valid_package(&self, package: &str) -> bool {
+        let allowed = |c: char| match self {
+            DocLanguage::Rust => c.is_ascii_alphanumeric() || c == '-' || c == '_',
+            DocLanguage::Python | DocLanguage::JavaScript => c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'),
+        };
+        let valid = |name: &str| !name.is_empty() && !name.starts_with('.') && name.chars().all(allowed);
+        
+        match package.strip_prefix('@').and_then(|scoped| scoped.split_once('/')) {
+            Some((scope, name)) if *self == DocLanguage::JavaScript => valid(scope) && valid(name),
+            _ => valid(package),
+        }
+    }
+}
+
+impl std::fmt::Display for DocLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DocLanguage {
+    type Err = UnsupportedLanguage;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DocLanguage::ALL.into_iter()
+            .find(|language| language.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnsupportedLanguage(s.to_string()))
+    }
+}
+
+/// A documentation language daipendency doesn't support
+#[derive(Debug)]
+pub struct UnsupportedLanguage(pub String);
+
+impl std::fmt::Display for UnsupportedLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let supported: Vec<&str> = DocLanguage::ALL.iter().map(DocLanguage::as_str).collect();
+        write!(f, "Unsupported language {}; expected one of {}", self.0, supported.join(", "))
+    }
+}
+
+impl std::error::Error for UnsupportedLanguage {}
+
+/// A package that its registry doesn't know
+#[derive(Debug)]
+pub struct PackageNotFound {
+    pub package: String,
+    pub registry: &'static str,
+}
+
+impl std::fmt::Display for PackageNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Package {} not found on {}", self.package, self.registry)
+    }
+}
+
+impl std::error::Error for PackageNotFound {}
+
+/// A version daipendency can't extract: it only documents the latest release,
+/// so older versions are served only while cached
+#[derive(Debug)]
+pub struct VersionUnavailable {
+    pub package: String,
+    pub version: String,
+    pub latest: String,
+}
+
+impl std::fmt::Display for VersionUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No cached documentation for {} {}; only the latest release {} can be extracted", self.package, self.version, self.latest)
+    }
+}
+
+impl std::error::Error for VersionUnavailable {}
+
+/// Whether a version string is safe to use in a cache file name
+fn is_valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && !version.starts_with('.')
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+/// Default verification prompt. Supports the `{task_id}`, `{pages}`, `{total_size}`
+/// and `{duration_ms}` placeholders; page samples and the answer format are appended.
+pub const DEFAULT_VERIFICATION_PROMPT: &str = "You are a web crawl verification agent. Please verify the following crawl report:
+            
+            Task ID: {task_id}
+            Pages Crawled: {pages}
+            Total Size: {total_size} bytes
+            Crawl Duration: {duration_ms} ms
+            
+            Please analyze the crawled pages and verify:
+            1. That the page sizes look reasonable
+            2. That the content types are valid
+            3. That the URL structure is consistent
+            4. That there are no obvious fake or malicious entries
+            
+            A sample of the crawled pages:
+            ";
+
+/// Outcome of re-fetching a sample of reported URLs
+#[derive(Debug, Clone)]
+pub struct SpotCheckResult {
+    /// Number of URLs that could be fetched and compared
+    pub checked: usize,
+    /// Descriptions of URLs whose live response contradicts the report
+    pub mismatches: Vec<String>,
+}
+
+impl SpotCheckResult {
+    /// Share of checked URLs that matched the report, or `None` if nothing could be checked
+    pub fn score(&self) -> Option<f64> {
+        if self.checked == 0 {
+            None
+        } else {
+            Some((self.checked - self.mismatches.len()) as f64 / self.checked as f64)
+        }
+    }
+}
+
+/// Reported pages a spot check may fetch: http(s) URLs on the task's domain, or its
+/// subdomains if the task follows them
+fn spot_check_candidates<'a>(task: &Task, pages: &'a [CrawledPage]) -> Vec<(&'a CrawledPage, Url)> {
+    let Some(task_host) = Url::parse(&task.target_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase())) else {
+        return Vec::new();
+    };
+    let task_host = task_host.trim_start_matches("www.");
+    
+    pages.iter()
+        .filter_map(|page| {
+            let url = Url::parse(&page.url).ok()?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return None;
+            }
+            
+            let host = url.host_str()?.to_ascii_lowercase();
+            let host = host.trim_start_matches("www.");
+            let on_domain = host == task_host
+                || (task.follow_subdomains && host.ends_with(&format!(".{}", task_host)));
+            on_domain.then_some((page, url))
+        })
+        .collect()
+}
+
+/// Whether a spot check may connect to an address: not loopback, private, link-local,
+/// unspecified, broadcast, multicast or shared address space
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || first == 0
+                || (first == 100 && (64..128).contains(&second)))
+        },
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(ip));
+            }
+            
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        },
+    }
+}
+
+/// Resolve a URL's host to an address a spot check may connect to; `None` if it can't be
+/// resolved or any of its addresses is local
+async fn resolve_public(url: &Url) -> Option<SocketAddr> {
+    let port = url.port_or_known_default()?;
+    let addrs: Vec<SocketAddr> = match url.host()? {
+        Host::Ipv4(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Ipv6(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Domain(domain) => tokio::net::lookup_host((domain, port)).await.ok()?.collect(),
+    };
+    
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public_address(addr.ip())) {
+        return None;
+    }
+    
+    addrs.first().copied()
+}
+
 /// LLM-based evaluator for crawl reports
 pub struct Evaluator {
     /// Ollama host URL
@@ -17,6 +330,16 @@ pub struct Evaluator {
     model: String,
     /// HTTP client
     client: Client,
+    /// Verification prompt template
+    prompt_template: String,
+    /// Minimum LLM confidence required to accept a report
+    min_confidence: f64,
+    /// Whether to stream generations instead of waiting for the full response
+    stream: bool,
+    /// Number of pages sampled into the verification prompt
+    sample_size: usize,
+    /// Sampling options sent with every generation
+    options: LlmOptions,
 }
 
 impl Evaluator {
@@ -29,9 +352,44 @@ impl Evaluator {
                 .timeout(Duration::from_secs(60))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            prompt_template: DEFAULT_VERIFICATION_PROMPT.to_string(),
+            min_confidence: 0.0,
+            stream: true,
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            options: LlmOptions::default(),
         }
     }
     
+    /// Set the sampling options (temperature, context window, top-p) sent to Ollama
+    pub fn with_llm_options(mut self, options: LlmOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
+    /// Set the number of pages sampled into the verification prompt
+    pub fn with_sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_size = sample_size.max(1);
+        self
+    }
+    
+    /// Enable or disable streaming LLM responses
+    pub fn with_streaming(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+    
+    /// Set the verification prompt template
+    pub fn with_prompt_template(mut self, template: &str) -> Self {
+        self.prompt_template = template.to_string();
+        self
+    }
+    
+    /// Set the minimum confidence required to accept a report
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+    
     /// Check if the Ollama service is available and find a working model
     pub async fn check_service(&mut self) -> Result<bool> {
         info!("Checking Ollama service at {}", self.host);
@@ -98,129 +456,376 @@ impl Evaluator {
         }
     }
     
-    /// Verify a crawl report using LLM
+    /// Verify a crawl report using LLM, blended with the heuristic checks
     pub async fn verify_report(&self, report: &CrawlReport) -> Result<(bool, f64, String)> {
+        // Run the heuristic checks first; they are used on their own if the LLM is unavailable
+        let (heuristic_valid, heuristic_score, heuristic_notes) = self.heuristic_verify(report);
+        info!("Heuristic verification result: valid={}, score={:.2}, notes={}",
+              heuristic_valid, heuristic_score, heuristic_notes);
+        
         // Create verification prompt
         let prompt = self.create_verification_prompt(report);
         
         // Query LLM
         info!("Querying LLM to verify report with {} pages", report.pages_count);
-        match self.query_llm(&prompt).await {
-            Ok(response) => {
-                // Extract verification result
-                match self.parse_verification_result(&response) {
-                    Ok((is_valid, confidence, reason)) => {
-                        info!("Report verification result: valid={}, confidence={:.2}, reason={}",
-                              is_valid, confidence, reason);
-                        Ok((is_valid, confidence, reason))
-                    },
+        let response = match self.query_llm(&prompt).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("LLM verification failed, using heuristic result: {}", e);
+                return Ok((heuristic_valid, heuristic_score, format!(
+                    "LLM verification unavailable ({}); heuristics: {}", e, heuristic_notes
+                )));
+            }
+        };
+        
+        // Extract verification result
+        let (is_valid, confidence, reason) = match self.parse_verification_result(&response) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to parse LLM verification result, using heuristic result: {}", e);
+                return Ok((heuristic_valid, heuristic_score, format!(
+                    "Failed to parse LLM response ({}); heuristics: {}", e, heuristic_notes
+                )));
+            }
+        };
+        
+        info!("Report verification result: valid={}, confidence={:.2}, reason={}",
+              is_valid, confidence, reason);
+        
+        // Don't trust a positive verdict the model isn't confident in
+        if is_valid && confidence < self.min_confidence {
+            info!("Rejecting report: confidence {:.2} is below the minimum of {:.2}",
+                  confidence, self.min_confidence);
+            return Ok((false, confidence, format!(
+                "Confidence {:.2} below required {:.2}: {}",
+                confidence, self.min_confidence, reason
+            )));
+        }
+        
+        // Blend the LLM verdict with the heuristic score
+        let llm_score = if is_valid { confidence } else { 1.0 - confidence };
+        let score = LLM_WEIGHT * llm_score + (1.0 - LLM_WEIGHT) * heuristic_score;
+        let verified = score >= 0.5;
+        
+        Ok((verified, score, format!("{}; heuristics: {}", reason, heuristic_notes)))
+    }
+    
+    /// Check a report against invariants that don't need an LLM: page sizes,
+    /// content types, URLs within the report domain, duplicates and status codes.
+    /// Returns whether the report passes, a score between 0 and 1, and notes on failed checks.
+    pub fn heuristic_verify(&self, report: &CrawlReport) -> (bool, f64, String) {
+        let pages = &report.pages;
+        if pages.is_empty() {
+            return (false, 0.0, "Report contains no pages".to_string());
+        }
+        
+        let total = pages.len() as f64;
+        let fraction = |count: usize| count as f64 / total;
+        let is_success = |page: &CrawledPage| matches!(page.status, Some(200..=299));
+        
+        // Sizes must be below the cap, and successful responses shouldn't be empty
+        let size_score = fraction(pages.iter()
+            .filter(|p| p.size <= MAX_PAGE_SIZE && (p.size > 0 || !is_success(p)))
+            .count());
+        
+        // Content types must look like a MIME type a web server would send
+        let type_score = fraction(pages.iter()
+            .filter(|p| match &p.content_type {
+                Some(content_type) => {
+                    let content_type = content_type.to_ascii_lowercase();
+                    PLAUSIBLE_CONTENT_TYPES.iter().any(|prefix| content_type.starts_with(prefix))
+                },
+                None => true,
+            })
+            .count());
+        
+        // URLs must belong to the reported domain
+        // (crawlers report either a bare host or the task's target URL)
+        let domain = Url::parse(&report.domain)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_else(|| report.domain.clone())
+            .to_ascii_lowercase();
+        let domain = domain.trim_start_matches("www.");
+        let domain_score = fraction(pages.iter()
+            .filter(|p| Url::parse(&p.url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+                .map(|host| {
+                    let host = host.trim_start_matches("www.");
+                    host == domain || host.ends_with(&format!(".{}", domain))
+                })
+                .unwrap_or(false))
+            .count());
+        
+        // The same URL shouldn't be reported repeatedly
+        let unique_urls: HashSet<&str> = pages.iter().map(|p| p.url.as_str()).collect();
+        let duplicate_score = fraction(unique_urls.len());
+        
+        // Status codes must be real, and most pages should have loaded
+        let status_score = fraction(pages.iter()
+            .filter(|p| matches!(p.status, None | Some(200..=399)))
+            .count());
+        
+        let checks = [
+            ("page sizes", size_score),
+            ("content types", type_score),
+            ("on-domain URLs", domain_score),
+            ("unique URLs", duplicate_score),
+            ("successful statuses", status_score),
+        ];
+        
+        let score = checks.iter().map(|(_, score)| score).sum::<f64>() / checks.len() as f64;
+        let failed: Vec<String> = checks.iter()
+            .filter(|(_, score)| *score < HEURISTIC_PASS_SCORE)
+            .map(|(name, score)| format!("{} {:.0}%", name, score * 100.0))
+            .collect();
+        
+        let notes = if failed.is_empty() {
+            format!("all checks passed (score {:.2})", score)
+        } else {
+            format!("score {:.2}, low: {}", score, failed.join(", "))
+        };
+        
+        (score >= HEURISTIC_PASS_SCORE, score, notes)
+    }
+    
+    /// Re-fetch a random sample of reported URLs and compare them with the report.
+    /// URLs that now 404 (or fail where the crawler reported success) and pages whose
+    /// live size differs from the reported size by more than a factor of
+    /// `SPOT_CHECK_SIZE_RATIO` are flagged. Fetch errors are skipped as inconclusive.
+    ///
+    /// The URLs come from the crawler, so only pages on the task's domain are sampled,
+    /// and a URL whose host resolves to a loopback, private or link-local address is
+    /// never fetched. Redirects aren't followed, since they could lead anywhere.
+    pub async fn spot_check(&self, task: &Task, report: &CrawlReport, sample_size: usize) -> SpotCheckResult {
+        let mut result = SpotCheckResult {
+            checked: 0,
+            mismatches: Vec::new(),
+        };
+        
+        let candidates = spot_check_candidates(task, &report.pages);
+        
+        // Unlike the LLM sample this must not be predictable, so mix in the clock
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let mut rng = SplitMix64::new(fnv1a(report.task_id.as_bytes()) ^ nanos);
+        
+        // Partial Fisher-Yates shuffle to pick the sample
+        let mut indices: Vec<usize> = (0..candidates.len()).collect();
+        let sample_size = sample_size.min(indices.len());
+        for i in 0..sample_size {
+            let j = i + (rng.next() % (indices.len() - i) as u64) as usize;
+            indices.swap(i, j);
+        }
+        
+        for &index in &indices[..sample_size] {
+            let (page, url) = &candidates[index];
+            
+            let Some(addr) = resolve_public(url).await else {
+                warn!("Spot check of {} skipped: it doesn't resolve to a public address", page.url);
+                continue;
+            };
+            
+            // Connect to the address just checked, so the host can't be re-resolved elsewhere
+            let client = match Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(SPOT_CHECK_TIMEOUT)
+                .resolve(url.host_str().unwrap_or_default(), addr)
+                .build() {
+                    Ok(client) => client,
                     Err(e) => {
-                        warn!("Failed to parse LLM verification result: {}", e);
-                        // Return a default response instead of failing
-                        Ok((true, 0.5, format!("Failed to parse LLM response: {}", e)))
+                        debug!("Spot check of {} inconclusive: {}", page.url, e);
+                        continue;
                     }
+                };
+            
+            let response = match client.get(url.clone()).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    debug!("Spot check of {} inconclusive: {}", page.url, e);
+                    continue;
                 }
-            },
-            Err(e) => {
-                warn!("LLM verification failed: {}", e);
-                // Return a default response instead of failing
-                Ok((true, 0.5, format!("LLM verification unavailable: {}", e)))
+            };
+            
+            let status = response.status();
+            let body = match response.bytes().await {
+                Ok(body) => body,
+                Err(e) => {
+                    debug!("Spot check of {} inconclusive: {}", page.url, e);
+                    continue;
+                }
+            };
+            
+            result.checked += 1;
+            
+            let reported_ok = matches!(page.status, None | Some(200..=399));
+            let live_ok = status.is_success() || status.is_redirection();
+            if status == reqwest::StatusCode::NOT_FOUND || (reported_ok && !live_ok) {
+                result.mismatches.push(format!("{} returned {}", page.url, status.as_u16()));
+                continue;
+            }
+            
+            // The size of a redirect says nothing about the page behind it
+            if status.is_redirection() {
+                continue;
+            }
+            
+            // Compare sizes, allowing for dynamic content
+            let live_size = body.len().max(1) as f64;
+            let reported_size = page.size.max(1) as f64;
+            let ratio = (live_size / reported_size).max(reported_size / live_size);
+            if ratio > SPOT_CHECK_SIZE_RATIO {
+                result.mismatches.push(format!(
+                    "{} is {} bytes live but reported as {}",
+                    page.url, body.len(), page.size
+                ));
             }
         }
+        
+        info!("Spot check of task {}: {} URLs checked, {} mismatched",
+              report.task_id, result.checked, result.mismatches.len());
+        
+        result
     }
     
-    /// Get API documentation for a package using daipendency
-    pub async fn get_api_documentation(&self, package: &str) -> Result<String> {
-        info!("Extracting API documentation for package: {}", package);
+    /// Get API documentation for a package using daipendency, enhanced by the LLM.
+    /// Documentation is cached per version for `API_DOCS_CACHE_TTL`. A requested version
+    /// that is cached is served without asking the registry; otherwise the registry gives
+    /// the latest release, so a new release is extracted afresh. daipendency only documents
+    /// the latest release, so other versions are only available from the cache.
+    /// `force_refresh` skips the cache. Returns the documented version with the documentation.
+    pub async fn get_api_documentation(
+        &self,
+        package: &str,
+        language: DocLanguage,
+        version: Option<&str>,
+        force_refresh: bool,
+    ) -> Result<(String, String)> {
+        let not_found = || PackageNotFound {
+            package: package.to_string(),
+            registry: language.registry(),
+        };
+        if !language.is_valid_package(package) || !version.map_or(true, is_valid_version) {
+            return Err(not_found().into());
+        }
         
-        // Check for cached documentation first
-        let cache_dir = "cache/api_docs";
+        let cache_dir = format!("{}/{}", API_DOCS_CACHE_DIR, language);
+        let cache_dir = cache_dir.as_str();
         if !std::path::Path::new(cache_dir).exists() {
             fs::create_dir_all(cache_dir)?;
         }
+        evict_api_docs(cache_dir, API_DOCS_CACHE_TTL, API_DOCS_CACHE_MAX_ENTRIES);
+        
+        if let (Some(version), false) = (version, force_refresh) {
+            if let Some(docs) = read_cached_api_docs(cache_dir, package, version)? {
+                return Ok((version.to_string(), docs));
+            }
+        }
         
-        let cache_path = format!("{}/{}.md", cache_dir, package);
-        if std::path::Path::new(&cache_path).exists() {
-            info!("Using cached API documentation for {}", package);
-            return fs::read_to_string(&cache_path)
-                .context(format!("Failed to read cached API documentation for {}", package));
+        let latest = self.latest_package_version(package, language).await?;
+        if let Some(version) = version.filter(|version| *version != latest) {
+            return Err(VersionUnavailable {
+                package: package.to_string(),
+                version: version.to_string(),
+                latest,
+            }.into());
         }
         
+        let cache_path = api_docs_cache_path(cache_dir, package, &latest);
+        if force_refresh {
+            info!("Refreshing API documentation for {} {}", package, latest);
+        } else if let Some(docs) = read_cached_api_docs(cache_dir, package, &latest)? {
+            return Ok((latest, docs));
+        }
+        info!("Extracting {} API documentation for package: {} {}", language, package, latest);
+        
         // Use the daipendency CLI to extract API documentation
-        let output = Command::new("daipendency")
-            .args(["extract-dep", package, "--language=rust"])
-            .output()
+        let mut daipendency = Command::new("daipendency");
+        daipendency.args(["extract-dep", package, &format!("--language={}", language)]);
+        let output = run_extractor(daipendency)
+            .await
             .context("Failed to run daipendency CLI")?;
         
-        if !output.status.success() {
+        let docs = if output.status.success() {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        } else {
             let error = String::from_utf8_lossy(&output.stderr);
             error!("daipendency CLI failed: {}", error);
             
+            // The extract_api_docs tool only handles Rust crates
+            if language != DocLanguage::Rust {
+                return Err(anyhow!("API documentation extraction failed for {} package {}: {}", language, package, error));
+            }
+            
             // Try alternative command
             info!("Trying alternative daipendency command");
-            let alt_output = Command::new("cargo")
-                .args(["run", "--bin", "extract_api_docs", "--", package])
-                .output()
+            let mut extract_api_docs = Command::new("cargo");
+            extract_api_docs.args(["run", "--bin", "extract_api_docs", "--", package]);
+            let alt_output = run_extractor(extract_api_docs)
+                .await
                 .context("Failed to run extract_api_docs tool")?;
-                
+            
             if !alt_output.status.success() {
                 let alt_error = String::from_utf8_lossy(&alt_output.stderr);
                 return Err(anyhow!("All API documentation extraction methods failed. daipendency error: {}, extract_api_docs error: {}", 
                     error, alt_error));
             }
             
-            let docs = String::from_utf8_lossy(&alt_output.stdout).to_string();
-            // Cache the documentation
-            fs::write(&cache_path, &docs)
-                .context(format!("Failed to cache API documentation for {}", package))?;
-                
-            return Ok(docs);
-        }
+            String::from_utf8_lossy(&alt_output.stdout).to_string()
+        };
+        debug!("Extracted API documentation for {} {}", package, latest);
         
-        let docs = String::from_utf8_lossy(&output.stdout).to_string();
-        debug!("Extracted API documentation for {}", package);
+        // Only enhanced documentation is cached, so a cache hit returns the same as a fresh extraction
+        let docs = match self.enhance_documentation_with_llm(&docs, package, language).await {
+            Ok(enhanced_docs) => enhanced_docs,
+            Err(e) => {
+                warn!("Failed to enhance documentation with LLM, returning it uncached: {}", e);
+                return Ok((latest, docs));
+            }
+        };
         
-        // Cache the documentation for future use
         fs::write(&cache_path, &docs)
             .context(format!("Failed to cache API documentation for {} to {}", package, cache_path))?;
+        info!("API documentation for {} {} saved to {}", package, latest, cache_path);
         
-        info!("API documentation for {} saved to {}", package, cache_path);
+        Ok((latest, docs))
+    }
+    
+    /// Look a package up in its registry, returning its latest release
+    async fn latest_package_version(&self, package: &str, language: DocLanguage) -> Result<String> {
+        let response = self.client.get(language.package_url(package))
+            .header(reqwest::header::USER_AGENT, "cryptocrawl-manager")
+            .send()
+            .await
+            .context(format!("Failed to look up {} on {}", package, language.registry()))?;
         
-        // Enhance documentation with LLM insights
-        if let Ok(enhanced_docs) = self.enhance_documentation_with_llm(&docs, package).await {
-            return Ok(enhanced_docs);
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(PackageNotFound {
+                package: package.to_string(),
+                registry: language.registry(),
+            }.into());
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!("{} lookup of {} failed: {}", language.registry(), package, response.status()));
         }
         
-        Ok(docs)
+        let info: serde_json::Value = response.json().await
+            .context(format!("Failed to parse {} response for {}", language.registry(), package))?;
+        language.latest_version(&info)
+            .filter(|version| is_valid_version(version))
+            .ok_or_else(|| anyhow!("{} lists no usable version of {}", language.registry(), package))
     }
     
     /// Enhance API documentation with LLM insights and examples
-    async fn enhance_documentation_with_llm(&self, docs: &str, package: &str) -> Result<String> {
-        let prompt = format!(
-            "You are an expert Rust developer. Below is the API documentation for the {} crate:
-            
-            {}
-            
-            Please enhance this documentation by:
-            1. Adding usage examples for the most important functions/methods
-            2. Explaining common patterns and best practices
-            3. Identifying potential pitfalls or gotchas
-            4. Providing context on how different components relate to each other
-            
-            Format your response as Markdown, preserving the original documentation and adding your enhancements.",
-            package, docs
-        );
+    async fn enhance_documentation_with_llm(&self, docs: &str, package: &str, language: DocLanguage) -> Result<String> {
+        let prompt = api_docs_enhancement_prompt(docs, package, language);
+        let response = self.query_llm(&prompt).await?;
+        info!("Enhanced API documentation for {} with LLM insights", package);
         
-        match self.query_llm(&prompt).await {
-            Ok(response) => {
-                info!("Enhanced API documentation for {} with LLM insights", package);
-                Ok(response)
-            },
-            Err(e) => {
-                warn!("Failed to enhance documentation with LLM: {}", e);
-                Ok(docs.to_string()) // Return original docs if enhancement fails
-            }
-        }
+        Ok(response)
     }
     
     /// Create verification prompt for LLM
@@ -231,30 +836,14 @@ impl Evaluator {
             None => 0,
         };
         
-        let mut prompt = format!(
-            "You are a web crawl verification agent. Please verify the following crawl report:
-            
-            Task ID: {}
-            Pages Crawled: {}
-            Total Size: {} bytes
-            Crawl Duration: {} ms
-            
-            Please analyze the crawled pages and verify:
-            1. That the page sizes look reasonable
-            2. That the content types are valid
-            3. That the URL structure is consistent
-            4. That there are no obvious fake or malicious entries
-            
-            The first 10 crawled pages are:
-            ",
-            report.task_id,
-            report.pages_count,
-            report.total_size,
-            duration
-        );
-        
-        // Add up to 10 page samples
-        for (i, page) in report.pages.iter().take(10).enumerate() {
+        let mut prompt = self.prompt_template
+            .replace("{task_id}", &report.task_id)
+            .replace("{pages}", &report.pages_count.to_string())
+            .replace("{total_size}", &report.total_size.to_string())
+            .replace("{duration_ms}", &duration.to_string());
+        
+        // Add a sample of pages from across the report
+        for (i, page) in self.sample_pages(report).into_iter().enumerate() {
             prompt.push_str(&format!(
                 "{}. URL: {}, Size: {} bytes, Content-Type: {}, Status: {}\n",
                 i + 1,
@@ -266,7 +855,7 @@ impl Evaluator {
         }
         
         prompt.push_str("\nBased on the above information, please respond with:
-            
+        
         VALID: [true/false]
         CONFIDENCE: [0.0-1.0]
         REASON: [brief explanation of your decision]");
@@ -274,16 +863,157 @@ impl Evaluator {
         prompt
     }
     
+    /// Pick the pages shown to the LLM.
+    ///
+    /// Taking the first N pages lets a crawler put genuine pages up front and pad the
+    /// rest of the report with garbage, so the sample is drawn from the whole report.
+    /// Pages are ordered by size and split into one bucket per sample slot, with one page
+    /// chosen at random from each bucket; this always covers both small and large pages.
+    /// The random choice is seeded from the task ID, so re-verifying the same report
+    /// shows the LLM the same pages and verdicts are reproducible, while a crawler
+    /// still can't tell in advance which pages will be inspected.
+    fn sample_pages<'a>(&self, report: &'a CrawlReport) -> Vec<&'a CrawledPage> {
+        let pages = &report.pages;
+        if pages.len() <= self.sample_size {
+            return pages.iter().collect();
+        }
+        
+        // Order page indices by size, breaking ties by position for stability
+        let mut by_size: Vec<usize> = (0..pages.len()).collect();
+        by_size.sort_by_key(|&i| (pages[i].size, i));
+        
+        // Choose one index from each size bucket
+        let mut rng = SplitMix64::new(fnv1a(report.task_id.as_bytes()));
+        let mut chosen: Vec<usize> = (0..self.sample_size)
+            .map(|bucket| {
+                let start = bucket * pages.len() / self.sample_size;
+                let end = (bucket + 1) * pages.len() / self.sample_size;
+                by_size[start + (rng.next() % (end - start) as u64) as usize]
+            })
+            .collect();
+        
+        // Present the sample in crawl order
+        chosen.sort_unstable();
+        chosen.into_iter().map(|i| &pages[i]).collect()
+    }
+    
+    /// Body of an Ollama `/api/generate` request, with the sampling options that are set
+    fn generate_request(&self, prompt: &str, stream: bool) -> serde_json::Value {
+        let mut request = serde_json::json!({
+            "model": self.model,
+            "prompt": prompt,
+            "stream": stream
+        });
+        
+        let options = serde_json::to_value(&self.options).unwrap_or_default();
+        if options.as_object().map_or(false, |options| !options.is_empty()) {
+            request["options"] = options;
+        }
+        
+        request
+    }
+    
     /// Query Ollama LLM
     async fn query_llm(&self, prompt: &str) -> Result<String> {
+        let _timer = METRICS.llm_call_seconds.start_timer();
+        
+        if self.stream {
+            self.query_llm_streaming(prompt).await
+        } else {
+            self.query_llm_blocking(prompt).await
+        }
+    }
+    
+    /// Query Ollama LLM with streaming, accumulating tokens as they arrive.
+    /// Only the gap between chunks is bounded, so long generations don't hit the request timeout.
+    async fn query_llm_streaming(&self, prompt: &str) -> Result<String> {
+        let url = format!("{}/api/generate", self.host);
+        
+        let mut response = self.client.post(&url)
+            .timeout(STREAM_TOTAL_TIMEOUT)
+            .json(&self.generate_request(prompt, true))
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_connect() {
+                    anyhow!("Failed to connect to LLM service: {}", e)
+                } else {
+                    anyhow!("LLM query failed: {}", e)
+                }
+            })?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            
+            return Err(anyhow!("LLM API error: {} - {}", status, error_text));
+        }
+        
+        // Ollama streams newline-delimited JSON objects; a chunk may end mid-line
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut output = String::new();
+        let mut tokens = 0;
+        
+        loop {
+            let chunk = tokio::time::timeout(STREAM_IDLE_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| anyhow!("LLM stream stalled for {:?} after {} tokens", STREAM_IDLE_TIMEOUT, tokens))?
+                .map_err(|e| anyhow!("LLM stream failed after {} tokens: {}", tokens, e))?;
+            
+            let chunk = match chunk {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            
+            buffer.extend_from_slice(&chunk);
+            
+            // Process every complete line in the buffer
+            while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                
+                let message: serde_json::Value = serde_json::from_str(line)
+                    .context("Failed to parse LLM stream message")?;
+                
+                if let Some(error) = message.get("error").and_then(|v| v.as_str()) {
+                    return Err(anyhow!("LLM API error: {}", error));
+                }
+                
+                if let Some(token) = message.get("response").and_then(|v| v.as_str()) {
+                    output.push_str(token);
+                    tokens += 1;
+                    
+                    if tokens % STREAM_PROGRESS_INTERVAL == 0 {
+                        info!("LLM generation in progress: {} tokens, {} chars", tokens, output.len());
+                    }
+                }
+                
+                if message.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    info!("LLM generation finished after {} tokens", tokens);
+                    return Ok(output);
+                }
+            }
+        }
+        
+        if output.is_empty() {
+            Err(anyhow!("LLM stream ended without a response"))
+        } else {
+            warn!("LLM stream ended without a done marker after {} tokens", tokens);
+            Ok(output)
+        }
+    }
+    
+    /// Query Ollama LLM and wait for the complete response
+    async fn query_llm_blocking(&self, prompt: &str) -> Result<String> {
         let url = format!("{}/api/generate", self.host);
         
         let response = match self.client.post(&url)
-            .json(&serde_json::json!({
-                "model": self.model,
-                "prompt": prompt,
-                "stream": false
-            }))
+            .json(&self.generate_request(prompt, false))
             .send()
             .await {
                 Ok(resp) => resp,
@@ -352,4 +1082,250 @@ impl Evaluator {
         
         Ok((valid, confidence, reason))
     }
-} 
\ No newline at end of file
+}
+
+/// Prompt asking the LLM to enhance the API documentation of a package, written for its language
+fn api_docs_enhancement_prompt(docs: &str, package: &str, language: DocLanguage) -> String {
+    format!(
+        "You are an expert {} developer. Below is the API documentation for the {} {}:
+        
+        {}
+        
+        Please enhance this documentation by:
+        1. Adding {} usage examples for the most important functions/methods
+        2. Explaining common patterns and best practices
+        3. Identifying potential pitfalls or gotchas
+        4. Providing context on how different components relate to each other
+        
+        Format your response as Markdown, preserving the original documentation and adding your enhancements.",
+        language.display_name(), package, language.package_noun(), docs, language.display_name()
+    )
+}
+
+/// Run an API documentation extractor without blocking the runtime,
+/// killing it if it runs longer than `API_DOCS_EXTRACT_TIMEOUT`
+async fn run_extractor(mut command: Command) -> Result<Output> {
+    command.kill_on_drop(true);
+    match tokio::time::timeout(API_DOCS_EXTRACT_TIMEOUT, command.output()).await {
+        Ok(output) => Ok(output?),
+        Err(_) => Err(anyhow!("Timed out after {}s", API_DOCS_EXTRACT_TIMEOUT.as_secs())),
+    }
+}
+
+/// Delete cached API documentation older than `ttl`, then the least recently used
+/// until at most `max_entries` are left
+fn evict_api_docs(cache_dir: &str, ttl: Duration, max_entries: usize) {
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to list API documentation cache {}: {}", cache_dir, e);
+            return;
+        }
+    };
+    
+    // Modification times double as last use, as cache hits touch the file
+    let mut cached: Vec<(std::path::PathBuf, SystemTime)> = entries.flatten()
+        .filter_map(|entry| {
+            let used = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+            Some((entry.path(), used))
+        })
+        .collect();
+    cached.sort_by(|a, b| b.1.cmp(&a.1));
+    
+    for (i, (path, used)) in cached.iter().enumerate() {
+        let stale = used.elapsed().map_or(false, |age| age > ttl);
+        if stale || i >= max_entries {
+            debug!("Evicting {} API documentation {}", if stale { "stale" } else { "least recently used" }, path.display());
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to evict {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Cache file for the documentation of a package version. The `/` of a scoped npm
+/// package is encoded, keeping every entry directly in the cache directory.
+fn api_docs_cache_path(cache_dir: &str, package: &str, version: &str) -> String {
+    format!("{}/{}-{}.md", cache_dir, package.replace('/', "%2F"), version)
+}
+
+/// Cached documentation of a package version, marking it as used
+fn read_cached_api_docs(cache_dir: &str, package: &str, version: &str) -> Result<Option<String>> {
+    let cache_path = api_docs_cache_path(cache_dir, package, version);
+    if !std::path::Path::new(&cache_path).exists() {
+        return Ok(None);
+    }
+    
+    info!("Using cached API documentation for {} {}", package, version);
+    let docs = fs::read_to_string(&cache_path)
+        .context(format!("Failed to read cached API documentation for {}", package))?;
+    mark_used(&cache_path);
+    
+    Ok(Some(docs))
+}
+
+/// Record that cached API documentation was used, so it is evicted last
+fn mark_used(cache_path: &str) {
+    let touched = fs::File::options()
+        .write(true)
+        .open(cache_path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = touched {
+        debug!("Failed to mark {} as used: {}", cache_path, e);
+    }
+}
+
+/// 64-bit FNV-1a hash, used instead of `DefaultHasher` because its output is stable across Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Small deterministic PRNG for reproducible page sampling
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+    
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn sampling_options_are_sent_with_generations() {
+        let evaluator = Evaluator::new("http://localhost:11434", "llama3")
+            .with_llm_options(LlmOptions {
+                temperature: Some(0.0),
+                num_ctx: Some(16384),
+                top_p: Some(0.5),
+            });
+        
+        let request = evaluator.generate_request("Verify this", false);
+        assert_eq!(request["model"], "llama3");
+        assert_eq!(request["stream"], false);
+        assert_eq!(request["options"], serde_json::json!({
+            "temperature": 0.0,
+            "num_ctx": 16384,
+            "top_p": 0.5,
+        }));
+        
+        let unset = LlmOptions { temperature: None, num_ctx: None, top_p: None };
+        let request = Evaluator::new("http://localhost:11434", "llama3")
+            .with_llm_options(unset)
+            .generate_request("Verify this", true);
+        assert!(request.get("options").is_none());
+    }
+    
+    #[test]
+    fn api_docs_are_evicted_by_age_then_least_recent_use() {
+        let dir = std::env::temp_dir().join(format!("cryptocrawl-api-docs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (name, days_ago) in [("tokio.md", 0), ("serde.md", 1), ("anyhow.md", 2), ("rand.md", 10)] {
+            let path = dir.join(name);
+            fs::write(&path, "docs").unwrap();
+            let used = now - Duration::from_secs(days_ago * 24 * 60 * 60);
+            fs::File::options().write(true).open(&path).unwrap().set_modified(used).unwrap();
+        }
+        
+        // rand is past the TTL; of the rest, anyhow was used least recently
+        evict_api_docs(dir.to_str().unwrap(), Duration::from_secs(7 * 24 * 60 * 60), 2);
+        
+        let mut remaining: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, ["serde.md", "tokio.md"]);
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn api_docs_prompt_matches_the_language() {
+        let prompt = api_docs_enhancement_prompt("def get(url): ...", "requests", DocLanguage::Python);
+        assert!(prompt.contains("expert Python developer"));
+        assert!(prompt.contains("the requests package"));
+        assert!(!prompt.contains("Rust"));
+        
+        let prompt = api_docs_enhancement_prompt("pub fn spawn()", "tokio", DocLanguage::Rust);
+        assert!(prompt.contains("expert Rust developer") && prompt.contains("the tokio crate"));
+    }
+    
+    #[test]
+    fn doc_languages_parse_case_insensitively() {
+        assert_eq!("rust".parse::<DocLanguage>().unwrap(), DocLanguage::Rust);
+        assert_eq!("Python".parse::<DocLanguage>().unwrap(), DocLanguage::Python);
+        assert_eq!("javascript".parse::<DocLanguage>().unwrap(), DocLanguage::JavaScript);
+        
+        let err = "cobol".parse::<DocLanguage>().unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported language cobol; expected one of rust, python, javascript");
+        
+        assert!(DocLanguage::Python.is_valid_package("zope.interface"));
+        assert!(!DocLanguage::Rust.is_valid_package("zope.interface"));
+        assert!(!DocLanguage::JavaScript.is_valid_package(".."));
+        assert!(DocLanguage::JavaScript.is_valid_package("@types/node"));
+        assert!(!DocLanguage::JavaScript.is_valid_package("@types/../node"));
+        assert!(!DocLanguage::JavaScript.is_valid_package("@types"));
+        assert!(!DocLanguage::JavaScript.is_valid_package("types/node"));
+        assert!(!DocLanguage::Python.is_valid_package("@types/node"));
+        assert_eq!(api_docs_cache_path("cache", "@types/node", "20.1.0"), "cache/@types%2Fnode-20.1.0.md");
+        assert_eq!(DocLanguage::JavaScript.package_url("@types/node"), "https://registry.npmjs.org/@types%2Fnode");
+    }
+    
+    #[test]
+    fn registry_responses_give_the_latest_version() {
+        let crates_io = serde_json::json!({ "crate": { "max_stable_version": "1.0.2", "max_version": "1.1.0-rc.1" } });
+        assert_eq!(DocLanguage::Rust.latest_version(&crates_io).as_deref(), Some("1.0.2"));
+        let prerelease_only = serde_json::json!({ "crate": { "max_stable_version": null, "max_version": "0.1.0-alpha" } });
+        assert_eq!(DocLanguage::Rust.latest_version(&prerelease_only).as_deref(), Some("0.1.0-alpha"));
+        
+        let pypi = serde_json::json!({ "info": { "version": "2.31.0" } });
+        assert_eq!(DocLanguage::Python.latest_version(&pypi).as_deref(), Some("2.31.0"));
+        let npm = serde_json::json!({ "dist-tags": { "latest": "4.18.2" } });
+        assert_eq!(DocLanguage::JavaScript.latest_version(&npm).as_deref(), Some("4.18.2"));
+        assert_eq!(DocLanguage::JavaScript.latest_version(&pypi), None);
+        
+        assert!(is_valid_version("1.0.0+build.5"));
+        assert!(!is_valid_version("../1.0.0"));
+        assert!(!is_valid_version(""));
+        assert_eq!(api_docs_cache_path("cache", "serde", "1.0.2"), "cache/serde-1.0.2.md");
+    }
+    
+    #[tokio::test]
+    async fn spot_checks_only_fetch_public_on_domain_urls() {
+        let task = Task::new("task".to_string(), "https://www.example.com".to_string(), 2, false, None, 0);
+        let pages: Vec<CrawledPage> = [
+            "https://example.com/",
+            "http://www.example.com/about",
+            "https://blog.example.com/",
+            "https://example.com.evil.net/",
+            "file:///etc/passwd",
+        ].iter().map(|url| CrawledPage { url: url.to_string(), status: Some(200), content_type: None, size: 1, timestamp: 0 }).collect();
+        
+        let urls: Vec<&str> = spot_check_candidates(&task, &pages).into_iter().map(|(page, _)| page.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/", "http://www.example.com/about"]);
+        
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{} should be refused", ip);
+        }
+        assert!(is_public_address("93.184.216.34".parse().unwrap()));
+        assert!(is_public_address("2606:2800:220:1::".parse().unwrap()));
+        
+        let metadata = Url::parse("http://169.254.169.254/latest/meta-data/").unwrap();
+        assert!(resolve_public(&metadata).await.is_none());
+        let localhost = Url::parse("http://[::1]:8000/").unwrap();
+        assert!(resolve_public(&localhost).await.is_none());
+    }
+}
@@ -1,8 +1,12 @@
 pub mod api;
 pub mod db;
 pub mod evaluator;
+pub mod metrics;
 pub mod models;
+pub mod rate_limit;
+pub mod scheduler;
 pub mod solana;
+pub mod webhook;
 
 // Re-export crates
 pub use rusqlite;
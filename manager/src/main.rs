@@ -1,8 +1,12 @@
 mod api;
 mod db;
 mod evaluator;
+mod metrics;
 mod models;
+mod rate_limit;
+mod scheduler;
 mod solana;
+mod webhook;
 
 use anyhow::{Result, Context};
 use clap::{Parser, Subcommand};
@@ -11,8 +15,10 @@ use std::path::Path;
 use std::fs;
 use db::Database;
 use evaluator::Evaluator;
+use metrics::METRICS;
 use solana::SolanaIntegration;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use once_cell::sync::OnceCell;
 
@@ -91,6 +97,16 @@ enum Command {
         /// Incentive amount
         #[clap(long, default_value = "0.1")]
         incentive_amount: f64,
+        
+        /// Assignment priority (higher is assigned first)
+        #[clap(long, default_value = "0")]
+        priority: i32,
+    },
+    
+    /// Check a config file and exit non-zero if it has problems
+    ValidateConfig {
+        /// Config file to check (default: the first one the server would load)
+        path: Option<String>,
     },
     
     /// Get API documentation for a package
@@ -104,24 +120,48 @@ enum Command {
     },
 }
 
+/// Where the config file is looked for, in order
+const CONFIG_PATHS: [&str; 3] = [
+    "config.toml",
+    "config/config.toml",
+    "/etc/cryptocrawl/config.toml",
+];
+
+/// Read, parse and validate a config file
+fn read_config(path: &str) -> Result<models::Config> {
+    let content = fs::read_to_string(path)
+        .context(format!("Failed to read config file {}", path))?;
+    
+    let config: models::Config = toml::from_str(&content)
+        .context(format!("Failed to parse config file {}", path))?;
+    
+    config.validate()
+        .context(format!("Config file {} is invalid", path))?;
+    
+    Ok(config)
+}
+
+/// Check a config file for the validate-config command
+fn validate_config(path: Option<&str>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => CONFIG_PATHS.into_iter()
+            .find(|path| Path::new(path).exists())
+            .context(format!("No config file found in {}", CONFIG_PATHS.join(", ")))?,
+    };
+    
+    read_config(path)?;
+    println!("{} is valid", path);
+    Ok(())
+}
+
 /// Load configuration from file
 fn load_config() -> Result<()> {
-    // Define config paths
-    let config_paths = vec![
-        "config.toml",
-        "config/config.toml",
-        "/etc/cryptocrawl/config.toml",
-    ];
-    
     // Try to load from one of the paths
-    for path in config_paths {
+    for path in CONFIG_PATHS {
         if Path::new(path).exists() {
             info!("Loading configuration from {}", path);
-            let content = fs::read_to_string(path)
-                .context(format!("Failed to read config file {}", path))?;
-            
-            let config: models::Config = toml::from_str(&content)
-                .context(format!("Failed to parse config file {}", path))?;
+            let config = read_config(path)?;
             
             // Initialize global config
             CONFIG.set(config).expect("Failed to set global config");
@@ -194,7 +234,7 @@ async fn start_server(db: Arc<Mutex<Database>>, evaluator: Arc<Evaluator>) -> Re
     
     // Start API server
     info!("Starting manager server on {}", addr);
-    api::start_api_server(db, evaluator, solana, &addr)
+    api::start_api_server(db, evaluator, solana, _config.clone(), &addr)
         .await
         .context("Failed to start API server")?;
     
@@ -202,8 +242,46 @@ async fn start_server(db: Arc<Mutex<Database>>, evaluator: Arc<Evaluator>) -> Re
 }
 
 /// Start the manager process
-async fn start_manager(_db: Arc<Mutex<Database>>, _evaluator: Arc<Evaluator>) -> Result<()> {
-    // Implementation will be added later
+async fn start_manager(db: Arc<Mutex<Database>>, _evaluator: Arc<Evaluator>) -> Result<()> {
+    let config = CONFIG.get().expect("Config not initialized");
+    
+    // Periodically return tasks from crashed or vanished crawlers to the pool
+    let assignment_timeout = Duration::from_secs(config.tasks.assignment_timeout_secs);
+    let reclaim_interval = Duration::from_secs(config.tasks.reclaim_interval_secs.max(1));
+    let reclaim_db = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(reclaim_interval);
+        loop {
+            interval.tick().await;
+            
+            let db = reclaim_db.lock().await;
+            match db.reclaim_stale_assignments(assignment_timeout) {
+                Ok(0) => {},
+                Ok(count) => info!("Reclaimed {} stale task assignments", count),
+                Err(e) => error!("Failed to reclaim stale task assignments: {}", e),
+            }
+        }
+    });
+    
+    // Create a fresh pending task from every schedule that has come due
+    let schedule_interval = Duration::from_secs(config.tasks.schedule_interval_secs.max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(schedule_interval);
+        loop {
+            interval.tick().await;
+            
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let db = db.lock().await;
+            match scheduler::run_due_schedules(&db, now) {
+                Ok(tasks) => METRICS.tasks_created.inc_by(tasks.len() as u64),
+                Err(e) => error!("Failed to run due schedules: {}", e),
+            }
+        }
+    });
+    
     info!("Manager process started");
     Ok(())
 }
@@ -235,7 +313,12 @@ async fn init_evaluator() -> Evaluator {
     let evaluator_config = &config.evaluator;
     
     // Create evaluator with deepseek-r1:14b as primary model
-    let mut evaluator = Evaluator::new(&evaluator_config.host, "deepseek-r1:14b");
+    let mut evaluator = Evaluator::new(&evaluator_config.host, "deepseek-r1:14b")
+        .with_prompt_template(&evaluator_config.prompt_template)
+        .with_min_confidence(evaluator_config.min_confidence)
+        .with_streaming(evaluator_config.stream)
+        .with_sample_size(evaluator_config.sample_size)
+        .with_llm_options(evaluator_config.options.clone());
     
     // Check if Ollama service is available and find a suitable model
     match evaluator.check_service().await {
@@ -263,6 +346,12 @@ async fn main() -> Result<()> {
     
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    
+    // Checking a config file needs neither the database nor the server
+    if let Command::ValidateConfig { path } = &args.command {
+        return validate_config(path.as_deref());
+    }
+    
     info!("Starting CryptoCrawl Manager");
     
     // Load configuration
@@ -276,11 +365,11 @@ async fn main() -> Result<()> {
     let evaluator = init_evaluator().await;
     let evaluator = Arc::new(evaluator);
     
-    // Start the server
-    start_server(db.clone(), evaluator.clone()).await?;
+    // Start the manager background work
+    start_manager(db.clone(), evaluator.clone()).await?;
     
-    // Start the manager
-    start_manager(db, evaluator).await?;
+    // Start the server
+    start_server(db, evaluator).await?;
     
     info!("Manager shutdown complete");
     Ok(())
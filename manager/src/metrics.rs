@@ -0,0 +1,79 @@
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
+
+/// Prometheus metrics for the manager, exposed at `GET /metrics`
+pub struct Metrics {
+    registry: Registry,
+    /// Tasks created through the API
+    pub tasks_created: IntCounter,
+    /// Tasks handed to a crawler
+    pub tasks_assigned: IntCounter,
+    /// Tasks a crawler submitted a final report for
+    pub tasks_completed: IntCounter,
+    /// Reports that passed verification
+    pub reports_verified: IntCounter,
+    /// Reports that failed verification
+    pub reports_rejected: IntCounter,
+    /// Seconds spent verifying a report, LLM and spot check included
+    pub verification_seconds: Histogram,
+    /// Seconds spent waiting for the LLM
+    pub llm_call_seconds: Histogram,
+}
+
+/// Metrics shared by the API handlers and the evaluator
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("cryptocrawl_manager".to_string()), None)
+            .expect("valid metrics prefix");
+        
+        let counter = |name: &str, help: &str| {
+            let counter = IntCounter::new(name, help).expect("valid counter");
+            registry.register(Box::new(counter.clone())).expect("unique counter name");
+            counter
+        };
+        let tasks_created = counter("tasks_created_total", "Tasks created through the API or by schedules");
+        let tasks_assigned = counter("tasks_assigned_total", "Tasks assigned to a crawler");
+        let tasks_completed = counter("tasks_completed_total", "Tasks with a final report");
+        let reports_verified = counter("reports_verified_total", "Reports that passed verification");
+        let reports_rejected = counter("reports_rejected_total", "Reports that failed verification");
+        
+        let histogram = |name: &str, help: &str, buckets: Vec<f64>| {
+            let histogram = Histogram::with_opts(HistogramOpts::new(name, help).buckets(buckets))
+                .expect("valid histogram");
+            registry.register(Box::new(histogram.clone())).expect("unique histogram name");
+            histogram
+        };
+        let verification_seconds = histogram(
+            "report_verification_seconds",
+            "Time spent verifying a report",
+            vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0],
+        );
+        let llm_call_seconds = histogram(
+            "llm_call_seconds",
+            "Time spent waiting for the LLM",
+            vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0],
+        );
+        
+        Self {
+            registry,
+            tasks_created,
+            tasks_assigned,
+            tasks_completed,
+            reports_verified,
+            reports_rejected,
+            verification_seconds,
+            llm_call_seconds,
+        }
+    }
+    
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Status of a crawling task
@@ -13,6 +14,23 @@ pub enum TaskStatus {
     Rejected,
 }
 
+impl std::str::FromStr for TaskStatus {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Pending" => Ok(TaskStatus::Pending),
+            "Assigned" => Ok(TaskStatus::Assigned),
+            "InProgress" => Ok(TaskStatus::InProgress),
+            "Completed" => Ok(TaskStatus::Completed),
+            "Failed" => Ok(TaskStatus::Failed),
+            "Verified" => Ok(TaskStatus::Verified),
+            "Rejected" => Ok(TaskStatus::Rejected),
+            _ => Err(format!("Unknown task status: {}", s)),
+        }
+    }
+}
+
 /// Represents a crawling task to be assigned to clients
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -38,6 +56,12 @@ pub struct Task {
     pub assigned_to: Option<String>,
     /// Incentive amount for completion
     pub incentive_amount: u64,
+    /// Assignment priority (higher is assigned first)
+    #[serde(default)]
+    pub priority: i32,
+    /// Webhook notified when the task's report is verified, overriding the configured one
+    #[serde(default)]
+    pub webhook_url: Option<String>,
 }
 
 impl Task {
@@ -47,7 +71,7 @@ impl Task {
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
-            
+        
         Task {
             id,
             target_url,
@@ -60,16 +84,30 @@ impl Task {
             status: TaskStatus::Pending,
             assigned_to: None,
             incentive_amount,
+            priority: 0,
+            webhook_url: None,
         }
     }
     
+    /// Set the assignment priority
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    
+    /// Set the webhook notified when the task's report is verified
+    pub fn with_webhook_url(mut self, webhook_url: Option<String>) -> Self {
+        self.webhook_url = webhook_url;
+        self
+    }
+    
     /// Assign task to a client
     pub fn assign(&mut self, client_id: String) {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
-            
+        
         self.assigned_to = Some(client_id);
         self.assigned_at = Some(now);
         self.status = TaskStatus::Assigned;
@@ -81,7 +119,7 @@ impl Task {
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
-            
+        
         self.completed_at = Some(now);
         self.status = TaskStatus::Completed;
     }
@@ -96,6 +134,70 @@ impl Task {
     }
 }
 
+/// Settings a schedule copies into each task it creates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskTemplate {
+    pub target_url: String,
+    pub max_depth: u32,
+    pub follow_subdomains: bool,
+    pub max_links: Option<u32>,
+    pub incentive_amount: u64,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+impl TaskTemplate {
+    /// A fresh pending task with the template's settings
+    pub fn to_task(&self, id: String) -> Task {
+        Task::new(
+            id,
+            self.target_url.clone(),
+            self.max_depth,
+            self.follow_subdomains,
+            self.max_links,
+            self.incentive_amount,
+        ).with_priority(self.priority)
+            .with_webhook_url(self.webhook_url.clone())
+    }
+}
+
+impl From<&Task> for TaskTemplate {
+    fn from(task: &Task) -> Self {
+        Self {
+            target_url: task.target_url.clone(),
+            max_depth: task.max_depth,
+            follow_subdomains: task.follow_subdomains,
+            max_links: task.max_links,
+            incentive_amount: task.incentive_amount,
+            priority: task.priority,
+            webhook_url: task.webhook_url.clone(),
+        }
+    }
+}
+
+/// Recurring crawl: a task is created from the template each time the cron expression fires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Unique identifier for the schedule
+    pub id: String,
+    /// Cron expression, with an optional leading seconds field
+    pub cron: String,
+    /// IANA time zone the expression is evaluated in, such as `Europe/Berlin`
+    pub timezone: String,
+    /// Settings of the tasks the schedule creates
+    pub template: TaskTemplate,
+    /// When the next task is due (Unix timestamp)
+    pub next_run: u64,
+    /// When the schedule last created a task
+    pub last_run: Option<u64>,
+    /// Task created by the last run
+    pub last_task_id: Option<String>,
+    /// Creation timestamp
+    pub created_at: u64,
+}
+
 /// Represents a single crawled page
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawledPage {
@@ -136,10 +238,119 @@ pub struct CrawlReport {
     pub verification_score: Option<f64>,
     /// LLM verification notes
     pub verification_notes: Option<String>,
+    /// Whether the crawl is still running and more pages may be appended
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl CrawlReport {
+    /// Add pages to the report, replacing any earlier entry for the same URL
+    pub fn add_pages(&mut self, pages: impl IntoIterator<Item = CrawledPage>) {
+        let mut index: HashMap<String, usize> = self.pages.iter()
+            .enumerate()
+            .map(|(i, page)| (page.url.clone(), i))
+            .collect();
+        
+        for page in pages {
+            match index.get(&page.url) {
+                Some(&i) => self.pages[i] = page,
+                None => {
+                    index.insert(page.url.clone(), self.pages.len());
+                    self.pages.push(page);
+                },
+            }
+        }
+        
+        self.pages_count = self.pages.len();
+        self.total_size = self.pages.iter().map(|p| p.size).sum();
+    }
+}
+
+/// Outcome recorded for a verified report, returned again if the report is resubmitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportVerification {
+    /// Whether the report passed verification
+    pub verified: bool,
+    /// Verification score
+    pub score: f64,
+    /// LLM verification notes
+    pub notes: String,
+    /// Transaction recording the verification on the blockchain
+    pub transaction_hash: String,
+    /// Incentive transferred for the report, if it was verified
+    pub incentive_paid: Option<u64>,
+}
+
+/// Latest progress reported by a crawler for a task it is working on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgress {
+    /// Task being crawled
+    pub task_id: String,
+    /// Crawler reporting the progress
+    pub client_id: String,
+    /// Pages crawled so far
+    pub pages_crawled: u64,
+    /// Bytes downloaded so far
+    pub bytes: u64,
+    /// Milliseconds since the crawl started
+    pub elapsed_ms: u64,
+    /// When the progress was last reported
+    pub updated_at: u64,
+}
+
+/// A crawler that has registered with the manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlerInfo {
+    /// Client ID of the crawler
+    pub client_id: String,
+    /// Capabilities declared at registration
+    pub capabilities: serde_json::Value,
+    /// When the crawler first registered
+    pub first_seen: u64,
+    /// When the crawler last registered or requested a task
+    pub last_seen: u64,
+}
+
+impl CrawlerInfo {
+    /// Check whether the crawler's declared capabilities cover a task's requirements.
+    /// Capabilities the crawler did not declare are treated as unrestricted.
+    pub fn can_handle(&self, task: &Task) -> bool {
+        let max_depth = self.capabilities.get("max_depth").and_then(|v| v.as_u64());
+        if let Some(max_depth) = max_depth {
+            if u64::from(task.max_depth) > max_depth {
+                return false;
+            }
+        }
+        
+        let follow_subdomains = self.capabilities.get("follow_subdomains").and_then(|v| v.as_bool());
+        if task.follow_subdomains && follow_subdomains == Some(false) {
+            return false;
+        }
+        
+        true
+    }
+}
+
+/// Totals across every task and report, for dashboards
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// Number of tasks in each status
+    pub tasks_by_status: HashMap<String, usize>,
+    /// Number of reports, partial ones included
+    pub reports: u64,
+    /// Pages crawled across all reports
+    pub total_pages: u64,
+    /// Bytes crawled across all reports
+    pub total_bytes: u64,
+    /// Lamports transferred for verified reports
+    pub total_incentives_paid: u64,
+    /// Mean verification score of the reports that have one
+    pub average_verification_score: Option<f64>,
 }
 
 /// Configuration for the manager
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Server configuration
     pub server: ServerConfig,
@@ -149,10 +360,26 @@ pub struct Config {
     pub solana: SolanaConfig,
     /// Evaluator configuration
     pub evaluator: EvaluatorConfig,
+    /// Task scheduling configuration
+    #[serde(default)]
+    pub tasks: TaskConfig,
+    /// API authentication configuration
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Per-client rate limits
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    /// How report payouts scale with the crawl's size
+    #[serde(default)]
+    pub incentives: IncentivePolicy,
+    /// Notifications sent when reports are verified
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
 }
 
 /// Server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerConfig {
     /// Host to bind to
     pub host: String,
@@ -162,6 +389,7 @@ pub struct ServerConfig {
 
 /// Database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DatabaseConfig {
     /// Database path
     pub path: String,
@@ -169,6 +397,7 @@ pub struct DatabaseConfig {
 
 /// Solana configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SolanaConfig {
     /// RPC endpoint
     pub rpc_endpoint: String,
@@ -180,11 +409,216 @@ pub struct SolanaConfig {
 
 /// Evaluator configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct EvaluatorConfig {
     /// Ollama host
     pub host: String,
     /// Model to use
     pub model: String,
+    /// Verification prompt template (`{task_id}`, `{pages}`, `{total_size}`, `{duration_ms}`)
+    #[serde(default = "default_prompt_template")]
+    pub prompt_template: String,
+    /// Minimum LLM confidence required to mark a report verified
+    #[serde(default)]
+    pub min_confidence: f64,
+    /// Stream LLM responses; disable to wait for the full response in one request
+    #[serde(default = "default_true")]
+    pub stream: bool,
+    /// Number of pages sampled into the verification prompt
+    #[serde(default = "default_sample_size")]
+    pub sample_size: usize,
+    /// Number of reported URLs the manager re-fetches to spot-check a report (0 disables)
+    #[serde(default = "default_spot_check_size")]
+    pub spot_check_size: usize,
+    /// Sampling options sent to Ollama with every generation
+    #[serde(default)]
+    pub options: LlmOptions,
+}
+
+/// Ollama sampling options; unset ones are left to the model's defaults.
+/// The defaults suit verification: no randomness, and room for a full page sample.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmOptions {
+    /// Sampling temperature; 0 gives the same verdict for the same report
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Context window in tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// Nucleus sampling cutoff
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+impl Default for LlmOptions {
+    fn default() -> Self {
+        Self {
+            temperature: Some(0.0),
+            num_ctx: Some(8192),
+            top_p: None,
+        }
+    }
+}
+
+fn default_spot_check_size() -> usize {
+    crate::evaluator::DEFAULT_SPOT_CHECK_SIZE
+}
+
+fn default_sample_size() -> usize {
+    crate::evaluator::DEFAULT_SAMPLE_SIZE
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_prompt_template() -> String {
+    crate::evaluator::DEFAULT_VERIFICATION_PROMPT.to_string()
+}
+
+/// Task scheduling configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskConfig {
+    /// Seconds without an assignment or heartbeat before an unreported task is returned to the pool
+    pub assignment_timeout_secs: u64,
+    /// Seconds between sweeps for stale assignments
+    pub reclaim_interval_secs: u64,
+    /// Seconds between checks for due schedules
+    pub schedule_interval_secs: u64,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            assignment_timeout_secs: 3600,
+            reclaim_interval_secs: 60,
+            schedule_interval_secs: 30,
+        }
+    }
+}
+
+/// API authentication configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Whether bearer tokens are required; only turn this off for local development
+    pub enabled: bool,
+    /// Keys accepted as `Authorization: Bearer <key>`. With none, only crawler tokens are
+    /// accepted, and no crawler can register to get one.
+    pub api_keys: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_keys: Vec::new(),
+        }
+    }
+}
+
+/// Webhook notifications sent after a report is verified
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Webhook for tasks that don't set their own; notifications are off when neither is set
+    pub url: Option<String>,
+    /// Key used to sign payloads with HMAC-SHA256
+    pub secret: Option<String>,
+    /// Delivery attempts before a notification is dropped
+    pub max_attempts: u32,
+    /// Timeout of each delivery attempt, in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            secret: None,
+            max_attempts: 5,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Check that a webhook URL is an absolute http(s) URL
+pub fn validate_webhook_url(url: &str) -> Result<(), String> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => Ok(()),
+        Ok(_) => Err(format!("must be an http(s) URL, got {:?}", url)),
+        Err(e) => Err(format!("is not a valid URL ({:?}): {}", url, e)),
+    }
+}
+
+/// Per-client rate limits for expensive endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Report submissions allowed per client per minute
+    pub reports_per_minute: u32,
+    /// Report submissions a client may make in a burst
+    pub report_burst: u32,
+    /// Task assignment requests allowed per client per minute
+    pub assignments_per_minute: u32,
+    /// Task assignment requests a client may make in a burst
+    pub assignment_burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            reports_per_minute: 6,
+            report_burst: 3,
+            assignments_per_minute: 30,
+            assignment_burst: 10,
+        }
+    }
+}
+
+/// How the payout for a verified report is computed: the task's incentive plus an
+/// amount per page and per megabyte successfully crawled, capped.
+/// With the default coefficients of zero, every report earns the task's flat incentive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IncentivePolicy {
+    /// Lamports paid for each page fetched with a 2xx status
+    pub per_page: u64,
+    /// Lamports paid for each megabyte (1,000,000 bytes) of those pages
+    pub per_megabyte: u64,
+    /// Pages paid for at most; further pages earn nothing
+    pub max_pages: usize,
+    /// Largest payout for one report in lamports, task incentive included
+    pub max_amount: u64,
+}
+
+impl Default for IncentivePolicy {
+    fn default() -> Self {
+        Self {
+            per_page: 0,
+            per_megabyte: 0,
+            max_pages: 10_000,
+            max_amount: 10_000_000_000,
+        }
+    }
+}
+
+impl IncentivePolicy {
+    /// Payout for a verified report of `task_incentive`, counting only pages fetched with a 2xx status
+    pub fn payout(&self, task_incentive: u64, pages: &[CrawledPage]) -> u64 {
+        let paid: Vec<&CrawledPage> = pages.iter()
+            .filter(|page| matches!(page.status, Some(200..=299)))
+            .take(self.max_pages)
+            .collect();
+        let bytes: u64 = paid.iter().map(|page| page.size as u64).sum();
+        
+        let size_bonus = self.per_page.saturating_mul(paid.len() as u64)
+            .saturating_add(self.per_megabyte.saturating_mul(bytes) / 1_000_000);
+        
+        task_incentive.saturating_add(size_bonus).min(self.max_amount)
+    }
 }
 
 impl Default for Config {
@@ -205,7 +639,189 @@ impl Default for Config {
             evaluator: EvaluatorConfig {
                 host: "http://localhost:11434".to_string(),
                 model: "deepseek-r1:14b".to_string(),
+                prompt_template: default_prompt_template(),
+                min_confidence: 0.0,
+                stream: true,
+                sample_size: default_sample_size(),
+                spot_check_size: default_spot_check_size(),
+                options: LlmOptions::default(),
             },
+            tasks: TaskConfig::default(),
+            auth: AuthConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            incentives: IncentivePolicy::default(),
+            webhooks: WebhookConfig::default(),
         }
     }
-} 
\ No newline at end of file
+}
+
+impl Config {
+    /// Check what parsing can't: required fields, port ranges, URLs and limits.
+    /// The error lists every problem found, not just the first.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+        
+        let required = [
+            ("server.host", &self.server.host),
+            ("database.path", &self.database.path),
+            ("solana.keypair_path", &self.solana.keypair_path),
+            ("solana.program_id", &self.solana.program_id),
+            ("evaluator.model", &self.evaluator.model),
+        ];
+        for (field, value) in required {
+            if value.trim().is_empty() {
+                problems.push(format!("{} must not be empty", field));
+            }
+        }
+        
+        if self.server.port == 0 {
+            problems.push("server.port must be between 1 and 65535".to_string());
+        }
+        
+        for (field, value) in [("solana.rpc_endpoint", &self.solana.rpc_endpoint), ("evaluator.host", &self.evaluator.host)] {
+            match url::Url::parse(value) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => {},
+                Ok(_) => problems.push(format!("{} must be an http(s) URL, got {:?}", field, value)),
+                Err(e) => problems.push(format!("{} is not a valid URL ({:?}): {}", field, value, e)),
+            }
+        }
+        
+        if !(0.0..=1.0).contains(&self.evaluator.min_confidence) {
+            problems.push(format!("evaluator.min_confidence must be between 0 and 1, got {}", self.evaluator.min_confidence));
+        }
+        let options = &self.evaluator.options;
+        if options.temperature.map_or(false, |temperature| temperature < 0.0) {
+            problems.push("evaluator.options.temperature must not be negative".to_string());
+        }
+        if options.num_ctx == Some(0) {
+            problems.push("evaluator.options.num_ctx must be at least 1".to_string());
+        }
+        if options.top_p.map_or(false, |top_p| !(top_p > 0.0 && top_p <= 1.0)) {
+            problems.push("evaluator.options.top_p must be greater than 0 and at most 1".to_string());
+        }
+        if self.evaluator.sample_size == 0 {
+            problems.push("evaluator.sample_size must be at least 1".to_string());
+        }
+        if self.tasks.assignment_timeout_secs == 0 {
+            problems.push("tasks.assignment_timeout_secs must be at least 1".to_string());
+        }
+        if let Some(url) = &self.webhooks.url {
+            if let Err(e) = validate_webhook_url(url) {
+                problems.push(format!("webhooks.url {}", e));
+            }
+        }
+        if self.webhooks.max_attempts == 0 {
+            problems.push("webhooks.max_attempts must be at least 1".to_string());
+        }
+        if self.auth.api_keys.iter().any(|key| key.trim().is_empty()) {
+            problems.push("auth.api_keys must not contain empty keys".to_string());
+        }
+        for (field, value) in [
+            ("rate_limits.reports_per_minute", self.rate_limits.reports_per_minute),
+            ("rate_limits.assignments_per_minute", self.rate_limits.assignments_per_minute),
+        ] {
+            if value == 0 {
+                problems.push(format!("{} must be at least 1", field));
+            }
+        }
+        
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Invalid configuration:\n  - {}", problems.join("\n  - ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn default_config_is_valid() {
+        Config::default().validate().unwrap();
+        
+        // Authentication stays on unless it is turned off explicitly
+        assert!(Config::default().auth.enabled);
+        let auth: AuthConfig = toml::from_str("api_keys = []").unwrap();
+        assert!(auth.enabled);
+    }
+    
+    #[test]
+    fn validation_lists_every_problem() {
+        let mut config = Config::default();
+        config.server.port = 0;
+        config.solana.rpc_endpoint = "devnet".to_string();
+        config.evaluator.host = "ftp://localhost".to_string();
+        config.database.path = " ".to_string();
+        
+        let error = config.validate().unwrap_err().to_string();
+        for field in ["server.port", "solana.rpc_endpoint", "evaluator.host", "database.path"] {
+            assert!(error.contains(field), "{} missing from: {}", field, error);
+        }
+    }
+    
+    #[test]
+    fn payouts_scale_with_successful_pages_up_to_the_caps() {
+        let page = |status, size| CrawledPage {
+            url: "https://example.com/".to_string(),
+            status: Some(status),
+            content_type: None,
+            size,
+            timestamp: 0,
+        };
+        let pages = vec![page(200, 2_000_000), page(200, 1_000_000), page(404, 5_000_000)];
+        
+        assert_eq!(IncentivePolicy::default().payout(100, &pages), 100);
+        
+        let policy = IncentivePolicy {
+            per_page: 10,
+            per_megabyte: 1_000,
+            max_pages: 10,
+            max_amount: 1_000_000,
+        };
+        assert_eq!(policy.payout(100, &pages), 100 + 2 * 10 + 3 * 1_000);
+        assert_eq!(IncentivePolicy { max_pages: 1, ..policy.clone() }.payout(100, &pages), 100 + 10 + 2 * 1_000);
+        assert_eq!(IncentivePolicy { max_amount: 500, ..policy }.payout(100, &pages), 500);
+    }
+    
+    #[test]
+    fn added_pages_replace_earlier_entries_for_the_same_url() {
+        let page = |url: &str, size| CrawledPage {
+            url: url.to_string(),
+            status: Some(200),
+            content_type: None,
+            size,
+            timestamp: 0,
+        };
+        let mut report = CrawlReport {
+            task_id: "task".to_string(),
+            client_id: "client".to_string(),
+            domain: "example.com".to_string(),
+            pages_count: 0,
+            total_size: 0,
+            pages: Vec::new(),
+            start_time: 0,
+            end_time: None,
+            verified: false,
+            verification_score: None,
+            verification_notes: None,
+            partial: false,
+        };
+        
+        report.add_pages(vec![page("https://example.com/", 10), page("https://example.com/a", 20)]);
+        report.add_pages(vec![page("https://example.com/a", 5), page("https://example.com/b", 1), page("https://example.com/b", 2)]);
+        
+        let urls: Vec<&str> = report.pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/", "https://example.com/a", "https://example.com/b"]);
+        assert_eq!(report.pages_count, 3);
+        assert_eq!(report.total_size, 10 + 5 + 2);
+    }
+    
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let content = toml::to_string(&Config::default()).unwrap()
+            .replace("[server]", "[server]\nprot = 8080");
+        assert!(toml::from_str::<Config>(&content).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of tracked clients above which idle buckets are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Token bucket for a single client
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens currently available
+    tokens: f64,
+    /// When the bucket was last refilled
+    last_refill: Instant,
+}
+
+/// Per-client token-bucket rate limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Maximum tokens a bucket can hold (the burst size)
+    capacity: f64,
+    /// Tokens added per second
+    refill_per_sec: f64,
+    /// Buckets keyed by client ID
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `per_minute` requests per client on average, with bursts of up to `burst`
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            capacity: f64::from(burst.max(1)),
+            refill_per_sec: f64::from(per_minute) / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Take a token for the client, returning false if it has exhausted its allowance
+    pub fn check(&self, client_id: &str) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        
+        // Forget clients whose buckets would be full again anyway
+        if buckets.len() > PRUNE_THRESHOLD {
+            let idle = Duration::from_secs_f64(self.capacity / self.refill_per_sec.max(f64::EPSILON));
+            buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < idle);
+        }
+        
+        let bucket = buckets.entry(client_id.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        
+        // Refill for the time elapsed since the last request
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+        
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::db::Database;
+use crate::models::{Schedule, Task, TaskTemplate};
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use log::{info, warn};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Time zone of schedules created without one
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// `next_run` of schedules that will never fire again
+const NEVER: u64 = i64::MAX as u64;
+
+/// A parsed cron expression and the time zone it fires in
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression evaluated in an IANA time zone such as `Europe/Berlin`.
+    /// Five-field expressions (`minute hour day month weekday`) fire at second 0;
+    /// six or seven fields start with seconds and may end with a year.
+    pub fn parse(expression: &str, timezone: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expression = if expression.split_whitespace().count() == 5 {
+            format!("0 {}", expression)
+        } else {
+            expression.to_string()
+        };
+        
+        let schedule = cron::Schedule::from_str(&expression)
+            .map_err(|e| format!("Invalid cron expression {:?}: {}", expression, e))?;
+        let timezone = timezone.parse::<Tz>()
+            .map_err(|_| format!("Unknown time zone {:?}", timezone))?;
+        
+        Ok(Self { schedule, timezone })
+    }
+    
+    /// First time after `after` (Unix timestamp) at which the expression fires, as wall-clock
+    /// time in the schedule's zone. `None` when it never fires again.
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        let after = Utc.timestamp_opt(after as i64, 0).single()?.with_timezone(&self.timezone);
+        self.schedule.after(&after)
+            .next()
+            .map(|next| next.timestamp().max(0) as u64)
+    }
+}
+
+/// Build a schedule that first fires after `now`
+pub fn new_schedule(expression: &str, timezone: &str, template: TaskTemplate, now: u64) -> Result<Schedule, String> {
+    let cron = CronSchedule::parse(expression, timezone)?;
+    let next_run = cron.next_after(now)
+        .ok_or_else(|| format!("Cron expression {:?} never fires", expression))?;
+    
+    Ok(Schedule {
+        id: Uuid::new_v4().to_string(),
+        cron: expression.trim().to_string(),
+        timezone: timezone.to_string(),
+        template,
+        next_run,
+        last_run: None,
+        last_task_id: None,
+        created_at: now,
+    })
+}
+
+/// Create a pending task for every schedule due at `now` and move each to its next run.
+///
+/// A schedule that missed several runs, because the manager was down, creates a single task
+/// and then waits for its next run after `now`, instead of catching up on every missed one.
+/// Schedules that can't be parsed or never fire again are pushed out of reach and logged.
+pub fn run_due_schedules(db: &Database, now: u64) -> Result<Vec<Task>> {
+    let mut created = Vec::new();
+    
+    for schedule in db.get_due_schedules(now)? {
+        let next_run = match CronSchedule::parse(&schedule.cron, &schedule.timezone) {
+            Ok(cron) => cron.next_after(now),
+            Err(e) => {
+                warn!("Schedule {} can't run: {}", schedule.id, e);
+                None
+            }
+        };
+        
+        let task = schedule.template.to_task(Uuid::new_v4().to_string());
+        db.record_schedule_run(&schedule.id, &task, now, next_run.unwrap_or(NEVER))?;
+        match next_run {
+            Some(next_run) => info!("Schedule {} created task {} for {}, next run at {}",
+                                    schedule.id, task.id, task.target_url, next_run),
+            None => warn!("Schedule {} created task {} and won't run again", schedule.id, task.id),
+        }
+        created.push(task);
+    }
+    
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    /// Unix timestamp of a UTC wall-clock time
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> u64 {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap().timestamp() as u64
+    }
+    
+    fn template() -> TaskTemplate {
+        TaskTemplate {
+            target_url: "https://docs.example.com".to_string(),
+            max_depth: 3,
+            follow_subdomains: false,
+            max_links: Some(500),
+            incentive_amount: 1_000,
+            priority: 2,
+            webhook_url: None,
+        }
+    }
+    
+    #[test]
+    fn nightly_runs_follow_the_time_zone_across_dst() {
+        // 02:00 in Berlin is 01:00 UTC in winter and 00:00 UTC in summer
+        let nightly = CronSchedule::parse("0 2 * * *", "Europe/Berlin").unwrap();
+        assert_eq!(nightly.next_after(utc(2024, 1, 15, 12, 0)), Some(utc(2024, 1, 16, 1, 0)));
+        assert_eq!(nightly.next_after(utc(2024, 7, 15, 12, 0)), Some(utc(2024, 7, 16, 0, 0)));
+        
+        let utc_nightly = CronSchedule::parse("0 0 2 * * *", DEFAULT_TIMEZONE).unwrap();
+        assert_eq!(utc_nightly.next_after(utc(2024, 7, 15, 12, 0)), Some(utc(2024, 7, 16, 2, 0)));
+        
+        assert!(CronSchedule::parse("every night", "UTC").is_err());
+        assert!(CronSchedule::parse("0 2 * * *", "Mars/Olympus_Mons").is_err());
+    }
+    
+    #[test]
+    fn due_schedules_create_one_task_and_move_on() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::new(dir.path().join("manager.db")).unwrap();
+        
+        let created_at = utc(2024, 3, 1, 12, 0);
+        let schedule = new_schedule("0 2 * * *", "UTC", template(), created_at).unwrap();
+        assert_eq!(schedule.next_run, utc(2024, 3, 2, 2, 0));
+        db.create_schedule(&schedule).unwrap();
+        
+        // Nothing is due before the first run
+        assert!(run_due_schedules(&db, utc(2024, 3, 2, 1, 59)).unwrap().is_empty());
+        
+        // Three nights were missed; only one task is created for them
+        let now = utc(2024, 3, 4, 9, 30);
+        let tasks = run_due_schedules(&db, now).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(TaskTemplate::from(&tasks[0]), template());
+        
+        let stored = db.get_task(&tasks[0].id).unwrap().unwrap();
+        assert_eq!(stored.target_url, "https://docs.example.com");
+        
+        let schedule = db.get_schedules().unwrap().remove(0);
+        assert_eq!(schedule.next_run, utc(2024, 3, 5, 2, 0));
+        assert_eq!(schedule.last_run, Some(now));
+        assert_eq!(schedule.last_task_id.as_deref(), Some(tasks[0].id.as_str()));
+        assert!(run_due_schedules(&db, now).unwrap().is_empty());
+    }
+}
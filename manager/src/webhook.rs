@@ -0,0 +1,130 @@
+use crate::models::WebhookConfig;
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Header carrying the hex HMAC-SHA256 of the request body, as `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "x-cryptocrawl-signature";
+
+/// Delay before the first retry; doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Payload posted once a report has been verified
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationEvent {
+    pub task_id: String,
+    pub verified: bool,
+    pub score: f64,
+    pub incentive_amount: Option<u64>,
+}
+
+/// Posts verification events to webhooks in the background
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    client: Client,
+    /// Webhook used for tasks without their own
+    default_url: Option<String>,
+    /// Key the payloads are signed with; unsigned when unset
+    secret: Option<String>,
+    /// Deliveries attempted before an event is dropped
+    max_attempts: u32,
+}
+
+impl WebhookNotifier {
+    /// Create a notifier from the webhook configuration
+    pub fn new(config: &WebhookConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        
+        Self {
+            client,
+            default_url: config.url.clone(),
+            secret: config.secret.clone(),
+            max_attempts: config.max_attempts.max(1),
+        }
+    }
+    
+    /// Send `event` to the task's webhook, or the configured one, without waiting for delivery
+    pub fn notify(&self, task_webhook: Option<&str>, event: VerificationEvent) {
+        let Some(url) = task_webhook.or(self.default_url.as_deref()).map(str::to_string) else {
+            return;
+        };
+        
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook event for task {}: {}", event.task_id, e);
+                return;
+            }
+        };
+        
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            notifier.deliver(&url, &event.task_id, body).await;
+        });
+    }
+    
+    /// POST the body, retrying with exponential backoff on errors and non-2xx responses
+    async fn deliver(&self, url: &str, task_id: &str, body: Vec<u8>) {
+        let signature = self.secret.as_deref().map(|secret| sign(secret, &body));
+        let mut backoff = INITIAL_BACKOFF;
+        
+        for attempt in 1..=self.max_attempts {
+            let mut request = self.client.post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+            
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!("Delivered webhook for task {} to {}", task_id, url);
+                    return;
+                }
+                Ok(response) => warn!("Webhook {} for task {} returned {} (attempt {}/{})",
+                                      url, task_id, response.status(), attempt, self.max_attempts),
+                Err(e) => warn!("Webhook {} for task {} failed: {} (attempt {}/{})",
+                                url, task_id, e, attempt, self.max_attempts),
+            }
+            
+            if attempt < self.max_attempts {
+                debug!("Retrying webhook for task {} in {:?}", task_id, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+        
+        warn!("Giving up on webhook {} for task {}", url, task_id);
+    }
+}
+
+/// Signature header value for a payload: `sha256=` and the hex HMAC-SHA256 of the body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn signature_matches_rfc_4231() {
+        // Test case 2 of RFC 4231
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        );
+    }
+}
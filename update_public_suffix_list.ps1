@@ -0,0 +1,40 @@
+# Refresh the public suffix list bundled with the crawler
+param (
+    [Parameter(Mandatory=$false)]
+    [string]$Url = "https://publicsuffix.org/list/public_suffix_list.dat",
+    
+    [Parameter(Mandatory=$false)]
+    [string]$OutputDir = "crawler/data"
+)
+
+$listFile = Join-Path $OutputDir "public_suffix_list.dat"
+$versionFile = Join-Path $OutputDir "public_suffix_list.version"
+$tempFile = "$listFile.download"
+
+Write-Host "Downloading public suffix list from $Url..." -ForegroundColor Cyan
+try {
+    Invoke-WebRequest -Uri $Url -OutFile $tempFile -UseBasicParsing
+} catch {
+    Write-Host "Error: Failed to download the public suffix list: $_" -ForegroundColor Red
+    exit 1
+}
+
+# Refuse anything that doesn't look like the list, so a bad download never replaces it
+if (-not (Select-String -Path $tempFile -Pattern "===BEGIN ICANN DOMAINS===" -Quiet)) {
+    Write-Host "Error: Downloaded file is not a public suffix list" -ForegroundColor Red
+    Remove-Item $tempFile
+    exit 1
+}
+
+Move-Item -Path $tempFile -Destination $listFile -Force
+
+$hash = (Get-FileHash -Path $listFile -Algorithm SHA256).Hash.ToLower()
+$date = Get-Date -Format "yyyy-MM-dd"
+@(
+    "source: $Url"
+    "fetched: $date"
+    "sha256: $hash"
+) | Set-Content -Path $versionFile
+
+Write-Host "Public suffix list updated ($date, sha256 $hash)" -ForegroundColor Green
+Write-Host "Rebuild the crawler to pick up the new list" -ForegroundColor Yellow